@@ -1,4 +1,8 @@
-#[derive(Debug, Clone, PartialEq)]
+// `NoneType` reads as Python's own `NoneType`, not a redundant echo of the
+// enum's name - renaming it would ripple through every `Type::NoneType`
+// match arm across the compiler for no behavioral benefit.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Type {
     Int,
     Float,
@@ -29,6 +33,10 @@ pub struct Program {
     pub imports: Vec<Import>,
 }
 
+// `ExprStatement` names what it is (a bare-expression statement) rather
+// than repeating `Statement` for its own sake - renaming it would ripple
+// through every match arm across the compiler for no behavioral benefit.
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 pub enum Statement {
     FunctionDef(FunctionDef),
@@ -38,11 +46,47 @@ pub enum Statement {
     For(ForLoop),
     While(WhileLoop),
     If(IfStatement),
-    Return(Option<Expression>),
-    Pass,
-    Break,
-    Continue,
-    ExprStatement(Expression),
+    Try(TryStatement),
+    Return(Option<Expression>, usize),
+    /// `yield expr` - only recognized inside the narrow "single loop, tail
+    /// yield" shape `IRLowering::lower_generator` compiles to a Rust
+    /// `Iterator`; anywhere else it lowers as a dropped value (see
+    /// `IRLowering::lower_statement`'s catch-all).
+    Yield(Expression, usize),
+    Pass(usize),
+    Break(usize),
+    Continue(usize),
+    ExprStatement(Expression, usize),
+    /// `global x, y` - binds `x`/`y`, for the rest of the enclosing
+    /// function, to the module-level global of that name rather than a
+    /// fresh local. See `IRLowering::lower_function`'s `global_names`
+    /// collection and `IRFunction::global_names`.
+    Global(Vec<String>, usize),
+}
+
+impl Statement {
+    /// 1-based source line this statement started on - fed into
+    /// `IRLowering::lower_statement`'s per-line `LineMarker` so `--profile-lines`
+    /// can attribute hit counts back to Python source (see `IRInstruction::LineMarker`).
+    pub fn line(&self) -> usize {
+        match self {
+            Statement::FunctionDef(f) => f.line,
+            Statement::ClassDef(c) => c.line,
+            Statement::Assign(a) => a.line,
+            Statement::AugAssign(a) => a.line,
+            Statement::For(f) => f.line,
+            Statement::While(w) => w.line,
+            Statement::If(i) => i.line,
+            Statement::Try(t) => t.line,
+            Statement::Return(_, line)
+            | Statement::Yield(_, line)
+            | Statement::Pass(line)
+            | Statement::Break(line)
+            | Statement::Continue(line)
+            | Statement::ExprStatement(_, line)
+            | Statement::Global(_, line) => *line,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +96,9 @@ pub struct FunctionDef {
     pub body: Vec<Statement>,
     pub return_type: Type,
     pub directives: Vec<String>,
+    /// 1-based line number of the `def` in the original source, for
+    /// provenance comments in generated code - see `IRCodegen::generate`.
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -64,14 +111,19 @@ pub struct Parameter {
 #[derive(Debug, Clone)]
 pub struct ClassDef {
     pub name: String,
+    // Parsed but not yet consumed downstream - class inheritance isn't
+    // modeled by the lowering/codegen passes yet.
+    #[allow(dead_code)]
     pub bases: Vec<String>,
     pub body: Vec<Statement>,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct Assignment {
     pub targets: Vec<String>,
     pub value: Expression,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -79,6 +131,7 @@ pub struct AugAssignment {
     pub target: String,
     pub op: BinOp,
     pub value: Expression,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -86,12 +139,14 @@ pub struct ForLoop {
     pub target: String,
     pub iter: Expression,
     pub body: Vec<Statement>,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct WhileLoop {
     pub condition: Expression,
     pub body: Vec<Statement>,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -99,6 +154,30 @@ pub struct IfStatement {
     pub condition: Expression,
     pub then_body: Vec<Statement>,
     pub else_body: Option<Vec<Statement>>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TryStatement {
+    pub body: Vec<Statement>,
+    pub handlers: Vec<ExceptHandler>,
+    pub finalbody: Vec<Statement>,
+    pub line: usize,
+}
+
+/// A single `except ...:` clause. `IRLowering::lower_try` only honors the
+/// first handler on a `try` (see there for why), but every handler is
+/// parsed and kept so a later pass can do proper type-based dispatch.
+#[derive(Debug, Clone)]
+pub struct ExceptHandler {
+    /// The name after `except`, e.g. `ZeroDivisionError` - `None` for a
+    /// bare `except:`. Kept for the type-based dispatch pass mentioned
+    /// above, which doesn't exist yet.
+    #[allow(dead_code)]
+    pub exception_type: Option<String>,
+    /// The name bound by `except ... as name`.
+    pub name: Option<String>,
+    pub body: Vec<Statement>,
 }
 
 #[derive(Debug, Clone)]
@@ -109,9 +188,14 @@ pub enum Expression {
     StringLit(String),
     Identifier(String),
     BinOp(Box<Expression>, BinOp, Box<Expression>),
+    // The following variants round out the grammar the AST models but that
+    // `PythonParser::parse_expression` doesn't yet produce - kept so a
+    // future parser pass can start emitting them without a type change.
+    #[allow(dead_code)]
     UnaryOp(UnaryOp, Box<Expression>),
     Call(Box<Expression>, Vec<Expression>),
     Index(Box<Expression>, Box<Expression>),
+    #[allow(dead_code)]
     Slice(
         Box<Expression>,
         Option<Box<Expression>>,
@@ -120,10 +204,32 @@ pub enum Expression {
     ),
     Attribute(Box<Expression>, String),
     List(Vec<Expression>),
+    #[allow(dead_code)]
     Dict(Vec<(Expression, Expression)>),
+    #[allow(dead_code)]
     Tuple(Vec<Expression>),
+    #[allow(dead_code)]
     Lambda(Vec<String>, Box<Expression>),
+    #[allow(dead_code)]
     Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+    /// An f-string, e.g. `f"{name}: {score:.2f}"`, broken into the literal
+    /// text between interpolations and the interpolations themselves.
+    FString(Vec<FStringPart>),
+    /// A `name=value` call argument, e.g. `print(x, sep=", ")` - only
+    /// meaningful to callees that specifically look for it (currently just
+    /// `IRLowering::lower_print`'s `sep`/`end`); anywhere else it lowers as
+    /// its wrapped value with the name dropped.
+    Kwarg(String, Box<Expression>),
+}
+
+/// One fragment of an `Expression::FString`.
+#[derive(Debug, Clone)]
+pub enum FStringPart {
+    Literal(String),
+    /// An `{expr}` or `{expr:spec}` interpolation - `spec` is kept as the
+    /// raw text between the `:` and the closing `}`, untranslated until
+    /// lowering.
+    Expr(Box<Expression>, Option<String>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -135,10 +241,17 @@ pub enum BinOp {
     FloorDiv,
     Mod,
     Pow,
+    // Bitwise ops and short-circuit logic round out the grammar the AST
+    // models but that the parser doesn't yet produce literal syntax for.
+    #[allow(dead_code)]
     LShift,
+    #[allow(dead_code)]
     RShift,
+    #[allow(dead_code)]
     BitOr,
+    #[allow(dead_code)]
     BitXor,
+    #[allow(dead_code)]
     BitAnd,
     Eq,
     NotEq,
@@ -146,14 +259,20 @@ pub enum BinOp {
     LtE,
     Gt,
     GtE,
+    #[allow(dead_code)]
     Is,
+    #[allow(dead_code)]
     IsNot,
     In,
     NotIn,
+    #[allow(dead_code)]
     And,
+    #[allow(dead_code)]
     Or,
 }
 
+// Not yet produced by the parser - see `Expression::UnaryOp`.
+#[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub enum UnaryOp {
     Not,
@@ -166,5 +285,8 @@ pub enum UnaryOp {
 pub struct Import {
     pub module: String,
     pub items: Option<Vec<String>>, // None = import *
+    // Parsed but not yet consumed downstream - `import x as y` aliasing
+    // isn't modeled by the lowering/codegen passes yet.
+    #[allow(dead_code)]
     pub alias: Option<String>,
 }
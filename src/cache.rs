@@ -1,14 +1,58 @@
 use anyhow::Result;
 /// Cache system
 /// Hash-based compilation caching
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 pub struct Cache {
     cache_dir: PathBuf,
 }
 
+/// Sidecar written next to a whole-file cache entry so `cache list` can
+/// show what it was compiled from - the cache itself is keyed purely by
+/// content hash and has no other way to recover the source path.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheMeta {
+    source_path: String,
+}
+
+/// One `cache list` row. `source_path` is `None` for an entry with a
+/// missing or unreadable `.meta.json` sidecar (e.g. cached before this
+/// field existed).
+pub struct CacheEntry {
+    pub hash: String,
+    pub source_path: Option<String>,
+    pub size_bytes: u64,
+    pub modified: SystemTime,
+}
+
+/// Running totals behind `adrenaline cache stats`, persisted next to the
+/// entries themselves in `stats.json` - a whole-file cache hit records how
+/// many bytes of generated Rust it returned and skips straight to
+/// `build_rust_project`; a miss records how long generating that content
+/// from scratch took, so `Cache::stats`'s "time saved" can report hits *
+/// the average observed miss duration as an estimate of the wall-clock time
+/// the cache has actually saved.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheStatsData {
+    hits: u64,
+    misses: u64,
+    bytes_served: u64,
+    miss_time_ns: u64,
+}
+
+/// `Cache::stats`'s return value - `CacheStatsData` plus the derived
+/// average-miss-based estimate `cache_stats` (the CLI command) prints.
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub bytes_served: u64,
+    pub time_saved: Duration,
+}
+
 impl Cache {
     pub fn new(_base_dir: &Path) -> Result<Self> {
         let adrenaline_home = dirs::home_dir()
@@ -26,6 +70,15 @@ impl Cache {
         hex::encode(result)
     }
 
+    /// Sibling of `get_hash` for binary data - used by `--deterministic`'s
+    /// rebuild check to compare two compiled binaries rather than source text.
+    pub fn get_hash_bytes(data: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        let result = hasher.finalize();
+        hex::encode(result)
+    }
+
     pub fn get_cache_path(&self, source_hash: &str) -> PathBuf {
         self.cache_dir.join(format!("{}.rs", source_hash))
     }
@@ -36,12 +89,109 @@ impl Cache {
 
     pub fn get_cached(&self, source_hash: &str) -> Result<String> {
         let path = self.get_cache_path(source_hash);
-        Ok(fs::read_to_string(path)?)
+        Self::read_compressed(&path)
     }
 
-    pub fn cache(&self, source_hash: &str, code: &str) -> Result<()> {
+    pub fn cache(&self, source_hash: &str, code: &str, source_path: &Path) -> Result<()> {
         let path = self.get_cache_path(source_hash);
-        fs::write(path, code)?;
+        Self::write_compressed(&path, code)?;
+        let meta = CacheMeta {
+            source_path: source_path.display().to_string(),
+        };
+        fs::write(self.get_meta_path(source_hash), serde_json::to_string(&meta)?)?;
+        Ok(())
+    }
+
+    /// zstd-compresses `contents` before writing to `path` - the generated
+    /// Rust for a heavily unrolled/vectorized numeric function can run to
+    /// several hundred KB, and the cache directory lives under `~/.adrenaline`
+    /// in a home directory that may be quota-limited, not a scratch disk.
+    fn write_compressed(path: &Path, contents: &str) -> Result<()> {
+        let compressed = zstd::encode_all(contents.as_bytes(), 0)?;
+        fs::write(path, compressed)?;
+        Ok(())
+    }
+
+    /// Inverse of `write_compressed` - every reader of a cache entry goes
+    /// through this (or `read_compressed_bytes`) rather than `fs::read*`
+    /// directly, so compression stays an implementation detail the rest of
+    /// the compiler never has to know about.
+    fn read_compressed(path: &Path) -> Result<String> {
+        let compressed = fs::read(path)?;
+        let decompressed = zstd::decode_all(compressed.as_slice())?;
+        Ok(String::from_utf8(decompressed)?)
+    }
+
+    fn get_meta_path(&self, source_hash: &str) -> PathBuf {
+        self.cache_dir.join(format!("{}.meta.json", source_hash))
+    }
+
+    /// Per-function counterpart to `get_cache_path` - a distinct `fn_`
+    /// prefix keeps a function's cached snippet from colliding with a
+    /// whole-file cache entry that happens to hash to the same value.
+    fn get_function_cache_path(&self, function_hash: &str) -> PathBuf {
+        self.cache_dir.join(format!("fn_{}.rs", function_hash))
+    }
+
+    // No caller checks for a cached function before fetching it yet - every
+    // call site goes straight to `get_cached_function` and handles a miss
+    // via its `Result`. Kept for a future caller that wants to branch
+    // without eating the read.
+    #[allow(dead_code)]
+    pub fn has_cached_function(&self, function_hash: &str) -> bool {
+        self.get_function_cache_path(function_hash).exists()
+    }
+
+    pub fn get_cached_function(&self, function_hash: &str) -> Result<String> {
+        let path = self.get_function_cache_path(function_hash);
+        Self::read_compressed(&path)
+    }
+
+    pub fn cache_function(&self, function_hash: &str, code: &str) -> Result<()> {
+        let path = self.get_function_cache_path(function_hash);
+        Self::write_compressed(&path, code)
+    }
+
+    /// Sibling of `get_function_cache_path` for a function's *optimized IR*
+    /// rather than its generated Rust - a distinct `.ir.json` extension
+    /// keeps it from colliding with the `fn_{hash}.rs` snippet cached under
+    /// the same hash space, and from `list_entries`, which only looks at
+    /// `.rs` files.
+    fn get_function_ir_cache_path(&self, function_hash: &str) -> PathBuf {
+        self.cache_dir.join(format!("fn_{}.ir.json", function_hash))
+    }
+
+    pub fn get_cached_function_ir(&self, function_hash: &str) -> Result<crate::ir::IRFunction> {
+        let path = self.get_function_ir_cache_path(function_hash);
+        Ok(serde_json::from_str(&Self::read_compressed(&path)?)?)
+    }
+
+    pub fn cache_function_ir(&self, function_hash: &str, function: &crate::ir::IRFunction) -> Result<()> {
+        let path = self.get_function_ir_cache_path(function_hash);
+        Self::write_compressed(&path, &serde_json::to_string(function)?)
+    }
+
+    /// `adrenaline cache export <path>`: packs the whole cache directory
+    /// into a single zstd-compressed tarball at `path`, so CI can stash one
+    /// file between jobs without knowing this is a directory of individually
+    /// zstd-compressed entries plus `.meta.json` sidecars.
+    pub fn export(&self, archive_path: &Path) -> Result<()> {
+        let file = fs::File::create(archive_path)?;
+        let encoder = zstd::Encoder::new(file, 0)?.auto_finish();
+        let mut builder = tar::Builder::new(encoder);
+        builder.append_dir_all(".", &self.cache_dir)?;
+        builder.finish()?;
+        Ok(())
+    }
+
+    /// Inverse of `export` - extracts `archive_path` into the cache
+    /// directory, on top of whatever's already there, so restoring a cache
+    /// from a previous CI job doesn't require clearing the current one.
+    pub fn import(&self, archive_path: &Path) -> Result<()> {
+        let file = fs::File::open(archive_path)?;
+        let decoder = zstd::Decoder::new(file)?;
+        let mut archive = tar::Archive::new(decoder);
+        archive.unpack(&self.cache_dir)?;
         Ok(())
     }
 
@@ -53,6 +203,57 @@ impl Cache {
         Ok(())
     }
 
+    fn stats_path(&self) -> PathBuf {
+        self.cache_dir.join("stats.json")
+    }
+
+    fn read_stats(&self) -> CacheStatsData {
+        fs::read_to_string(self.stats_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_stats(&self, stats: &CacheStatsData) -> Result<()> {
+        fs::write(self.stats_path(), serde_json::to_string(stats)?)?;
+        Ok(())
+    }
+
+    /// Records a cache hit of `bytes_returned` bytes - called wherever a
+    /// cache lookup (`get_cached`/`get_cached_function`/
+    /// `get_cached_function_ir`) succeeds and the caller reuses the result
+    /// instead of regenerating it.
+    pub fn record_hit(&self, bytes_returned: u64) -> Result<()> {
+        let mut stats = self.read_stats();
+        stats.hits += 1;
+        stats.bytes_served += bytes_returned;
+        self.write_stats(&stats)
+    }
+
+    /// Records a cache miss that took `generation_time` to produce - called
+    /// wherever a lookup fails and the caller falls through to actually
+    /// generating (and then caching) the content.
+    pub fn record_miss(&self, generation_time: Duration) -> Result<()> {
+        let mut stats = self.read_stats();
+        stats.misses += 1;
+        stats.miss_time_ns += generation_time.as_nanos() as u64;
+        self.write_stats(&stats)
+    }
+
+    /// `adrenaline cache stats`. `time_saved` is an estimate: hits multiplied
+    /// by the average time a miss has taken to generate, since a hit itself
+    /// doesn't redo the work it skipped and so has nothing real to time.
+    pub fn stats(&self) -> CacheStats {
+        let stats = self.read_stats();
+        let avg_miss_time_ns = stats.miss_time_ns.checked_div(stats.misses).unwrap_or(0);
+        CacheStats {
+            hits: stats.hits,
+            misses: stats.misses,
+            bytes_served: stats.bytes_served,
+            time_saved: Duration::from_nanos(avg_miss_time_ns * stats.hits),
+        }
+    }
+
     pub fn size(&self) -> Result<u64> {
         let mut total = 0u64;
         for entry in fs::read_dir(&self.cache_dir)? {
@@ -64,4 +265,53 @@ impl Cache {
         }
         Ok(total)
     }
+
+    /// Every whole-file cache entry (skips `fn_*` per-function entries and
+    /// `.meta.json` sidecars), newest first.
+    pub fn list_entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.cache_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(hash) = file_name.strip_suffix(".rs") else {
+                continue;
+            };
+            if hash.starts_with("fn_") {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            let source_path = fs::read_to_string(self.get_meta_path(hash))
+                .ok()
+                .and_then(|json| serde_json::from_str::<CacheMeta>(&json).ok())
+                .map(|meta| meta.source_path);
+            entries.push(CacheEntry {
+                hash: hash.to_string(),
+                source_path,
+                size_bytes: metadata.len(),
+                modified: metadata.modified()?,
+            });
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.modified));
+        Ok(entries)
+    }
+
+    /// Removes whole-file cache entries (and their `.meta.json` sidecar, if
+    /// any) last modified more than `max_age` ago, returning how many were
+    /// removed.
+    pub fn prune(&self, max_age: Duration) -> Result<usize> {
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for entry in self.list_entries()? {
+            let age = now.duration_since(entry.modified).unwrap_or(Duration::ZERO);
+            if age > max_age {
+                let _ = fs::remove_file(self.get_cache_path(&entry.hash));
+                let _ = fs::remove_file(self.get_meta_path(&entry.hash));
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
 }
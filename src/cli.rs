@@ -1,11 +1,20 @@
 /// Command-line interface
 /// Polished, user-friendly CLI using clap
 use crate::compiler::Compiler;
+use crate::config::ProjectConfig;
+use crate::directives::OverflowMode;
+use crate::ir_lowering::IRLowering;
+use crate::jit;
+use crate::optimizer::IROptimizer;
+use crate::parser::AdrenalineParser;
 use crate::repl::Repl;
+use crate::type_inference::TypeInference;
 use crate::diagnostics::*;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -22,9 +31,30 @@ pub struct Cli {
     #[arg(global = true, short, long, default_value = ".")]
     project: PathBuf,
 
-    /// Verbose output
+    /// Verbose output - repeat for more detail (`-v` for info-level log
+    /// messages, `-vv` for debug)
+    #[arg(global = true, short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Suppress progress chatter (`print_info`); errors and the final
+    /// result still print
     #[arg(global = true, short, long)]
-    verbose: bool,
+    quiet: bool,
+
+    /// Output format for build/check/profile/bench
+    #[arg(global = true, long, value_enum, default_value_t = OutputFormatFlag::Text)]
+    format: OutputFormatFlag,
+}
+
+/// `--format`: `text` keeps the existing emoji `print_*` lines, `json`
+/// makes `build`/`check`/`profile`/`bench` emit one structured
+/// `emit_json` event apiece instead, for editors and CI pipelines to
+/// parse. See `diagnostics::json_mode`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum OutputFormatFlag {
+    #[default]
+    Text,
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
@@ -32,27 +62,207 @@ enum Commands {
     /// Build Python to native binary
     #[command(about = "Compile Python to optimized native binary")]
     Build {
-        /// Python source file
-        file: PathBuf,
+        /// Python source file(s), or `-` to read the script from stdin.
+        /// Also accepts a directory (built non-recursively for every `.py`
+        /// file directly inside it) or a glob like `src/*.py`, so several
+        /// files can be built in one invocation without a shell loop.
+        #[arg(required = true, num_args = 1..)]
+        files: Vec<PathBuf>,
 
-        /// Output binary name
+        /// Output stem when `file` is `-`, since there's no source file name
+        /// to derive one from [default: stdin]
+        #[arg(long, default_value = "stdin")]
+        name: String,
+
+        /// Output directory for the compiled binary [default: alongside the
+        /// source file, or `adrenaline.toml`'s `output_dir`]
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Optimization level (0-3)
-        #[arg(short, long, default_value = "3")]
-        opt_level: u8,
+        /// Optimization level (0-3) [default: 3, or `adrenaline.toml`'s
+        /// `opt_level`]
+        #[arg(short, long)]
+        opt_level: Option<u8>,
+
+        /// Print why each optimization pass did or didn't fire
+        #[arg(long)]
+        remarks: bool,
+
+        /// Print remarks as JSON instead of text
+        #[arg(long, requires = "remarks")]
+        remarks_json: bool,
+
+        /// Allow reassociation and FMA-style folding of arithmetic, trading
+        /// bit-exact results for throughput
+        #[arg(long)]
+        fast_math: bool,
+
+        /// Build a PyO3 extension module (a `.so` importable from Python)
+        /// instead of a standalone binary
+        #[arg(long)]
+        lib_python: bool,
+
+        /// Build a `staticlib`/`rlib` (plus a generated C header for the
+        /// subset of functions with a C-representable signature) instead of
+        /// a standalone binary, so the compiled kernel can be linked into an
+        /// existing Rust or C++ application
+        #[arg(long, conflicts_with = "lib_python")]
+        lib: bool,
+
+        /// Compile as usual (`rust`, the default; also written next to the
+        /// binary for inspection) or embed a kernel as portable C where no
+        /// Rust toolchain is available (only a narrow integer/bool subset of
+        /// the IR is supported) - or, for `ir`/`asm`/`llvm-ir`, skip the
+        /// final binary and print/write that intermediate artifact instead,
+        /// honoring `--output -` for stdout.
+        #[arg(long, value_enum, default_value = "rust")]
+        emit: EmitTarget,
+
+        /// Integer overflow policy for plain `+`/`-`/`*`. Defaults to
+        /// `checked` at `-O0` and `wrapping` otherwise; a
+        /// `#adrenaline:overflow-*` directive on a function overrides this.
+        #[arg(long, value_enum)]
+        overflow: Option<OverflowFlag>,
+
+        /// Rebuild on every save instead of compiling once and exiting -
+        /// watches `file`'s directory for `.py` changes.
+        #[arg(long)]
+        watch: bool,
+
+        /// Cross-compile for a different Rust target triple (e.g.
+        /// `x86_64-pc-windows-gnu`), forwarded to `cargo build --target`
+        /// [default: none, or `adrenaline.toml`'s `target`]. The triple is
+        /// checked against `rustup target list --installed` first, and
+        /// folded into the output binary's name so builds for different
+        /// targets don't overwrite each other.
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Cross-compile against the musl target triple for this host
+        /// (e.g. `x86_64-unknown-linux-musl`) and strip the resulting
+        /// binary, for a dependency-free executable that runs unmodified in
+        /// a minimal container - shorthand for `--target
+        /// <arch>-unknown-linux-musl` plus a `strip` pass, so most users
+        /// don't need to know the triple by name.
+        #[arg(long = "static", conflicts_with = "target")]
+        static_binary: bool,
+
+        /// Which generated `[profile.*]` (`Cargo.toml`'s `dev`/`release`,
+        /// see `compiler::BuildProfileSettings`) actually builds - `dev`
+        /// for fast unoptimized native builds while iterating, `release`
+        /// for the fully optimized one. Each can be further customized
+        /// (`lto`, `codegen_units`, `panic_abort`, `debug`) with a
+        /// `[profile.dev]`/`[profile.release]` table in `adrenaline.toml`.
+        /// [default: `dev` at `-O0`, `release` otherwise]
+        #[arg(long, value_enum)]
+        profile: Option<BuildProfileFlag>,
+
+        /// Write a `<binary>.manifest.json` alongside the binary recording
+        /// the source hash, compiler version, `#adrenaline:*` directives
+        /// seen, optimization decisions, build duration and output path -
+        /// for reproducibility audits and CI artifact tracking.
+        #[arg(long)]
+        manifest: bool,
+
+        /// Pin the toolchain flags that would otherwise let two builds of
+        /// the same source disagree (embedded build-dir paths, build
+        /// timestamps) and rebuild once more to confirm the output binary
+        /// actually came out byte-identical, so it can be verified against
+        /// source.
+        #[arg(long)]
+        deterministic: bool,
+
+        /// Ceiling on a hot function's own instruction count while its loop
+        /// passes are still iterating - crossing it (or `--opt-timeout`)
+        /// backs that function off to `Basic` instead of letting nested
+        /// loop unrolling grow it without bound [default: 50000]
+        #[arg(long)]
+        opt_budget: Option<usize>,
+
+        /// Wall-clock ceiling, in seconds, on the same loop passes -
+        /// crossing it (or `--opt-budget`) backs that function off to
+        /// `Basic` instead of letting `rustc` hang on the result
+        /// [default: 10]
+        #[arg(long)]
+        opt_timeout: Option<u64>,
+
+        /// Run `cc`/`rustc`/`cargo` with a scrubbed environment (so a
+        /// dependency's build script can't read secrets out of the
+        /// caller's own env) and, on the `cargo` path, `--offline --locked`
+        /// against a lockfile pinned the first time it succeeds (so the
+        /// build can't silently pull in a new dependency from the
+        /// network). This is environment and dependency hygiene, not
+        /// process/filesystem/OS-level isolation - a malicious `build.rs`
+        /// or proc-macro already in the pinned lockfile can still read the
+        /// filesystem, spawn subprocesses, or make arbitrary syscalls.
+        #[arg(long)]
+        sandbox: bool,
+
+        /// Inject call counters and timers into every generated function
+        /// (via `adrenaline_runtime::profile_function!`) and write a
+        /// per-function profile - `adrenaline_profile.json` next to where
+        /// the binary runs, or `$ADRENALINE_PROFILE_PATH` - once the
+        /// program exits normally, instead of `adrenaline profile`'s
+        /// coarser whole-run timing.
+        #[arg(long)]
+        profile_instrument: bool,
+
+        /// Feed a profile written by a `--profile-instrument` run (or
+        /// `adrenaline profile`'s `Profiler::save_to_file`) back into this
+        /// build - functions it names as hot get promoted to `Aggressive`/
+        /// `Extreme` optimization (see `IROptimizer::apply_profile`)
+        /// instead of whatever `--opt-level` would have picked uniformly.
+        #[arg(long)]
+        profile_use: Option<PathBuf>,
+
+        /// Additionally install an instrumented global allocator that
+        /// attributes allocation counts/bytes to whichever function made
+        /// them, reported alongside `--profile-instrument`'s call counts and
+        /// timings - allocation churn in a hot loop is a common reason
+        /// compiled code isn't as fast as expected. Implies
+        /// `--profile-instrument`.
+        #[arg(long)]
+        profile_alloc: bool,
+
+        /// Additionally render every `IRInstruction::LineMarker` as a real
+        /// hit-count call, so the compiled binary writes a per-Python-line
+        /// report (`adrenaline_line_profile.json`, or
+        /// `$ADRENALINE_LINE_PROFILE_PATH`) alongside any
+        /// `--profile-instrument`/`--profile-alloc` per-function one. See
+        /// `adrenaline profile --annotate` for a human-readable view of it.
+        #[arg(long)]
+        profile_lines: bool,
+
+        /// Additionally read cache-miss, branch-miss, and instruction counts
+        /// from `perf_event_open` around each call, reported alongside
+        /// `--profile-instrument`'s call counts and timings - useful for
+        /// telling whether a SIMD/tiling directive actually changed the
+        /// machine work a hot function does, not just its wall time. Linux
+        /// only; a no-op elsewhere. Implies `--profile-instrument`.
+        #[arg(long)]
+        profile_hwcounters: bool,
     },
 
     /// Run compiled binary
     #[command(about = "Execute a compiled binary")]
     Run {
-        /// Binary or Python file to run
+        /// Binary or Python file to run, or `-` to read a Python script from
+        /// stdin
         file: PathBuf,
 
+        /// Output stem when `file` is `-`, since there's no source file name
+        /// to derive one from [default: stdin]
+        #[arg(long, default_value = "stdin")]
+        name: String,
+
         /// Arguments to pass
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
+
+        /// Try the in-process Cranelift JIT fast path first, falling back to
+        /// a full cargo build for anything it doesn't support
+        #[arg(long)]
+        jit: bool,
     },
 
     /// Interactive Python REPL
@@ -62,12 +272,80 @@ enum Commands {
     /// Profile compiled code
     #[command(about = "Show profiling information")]
     Profile {
-        /// Binary to profile
-        file: PathBuf,
+        /// Python file to build and profile - required unless `--compare`
+        /// is given
+        file: Option<PathBuf>,
 
         /// Number of iterations
         #[arg(short, long, default_value = "1000")]
         iterations: usize,
+
+        /// Replace the iterations loop with a single run driven by a system
+        /// sampling profiler (`perf` on Linux, `dtrace` on macOS) instead of
+        /// requiring the binary to be rebuilt with `--profile-instrument`.
+        /// See `Compiler::sample_profile`.
+        #[arg(long)]
+        sample: bool,
+
+        /// Sample the binary's running stacks with `perf` and render them as
+        /// a flame graph SVG at this path - skipped, with a warning, if
+        /// `perf` isn't on `PATH`. See `Compiler::record_flamegraph`.
+        #[arg(long)]
+        flamegraph: Option<PathBuf>,
+
+        /// Rebuild with `--profile-lines`, run once, and print the Python
+        /// source annotated with each line's hit count - like
+        /// `line_profiler`, but for the compiled code. See
+        /// `Compiler::annotate`.
+        #[arg(long)]
+        annotate: bool,
+
+        /// Render the report in this format instead of the default table,
+        /// for feeding it to external tooling - see
+        /// `Compiler::render_profile_report_as`.
+        #[arg(long, value_enum)]
+        output: Option<ProfileOutputFormat>,
+
+        /// Compare two reports saved by `--output json`/`Profiler::save_to_file`
+        /// instead of profiling `file`, printing per-function deltas - see
+        /// `Compiler::compare_profiles`. Useful as a CI performance gate after
+        /// a code or compiler change.
+        #[arg(long, num_args = 2, value_names = ["OLD", "NEW"])]
+        compare: Option<Vec<PathBuf>>,
+
+        /// Fraction of regression in a function's total time (e.g. `0.1` for
+        /// 10%) beyond which `--compare` reports a failure and exits non-zero.
+        #[arg(long, default_value = "0.1", requires = "compare")]
+        threshold: f64,
+
+        /// Rebuild with `--profile-instrument`, launch the binary in the
+        /// background instead of waiting for it to exit, and redraw an
+        /// updating top-like table of hottest functions from its live
+        /// counters every half second - for a long-running simulation where
+        /// waiting for exit to see a report isn't practical. Attaches over a
+        /// Unix domain socket, so this is Unix only. See
+        /// `Compiler::live_profile`.
+        #[arg(long)]
+        live: bool,
+    },
+
+    /// Profile a build, then rebuild with the hot functions it finds
+    /// promoted to a higher optimization level
+    #[command(about = "Profile, then recompile hot functions at a higher optimization level")]
+    Optimize {
+        /// Python file to build and optimize
+        file: PathBuf,
+    },
+
+    /// Compare the compiled binary against the system `python3` interpreter
+    #[command(about = "Benchmark compiled code against CPython")]
+    Bench {
+        /// Python file to build and benchmark
+        file: PathBuf,
+
+        /// Timed runs per interpreter, averaged
+        #[arg(short, long, default_value = "3")]
+        iterations: usize,
     },
 
     /// Clear compilation cache
@@ -77,6 +355,19 @@ enum Commands {
         action: CacheAction,
     },
 
+    /// Remove stale build directories
+    #[command(about = "Remove ~/.adrenaline build_*/cbuild_*/lib_*/pylib_*/emit_* directories, distinct from `cache`")]
+    Clean {
+        /// Remove every build directory regardless of age
+        #[arg(long)]
+        all: bool,
+
+        /// Remove build directories last modified more than this many days
+        /// ago [ignored with --all]
+        #[arg(long, default_value = "30")]
+        older_than: u64,
+    },
+
     /// Show compiler diagnostics
     #[command(about = "Check file for issues")]
     Check {
@@ -84,6 +375,62 @@ enum Commands {
         file: PathBuf,
     },
 
+    /// Translate to Rust without building
+    #[command(about = "Print the generated Rust for a file without invoking cargo")]
+    Emit {
+        /// Python file to translate
+        file: PathBuf,
+
+        /// Where to write the generated Rust [default: stdout]. `-` also
+        /// means stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Optimization level (0-3) [default: 3, or `adrenaline.toml`'s
+        /// `opt_level`]
+        #[arg(short = 'O', long)]
+        opt_level: Option<u8>,
+    },
+
+    /// Compile and run test_* functions
+    #[command(about = "Discover, compile, and run test_* functions natively")]
+    Test {
+        /// Python file to test
+        file: PathBuf,
+
+        /// Optimization level (0-3) [default: 3, or `adrenaline.toml`'s
+        /// `opt_level`]
+        #[arg(short = 'O', long)]
+        opt_level: Option<u8>,
+    },
+
+    /// Diff CPython vs compiled-binary behavior
+    #[command(about = "Run under python3 and the compiled binary, diffing stdout/exit code")]
+    Diff {
+        /// Python source file
+        file: PathBuf,
+
+        /// Arguments forwarded to both python3 and the compiled binary
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+
+        /// Optimization level (0-3) [default: 3, or `adrenaline.toml`'s
+        /// `opt_level`]
+        #[arg(short = 'O', long)]
+        opt_level: Option<u8>,
+    },
+
+    /// Check the local toolchain and environment
+    #[command(about = "Verify cargo/rustc/python3, targets, and ~/.adrenaline are set up correctly")]
+    Doctor,
+
+    /// Interactively suggest optimization directives
+    #[command(about = "Profile a file and interactively suggest #adrenaline:* directives")]
+    Advise {
+        /// Python file to analyze
+        file: PathBuf,
+    },
+
     /// Show help and examples
     #[command(about = "Display help information")]
     Help {
@@ -92,6 +439,94 @@ enum Commands {
     },
 }
 
+/// Backend (or intermediate artifact) targeted by `Build`'s `--emit` flag.
+/// `Rust`/`C` pick a full build the way they always have; `Ir`/`Asm`/`LlvmIr`
+/// stop short of a binary and hand back that artifact's text instead - see
+/// `Compiler::emit_artifact`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum EmitTarget {
+    Rust,
+    C,
+    Ir,
+    Asm,
+    LlvmIr,
+}
+
+impl EmitTarget {
+    /// `Some` for the three intermediate-artifact variants; `None` for
+    /// `Rust`/`C`, which stay on the existing full-build path in `build`.
+    fn as_artifact(self) -> Option<crate::compiler::Artifact> {
+        match self {
+            EmitTarget::Rust | EmitTarget::C => None,
+            EmitTarget::Ir => Some(crate::compiler::Artifact::Ir),
+            EmitTarget::Asm => Some(crate::compiler::Artifact::Asm),
+            EmitTarget::LlvmIr => Some(crate::compiler::Artifact::LlvmIr),
+        }
+    }
+}
+
+/// Policy targeted by `Build`'s `--overflow` flag - mirrors
+/// `directives::OverflowMode`, just spelled the way clap likes to name
+/// values (`wrapping`/`checked`/`bigint`).
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OverflowFlag {
+    Wrapping,
+    Checked,
+    Bigint,
+}
+
+impl From<OverflowFlag> for OverflowMode {
+    fn from(flag: OverflowFlag) -> Self {
+        match flag {
+            OverflowFlag::Wrapping => OverflowMode::Wrap,
+            OverflowFlag::Checked => OverflowMode::Checked,
+            OverflowFlag::Bigint => OverflowMode::Bigint,
+        }
+    }
+}
+
+/// `Build`'s `--profile` flag - which of the generated crate's two
+/// `[profile.*]` sections (see `compiler::BuildProfileSettings`) actually
+/// builds.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum BuildProfileFlag {
+    Dev,
+    Release,
+}
+
+impl BuildProfileFlag {
+    fn as_str(self) -> &'static str {
+        match self {
+            BuildProfileFlag::Dev => "dev",
+            BuildProfileFlag::Release => "release",
+        }
+    }
+}
+
+/// `Profile`'s `--output` flag - an alternative rendering of the same
+/// `Vec<ProfileData>` `Compiler::render_profile_report` otherwise prints as
+/// a table, so a report can be ingested by external tooling: `Csv` for a
+/// spreadsheet or dashboard, `Callgrind` for kcachegrind/speedscope, `Json`
+/// for anything else (the default table already has a JSON form via the
+/// global `--format json`, but this covers `--output json` without also
+/// switching every other command's output).
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ProfileOutputFormat {
+    Json,
+    Csv,
+    Callgrind,
+}
+
+impl From<ProfileOutputFormat> for crate::profiler::ReportFormat {
+    fn from(flag: ProfileOutputFormat) -> Self {
+        match flag {
+            ProfileOutputFormat::Json => crate::profiler::ReportFormat::Json,
+            ProfileOutputFormat::Csv => crate::profiler::ReportFormat::Csv,
+            ProfileOutputFormat::Callgrind => crate::profiler::ReportFormat::Callgrind,
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 enum CacheAction {
     /// Clear all cached compilations
@@ -101,6 +536,36 @@ enum CacheAction {
     /// Show cache size
     #[command(about = "Display cache directory size")]
     Size,
+
+    /// List cached compilations
+    #[command(about = "Show cache entries with source file, age, and size")]
+    List,
+
+    /// Remove stale cached compilations
+    #[command(about = "Remove cache entries older than a threshold")]
+    Prune {
+        /// Remove entries last modified more than this many days ago
+        #[arg(long, default_value = "30")]
+        older_than: u64,
+    },
+
+    /// Show cache effectiveness
+    #[command(about = "Show cache hits, misses, hit rate, bytes served, and estimated time saved")]
+    Stats,
+
+    /// Pack the cache directory into a single archive
+    #[command(about = "Export the cache to a zstd-compressed tarball, e.g. for CI to persist between jobs")]
+    Export {
+        /// Destination archive path, e.g. `cache.tar.zst`
+        path: PathBuf,
+    },
+
+    /// Restore a cache directory from an archive made with `cache export`
+    #[command(about = "Import a cache archive made with `cache export`, on top of the current cache")]
+    Import {
+        /// Archive path, e.g. `cache.tar.zst`
+        path: PathBuf,
+    },
 }
 
 impl Cli {
@@ -109,14 +574,106 @@ impl Cli {
     }
 
     pub fn execute(&self) -> Result<()> {
-        if self.verbose {
-            log::set_max_level(log::LevelFilter::Debug);
-        }
+        let level = match (self.quiet, self.verbose) {
+            (true, _) => log::LevelFilter::Error,
+            (false, 0) => log::LevelFilter::Warn,
+            (false, 1) => log::LevelFilter::Info,
+            (false, _) => log::LevelFilter::Debug,
+        };
+        log::set_max_level(level);
+        set_quiet_mode(self.quiet);
+        set_json_mode(self.format == OutputFormatFlag::Json);
 
         match &self.command {
-            Some(Commands::Build { file, .. }) => self.build(file),
+            Some(Commands::Build {
+                files,
+                name,
+                output,
+                opt_level,
+                remarks,
+                remarks_json,
+                fast_math,
+                lib_python,
+                lib,
+                emit,
+                overflow,
+                watch,
+                target,
+                static_binary,
+                profile,
+                manifest,
+                deterministic,
+                opt_budget,
+                opt_timeout,
+                sandbox,
+                profile_instrument,
+                profile_use,
+                profile_alloc,
+                profile_lines,
+                profile_hwcounters,
+            }) => {
+                if *watch {
+                    if files.len() != 1 || files[0].as_os_str() == "-" {
+                        print_error("--watch only supports a single file, not stdin or multiple inputs");
+                        return Err(anyhow::anyhow!("--watch only supports a single file"));
+                    }
+                    self.watch(
+                        &files[0],
+                        name,
+                        output.clone(),
+                        *opt_level,
+                        *remarks,
+                        *remarks_json,
+                        *fast_math,
+                        *lib_python,
+                        *lib,
+                        *emit,
+                        *overflow,
+                        target.clone(),
+                        *static_binary,
+                        *profile,
+                        *manifest,
+                        *deterministic,
+                        *opt_budget,
+                        *opt_timeout,
+                        *sandbox,
+                        *profile_instrument,
+                        profile_use.clone(),
+                        *profile_alloc,
+                        *profile_lines,
+                        *profile_hwcounters,
+                    )
+                } else {
+                    self.build_many(
+                        files,
+                        name,
+                        output.clone(),
+                        *opt_level,
+                        *remarks,
+                        *remarks_json,
+                        *fast_math,
+                        *lib_python,
+                        *lib,
+                        *emit,
+                        *overflow,
+                        target.clone(),
+                        *static_binary,
+                        *profile,
+                        *manifest,
+                        *deterministic,
+                        *opt_budget,
+                        *opt_timeout,
+                        *sandbox,
+                        *profile_instrument,
+                        profile_use.clone(),
+                        *profile_alloc,
+                        *profile_lines,
+                        *profile_hwcounters,
+                    )
+                }
+            }
 
-            Some(Commands::Run { file, args }) => self.run(file, args),
+            Some(Commands::Run { file, name, args, jit }) => self.run(file, name, args, *jit),
 
             Some(Commands::Repl) | None => {
                 // Start REPL if no command or explicit repl
@@ -124,12 +681,40 @@ impl Cli {
                 repl.run()
             }
 
-            Some(Commands::Profile {
-                file: _,
-                iterations: _,
-            }) => {
-                print_info("Profiling support coming soon");
-                Ok(())
+            Some(Commands::Profile { file, iterations, sample, flamegraph, annotate, output, compare, threshold, live }) => {
+                if let Some(paths) = compare {
+                    return Compiler::compare_profiles(&paths[0], &paths[1], *threshold);
+                }
+                let file = file.as_ref().ok_or_else(|| {
+                    anyhow::anyhow!("FILE is required unless --compare is given")
+                })?;
+                if !file.exists() {
+                    print_error(&format!("File not found: {}", file.display()));
+                    return Err(anyhow::anyhow!("File not found"));
+                }
+                let config = ProjectConfig::load(&self.project)?;
+                let opt_level = config.opt_level.unwrap_or(3);
+                let mut compiler = Compiler::new(&self.project)?;
+                compiler.set_project_config(config);
+                if *annotate {
+                    return compiler.annotate(file, opt_level);
+                }
+                if *live {
+                    return compiler.live_profile(file, opt_level);
+                }
+                compiler.profile(file, opt_level, *iterations, *sample, flamegraph.as_deref(), output.map(Into::into))
+            }
+
+            Some(Commands::Bench { file, iterations }) => {
+                if !file.exists() {
+                    print_error(&format!("File not found: {}", file.display()));
+                    return Err(anyhow::anyhow!("File not found"));
+                }
+                let config = ProjectConfig::load(&self.project)?;
+                let opt_level = config.opt_level.unwrap_or(3);
+                let mut compiler = Compiler::new(&self.project)?;
+                compiler.set_project_config(config);
+                compiler.bench(file, opt_level, *iterations)
             }
 
             Some(Commands::Cache { action }) => match action {
@@ -139,16 +724,126 @@ impl Cli {
                 }
                 CacheAction::Size => {
                     let compiler = Compiler::new(&self.project)?;
-                    compiler.clear_cache()
+                    compiler.cache_size()
+                }
+                CacheAction::List => {
+                    let compiler = Compiler::new(&self.project)?;
+                    compiler.cache_list()
+                }
+                CacheAction::Prune { older_than } => {
+                    let compiler = Compiler::new(&self.project)?;
+                    compiler.cache_prune(std::time::Duration::from_secs(older_than * 86400))
+                }
+                CacheAction::Stats => {
+                    let compiler = Compiler::new(&self.project)?;
+                    compiler.cache_stats()
+                }
+                CacheAction::Export { path } => {
+                    let compiler = Compiler::new(&self.project)?;
+                    compiler.cache_export(path)
+                }
+                CacheAction::Import { path } => {
+                    let compiler = Compiler::new(&self.project)?;
+                    compiler.cache_import(path)
                 }
             },
 
+            Some(Commands::Clean { all, older_than }) => {
+                let compiler = Compiler::new(&self.project)?;
+                compiler.clean(*all, std::time::Duration::from_secs(older_than * 86400))
+            }
+
             Some(Commands::Check { file }) => {
-                print_info(&format!("Checking {}...", file.display()));
-                print_success("No issues found");
+                if !file.exists() {
+                    print_error(&format!("File not found: {}", file.display()));
+                    return Err(anyhow::anyhow!("File not found"));
+                }
+                let mut compiler = Compiler::new(&self.project)?;
+                compiler.check(file)
+            }
+
+            Some(Commands::Emit { file, output, opt_level }) => {
+                if !file.exists() {
+                    print_error(&format!("File not found: {}", file.display()));
+                    return Err(anyhow::anyhow!("File not found"));
+                }
+                let config = ProjectConfig::load(&self.project)?;
+                let opt_level = opt_level.or(config.opt_level).unwrap_or(3);
+                let mut compiler = Compiler::new(&self.project)?;
+                compiler.set_project_config(config);
+                let rust_code = compiler.emit(file, opt_level)?;
+                match output.as_deref() {
+                    Some(path) if path != Path::new("-") => {
+                        fs::write(path, &rust_code)?;
+                        print_success(&format!("Wrote {}", path.display()));
+                    }
+                    _ => println!("{rust_code}"),
+                }
                 Ok(())
             }
 
+            Some(Commands::Test { file, opt_level }) => {
+                if !file.exists() {
+                    print_error(&format!("File not found: {}", file.display()));
+                    return Err(anyhow::anyhow!("File not found"));
+                }
+                let config = ProjectConfig::load(&self.project)?;
+                let opt_level = opt_level.or(config.opt_level).unwrap_or(3);
+                let mut compiler = Compiler::new(&self.project)?;
+                compiler.set_project_config(config);
+                compiler.test(file, opt_level)
+            }
+
+            Some(Commands::Diff { file, args, opt_level }) => {
+                if !file.exists() {
+                    print_error(&format!("File not found: {}", file.display()));
+                    return Err(anyhow::anyhow!("File not found"));
+                }
+                let config = ProjectConfig::load(&self.project)?;
+                let opt_level = opt_level.or(config.opt_level).unwrap_or(3);
+                let mut compiler = Compiler::new(&self.project)?;
+                compiler.set_project_config(config);
+
+                use std::io::{IsTerminal, Read as _};
+                let stdin_data = if std::io::stdin().is_terminal() {
+                    Vec::new()
+                } else {
+                    let mut buf = Vec::new();
+                    std::io::stdin().read_to_end(&mut buf)?;
+                    buf
+                };
+
+                compiler.diff(file, opt_level, args, &stdin_data)
+            }
+
+            Some(Commands::Doctor) => {
+                let compiler = Compiler::new(&self.project)?;
+                compiler.doctor()
+            }
+
+            Some(Commands::Advise { file }) => {
+                if !file.exists() {
+                    print_error(&format!("File not found: {}", file.display()));
+                    return Err(anyhow::anyhow!("File not found"));
+                }
+                let config = ProjectConfig::load(&self.project)?;
+                let mut compiler = Compiler::new(&self.project)?;
+                compiler.set_project_config(config);
+                compiler.advise(file)
+            }
+
+            Some(Commands::Optimize { file }) => {
+                if !file.exists() {
+                    print_error(&format!("File not found: {}", file.display()));
+                    return Err(anyhow::anyhow!("File not found"));
+                }
+                let config = ProjectConfig::load(&self.project)?;
+                let opt_level = config.opt_level.unwrap_or(3);
+                let mut compiler = Compiler::new(&self.project)?;
+                compiler.set_project_config(config);
+                compiler.optimize(file, opt_level)
+            }
+
             Some(Commands::Help { topic }) => {
                 self.show_help(topic.as_deref());
                 Ok(())
@@ -156,25 +851,512 @@ impl Cli {
         }
     }
 
-    fn build(&self, file: &PathBuf) -> Result<()> {
+    /// `adrenaline build`'s entry point for its positional `files`: expands
+    /// directories and glob patterns (see `expand_build_inputs`) and, for
+    /// the common single-file case, hands off to `build` unchanged so its
+    /// existing per-file behavior (including stdin `-`) is untouched. Only
+    /// once expansion actually produces more than one file does it switch
+    /// to building each in turn and printing a final success/failure count,
+    /// instead of requiring a shell loop.
+    #[allow(clippy::too_many_arguments)]
+    fn build_many(
+        &self,
+        files: &[PathBuf],
+        name: &str,
+        output: Option<PathBuf>,
+        opt_level: Option<u8>,
+        remarks: bool,
+        remarks_json: bool,
+        fast_math: bool,
+        lib_python: bool,
+        lib: bool,
+        emit: EmitTarget,
+        overflow: Option<OverflowFlag>,
+        target: Option<String>,
+        static_binary: bool,
+        profile: Option<BuildProfileFlag>,
+        manifest: bool,
+        deterministic: bool,
+        opt_budget: Option<usize>,
+        opt_timeout: Option<u64>,
+        sandbox: bool,
+        profile_instrument: bool,
+        profile_use: Option<PathBuf>,
+        profile_alloc: bool,
+        profile_lines: bool,
+        profile_hwcounters: bool,
+    ) -> Result<()> {
+        let expanded = Self::expand_build_inputs(files)?;
+
+        if expanded.len() == 1 {
+            return self.build(
+                &expanded[0], name, output, opt_level, remarks, remarks_json, fast_math,
+                lib_python, lib, emit, overflow, target, static_binary, profile, manifest,
+                deterministic, opt_budget, opt_timeout, sandbox, profile_instrument, profile_use,
+                profile_alloc, profile_lines, profile_hwcounters,
+            );
+        }
+
+        if expanded.iter().any(|f| f.as_os_str() == "-") {
+            print_error("stdin ('-') can't be combined with other build inputs");
+            return Err(anyhow::anyhow!("stdin ('-') can't be combined with other build inputs"));
+        }
+
+        let mut succeeded = 0usize;
+        let mut failed = Vec::new();
+        for f in &expanded {
+            let result = self.build(
+                f, name, output.clone(), opt_level, remarks, remarks_json, fast_math,
+                lib_python, lib, emit, overflow, target.clone(), static_binary, profile,
+                manifest, deterministic, opt_budget, opt_timeout, sandbox, profile_instrument,
+                profile_use.clone(), profile_alloc, profile_lines, profile_hwcounters,
+            );
+            match result {
+                Ok(()) => succeeded += 1,
+                Err(e) => {
+                    print_error(&format!("{}: {e}", f.display()));
+                    failed.push(f.clone());
+                }
+            }
+        }
+
+        if json_mode() {
+            emit_json(&serde_json::json!({
+                "event": "build_summary",
+                "total": expanded.len(),
+                "succeeded": succeeded,
+                "failed": failed.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+            }));
+        } else if failed.is_empty() {
+            print_success(&format!("Built {succeeded}/{} files successfully", expanded.len()));
+        } else {
+            print_error(&format!(
+                "Built {succeeded}/{} files successfully ({} failed: {})",
+                expanded.len(),
+                failed.len(),
+                failed.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "),
+            ));
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("{} of {} builds failed", failed.len(), expanded.len()))
+        }
+    }
+
+    /// Expands `build`'s positional `files` into concrete `.py` paths: a
+    /// directory is scanned (non-recursively, matching a single `build`
+    /// invocation's own one-file-one-output convention) for `.py` entries,
+    /// a pattern containing `*`/`?`/`[` is matched against its parent
+    /// directory with a regex translated from the glob (there's no
+    /// dedicated glob crate in this workspace) - anything else, including
+    /// the stdin sentinel `-`, passes through unchanged.
+    fn expand_build_inputs(files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let mut expanded = Vec::new();
+        for input in files {
+            if input.as_os_str() == "-" {
+                expanded.push(input.clone());
+            } else if input.is_dir() {
+                let mut entries: Vec<PathBuf> = fs::read_dir(input)?
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("py"))
+                    .collect();
+                entries.sort();
+                expanded.extend(entries);
+            } else if Self::is_glob_pattern(input) {
+                expanded.extend(Self::expand_glob(input)?);
+            } else {
+                expanded.push(input.clone());
+            }
+        }
+        Ok(expanded)
+    }
+
+    fn is_glob_pattern(path: &Path) -> bool {
+        path.to_string_lossy().contains(['*', '?', '['])
+    }
+
+    fn expand_glob(pattern: &Path) -> Result<Vec<PathBuf>> {
+        let dir = pattern.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+        let name_pattern = pattern.file_name().and_then(|s| s.to_str()).unwrap_or("*");
+        let regex_str = format!(
+            "^{}$",
+            regex::escape(name_pattern).replace(r"\*", ".*").replace(r"\?", ".")
+        );
+        let re = regex::Regex::new(&regex_str)?;
+
+        let mut matches: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|n| re.is_match(n))
+                    .unwrap_or(false)
+            })
+            .collect();
+        matches.sort();
+
+        if matches.is_empty() {
+            print_warning(&format!("No files matched {}", pattern.display()));
+        }
+        Ok(matches)
+    }
+
+    /// `adrenaline build -`: reads the script from stdin into `<name>.py` in
+    /// the current directory so the rest of `build` (and its output, which
+    /// lands next to the source unless `--output` says otherwise) works
+    /// unmodified, then removes that temporary source file once the real
+    /// build artifact has been produced.
+    #[allow(clippy::too_many_arguments)]
+    fn build(
+        &self,
+        file: &Path,
+        name: &str,
+        output: Option<PathBuf>,
+        opt_level: Option<u8>,
+        remarks: bool,
+        remarks_json: bool,
+        fast_math: bool,
+        lib_python: bool,
+        lib: bool,
+        emit: EmitTarget,
+        overflow: Option<OverflowFlag>,
+        target: Option<String>,
+        static_binary: bool,
+        profile: Option<BuildProfileFlag>,
+        manifest: bool,
+        deterministic: bool,
+        opt_budget: Option<usize>,
+        opt_timeout: Option<u64>,
+        sandbox: bool,
+        profile_instrument: bool,
+        profile_use: Option<PathBuf>,
+        profile_alloc: bool,
+        profile_lines: bool,
+        profile_hwcounters: bool,
+    ) -> Result<()> {
+        if file.as_os_str() == "-" {
+            let source_file = Self::materialize_stdin_source(Path::new("."), name)?;
+            let result = self.build_impl(
+                &source_file, output, opt_level, remarks, remarks_json, fast_math, lib_python,
+                lib, emit, overflow, target, static_binary, profile, manifest, deterministic,
+                opt_budget, opt_timeout, sandbox, profile_instrument, profile_use.clone(),
+                profile_alloc, profile_lines, profile_hwcounters,
+            );
+            let _ = fs::remove_file(&source_file);
+            return result;
+        }
+        self.build_impl(
+            file, output, opt_level, remarks, remarks_json, fast_math, lib_python, lib, emit,
+            overflow, target, static_binary, profile, manifest, deterministic, opt_budget,
+            opt_timeout, sandbox, profile_instrument, profile_use, profile_alloc, profile_lines,
+            profile_hwcounters,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_impl(
+        &self,
+        file: &Path,
+        output: Option<PathBuf>,
+        opt_level: Option<u8>,
+        remarks: bool,
+        remarks_json: bool,
+        fast_math: bool,
+        lib_python: bool,
+        lib: bool,
+        emit: EmitTarget,
+        overflow: Option<OverflowFlag>,
+        target: Option<String>,
+        static_binary: bool,
+        profile: Option<BuildProfileFlag>,
+        manifest: bool,
+        deterministic: bool,
+        opt_budget: Option<usize>,
+        opt_timeout: Option<u64>,
+        sandbox: bool,
+        profile_instrument: bool,
+        profile_use: Option<PathBuf>,
+        profile_alloc: bool,
+        profile_lines: bool,
+        profile_hwcounters: bool,
+    ) -> Result<()> {
         if !file.exists() {
             print_error(&format!("File not found: {}", file.display()));
             return Err(anyhow::anyhow!("File not found"));
         }
 
+        let start = std::time::Instant::now();
+        let target = if static_binary { Some(Self::musl_target_triple()) } else { target };
+
+        let mut config = ProjectConfig::load(&self.project)?;
+        // `--output`/`--opt-level`/`--target` are explicit per-invocation
+        // overrides, so they win over whatever `adrenaline.toml` says.
+        if output.is_some() {
+            config.output_dir = output;
+        }
+        if target.is_some() {
+            config.target = target;
+        }
+        let opt_level = opt_level.or(config.opt_level).unwrap_or(3);
+        let artifact_dest = config.output_dir.clone();
+
         let mut compiler = Compiler::new(&self.project)?;
-        let _binary = compiler.compile(file)?;
+        compiler.set_project_config(config);
+        if fast_math {
+            compiler.enable_fast_math();
+        }
+        if let Some(overflow) = overflow {
+            compiler.set_overflow_mode(OverflowMode::from(overflow));
+        }
+        compiler.set_build_profile(profile.map(BuildProfileFlag::as_str).map(String::from));
+        if manifest {
+            compiler.enable_manifest();
+        }
+        if deterministic {
+            compiler.enable_deterministic();
+        }
+        if let Some(budget) = opt_budget {
+            compiler.set_opt_budget(budget);
+        }
+        if let Some(timeout) = opt_timeout {
+            compiler.set_opt_timeout(timeout);
+        }
+        if sandbox {
+            compiler.enable_sandbox();
+        }
+        if profile_instrument {
+            compiler.enable_profile_instrument();
+        }
+        if let Some(profile_use) = profile_use {
+            compiler.set_profile_use(profile_use);
+        }
+        if profile_alloc {
+            compiler.enable_profile_alloc();
+        }
+        if profile_lines {
+            compiler.enable_profile_lines();
+        }
+        if profile_hwcounters {
+            compiler.enable_profile_hwcounters();
+        }
+
+        if let Some(artifact) = emit.as_artifact() {
+            let text = compiler.emit_artifact(file, opt_level, artifact)?;
+            if remarks {
+                compiler.print_remarks(remarks_json)?;
+            }
+            let result = Self::write_artifact(artifact_dest.as_deref(), file, emit, &text);
+            if json_mode() {
+                emit_json(&serde_json::json!({
+                    "event": "build",
+                    "success": result.is_ok(),
+                    "duration_ms": start.elapsed().as_secs_f64() * 1000.0,
+                }));
+            }
+            return result;
+        }
+
+        let artifact_path = if lib_python {
+            compiler.compile_python_extension(file, opt_level)?
+        } else if lib {
+            compiler.compile_lib(file, opt_level)?
+        } else if emit == EmitTarget::C {
+            compiler.compile_c(file, opt_level)?
+        } else {
+            let binary = compiler.compile(file, opt_level)?;
+            if static_binary {
+                Self::strip_binary(&binary);
+            }
+            binary
+        };
+
+        if remarks {
+            compiler.print_remarks(remarks_json)?;
+        }
+
+        if json_mode() {
+            emit_json(&serde_json::json!({
+                "event": "build",
+                "success": true,
+                "artifact": artifact_path.display().to_string(),
+                "duration_ms": start.elapsed().as_secs_f64() * 1000.0,
+            }));
+        } else {
+            print_success("Build complete!");
+        }
+        Ok(())
+    }
+
+    /// Backs `build -`/`run -`: reads all of stdin and writes it out as
+    /// `<dir>/<name>.py` so the rest of the pipeline, which only knows how
+    /// to compile a real file, doesn't need a stdin-aware code path of its
+    /// own.
+    fn materialize_stdin_source(dir: &Path, name: &str) -> Result<PathBuf> {
+        use std::io::Read as _;
+        let mut source = String::new();
+        std::io::stdin().read_to_string(&mut source)?;
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{name}.py"));
+        fs::write(&path, source)?;
+        Ok(path)
+    }
+
+    /// The musl target triple for `--static` on this host - the common
+    /// `<arch>-unknown-linux-musl` shape covers every architecture Rust's
+    /// musl target actually ships for, so no per-arch table is needed.
+    fn musl_target_triple() -> String {
+        format!("{}-unknown-linux-musl", std::env::consts::ARCH)
+    }
+
+    /// Best-effort like `rustfmt` in `compiler.rs`: a missing `strip` on
+    /// `PATH` shouldn't fail a build that otherwise fully succeeded, just
+    /// leave the binary unstripped.
+    fn strip_binary(binary: &Path) {
+        let _ = Command::new("strip").arg(binary).output();
+    }
+
+    /// Prints `text` to stdout for `--output -`, otherwise writes
+    /// `<file's stem>.<ext>` (extension picked by `emit`) into `dest` if
+    /// given, else next to `file` itself - the same "alongside the source
+    /// unless told otherwise" default `build` uses for the binary.
+    fn write_artifact(dest: Option<&Path>, file: &Path, emit: EmitTarget, text: &str) -> Result<()> {
+        if dest == Some(Path::new("-")) {
+            println!("{text}");
+            return Ok(());
+        }
+
+        let ext = match emit {
+            EmitTarget::Ir => "ir.txt",
+            EmitTarget::Asm => "s",
+            EmitTarget::LlvmIr => "ll",
+            EmitTarget::Rust | EmitTarget::C => unreachable!("only called for artifact variants"),
+        };
+        let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+        let source_dir = file.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+        let dir = match dest {
+            Some(dir) => source_dir.join(dir),
+            None => source_dir.to_path_buf(),
+        };
+        fs::create_dir_all(&dir)?;
+        let artifact_path = dir.join(format!("{stem}.{ext}"));
+        fs::write(&artifact_path, text)?;
+        print_success(&format!("Wrote {}", artifact_path.display()));
+        Ok(())
+    }
+
+    /// `adrenaline build --watch`: builds once up front, then rebuilds every
+    /// time a `.py` file in `file`'s directory changes, printing a concise
+    /// success/error line per rebuild instead of exiting - a build error
+    /// here is reported the same way `build` already reports one (see
+    /// `Compiler::build_rust_project`) and doesn't stop the watch.
+    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
+    fn watch(
+        &self,
+        file: &Path,
+        name: &str,
+        output: Option<PathBuf>,
+        opt_level: Option<u8>,
+        remarks: bool,
+        remarks_json: bool,
+        fast_math: bool,
+        lib_python: bool,
+        lib: bool,
+        emit: EmitTarget,
+        overflow: Option<OverflowFlag>,
+        target: Option<String>,
+        static_binary: bool,
+        profile: Option<BuildProfileFlag>,
+        manifest: bool,
+        deterministic: bool,
+        opt_budget: Option<usize>,
+        opt_timeout: Option<u64>,
+        sandbox: bool,
+        profile_instrument: bool,
+        profile_use: Option<PathBuf>,
+        profile_alloc: bool,
+        profile_lines: bool,
+        profile_hwcounters: bool,
+    ) -> Result<()> {
+        use notify::{RecursiveMode, Watcher};
+
+        let rebuild = || {
+            if let Err(e) = self.build(file, name, output.clone(), opt_level, remarks, remarks_json, fast_math, lib_python, lib, emit, overflow, target.clone(), static_binary, profile, manifest, deterministic, opt_budget, opt_timeout, sandbox, profile_instrument, profile_use.clone(), profile_alloc, profile_lines, profile_hwcounters) {
+                print_error(&format!("{e}"));
+            }
+        };
+
+        print_info(&format!("Watching {} for changes (Ctrl+C to stop)...", file.display()));
+        rebuild();
+
+        let watch_dir = file.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(watch_dir, RecursiveMode::NonRecursive)?;
+
+        for event in rx {
+            let Ok(event) = event else { continue };
+            if !matches!(event.kind, notify::EventKind::Modify(_) | notify::EventKind::Create(_)) {
+                continue;
+            }
+            if !event.paths.iter().any(|p| p.extension().and_then(|s| s.to_str()) == Some("py")) {
+                continue;
+            }
+            print_info(&format!("Change detected, rebuilding {}...", file.display()));
+            rebuild();
+        }
 
-        print_success("Build complete!");
         Ok(())
     }
 
-    fn run(&self, file: &PathBuf, args: &Vec<String>) -> Result<()> {
+    fn run(&self, file: &PathBuf, name: &str, args: &[String], jit: bool) -> Result<()> {
+        let config = ProjectConfig::load(&self.project)?;
+        let opt_level = config.opt_level.unwrap_or(3);
         let mut compiler = Compiler::new(&self.project)?;
+        compiler.set_project_config(config);
+
+        // `run -`: there's no real source directory to litter, so the
+        // temporary source lives entirely under a scratch dir and the build
+        // itself is always ephemeral (see `Compiler::compile_ephemeral`),
+        // regardless of `--jit`.
+        if file.as_os_str() == "-" {
+            let scratch_dir = std::env::temp_dir().join(format!("adrenaline_stdin_{}", std::process::id()));
+            let source_file = Self::materialize_stdin_source(&scratch_dir, name)?;
+            let result = (|| -> Result<()> {
+                if jit {
+                    if let Some(result) = self.try_jit(&source_file) {
+                        print_success(&format!("Ran via JIT, result = {result}"));
+                        return Ok(());
+                    }
+                }
+                let binary = compiler.compile_ephemeral(&source_file, opt_level)?;
+                compiler.run(&binary, args)
+            })();
+            let _ = fs::remove_dir_all(&scratch_dir);
+            return result;
+        }
 
         if file.extension().and_then(|s| s.to_str()) == Some("py") {
+            if jit {
+                if let Some(result) = self.try_jit(file) {
+                    print_success(&format!("Ran via JIT, result = {result}"));
+                    return Ok(());
+                }
+
+                // The JIT fast path missed, but `--jit` was still asked for
+                // - honor its spirit of not littering the source directory
+                // with a binary even on the cargo-build fallback.
+                let binary = compiler.compile_ephemeral(file, opt_level)?;
+                compiler.run(&binary, args)?;
+                return Ok(());
+            }
+
             // Compile first
-            let binary = compiler.compile(file)?;
+            let binary = compiler.compile(file, opt_level)?;
             compiler.run(&binary, args)?;
         } else {
             // Run binary directly
@@ -184,6 +1366,27 @@ impl Cli {
         Ok(())
     }
 
+    /// Attempts the Cranelift fast path for `--jit`, returning `None` (and
+    /// printing why) for anything outside `jit::JitBackend`'s narrow
+    /// integer/bool subset so `run` can fall back to the normal cargo build.
+    fn try_jit(&self, file: &PathBuf) -> Option<i64> {
+        let source = fs::read_to_string(file).ok()?;
+        let mut program = AdrenalineParser::parse(&source).ok()?;
+        TypeInference::new().infer_program(&mut program);
+        let mut module = IRLowering::lower_program(&program);
+        IROptimizer::new().optimize(&mut module);
+
+        match jit::try_run(&module) {
+            Ok(result) => Some(result),
+            Err(reason) => {
+                print_info(&format!(
+                    "JIT fast path unavailable ({reason}), falling back to native build"
+                ));
+                None
+            }
+        }
+    }
+
     fn show_help(&self, topic: Option<&str>) {
         let help = match topic {
             Some("directives") => {
@@ -211,6 +1414,10 @@ Directives guide compilation decisions. Add them as comments in your code:
   #adrenaline:cache
     Cache compiled output based on source hash
 
+  #adrenaline:fast-math
+    Allow reassociation and FMA-style folding of arithmetic, trading
+    bit-exact results for throughput
+
 Example:
   def matrix_multiply(a, b):
       #adrenaline:hot
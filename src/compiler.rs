@@ -1,19 +1,310 @@
 /// Main compiler pipeline
 /// Orchestrates the full compilation process
+use crate::ast_types::{Program, Statement};
 use crate::cache::Cache;
-use crate::codegen::RustCodegen;
+use crate::config::ProjectConfig;
 use crate::diagnostics::*;
+use crate::directives::OverflowMode;
+use crate::ir::IRModule;
+use crate::ir_codegen::{IRCodegen, SourceMapEntry, CATCH_UNWIND_MARKER, SIMD_CHUNK_MARKER};
+use crate::ir_lowering::IRLowering;
+use crate::optimizer::{IROptimizer, OptimizationRemark};
 use crate::parser::AdrenalineParser;
 use crate::profiler::Profiler;
 use crate::type_inference::TypeInference;
 use anyhow::{anyhow, Result};
+use inferno::collapse::Collapse;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::fs;
+use std::io::Read;
+use std::io::Write as _;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::time::Instant;
 
 pub struct Compiler {
     cache: Cache,
     profiler: Profiler,
+    fast_math: bool,
+    /// `--overflow` override, if the CLI passed one - `None` defers to
+    /// `compile`'s opt-level-based default (see `set_overflow_mode`).
+    overflow_mode: Option<OverflowMode>,
+    /// Remarks from the most recent `compile()` call, surfaced by
+    /// `print_remarks`.
+    remarks: Vec<OptimizationRemark>,
+    /// `adrenaline.toml`, if the project has one - see `set_project_config`.
+    /// Defaults to `ProjectConfig::default()` (every setting untouched)
+    /// until a caller loads and sets a real one.
+    project_config: ProjectConfig,
+    /// `--profile` override, if the CLI passed one - `None` defers to
+    /// `--opt-level`'s existing dev/release split (see `active_profile`).
+    build_profile: Option<String>,
+    /// `--manifest`: write a `BuildManifest` next to the binary once
+    /// `build_rust_project` finishes, see `write_manifest`.
+    manifest: bool,
+    /// `#adrenaline:*` directives found anywhere in the most recent
+    /// `compile()`'s source, for `BuildManifest::directives_seen` - computed
+    /// once up front (see `scan_directives`) so it's available even on a
+    /// cache hit, which skips parsing entirely.
+    directives_seen: Vec<String>,
+    /// `--deterministic`: pins the flags that would otherwise let two
+    /// builds of the same source disagree (embedded build-dir paths, a
+    /// timestamp), and rebuilds once more to check the result actually
+    /// came out identical. See `build_via_rustc`/`build_via_cargo` and
+    /// `verify_reproducible`.
+    deterministic: bool,
+    /// `--opt-budget` override, if the CLI passed one - `None` defers to
+    /// `IROptimizer`'s own `DEFAULT_INSTRUCTION_BUDGET`.
+    opt_budget: Option<usize>,
+    /// `--opt-timeout` override (seconds), if the CLI passed one - `None`
+    /// defers to `IROptimizer`'s own `DEFAULT_STAGE_TIMEOUT`.
+    opt_timeout: Option<std::time::Duration>,
+    /// `--sandbox`: build subprocesses run with a scrubbed environment and,
+    /// on the `cargo` path, `--offline --locked` against a pinned lockfile
+    /// instead of letting `cargo` resolve (and potentially fetch) whatever
+    /// it likes - see `scrub_env`/`pin_lockfile`.
+    sandbox: bool,
+    /// `--profile-instrument`: `compile_internal` sets
+    /// `IRCodegen::set_profile_instrument`, so every generated function
+    /// reports real per-function call counts and timings via
+    /// `adrenaline_runtime::profile_function!` instead of `profile`'s
+    /// coarse whole-run timing - see `IRCodegen::generate_function`.
+    profile_instrument: bool,
+    /// `--profile-use <file>`: a profile written by a `--profile-instrument`
+    /// run (or `Profiler::save_to_file`), fed into
+    /// `IROptimizer::apply_profile` before optimizing so a hot function
+    /// (per the *previous* run's call counts) gets `Aggressive`/`Extreme`
+    /// treatment this build, closing the loop `--profile-instrument` opens.
+    profile_use: Option<PathBuf>,
+    /// `--profile-alloc`: `compile_internal` sets both this and
+    /// `IRCodegen::set_profile_instrument` (allocation attribution needs the
+    /// same per-function scoping call-time instrumentation already
+    /// provides), so the generated binary installs `CountingAllocator` and
+    /// reports per-function allocation counts/bytes alongside call counts -
+    /// see `IRCodegen::set_profile_alloc`.
+    profile_alloc: bool,
+    /// `--profile-lines`: `compile_internal` sets `IRCodegen::set_profile_lines`,
+    /// so every Python statement (including ones nested inside a loop/`if`/
+    /// `try`) gets a hit-count call attributing time spent to the line it
+    /// came from - see `IRLowering::lower_statement`'s `LineMarker` and
+    /// `Compiler::annotate`.
+    profile_lines: bool,
+    /// `--profile-hwcounters`: `compile_internal` sets both this and
+    /// `IRCodegen::set_profile_instrument` (hardware-counter attribution
+    /// needs the same per-function scoping call-time instrumentation
+    /// already provides), so the generated binary reads cache-miss,
+    /// branch-miss, and instruction counts from `perf_event_open` around
+    /// each call and reports them alongside call counts and timings - see
+    /// `IRCodegen::set_profile_hwcounters`. Linux-only; a no-op elsewhere.
+    profile_hwcounters: bool,
+    /// `adrenaline profile --live`: `compile_internal` sets both this and
+    /// `IRCodegen::set_profile_instrument` (live snapshots need the same
+    /// per-function scoping call-time instrumentation already provides), so
+    /// the generated binary's `main` starts
+    /// `adrenaline_runtime::profiling::maybe_serve_live` before running -
+    /// see `IRCodegen::set_profile_live` and `live_profile`.
+    profile_live: bool,
+}
+
+/// The `--opt-level` CLI flag (0-3), mapped onto both `ir::OptimizationLevel`
+/// (for a future IR pass) and the Cargo profile used to build the
+/// generated crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptLevel {
+    O0,
+    O1,
+    O2,
+    O3,
+}
+
+impl OptLevel {
+    pub fn from_flag(level: u8) -> Self {
+        match level {
+            0 => OptLevel::O0,
+            1 => OptLevel::O1,
+            2 => OptLevel::O2,
+            _ => OptLevel::O3,
+        }
+    }
+
+    // No caller threads `OptLevel` through to the IR optimizer yet - the
+    // pipeline picks `OptimizationLevel` directly. Kept for when it does.
+    #[allow(dead_code)]
+    pub fn ir_level(self) -> crate::ir::OptimizationLevel {
+        match self {
+            OptLevel::O0 => crate::ir::OptimizationLevel::None,
+            OptLevel::O1 => crate::ir::OptimizationLevel::Basic,
+            OptLevel::O2 => crate::ir::OptimizationLevel::Aggressive,
+            OptLevel::O3 => crate::ir::OptimizationLevel::Extreme,
+        }
+    }
+
+    /// `-O0` skips `--release` entirely so a build is a fast debug build,
+    /// not just a release build with a low opt-level.
+    fn cargo_profile(self) -> &'static str {
+        match self {
+            OptLevel::O0 => "debug",
+            _ => "release",
+        }
+    }
+
+    fn cargo_opt_level(self) -> &'static str {
+        match self {
+            OptLevel::O0 => "0",
+            OptLevel::O1 => "1",
+            OptLevel::O2 => "2",
+            OptLevel::O3 => "3",
+        }
+    }
+
+    fn cargo_lto(self) -> bool {
+        matches!(self, OptLevel::O3)
+    }
+
+    fn cargo_codegen_units(self) -> u32 {
+        if matches!(self, OptLevel::O3) {
+            1
+        } else {
+            16
+        }
+    }
+}
+
+/// One of the generated crate's `[profile.dev]`/`[profile.release]`
+/// sections - Cargo's own knobs (opt-level, lto, codegen-units, panic
+/// strategy, debug symbols), resolved from `dev()`/`release()`'s
+/// defaults and then `apply_override`'s corresponding `[profile.dev]`/
+/// `[profile.release]` table in `adrenaline.toml` (see
+/// `config::ProfileOverride`), if any. `--profile` (`Compiler::active_profile`)
+/// picks which of the two actually builds; `write_cargo_toml` always emits
+/// both, same as Cargo's own default `Cargo.toml` template does.
+#[derive(Debug, Clone, Copy)]
+struct BuildProfileSettings {
+    /// Cargo's own name for this profile - the `[profile.*]` header.
+    name: &'static str,
+    /// `target/<dir>` this profile's build lands in - `dev` is the one
+    /// Cargo quirk here, its own output directory is still called
+    /// `debug`.
+    dir: &'static str,
+    opt_level: &'static str,
+    lto: bool,
+    codegen_units: u32,
+    panic_abort: bool,
+    debug: bool,
+}
+
+impl BuildProfileSettings {
+    /// Fast, debuggable native builds for iteration - unoptimized, no LTO,
+    /// debug symbols on, matching what a bare `cargo build` (no `--release`)
+    /// already does today.
+    fn dev() -> Self {
+        Self {
+            name: "dev",
+            dir: "debug",
+            opt_level: "0",
+            lto: false,
+            codegen_units: 256,
+            panic_abort: false,
+            debug: true,
+        }
+    }
+
+    /// Defaults to whatever `--opt-level` already implies, so a build with
+    /// no `--profile`/`[profile.release]` override behaves exactly as it
+    /// did before `--profile` existed.
+    fn release(opt_level: OptLevel) -> Self {
+        Self {
+            name: "release",
+            dir: "release",
+            opt_level: opt_level.cargo_opt_level(),
+            lto: opt_level.cargo_lto(),
+            codegen_units: opt_level.cargo_codegen_units(),
+            panic_abort: false,
+            debug: false,
+        }
+    }
+
+    fn apply_override(mut self, over: &crate::config::ProfileOverride) -> Self {
+        if let Some(lto) = over.lto {
+            self.lto = lto;
+        }
+        if let Some(codegen_units) = over.codegen_units {
+            self.codegen_units = codegen_units;
+        }
+        if let Some(panic_abort) = over.panic_abort {
+            self.panic_abort = panic_abort;
+        }
+        if let Some(debug) = over.debug {
+            self.debug = debug;
+        }
+        self
+    }
+
+    fn write_toml_section(&self, content: &mut String) {
+        let _ = write!(
+            content,
+            "\n[profile.{}]\nopt-level = {}\nlto = {}\ncodegen-units = {}\ndebug = {}\n",
+            self.name, self.opt_level, self.lto, self.codegen_units, self.debug
+        );
+        // Cargo's own default (`panic = "unwind"`) is left implicit -
+        // only an explicit override writes the line, so most generated
+        // manifests don't carry a key nobody asked to change.
+        if self.panic_abort {
+            let _ = writeln!(content, "panic = \"abort\"");
+        }
+    }
+}
+
+/// `--emit`'s `ir`/`asm`/`llvm-ir` values (`rust`/`c` stay `EmitTarget` in
+/// `cli.rs`, since those pick a full build backend rather than dumping an
+/// intermediate artifact - see `Compiler::emit_artifact`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    /// Debug-formatted optimized IR module, no `rustc` invocation needed.
+    Ir,
+    /// Assembly for the generated crate, via `cargo rustc -- --emit=asm`.
+    Asm,
+    /// LLVM IR for the generated crate, via `cargo rustc -- --emit=llvm-ir`.
+    LlvmIr,
+}
+
+/// Written next to the binary by `--manifest`, for reproducibility audits
+/// and CI artifact tracking: what was compiled (`source_hash`, `directives_seen`),
+/// what compiled it (`compiler_version`), what it decided (`optimizations`,
+/// reusing the same `OptimizationRemark`s as `--remarks`), and how long it
+/// took (`build_duration_ms`) to produce `output_path`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildManifest {
+    pub source_hash: String,
+    pub compiler_version: &'static str,
+    pub directives_seen: Vec<String>,
+    pub optimizations: Vec<OptimizationRemark>,
+    pub build_duration_ms: u128,
+    pub output_path: PathBuf,
+}
+
+/// One `bench` run's measurements - `peak_rss_kb` is `None` wherever
+/// `/usr/bin/time -v` isn't available to measure it.
+struct BenchRun {
+    wall: std::time::Duration,
+    peak_rss_kb: Option<u64>,
+    stdout: Vec<u8>,
+}
+
+/// One `diff` run's captured result.
+struct DiffRun {
+    stdout: Vec<u8>,
+    exit_code: Option<i32>,
+}
+
+/// One entry of `line_profiling::write_report`'s JSON, read back by
+/// `Compiler::annotate`.
+#[derive(Deserialize)]
+struct LineHit {
+    line: usize,
+    hit_count: u64,
 }
 
 impl Compiler {
@@ -21,26 +312,210 @@ impl Compiler {
         Ok(Self {
             cache: Cache::new(project_dir)?,
             profiler: Profiler::new(),
+            fast_math: false,
+            overflow_mode: None,
+            remarks: Vec::new(),
+            project_config: ProjectConfig::default(),
+            build_profile: None,
+            manifest: false,
+            directives_seen: Vec::new(),
+            deterministic: false,
+            opt_budget: None,
+            opt_timeout: None,
+            sandbox: false,
+            profile_instrument: false,
+            profile_use: None,
+            profile_alloc: false,
+            profile_lines: false,
+            profile_hwcounters: false,
+            profile_live: false,
         })
     }
 
-    /// Main compilation entry point
-    pub fn compile(&mut self, source_path: &Path) -> Result<PathBuf> {
+    /// Enables `--fast-math` for this compilation, forwarded to
+    /// `IROptimizer::enable_fast_math` when `compile` lowers to IR.
+    pub fn enable_fast_math(&mut self) {
+        self.fast_math = true;
+    }
+
+    /// Enables `--manifest`: `compile`'s call to `build_rust_project` writes
+    /// a `BuildManifest` next to the binary once the build succeeds.
+    pub fn enable_manifest(&mut self) {
+        self.manifest = true;
+    }
+
+    /// Enables `--deterministic`: `build_rust_project` pins the flags that
+    /// let two builds of the same source disagree and rebuilds once more
+    /// to confirm they didn't.
+    pub fn enable_deterministic(&mut self) {
+        self.deterministic = true;
+    }
+
+    /// Enables `--sandbox`: every build subprocess (`cc`, `rustc`, `cargo`)
+    /// runs with a scrubbed environment (see `scrub_env`), and the `cargo`
+    /// path additionally builds `--offline --locked` against a lockfile
+    /// pinned once with network access and reused after that (see
+    /// `pin_lockfile`) - so compiling an untrusted Python file can't reach
+    /// the network or read secrets out of the caller's own env through a
+    /// dependency's build script.
+    pub fn enable_sandbox(&mut self) {
+        self.sandbox = true;
+    }
+
+    /// Enables `--profile-instrument`: `compile`'s call to `IRCodegen`
+    /// wraps every generated function body in
+    /// `adrenaline_runtime::profile_function!`, so the compiled binary
+    /// writes a real per-function profile to disk on exit instead of
+    /// reporting nothing.
+    pub fn enable_profile_instrument(&mut self) {
+        self.profile_instrument = true;
+    }
+
+    /// Sets `--profile-use <file>`: `compile`'s call to
+    /// `IROptimizer::apply_profile` will load `path` (the format
+    /// `Profiler::save_to_file`/an instrumented run's `write_report`
+    /// write) and promote the functions it names as hot before optimizing.
+    pub fn set_profile_use(&mut self, path: PathBuf) {
+        self.profile_use = Some(path);
+    }
+
+    /// Enables `--profile-alloc`: `compile`'s call to `IRCodegen` also
+    /// installs `adrenaline_runtime::alloc_profiling::CountingAllocator` as
+    /// the compiled binary's global allocator, so its per-function profile
+    /// includes allocation counts/bytes alongside call counts and timings.
+    pub fn enable_profile_alloc(&mut self) {
+        self.profile_alloc = true;
+    }
+
+    /// Enables `--profile-lines`: `compile`'s call to `IRCodegen` renders
+    /// every Python statement's `LineMarker` as a real hit-count call, so
+    /// the compiled binary writes a per-line report alongside any
+    /// `--profile-instrument`/`--profile-alloc` per-function one.
+    pub fn enable_profile_lines(&mut self) {
+        self.profile_lines = true;
+    }
+
+    /// Enables `--profile-hwcounters`: `compile`'s call to `IRCodegen` also
+    /// reads cache-miss, branch-miss, and instruction counts around each
+    /// call (via `adrenaline_runtime::hw_counters`, Linux-only), so the
+    /// compiled binary's per-function profile includes them alongside call
+    /// counts and timings.
+    pub fn enable_profile_hwcounters(&mut self) {
+        self.profile_hwcounters = true;
+    }
+
+    /// Enables `adrenaline profile --live` (see `live_profile`):
+    /// `compile`'s call to `IRCodegen` also sets `--profile-instrument`
+    /// (live snapshots need the same per-function scoping call-time
+    /// instrumentation already provides) and has the generated `main` call
+    /// `adrenaline_runtime::profiling::maybe_serve_live` at startup, so a
+    /// client can attach to its counters over a Unix domain socket while it
+    /// runs instead of waiting for it to exit.
+    pub fn enable_profile_live(&mut self) {
+        self.profile_live = true;
+    }
+
+    /// Overrides `IROptimizer`'s default instruction-count budget for a hot
+    /// function's loop passes - set from `--opt-budget`. See
+    /// `optimizer::DEFAULT_INSTRUCTION_BUDGET`.
+    pub fn set_opt_budget(&mut self, budget: usize) {
+        self.opt_budget = Some(budget);
+    }
+
+    /// Overrides `IROptimizer`'s default wall-clock timeout for the same
+    /// loop passes - set from `--opt-timeout` (seconds). See
+    /// `optimizer::DEFAULT_STAGE_TIMEOUT`.
+    pub fn set_opt_timeout(&mut self, timeout_secs: u64) {
+        self.opt_timeout = Some(std::time::Duration::from_secs(timeout_secs));
+    }
+
+    /// Builds an `IROptimizer` with this compilation's `--fast-math`/
+    /// `--opt-budget`/`--opt-timeout` overrides applied - shared by every
+    /// `compile*` entry point instead of repeating the same three `if`s.
+    fn new_optimizer(&self) -> IROptimizer {
+        let mut optimizer = IROptimizer::new();
+        if self.fast_math {
+            optimizer.enable_fast_math();
+        }
+        if let Some(budget) = self.opt_budget {
+            optimizer.set_instruction_budget(budget);
+        }
+        if let Some(timeout) = self.opt_timeout {
+            optimizer.set_stage_timeout(timeout);
+        }
+        optimizer
+    }
+
+    /// Applies `adrenaline.toml` settings (see `config::ProjectConfig`) to
+    /// every `compile*` call made afterward - per-glob directives, extra
+    /// Cargo dependencies, `--target`, output directory, and cache
+    /// behavior. Left at `ProjectConfig::default()` (every setting
+    /// untouched) for a project with no config file.
+    pub fn set_project_config(&mut self, config: ProjectConfig) {
+        self.project_config = config;
+    }
+
+    /// Overrides `compile`'s opt-level-based default overflow policy with an
+    /// explicit `--overflow` choice.
+    pub fn set_overflow_mode(&mut self, mode: OverflowMode) {
+        self.overflow_mode = Some(mode);
+    }
+
+    /// Overrides `--opt-level`'s default choice of `dev` (at `-O0`) or
+    /// `release` (otherwise) with an explicit `--profile dev`/`--profile
+    /// release`, see `active_profile`.
+    pub fn set_build_profile(&mut self, profile: Option<String>) {
+        self.build_profile = profile;
+    }
+
+    /// Main compilation entry point. `opt_level` (0-3) is mapped onto
+    /// `ir::OptimizationLevel` and used to pick the generated Cargo
+    /// profile, so `-O0` gets a fast debug build instead of always paying
+    /// for the hard-coded LTO release profile. Copies the binary next to
+    /// `source_path` - see `compile_ephemeral` for a `run`-only variant
+    /// that skips that copy.
+    pub fn compile(&mut self, source_path: &Path, opt_level: u8) -> Result<PathBuf> {
+        self.compile_internal(source_path, opt_level, true)
+    }
+
+    /// `adrenaline run --jit`'s fallback for anything outside
+    /// `jit::try_run`'s narrow subset: the same full build as `compile`,
+    /// just without the final copy to `source_path`'s directory - `run`
+    /// only needs the binary long enough to execute it once, and leaving
+    /// one behind for every file a JIT-unsupported one-off script touches
+    /// is exactly the littering `--jit` is meant to avoid. The binary
+    /// still lands in `~/.adrenaline/build_*` (see `build_rust_project`
+    /// and `adrenaline clean`), it just never leaves that directory.
+    pub fn compile_ephemeral(&mut self, source_path: &Path, opt_level: u8) -> Result<PathBuf> {
+        self.compile_internal(source_path, opt_level, false)
+    }
+
+    fn compile_internal(&mut self, source_path: &Path, opt_level: u8, copy_to_source: bool) -> Result<PathBuf> {
         print_info(&format!("Compiling {}...", source_path.display()));
+        let opt_level = OptLevel::from_flag(opt_level);
+        self.remarks.clear();
 
         // Read source
         let source = fs::read_to_string(source_path)?;
-        let source_hash = Cache::get_hash(&source);
+        let profile_data: Vec<crate::profiler::ProfileData> = match &self.profile_use {
+            Some(path) => Profiler::load_from_file(path)?,
+            None => Vec::new(),
+        };
+        let source_hash = Cache::get_hash(&self.cache_key_material(&source, opt_level, &profile_data));
+        self.directives_seen = Self::scan_directives(&source);
 
         // Check cache
-        if self.cache.has_cached(&source_hash) {
+        if self.project_config.cache_enabled() && self.cache.has_cached(&source_hash) {
             print_info("Using cached compilation");
             let cached_code = self.cache.get_cached(&source_hash)?;
 
             // Try building cached code first. If it fails due to generated code problems
             // (e.g., missing `main`), clear cache and fall through to re-generate.
-            match self.build_rust_project(source_path, &cached_code) {
-                Ok(path) => return Ok(path),
+            match self.build_rust_project(source_path, &cached_code, opt_level, copy_to_source) {
+                Ok(path) => {
+                    let _ = self.cache.record_hit(cached_code.len() as u64);
+                    return Ok(path);
+                }
                 Err(e) => {
                     let err_str = e.to_string();
                     if err_str.contains("main function not found")
@@ -57,164 +532,4014 @@ impl Compiler {
                 }
             }
         }
+        let generation_start = Instant::now();
 
         // Parse
         print_info("Parsing Python...");
-        let program = AdrenalineParser::parse(&source)?;
+        let mut program = AdrenalineParser::parse(&source)?;
+        Self::resolve_local_imports(source_path, &mut program, &mut std::collections::HashSet::new())?;
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
 
         // Type inference
         print_info("Running type inference...");
-        let mut program = program;
         let mut type_inference = TypeInference::new();
         type_inference.infer_program(&mut program);
 
-        // Generate Rust code directly from AST
+        // Lower to IR, run the optimizer, then generate Rust from the
+        // optimized IR - so directives and `--opt-level` actually change
+        // what gets emitted, instead of codegen reading straight off the AST.
+        print_info("Lowering to IR...");
+        let mut module = IRLowering::lower_program(&program);
+        Self::attach_python_fallbacks(&mut module, &source);
+
+        print_info("Optimizing...");
+        let mut optimizer = self.new_optimizer();
+        if !profile_data.is_empty() {
+            optimizer.apply_profile(&mut module, &profile_data);
+        }
+        if self.project_config.cache_enabled() {
+            optimizer.optimize_incremental(&mut module, &self.cache);
+        } else {
+            optimizer.optimize(&mut module);
+        }
+        self.remarks = optimizer.remarks().to_vec();
+
         print_info("Generating Rust code...");
-        let mut codegen = RustCodegen::new();
-        let rust_code = codegen.generate(&program);
+        let mut codegen = IRCodegen::new();
+        codegen.set_provenance(&source_path.display().to_string(), &self.remarks);
+        // A `-O0` build favors catching a bug over shaving cycles off not
+        // checking for one; anything higher defaults to wrapping, matching
+        // what an unchecked release build would have done anyway. Either can
+        // be overridden with `--overflow` or a per-function directive.
+        codegen.set_overflow_mode(self.overflow_mode.unwrap_or(if opt_level == OptLevel::O0 {
+            OverflowMode::Checked
+        } else {
+            OverflowMode::Wrap
+        }));
+        if self.profile_instrument {
+            codegen.set_profile_instrument(true);
+        }
+        if self.profile_alloc {
+            // Allocation attribution needs the same per-function
+            // enter/exit scoping call-time instrumentation already does.
+            codegen.set_profile_instrument(true);
+            codegen.set_profile_alloc(true);
+        }
+        if self.profile_lines {
+            codegen.set_profile_lines(true);
+        }
+        if self.profile_hwcounters {
+            // Hardware-counter attribution needs the same per-function
+            // enter/exit scoping call-time instrumentation already does.
+            codegen.set_profile_instrument(true);
+            codegen.set_profile_hwcounters(true);
+        }
+        if self.profile_live {
+            // Serving live snapshots needs the same per-function enter/exit
+            // scoping call-time instrumentation already does.
+            codegen.set_profile_instrument(true);
+            codegen.set_profile_live(true);
+        }
+        // `generate_incremental` reuses a previous run's cached Rust for any
+        // function whose own IR and codegen context are unchanged (see
+        // `IRCodegen::function_cache_key`), so a one-function edit in a
+        // large file doesn't pay for regenerating every other function too.
+        // Skipped entirely alongside the whole-file cache when disabled.
+        let rust_code = if self.project_config.cache_enabled() {
+            codegen.generate_incremental(&module, &self.cache)
+        } else {
+            codegen.generate(&module)
+        };
 
         // Cache the generated code
-        self.cache.cache(&source_hash, &rust_code)?;
+        if self.project_config.cache_enabled() {
+            self.cache.cache(&source_hash, &rust_code, source_path)?;
+            let _ = self.cache.record_miss(generation_start.elapsed());
+        }
 
         // Build and compile
-        self.build_rust_project(source_path, &rust_code)
+        self.build_rust_project(source_path, &rust_code, opt_level, copy_to_source)
     }
 
-    fn build_rust_project(&self, source_path: &Path, rust_code: &str) -> Result<PathBuf> {
-        // Use ~/.adrenaline/ for all temporary files
-        let adrenaline_home = dirs::home_dir()
-            .ok_or_else(|| anyhow!("Could not determine home directory"))?
-            .join(".adrenaline");
-        
-        fs::create_dir_all(&adrenaline_home)?;
-        
-        // Create unique build directory based on source file hash
-        let source_hash = Cache::get_hash(&fs::read_to_string(source_path)?);
-        let build_dir = adrenaline_home.join(format!("build_{}", &source_hash[..8]));
-        let src_dir = build_dir.join("src");
+    /// Compiles `source_path` to a PyO3 `cdylib` instead of a `main`-driven
+    /// binary, so `import {stem}_adrenaline` works from an existing Python
+    /// program instead of the whole script being replaced by a compiled
+    /// binary. Shares `compile`'s parse/infer/lower/optimize pipeline; only
+    /// codegen and the packaging step differ.
+    pub fn compile_python_extension(&mut self, source_path: &Path, opt_level: u8) -> Result<PathBuf> {
+        print_info(&format!("Compiling {} as a Python extension module...", source_path.display()));
+        let opt_level = OptLevel::from_flag(opt_level);
+        self.remarks.clear();
 
-        // Create directories
-        fs::create_dir_all(&src_dir)?;
+        let source = fs::read_to_string(source_path)?;
 
-        // Write Rust source
-        let main_rs = src_dir.join("main.rs");
-        fs::write(&main_rs, rust_code)?;
+        print_info("Parsing Python...");
+        let mut program = AdrenalineParser::parse(&source)?;
+        Self::resolve_local_imports(source_path, &mut program, &mut std::collections::HashSet::new())?;
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
 
-        // Write Cargo.toml if needed
-        let cargo_toml = build_dir.join("Cargo.toml");
-        if !cargo_toml.exists() {
-            self.write_cargo_toml(&cargo_toml)?;
-        }
+        print_info("Running type inference...");
+        let mut type_inference = TypeInference::new();
+        type_inference.infer_program(&mut program);
 
-        // Run cargo build silently
-        let output = Command::new("cargo")
-            .arg("build")
-            .arg("--release")
-            .arg("--manifest-path")
-            .arg(&cargo_toml)
-            .output()?;
+        print_info("Lowering to IR...");
+        let mut module = IRLowering::lower_program(&program);
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-            print_error(&format!("Rust compilation failed:\n{}", stderr));
-            return Err(anyhow!(stderr));
+        print_info("Optimizing...");
+        let mut optimizer = self.new_optimizer();
+        optimizer.optimize(&mut module);
+        self.remarks = optimizer.remarks().to_vec();
+
+        let module_name = format!(
+            "{}_adrenaline",
+            source_path.file_stem().and_then(|s| s.to_str()).unwrap_or("adrenaline_module")
+        );
+
+        print_info("Generating PyO3 extension module...");
+        let mut codegen = IRCodegen::new();
+        let rust_code = codegen.generate_pyo3(&module, &module_name);
+
+        let output_lib = self.build_python_extension(source_path, &rust_code, &module_name, opt_level)?;
+
+        let pyi_path = output_lib.with_extension("pyi");
+        fs::write(&pyi_path, IRCodegen::generate_pyi(&module))?;
+        print_success(&format!("Generated type stub {}", pyi_path.display()));
+
+        Ok(output_lib)
+    }
+
+    /// `adrenaline check <file>`: parses, runs type inference, validates
+    /// `#adrenaline:*` directives, and runs `IRLowering` to catch anything
+    /// still unsupported - all without a `cargo`/`rustc` build - printing a
+    /// real miette diagnostic per problem and returning `Err` (a non-zero
+    /// exit) if anything turned up.
+    pub fn check(&mut self, source_path: &Path) -> Result<()> {
+        print_info(&format!("Checking {}...", source_path.display()));
+
+        let source = fs::read_to_string(source_path)?;
+
+        // `AdrenalineParser::parse` doesn't track a source position on its
+        // errors (see `parser::PythonParser`), so there's no span to attach
+        // a real `ParseError` diagnostic to - report it the same
+        // span-less way an IO or Rust-side compilation failure would be.
+        let mut program = match AdrenalineParser::parse(&source) {
+            Ok(program) => program,
+            Err(e) => {
+                if json_mode() {
+                    emit_json(&serde_json::json!({
+                        "event": "check",
+                        "file": source_path.display().to_string(),
+                        "success": false,
+                        "diagnostics": [e.to_string()],
+                    }));
+                } else {
+                    eprintln!("{:?}", miette::Report::new(DiagnosticBuilder::compilation_failed(&e.to_string())));
+                }
+                return Err(anyhow!("Parse error"));
+            }
+        };
+
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
+
+        let mut issues = Vec::new();
+        Self::check_directives(&program, &source, &mut issues);
+
+        let mut type_inference = TypeInference::new();
+        type_inference.infer_program(&mut program);
+        Self::check_types(&program, &source, &mut issues);
+
+        // Some constructs still hit an `unreachable!()` deep in
+        // `IRLowering` instead of failing gracefully - catch that here
+        // instead of leaving it to crash a real `compile`/`compile_c` run.
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let lowering_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| IRLowering::lower_program(&program)));
+        std::panic::set_hook(previous_hook);
+        if let Err(panic_payload) = lowering_result {
+            let message = panic_payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic_payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "an unsupported construct crashed IR lowering".to_string());
+            issues.push(DiagnosticBuilder::unsupported_feature(
+                &source,
+                0,
+                0,
+                &message,
+                Some("wrap the offending function in `#adrenaline:no-compile` to fall back to the embedded Python interpreter".to_string()),
+            ));
         }
 
-        // Copy binary to source file's directory
-        let source_dir = source_path
-            .parent()
-            .unwrap_or_else(|| Path::new("."));
-        let source_stem = source_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("output");
-        
-        let output_binary = source_dir.join(if cfg!(target_os = "windows") {
-            format!("{}.exe", source_stem)
-        } else {
-            source_stem.to_string()
-        });
+        if json_mode() {
+            emit_json(&serde_json::json!({
+                "event": "check",
+                "file": source_path.display().to_string(),
+                "success": issues.is_empty(),
+                "diagnostics": issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>(),
+            }));
+            return if issues.is_empty() { Ok(()) } else { Err(anyhow!("{} issue(s) found", issues.len())) };
+        }
 
-        let build_binary = build_dir
-            .join("target/release")
-            .join(if cfg!(target_os = "windows") {
-                "main.exe"
-            } else {
-                "main"
-            });
+        if issues.is_empty() {
+            print_success("No issues found");
+            Ok(())
+        } else {
+            let count = issues.len();
+            for issue in issues {
+                eprintln!("{:?}", miette::Report::new(issue));
+            }
+            Err(anyhow!("{count} issue(s) found"))
+        }
+    }
 
-        if !build_binary.exists() {
-            return Err(anyhow!("Binary not found after compilation"));
+    /// `check`'s directive validation: flags any `#adrenaline:*` string that
+    /// doesn't parse into a known `Directive` (see `Directive::from_string`),
+    /// which `apply_config_directives`/lowering otherwise silently ignore.
+    fn check_directives(program: &Program, source: &str, issues: &mut Vec<CompileError>) {
+        for stmt in &program.statements {
+            let Statement::FunctionDef(func) = stmt else { continue };
+            for directive in &func.directives {
+                if crate::directives::Directive::from_string(directive).is_none() {
+                    issues.push(DiagnosticBuilder::unsupported_feature(
+                        source,
+                        func.line,
+                        0,
+                        &format!("unknown directive `#adrenaline:{directive}` on function `{}`", func.name),
+                        Some("see directives.rs for the supported set".to_string()),
+                    ));
+                }
+            }
         }
+    }
 
-        fs::copy(&build_binary, &output_binary)?;
-        print_success(&format!("Successfully compiled to {}", output_binary.display()));
-        Ok(output_binary)
+    /// `check`'s type validation: a parameter or return type that's still
+    /// `Type::Unknown` after inference compiles down to Rust `()` (see
+    /// `TypeInference::infer_program`'s known parameter-inference gap),
+    /// silently breaking the function it's attached to.
+    fn check_types(program: &Program, source: &str, issues: &mut Vec<CompileError>) {
+        for stmt in &program.statements {
+            let Statement::FunctionDef(func) = stmt else { continue };
+            if func.return_type == crate::ast_types::Type::Unknown {
+                issues.push(DiagnosticBuilder::type_error_at(
+                    source,
+                    func.line,
+                    &format!("function `{}` has an unresolved return type", func.name),
+                ));
+            }
+            for param in &func.params {
+                if param.typ == crate::ast_types::Type::Unknown {
+                    issues.push(DiagnosticBuilder::type_error_at(
+                        source,
+                        func.line,
+                        &format!("parameter `{}` of function `{}` has an unresolved type", param.name, func.name),
+                    ));
+                }
+            }
+        }
     }
 
-    fn write_cargo_toml(&self, path: &Path) -> Result<()> {
-        let content = r#"[package]
-name = "adrenaline-generated"
-version = "0.1.0"
-edition = "2021"
+    /// `adrenaline advise <file>`: an interactive pass suggesting
+    /// `#adrenaline:*` directives, and (on confirmation) writing them into
+    /// the file. "Hot" is a single timed run of a `-O0` build - like
+    /// `profile`, this compiler has no per-function call counts, only
+    /// whole-program wall time, so a file with more than one candidate
+    /// function can't tell which of them the time actually went to; the
+    /// rest of the analysis (loop nesting, numeric types, self-recursion)
+    /// is purely static, over the parsed `FunctionDef`s.
+    pub fn advise(&mut self, source_path: &Path) -> Result<()> {
+        let source = fs::read_to_string(source_path)?;
+        let mut program = AdrenalineParser::parse(&source).map_err(|e| anyhow!(e))?;
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
 
-[[bin]]
-name = "main"
-path = "src/main.rs"
+        let mut type_inference = TypeInference::new();
+        type_inference.infer_program(&mut program);
 
-[dependencies]
-rayon = "1.7"
+        print_info(&format!("Running a quick profile of {}...", source_path.display()));
+        let binary = self.compile(source_path, 0)?;
+        let start = Instant::now();
+        let status = Command::new(&binary).stdout(Stdio::null()).stderr(Stdio::null()).status()?;
+        let elapsed = start.elapsed();
+        let is_hot = status.success() && elapsed >= Self::ADVISE_HOT_THRESHOLD;
+
+        let mut accepted: Vec<(String, Vec<crate::directives::Directive>)> = Vec::new();
+        for stmt in &program.statements {
+            let Statement::FunctionDef(func) = stmt else { continue };
+            let existing = crate::directives::DirectiveSet::from_strings(&func.directives);
+            let mut confirmed = Vec::new();
+            for directive in Self::advise_candidates(func, is_hot) {
+                if existing.has(directive) {
+                    continue;
+                }
+                let prompt = format!(
+                    "function {} looks {}; add #adrenaline:{}? [y/n] ",
+                    func.name,
+                    Self::advise_reason(directive),
+                    directive.as_str(),
+                );
+                if Self::confirm(&prompt)? {
+                    confirmed.push(directive);
+                }
+            }
+            if !confirmed.is_empty() {
+                accepted.push((func.name.clone(), confirmed));
+            }
+        }
 
-[profile.release]
-opt-level = 3
-lto = true
-codegen-units = 1
-"#;
+        if accepted.is_empty() {
+            print_success("No directives added");
+            return Ok(());
+        }
 
-        fs::write(path, content)?;
+        Self::write_advice(source_path, &source, &program, &accepted)?;
+        print_success(&format!("Updated {}", source_path.display()));
         Ok(())
     }
 
-    pub fn run(&self, binary: &Path, args: &[String]) -> Result<()> {
-        let mut cmd = Command::new(binary);
-        cmd.args(args);
+    /// Below this, a "hot" build's wall time is noise, not signal - a `-O0`
+    /// build of even a trivial script still costs a process spawn and a
+    /// page-in, so anything faster than this can't be distinguished from
+    /// that overhead.
+    const ADVISE_HOT_THRESHOLD: std::time::Duration = std::time::Duration::from_millis(5);
 
-        let status = cmd.status()?;
+    /// Which directives `advise` considers for `func`, given whether the
+    /// whole-program run looked hot. Only directives with a plausible static
+    /// signal are offered - `hot` and `inline` on any hot, non-recursive
+    /// numeric function; `parallel`/`simd` additionally require a loop;
+    /// `memoize` requires self-recursion (the classic "naive fibonacci"
+    /// shape) rather than a loop.
+    fn advise_candidates(func: &crate::ast_types::FunctionDef, is_hot: bool) -> Vec<crate::directives::Directive> {
+        use crate::directives::Directive;
 
-        if !status.success() {
-            return Err(anyhow!("Execution failed"));
+        if !is_hot || !Self::function_is_numeric(func) {
+            return Vec::new();
         }
 
-        Ok(())
+        let recursive = Self::is_recursive(func);
+        let loop_depth = Self::max_loop_depth(&func.body);
+        let mut candidates = Vec::new();
+
+        if recursive {
+            candidates.push(Directive::Memoize);
+        } else {
+            candidates.push(Directive::Hot);
+            candidates.push(Directive::Inline);
+            if loop_depth > 0 {
+                candidates.push(Directive::Parallel);
+            }
+            if loop_depth > 0 && func.params.iter().any(|p| p.typ.is_array_like()) {
+                candidates.push(Directive::Simd);
+            }
+        }
+
+        candidates
     }
 
-    pub fn profile_report(&self) -> Result<()> {
-        let report = self.profiler.report();
+    /// Short, human-readable reason shown in `advise`'s confirmation prompt
+    /// for why a directive was suggested - purely cosmetic, doesn't affect
+    /// which directives are offered (see `advise_candidates`).
+    fn advise_reason(directive: crate::directives::Directive) -> &'static str {
+        use crate::directives::Directive;
+        match directive {
+            Directive::Memoize => "hot and recursive",
+            Directive::Parallel => "hot and data-parallel",
+            Directive::Simd => "hot and array-heavy",
+            _ => "hot",
+        }
+    }
 
-        if report.is_empty() {
-            print_info("No profiling data available");
-            return Ok(());
+    /// All parameters and the return type are `int`/`float` (or an
+    /// array/list of one) - the numeric-only shape every directive
+    /// `advise` suggests assumes (see `IRCodegen`'s numeric fast paths).
+    fn function_is_numeric(func: &crate::ast_types::FunctionDef) -> bool {
+        let numeric_or_array = |t: &crate::ast_types::Type| t.is_numeric() || t.is_array_like();
+        numeric_or_array(&func.return_type) && func.params.iter().all(|p| numeric_or_array(&p.typ))
+    }
+
+    /// Greatest `for`/`while` nesting depth anywhere in `body`, following
+    /// both branches of an `if` and the body of a `try` - mirrors
+    /// `type_inference::TypeInference::infer_statement`'s recursive
+    /// traversal over the same `Statement` shapes.
+    fn max_loop_depth(body: &[Statement]) -> usize {
+        body.iter()
+            .map(|stmt| match stmt {
+                Statement::For(f) => 1 + Self::max_loop_depth(&f.body),
+                Statement::While(w) => 1 + Self::max_loop_depth(&w.body),
+                Statement::If(i) => {
+                    let else_depth = i.else_body.as_deref().map(Self::max_loop_depth).unwrap_or(0);
+                    Self::max_loop_depth(&i.then_body).max(else_depth)
+                }
+                Statement::Try(t) => Self::max_loop_depth(&t.body),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether `func`'s body contains a call back to its own name -
+    /// `memoize`'s only static signal, since this compiler doesn't track
+    /// per-call-site counts (see `advise`'s doc comment).
+    fn is_recursive(func: &crate::ast_types::FunctionDef) -> bool {
+        Self::body_calls(&func.body, &func.name)
+    }
+
+    fn body_calls(body: &[Statement], name: &str) -> bool {
+        body.iter().any(|stmt| match stmt {
+            Statement::ExprStatement(e, _) | Statement::Yield(e, _) => Self::expr_calls(e, name),
+            Statement::Return(Some(e), _) => Self::expr_calls(e, name),
+            Statement::Assign(a) => Self::expr_calls(&a.value, name),
+            Statement::AugAssign(a) => Self::expr_calls(&a.value, name),
+            Statement::For(f) => Self::expr_calls(&f.iter, name) || Self::body_calls(&f.body, name),
+            Statement::While(w) => Self::expr_calls(&w.condition, name) || Self::body_calls(&w.body, name),
+            Statement::If(i) => {
+                Self::expr_calls(&i.condition, name)
+                    || Self::body_calls(&i.then_body, name)
+                    || i.else_body.as_deref().map(|b| Self::body_calls(b, name)).unwrap_or(false)
+            }
+            Statement::Try(t) => {
+                Self::body_calls(&t.body, name)
+                    || t.handlers.iter().any(|h| Self::body_calls(&h.body, name))
+                    || Self::body_calls(&t.finalbody, name)
+            }
+            _ => false,
+        })
+    }
+
+    fn expr_calls(expr: &crate::ast_types::Expression, name: &str) -> bool {
+        use crate::ast_types::Expression;
+        match expr {
+            Expression::Call(callee, args) => {
+                matches!(callee.as_ref(), Expression::Identifier(n) if n == name) || args.iter().any(|a| Self::expr_calls(a, name))
+            }
+            Expression::BinOp(l, _, r) => Self::expr_calls(l, name) || Self::expr_calls(r, name),
+            Expression::UnaryOp(_, e) => Self::expr_calls(e, name),
+            Expression::Index(a, b) => Self::expr_calls(a, name) || Self::expr_calls(b, name),
+            Expression::Attribute(e, _) => Self::expr_calls(e, name),
+            Expression::List(items) | Expression::Tuple(items) => items.iter().any(|e| Self::expr_calls(e, name)),
+            Expression::Dict(pairs) => pairs.iter().any(|(k, v)| Self::expr_calls(k, name) || Self::expr_calls(v, name)),
+            Expression::Conditional(c, t, f) => Self::expr_calls(c, name) || Self::expr_calls(t, name) || Self::expr_calls(f, name),
+            Expression::Kwarg(_, e) => Self::expr_calls(e, name),
+            _ => false,
         }
+    }
 
-        println!(
-            "\n{:<30} {:<12} {:<12} {:<12}",
-            "Function", "Calls", "Total (ms)", "Avg (μs)"
-        );
-        println!("{}", "-".repeat(66));
+    /// Reads a `y`/`n` answer from stdin for `advise`'s per-suggestion
+    /// prompt. Plain `stdin`, not `rustyline` (see `repl.rs`) - there's no
+    /// line editing or history to justify pulling that in for a single
+    /// yes/no answer.
+    fn confirm(prompt: &str) -> Result<bool> {
+        print!("{prompt}");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
 
-        for data in report {
-            println!(
-                "{:<30} {:<12} {:<12.2} {:<12.2}",
-                data.function, data.call_count, data.total_time_ms, data.avg_time_us
-            );
+    /// Writes each accepted `(function, directives)` pair from `advise`
+    /// back into `source_path` as `#adrenaline:*` comment lines just inside
+    /// the function's body - `parser::AdrenalineParser::parse_block`
+    /// recognizes a directive anywhere in a function's body, not only
+    /// directly under `def`, but putting new ones first keeps them visible.
+    /// Insertions are applied bottom-up by line so an earlier function's
+    /// `def` line doesn't shift out from under a later one.
+    fn write_advice(
+        source_path: &Path,
+        source: &str,
+        program: &Program,
+        accepted: &[(String, Vec<crate::directives::Directive>)],
+    ) -> Result<()> {
+        let mut lines: Vec<String> = source.lines().map(|l| l.to_string()).collect();
+
+        let mut targets: Vec<(usize, &[crate::directives::Directive])> = Vec::new();
+        for stmt in &program.statements {
+            let Statement::FunctionDef(func) = stmt else { continue };
+            if let Some((_, directives)) = accepted.iter().find(|(name, _)| *name == func.name) {
+                targets.push((func.line, directives));
+            }
+        }
+        targets.sort_by_key(|(line, _)| std::cmp::Reverse(*line));
+
+        for (def_line, directives) in targets {
+            let def_index = def_line.saturating_sub(1);
+            let def_indent = lines[def_index].len() - lines[def_index].trim_start().len();
+            let body_indent = " ".repeat(def_indent + 4);
+            for directive in directives.iter().rev() {
+                lines.insert(def_index + 1, format!("{body_indent}#adrenaline:{}", directive.as_str()));
+            }
         }
 
+        fs::write(source_path, lines.join("\n") + "\n")?;
         Ok(())
     }
 
-    pub fn clear_cache(&self) -> Result<()> {
-        self.cache.clear()?;
-        print_success("Cache cleared");
-        Ok(())
+    /// Compiles `source_path` to portable C instead of Rust, for embedding a
+    /// kernel in a build that has no Rust toolchain. Shares `compile`'s
+    /// parse/infer/lower/optimize pipeline; only codegen and the build step
+    /// (`cc` instead of `cargo`) differ. `IRCodegen::generate_c` only covers
+    /// a narrow integer/bool subset of the IR, so this fails with a
+    /// descriptive error for anything wider rather than silently falling
+    /// back to the Rust backend.
+    pub fn compile_c(&mut self, source_path: &Path, opt_level: u8) -> Result<PathBuf> {
+        print_info(&format!("Compiling {} to C...", source_path.display()));
+        let opt_level = OptLevel::from_flag(opt_level);
+        self.remarks.clear();
+
+        let source = fs::read_to_string(source_path)?;
+
+        print_info("Parsing Python...");
+        let mut program = AdrenalineParser::parse(&source)?;
+        Self::resolve_local_imports(source_path, &mut program, &mut std::collections::HashSet::new())?;
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
+
+        print_info("Running type inference...");
+        let mut type_inference = TypeInference::new();
+        type_inference.infer_program(&mut program);
+
+        print_info("Lowering to IR...");
+        let mut module = IRLowering::lower_program(&program);
+
+        print_info("Optimizing...");
+        let mut optimizer = self.new_optimizer();
+        // Every top-level function is a separate export here, not just
+        // whatever the synthesized `main` happens to call.
+        optimizer.keep_all_functions();
+        optimizer.optimize(&mut module);
+        self.remarks = optimizer.remarks().to_vec();
+
+        print_info("Generating C code...");
+        let mut codegen = IRCodegen::new();
+        let c_code = codegen.generate_c(&module).map_err(|e| anyhow!("C backend: {e}"))?;
+
+        self.build_c_project(source_path, &c_code, opt_level)
+    }
+
+    /// Compiles `source_path` to a `staticlib`/`rlib` instead of a
+    /// `main`-driven binary, so the compiled kernel can be linked into an
+    /// existing Rust or C++ application. Shares `compile`'s parse/infer/
+    /// lower/optimize pipeline, same as `compile_c`; only codegen and the
+    /// packaging step (`build_lib_project`) differ. Every top-level function
+    /// is exported, same reasoning as `compile_c`'s `keep_all_functions`
+    /// call - a library has no `main` to trace reachability from.
+    pub fn compile_lib(&mut self, source_path: &Path, opt_level: u8) -> Result<PathBuf> {
+        print_info(&format!("Compiling {} as a static/rlib library...", source_path.display()));
+        let opt_level = OptLevel::from_flag(opt_level);
+        self.remarks.clear();
+
+        let source = fs::read_to_string(source_path)?;
+
+        print_info("Parsing Python...");
+        let mut program = AdrenalineParser::parse(&source)?;
+        Self::resolve_local_imports(source_path, &mut program, &mut std::collections::HashSet::new())?;
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
+
+        print_info("Running type inference...");
+        let mut type_inference = TypeInference::new();
+        type_inference.infer_program(&mut program);
+
+        print_info("Lowering to IR...");
+        let mut module = IRLowering::lower_program(&program);
+
+        print_info("Optimizing...");
+        let mut optimizer = self.new_optimizer();
+        optimizer.keep_all_functions();
+        optimizer.optimize(&mut module);
+        self.remarks = optimizer.remarks().to_vec();
+
+        let lib_name = format!(
+            "{}_adrenaline",
+            source_path.file_stem().and_then(|s| s.to_str()).unwrap_or("adrenaline_lib")
+        );
+
+        print_info("Generating Rust library...");
+        let mut codegen = IRCodegen::new();
+        let rust_code = codegen.generate_lib(&module);
+        let header_guard = format!("{}_H", lib_name.to_uppercase());
+        let c_header = IRCodegen::generate_c_header(&module, &header_guard);
+
+        self.build_lib_project(source_path, &rust_code, &c_header, &lib_name, opt_level)
+    }
+
+    /// Runs `compile`'s parse/infer/lower/optimize pipeline but stops short
+    /// of producing a binary, returning the text of an intermediate artifact
+    /// instead - for `Artifact::Ir` that's the optimized IR module itself
+    /// (no codegen or `rustc` involved); for `Artifact::Asm`/`Artifact::LlvmIr`
+    /// it's `rustc`'s own `--emit` output for the generated crate, via
+    /// `build_and_emit_rustc_artifact`.
+    pub fn emit_artifact(&mut self, source_path: &Path, opt_level: u8, artifact: Artifact) -> Result<String> {
+        print_info(&format!("Compiling {}...", source_path.display()));
+        let opt_level = OptLevel::from_flag(opt_level);
+        self.remarks.clear();
+
+        let source = fs::read_to_string(source_path)?;
+
+        print_info("Parsing Python...");
+        let mut program = AdrenalineParser::parse(&source)?;
+        Self::resolve_local_imports(source_path, &mut program, &mut std::collections::HashSet::new())?;
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
+
+        print_info("Running type inference...");
+        let mut type_inference = TypeInference::new();
+        type_inference.infer_program(&mut program);
+
+        print_info("Lowering to IR...");
+        let mut module = IRLowering::lower_program(&program);
+
+        print_info("Optimizing...");
+        let mut optimizer = self.new_optimizer();
+        optimizer.optimize(&mut module);
+        self.remarks = optimizer.remarks().to_vec();
+
+        if artifact == Artifact::Ir {
+            return Ok(format!("{:#?}", module));
+        }
+
+        print_info("Generating Rust code...");
+        let mut codegen = IRCodegen::new();
+        codegen.set_provenance(&source_path.display().to_string(), &self.remarks);
+        codegen.set_overflow_mode(self.overflow_mode.unwrap_or(if opt_level == OptLevel::O0 {
+            OverflowMode::Checked
+        } else {
+            OverflowMode::Wrap
+        }));
+        let rust_code = codegen.generate(&module);
+
+        let (rustc_emit, out_ext) = match artifact {
+            Artifact::Asm => ("asm", "s"),
+            Artifact::LlvmIr => ("llvm-ir", "ll"),
+            Artifact::Ir => unreachable!("handled above"),
+        };
+        self.build_and_emit_rustc_artifact(source_path, &rust_code, opt_level, rustc_emit, out_ext)
+    }
+
+    /// Runs `compile`'s parse/infer/lower/optimize/codegen pipeline and
+    /// returns the generated Rust source as a string, stopping short of
+    /// `build_rust_project` - for users who want to vendor the translation
+    /// into an existing Rust project, or review it before ever invoking
+    /// `cargo`.
+    pub fn emit(&mut self, source_path: &Path, opt_level: u8) -> Result<String> {
+        print_info(&format!("Translating {}...", source_path.display()));
+        let opt_level = OptLevel::from_flag(opt_level);
+        self.remarks.clear();
+
+        let source = fs::read_to_string(source_path)?;
+
+        print_info("Parsing Python...");
+        let mut program = AdrenalineParser::parse(&source)?;
+        Self::resolve_local_imports(source_path, &mut program, &mut std::collections::HashSet::new())?;
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
+
+        print_info("Running type inference...");
+        let mut type_inference = TypeInference::new();
+        type_inference.infer_program(&mut program);
+
+        print_info("Lowering to IR...");
+        let mut module = IRLowering::lower_program(&program);
+        Self::attach_python_fallbacks(&mut module, &source);
+
+        print_info("Optimizing...");
+        let mut optimizer = self.new_optimizer();
+        optimizer.optimize(&mut module);
+        self.remarks = optimizer.remarks().to_vec();
+
+        print_info("Generating Rust code...");
+        let mut codegen = IRCodegen::new();
+        codegen.set_provenance(&source_path.display().to_string(), &self.remarks);
+        codegen.set_overflow_mode(self.overflow_mode.unwrap_or(if opt_level == OptLevel::O0 {
+            OverflowMode::Checked
+        } else {
+            OverflowMode::Wrap
+        }));
+        Ok(codegen.generate(&module))
+    }
+
+    /// Discovers top-level `test_*` functions, compiles them alongside the
+    /// rest of the file into one binary the way `compile_lib` does (`--emit`
+    /// as C via `generate_lib`, `keep_all_functions` so a test isn't DCE'd
+    /// away just because nothing else calls it), and runs that binary
+    /// natively, printing a `cargo test`-style pass/fail line per test.
+    /// `assert` isn't a statement this parser recognizes (see
+    /// `parser::PythonParser`), so a test function is a plain predicate:
+    /// returning `True` is a pass, `False` a failure, and a panic (e.g. a
+    /// checked-arithmetic overflow) also counts as a failure.
+    pub fn test(&mut self, source_path: &Path, opt_level: u8) -> Result<()> {
+        print_info(&format!("Testing {}...", source_path.display()));
+        let opt_level = OptLevel::from_flag(opt_level);
+        self.remarks.clear();
+
+        let source = fs::read_to_string(source_path)?;
+
+        print_info("Parsing Python...");
+        let mut program = AdrenalineParser::parse(&source)?;
+        Self::resolve_local_imports(source_path, &mut program, &mut std::collections::HashSet::new())?;
+        self.apply_config_directives(&mut program);
+        self.apply_crate_directives(&program);
+
+        let has_test_fn = program
+            .statements
+            .iter()
+            .any(|stmt| matches!(stmt, Statement::FunctionDef(func) if func.name.starts_with("test_")));
+        if !has_test_fn {
+            print_info("No test_* functions found");
+            return Ok(());
+        }
+
+        print_info("Running type inference...");
+        let mut type_inference = TypeInference::new();
+        type_inference.infer_program(&mut program);
+
+        let mut runnable = Vec::new();
+        for stmt in &program.statements {
+            let Statement::FunctionDef(func) = stmt else { continue };
+            if !func.name.starts_with("test_") {
+                continue;
+            }
+            if !func.params.is_empty() {
+                print_warning(&format!("Skipping {} (test functions take no parameters)", func.name));
+            } else if func.return_type != crate::ast_types::Type::Bool {
+                print_warning(&format!(
+                    "Skipping {} (must return bool: True for pass, False for fail)",
+                    func.name
+                ));
+            } else {
+                runnable.push(func.name.clone());
+            }
+        }
+        if runnable.is_empty() {
+            print_error("No runnable test_* functions found");
+            return Err(anyhow!("no runnable tests"));
+        }
+
+        print_info("Lowering to IR...");
+        let mut module = IRLowering::lower_program(&program);
+
+        print_info("Optimizing...");
+        let mut optimizer = self.new_optimizer();
+        optimizer.keep_all_functions();
+        optimizer.optimize(&mut module);
+        self.remarks = optimizer.remarks().to_vec();
+
+        print_info("Generating Rust code...");
+        let mut codegen = IRCodegen::new();
+        let mut rust_code = codegen.generate_lib(&module);
+        rust_code.push_str(&Self::generate_test_harness(&runnable));
+
+        let binary = self.build_rust_project(source_path, &rust_code, opt_level, true)?;
+
+        let status = Command::new(&binary).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!("one or more tests failed"))
+        }
+    }
+
+    /// Synthesized `fn main` appended to `test`'s `generate_lib` output -
+    /// calls each runnable `test_*` function under `catch_unwind`, so a
+    /// panic in one test is reported as that test's failure instead of
+    /// aborting the whole run, then prints a `cargo test`-style summary
+    /// line and exits non-zero if anything failed.
+    fn generate_test_harness(names: &[String]) -> String {
+        let mut out = String::new();
+        writeln!(out, "\nfn main() {{").ok();
+        writeln!(out, "    let mut passed = 0usize;").ok();
+        writeln!(out, "    let mut failed = 0usize;").ok();
+        for name in names {
+            writeln!(out, "    match std::panic::catch_unwind(|| {name}()) {{").ok();
+            writeln!(out, "        Ok(true) => {{ println!(\"test {name} ... ok\"); passed += 1; }}").ok();
+            writeln!(
+                out,
+                "        Ok(false) => {{ println!(\"test {name} ... FAILED (returned False)\"); failed += 1; }}"
+            )
+            .ok();
+            writeln!(out, "        Err(payload) => {{").ok();
+            writeln!(
+                out,
+                "            let message = payload.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| payload.downcast_ref::<String>().cloned()).unwrap_or_else(|| \"panicked\".to_string());"
+            )
+            .ok();
+            writeln!(out, "            println!(\"test {name} ... FAILED ({{message}})\");").ok();
+            writeln!(out, "            failed += 1;").ok();
+            writeln!(out, "        }}").ok();
+            writeln!(out, "    }}").ok();
+        }
+        writeln!(out, "    println!();").ok();
+        writeln!(
+            out,
+            "    println!(\"test result: {{}}. {{}} passed; {{}} failed\", if failed == 0 {{ \"ok\" }} else {{ \"FAILED\" }}, passed, failed);"
+        )
+        .ok();
+        writeln!(out, "    std::process::exit(if failed == 0 {{ 0 }} else {{ 1 }});").ok();
+        writeln!(out, "}}").ok();
+        out
+    }
+
+    /// `generate_c` has no `main` to link (see its doc comment), so there's
+    /// no executable to produce here the way `build_rust_project` does -
+    /// the deliverable is the `.c` file itself, copied next to the source
+    /// the same way `build_python_extension` copies out a `.so`. It's still
+    /// smoke-tested by compiling it to an object file with `cc` first, so a
+    /// build failure is caught here instead of silently shipping C that
+    /// doesn't actually compile.
+    fn build_c_project(&self, source_path: &Path, c_code: &str, opt_level: OptLevel) -> Result<PathBuf> {
+        let adrenaline_home = dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".adrenaline");
+
+        fs::create_dir_all(&adrenaline_home)?;
+
+        let source_hash = Cache::get_hash(&fs::read_to_string(source_path)?);
+        let build_dir = adrenaline_home.join(format!("cbuild_{}", &source_hash[..8]));
+        fs::create_dir_all(&build_dir)?;
+
+        let source_stem = source_path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+        let c_source = build_dir.join(format!("{source_stem}.c"));
+        fs::write(&c_source, c_code)?;
+
+        let object_file = build_dir.join(format!("{source_stem}.o"));
+        let mut cmd = Command::new("cc");
+        if self.sandbox {
+            Self::scrub_env(&mut cmd);
+        }
+        cmd.arg("-c")
+            .arg(&c_source)
+            .arg("-o")
+            .arg(&object_file)
+            .arg(format!("-O{}", opt_level.cargo_opt_level()));
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            print_error(&format!("C compilation failed:\n{}", stderr));
+            return Err(anyhow!(stderr));
+        }
+
+        let source_dir = source_path.parent().unwrap_or_else(|| Path::new("."));
+        let output_c = source_dir.join(format!("{source_stem}.c"));
+        fs::copy(&c_source, &output_c)?;
+        print_success(&format!("Successfully compiled to {}", output_c.display()));
+        Ok(output_c)
+    }
+
+    /// Follows `import mymodule` / `from mymodule import ...` to a sibling
+    /// `mymodule.py` file next to `source_path` and splices its top-level
+    /// `def`/`class` statements into `program`, so a function or class
+    /// defined in another file of the same project is visible to the one
+    /// being compiled - `IRLowering` already treats every top-level
+    /// `def`/`class` as one flat namespace regardless of which file it came
+    /// from, the same way the single generated `main.rs` would if you'd
+    /// pasted both files together by hand, so no codegen changes are needed
+    /// once the ASTs are merged here. An import with no matching sibling
+    /// file (a stdlib module like `math`, or a real third-party package) is
+    /// left alone, same as before this existed. Only definitions travel
+    /// across the import, not a module's own top-level script statements -
+    /// Python only runs an imported module's `if __name__ == "__main__":`
+    /// body when it's executed directly, not when it's imported, and a
+    /// module's other top-level statements (bare assignments, print calls)
+    /// are a narrower case left unhandled for now. `visited` guards against
+    /// re-splicing the same file twice, for both a diamond import and an
+    /// accidental import cycle.
+    fn resolve_local_imports(
+        source_path: &Path,
+        program: &mut Program,
+        visited: &mut std::collections::HashSet<PathBuf>,
+    ) -> Result<()> {
+        let canonical = source_path.canonicalize().unwrap_or_else(|_| source_path.to_path_buf());
+        if !visited.insert(canonical) {
+            return Ok(());
+        }
+        let source_dir = source_path.parent().unwrap_or_else(|| Path::new("."));
+
+        for import in program.imports.clone() {
+            let module_path = source_dir.join(format!("{}.py", import.module));
+            if !module_path.is_file() {
+                continue;
+            }
+            let module_source = fs::read_to_string(&module_path)?;
+            let mut module_program = AdrenalineParser::parse(&module_source)?;
+            Self::resolve_local_imports(&module_path, &mut module_program, visited)?;
+
+            let existing: std::collections::HashSet<String> = program
+                .statements
+                .iter()
+                .filter_map(Self::definition_name)
+                .map(str::to_string)
+                .collect();
+            for stmt in module_program.statements {
+                if let Some(name) = Self::definition_name(&stmt) {
+                    // `from x import a, b` only brings in the named
+                    // definitions; a bare `import x` (or `from x import
+                    // *`, which the parser doesn't distinguish from it -
+                    // see `parse_import`) has no such list, so everything
+                    // is spliced in.
+                    let wanted = match &import.items {
+                        Some(items) => items.iter().any(|i| i == name),
+                        None => true,
+                    };
+                    if wanted && !existing.contains(name) {
+                        program.statements.push(stmt);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The name a top-level `def`/`class` statement defines, for
+    /// `resolve_local_imports`'s de-duplication - `None` for every other
+    /// statement kind, which an import never carries across.
+    fn definition_name(stmt: &Statement) -> Option<&str> {
+        match stmt {
+            Statement::FunctionDef(f) => Some(f.name.as_str()),
+            Statement::ClassDef(c) => Some(c.name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Adds `adrenaline.toml`'s `[directives]` glob matches onto every
+    /// top-level function's own directive comments, skipping any name a
+    /// function already carries - a config entry only ever adds directives,
+    /// never removes one written in the source. Only top-level `def`s are
+    /// covered, the same scope `resolve_local_imports` uses for splicing.
+    fn apply_config_directives(&self, program: &mut Program) {
+        if self.project_config.directives.is_empty() {
+            return;
+        }
+        for stmt in &mut program.statements {
+            if let Statement::FunctionDef(func) = stmt {
+                for directive in self.project_config.directives_for(&func.name) {
+                    if !func.directives.contains(&directive) {
+                        func.directives.push(directive);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Merges `#adrenaline:crate name=version` directives (e.g.
+    /// `#adrenaline:crate ndarray=0.15`) into `self.project_config`'s
+    /// `[dependencies]`, the same map `adrenaline.toml`'s `[dependencies]`
+    /// feeds into `write_cargo_toml` - so hand-written code that leans on an
+    /// ecosystem crate doesn't need a project-wide config entry just for
+    /// itself. A project-level `adrenaline.toml` version wins over a
+    /// directive naming the same crate, on the theory that the team's
+    /// pinned version is more deliberate than an individual function's ask.
+    /// `Directive::from_string` doesn't recognize `crate ...` (it's not a
+    /// compiler behavior flag), so this reads the raw directive strings
+    /// directly rather than going through `DirectiveSet`. Only top-level
+    /// `def`s are covered, the same scope `apply_config_directives` uses.
+    ///
+    /// Ignored entirely under `--sandbox`: `pin_lockfile` caches one
+    /// `Cargo.lock` under `adrenaline_home/sandbox/` on the assumption that
+    /// every sandboxed build resolves the exact same `[dependencies]` table,
+    /// which only holds if source itself can never add one. Letting a
+    /// directive through here would let untrusted Python source expand its
+    /// own dependency set - and, via the shared lockfile, either fail a
+    /// differently-configured concurrent build outright or hand it a stale
+    /// resolution for a crate it never asked to pin.
+    fn apply_crate_directives(&mut self, program: &Program) {
+        for stmt in &program.statements {
+            let Statement::FunctionDef(func) = stmt else { continue };
+            for directive in &func.directives {
+                let Some(spec) = directive.trim().strip_prefix("crate ") else { continue };
+                if self.sandbox {
+                    print_warning(&format!(
+                        "ignoring `#adrenaline:crate {spec}` on {} under --sandbox (add it to adrenaline.toml instead)",
+                        func.name
+                    ));
+                    continue;
+                }
+                let Some((name, version)) = spec.trim().split_once('=') else {
+                    print_error(&format!(
+                        "malformed `#adrenaline:crate` directive on {}: expected `crate name=version`, got `crate {spec}`",
+                        func.name
+                    ));
+                    continue;
+                };
+                self.project_config
+                    .dependencies
+                    .entry(name.trim().to_string())
+                    .or_insert_with(|| version.trim().to_string());
+            }
+        }
+    }
+
+    /// For every function still carrying `#adrenaline:no-compile` after
+    /// lowering, slices its exact source text out of `source` (see
+    /// `slice_function_source`) and stashes it on the `IRFunction` so
+    /// `IRCodegen::generate_function` can embed it as a PyO3 fallback
+    /// instead of translating the (possibly unsupported) lowered body -
+    /// this is what lets a partially-supported script still produce one
+    /// working binary rather than failing the whole build.
+    fn attach_python_fallbacks(module: &mut IRModule, source: &str) {
+        let lines: Vec<&str> = source.lines().collect();
+        for function in &mut module.functions {
+            if function.directives.should_compile() {
+                continue;
+            }
+            if let Some(def_line) = function.source_line {
+                function.python_source = Self::slice_function_source(&lines, def_line);
+            }
+        }
+    }
+
+    /// The original source lines making up the `def` at `def_line` (1-based)
+    /// and its whole body - found the same way the line-oriented `parser.rs`
+    /// itself finds a block's end, by walking forward until a non-blank line
+    /// dedents to (or past) the `def`'s own indentation, since nothing
+    /// upstream of `IRLowering` keeps source spans alongside the AST.
+    fn slice_function_source(lines: &[&str], def_line: usize) -> Option<String> {
+        let start = def_line.checked_sub(1)?;
+        let header = *lines.get(start)?;
+        let base_indent = header.len() - header.trim_start().len();
+        let mut end = start + 1;
+        while end < lines.len() {
+            let line = lines[end];
+            let indent = line.len() - line.trim_start().len();
+            if !line.trim().is_empty() && indent <= base_indent {
+                break;
+            }
+            end += 1;
+        }
+        Some(lines[start..end].join("\n"))
+    }
+
+    /// Every `#adrenaline:*` directive comment in `source`, for
+    /// `BuildManifest::directives_seen` - a plain text scan rather than
+    /// reading them off the parsed `Program` so it also works on a cache
+    /// hit, which skips parsing entirely. Deduplicated and sorted, since a
+    /// manifest is asking "which directives does this script use", not a
+    /// per-line log.
+    fn scan_directives(source: &str) -> Vec<String> {
+        let mut directives: Vec<String> = source
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('#')?.trim().strip_prefix("adrenaline:"))
+            .map(|d| d.trim().to_string())
+            .collect();
+        directives.sort();
+        directives.dedup();
+        directives
+    }
+
+    /// Everything besides `source` itself that changes what `compile_internal`
+    /// emits for it, folded into the whole-file cache key alongside `source` -
+    /// the crate's own version (a codegen fix between releases must not serve
+    /// an old release's cached Rust), `opt_level` and `--target` (change the
+    /// build, and `opt_level` also picks the default overflow policy),
+    /// `--overflow`/`--fast-math` (override that policy directly), and
+    /// `adrenaline.toml`'s `[directives]` (injected into functions by
+    /// `apply_config_directives` without ever touching `source`'s own text),
+    /// `--profile-instrument`/`--profile-alloc`/`--profile-hwcounters`/
+    /// `--live` (an instrumented and a plain build must never share a cached
+    /// entry - `--profile-alloc`/`--profile-hwcounters`/`--live` each change
+    /// the whole-file boilerplate on top of what `--profile-instrument`
+    /// already changes), and `profile_data` (`--profile-use`'s loaded
+    /// contents - a stale hot-function promotion must be a cache miss just
+    /// like a changed `--opt-level` would be). Without this, editing only
+    /// `adrenaline.toml`, an `--opt-level`/`--overflow`/`--profile-instrument`/
+    /// `--profile-alloc` flag, or the `--profile-use` file between two
+    /// builds of the same unchanged script would silently keep serving the
+    /// previous, now-incompatible cache entry - the "workaround" this key
+    /// exists to make unnecessary.
+    fn cache_key_material(
+        &self,
+        source: &str,
+        opt_level: OptLevel,
+        profile_data: &[crate::profiler::ProfileData],
+    ) -> String {
+        format!(
+            "{}|{}|{:?}|{:?}|{:?}|{:?}|{}|{}|{}|{}|{}|{}|{:?}",
+            env!("CARGO_PKG_VERSION"),
+            source,
+            opt_level,
+            self.project_config.target,
+            self.project_config.directives,
+            self.overflow_mode,
+            self.fast_math,
+            self.profile_instrument,
+            self.profile_alloc,
+            self.profile_lines,
+            self.profile_hwcounters,
+            self.profile_live,
+            profile_data,
+        )
+    }
+
+    /// Checks `rustup target list --installed` for `target` before handing
+    /// it to `cargo build --target`, which otherwise fails with a generic
+    /// "can't find crate for `core`" error that doesn't say what's actually
+    /// missing. Best-effort like `rustfmt` above: if `rustup` itself isn't on
+    /// `PATH` (e.g. a non-rustup toolchain), the check is skipped rather than
+    /// blocking a build that might well succeed.
+    fn verify_target_installed(target: &str) -> Result<()> {
+        let Ok(output) = Command::new("rustup").args(["target", "list", "--installed"]).output() else {
+            return Ok(());
+        };
+        if !output.status.success() {
+            return Ok(());
+        }
+        let installed = String::from_utf8_lossy(&output.stdout);
+        if installed.lines().any(|line| line.trim() == target) {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "target '{target}' is not installed - run `rustup target add {target}`"
+        ))
+    }
+
+    /// Every generated crate (`build_rust_project`,
+    /// `build_and_emit_rustc_artifact`, `build_python_extension`) shares this
+    /// one `target/` instead of each getting its own under its own
+    /// `build_<hash>`/`pylib_<hash>` directory - dependency versions are
+    /// pinned identically across all of them (see `write_cargo_toml`), so
+    /// `rayon`, `num-bigint`, and friends only pay their cold-build cost
+    /// once, ever, instead of on every source file's first build. Honors an
+    /// already-set `CARGO_TARGET_DIR` the way `cargo` itself would, for
+    /// anyone who wants to point it somewhere else (e.g. a faster disk).
+    fn shared_target_dir(adrenaline_home: &Path) -> PathBuf {
+        std::env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| adrenaline_home.join("target"))
+    }
+
+    /// Whether `rust_code` can skip `cargo` and go straight through
+    /// `rustc` - true only when nothing about this build actually needs
+    /// dependency resolution: no `adrenaline.toml`/`#adrenaline:crate`
+    /// dependencies, no `--target` cross-build (`rustc` alone can't fetch a
+    /// cross std the way `cargo build --target` does), no
+    /// `#adrenaline:simd` chunk (needs `target-cpu=native`, set via
+    /// `write_cargo_config`), and none of the crates every generated
+    /// `Cargo.toml` otherwise carries unconditionally
+    /// (`rayon`/`num-bigint`/`num-traits`/`num-integer`/`rand`/`once_cell`,
+    /// see `write_cargo_toml`, plus `adrenaline-runtime` from
+    /// `ensure_runtime_crate`) are actually referenced by the generated
+    /// code.
+    fn can_skip_cargo(&self, rust_code: &str) -> bool {
+        const OPTIONAL_CRATE_MARKERS: &[&str] = &[
+            "rayon::",
+            "num_bigint::",
+            "num_traits::",
+            "num_integer::",
+            "rand::",
+            "once_cell::",
+            "adrenaline_runtime::",
+        ];
+        self.project_config.dependencies.is_empty()
+            && self.project_config.target.is_none()
+            && !rust_code.contains(SIMD_CHUNK_MARKER)
+            && !OPTIONAL_CRATE_MARKERS.iter().any(|marker| rust_code.contains(marker))
+    }
+
+    /// `can_skip_cargo`'s fast path: `rustc -C opt-level=... main.rs`
+    /// directly, mirroring whichever `[profile.*]` `active_profile` says
+    /// `write_cargo_toml` would otherwise put in the generated Cargo.toml.
+    fn build_via_rustc(
+        &self,
+        source_path: &Path,
+        main_rs: &Path,
+        build_dir: &Path,
+        rust_code: &str,
+        opt_level: OptLevel,
+    ) -> Result<PathBuf> {
+        let binary_path = build_dir.join(if cfg!(target_os = "windows") { "main.exe" } else { "main" });
+        let profile = self.active_profile(opt_level);
+
+        let mut cmd = Command::new("rustc");
+        if self.sandbox {
+            Self::scrub_env(&mut cmd);
+        }
+        cmd.arg(main_rs).arg("-o").arg(&binary_path);
+        cmd.arg(format!("-Copt-level={}", profile.opt_level));
+        cmd.arg(format!("-Ccodegen-units={}", profile.codegen_units));
+        cmd.arg(format!("-Cdebuginfo={}", if profile.debug { 2 } else { 0 }));
+        if profile.lto {
+            cmd.arg("-Clto");
+        }
+        if profile.panic_abort {
+            cmd.arg("-Cpanic=abort");
+        }
+        if self.deterministic {
+            cmd.arg(Self::deterministic_remap_flag(build_dir));
+            cmd.env("SOURCE_DATE_EPOCH", "0");
+        }
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let source_map = IRCodegen::build_source_map(rust_code);
+            let annotated = Self::translate_generated_lines(&stderr, source_path, &source_map);
+            print_error(&format!("Rust compilation failed:\n{}", annotated));
+            return Err(anyhow!(annotated));
+        }
+
+        Ok(binary_path)
+    }
+
+    /// An animated "cargo build..." spinner for the blocking `cmd.output()`
+    /// call in `build_via_cargo` - real cargo progress would need
+    /// `--message-format=json` and a parser for it, so this settles for
+    /// showing *something* is happening during what's usually the slowest
+    /// part of a build. `None` in `--quiet`/`--format json`, where a
+    /// spinner would just be noise (or corrupt the JSON stream).
+    fn start_build_spinner(message: &str) -> Option<indicatif::ProgressBar> {
+        if quiet_mode() || json_mode() {
+            return None;
+        }
+        let pb = indicatif::ProgressBar::new_spinner();
+        pb.set_style(indicatif::ProgressStyle::with_template("{spinner:.green} {msg}").unwrap());
+        pb.set_message(message.to_string());
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
+        Some(pb)
+    }
+
+    /// The full `cargo build` path, for anything `can_skip_cargo` rules out.
+    fn build_via_cargo(
+        &self,
+        source_path: &Path,
+        cargo_toml: &Path,
+        target_dir: &Path,
+        rust_code: &str,
+        opt_level: OptLevel,
+    ) -> Result<PathBuf> {
+        let profile = self.active_profile(opt_level);
+        let mut cmd = Command::new("cargo");
+        if self.sandbox {
+            Self::scrub_env(&mut cmd);
+        }
+        cmd.arg("build").arg("--manifest-path").arg(cargo_toml);
+        cmd.env("CARGO_TARGET_DIR", target_dir);
+        if profile.dir == "release" {
+            cmd.arg("--release");
+        }
+        if let Some(target) = &self.project_config.target {
+            cmd.arg("--target").arg(target);
+        }
+        if self.deterministic {
+            let build_dir = cargo_toml.parent().unwrap_or(cargo_toml);
+            cmd.env("RUSTFLAGS", Self::deterministic_remap_flag(build_dir));
+            cmd.env("SOURCE_DATE_EPOCH", "0");
+        }
+        if self.sandbox {
+            let build_dir = cargo_toml.parent().unwrap_or(cargo_toml);
+            self.pin_lockfile(build_dir)?;
+            cmd.arg("--offline").arg("--locked");
+        }
+        let progress = Self::start_build_spinner("cargo build...");
+        let output = cmd.output()?;
+        if let Some(progress) = progress {
+            progress.finish_and_clear();
+        }
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let source_map = IRCodegen::build_source_map(rust_code);
+            let annotated = Self::translate_generated_lines(&stderr, source_path, &source_map);
+            print_error(&format!("Rust compilation failed:\n{}", annotated));
+            return Err(anyhow!(annotated));
+        }
+
+        if self.sandbox {
+            let build_dir = cargo_toml.parent().unwrap_or(cargo_toml);
+            Self::cache_lockfile(build_dir)?;
+        }
+
+        // `--target <triple>` builds land under `<triple>/<profile>/` within
+        // `target_dir` instead of `<profile>/` directly.
+        let target_subdir = match &self.project_config.target {
+            Some(target) => format!("{}/{}", target, profile.dir),
+            None => profile.dir.to_string(),
+        };
+        let is_windows_binary = match &self.project_config.target {
+            Some(target) => target.contains("windows"),
+            None => cfg!(target_os = "windows"),
+        };
+        Ok(target_dir
+            .join(target_subdir)
+            .join(if is_windows_binary { "main.exe" } else { "main" }))
+    }
+
+    /// `--deterministic`'s toolchain pin shared by both build backends:
+    /// `build_dir`'s absolute path otherwise ends up embedded in debug info
+    /// and any `file!()`/panic-location string, and that path differs
+    /// across machines (and even across `$HOME`s on the same one), which
+    /// alone would make two honest builds of the same source disagree.
+    fn deterministic_remap_flag(build_dir: &Path) -> String {
+        format!("--remap-path-prefix={}=/adrenaline-build", build_dir.display())
+    }
+
+    /// `--sandbox`'s environment scrub, shared by every build subprocess:
+    /// clears whatever the caller's shell happened to export - API keys,
+    /// tokens, proxy settings, anything a dependency's build script could
+    /// read - and re-adds only the handful of variables the toolchain
+    /// itself needs to find `rustc`/`cargo` and their caches. Must be
+    /// called before any other `.env(...)` call on the same `Command`,
+    /// since `env_clear` wipes the whole map being built, not just the
+    /// inherited one.
+    fn scrub_env(cmd: &mut Command) {
+        const KEEP: &[&str] = &["PATH", "HOME", "USERPROFILE", "CARGO_HOME", "RUSTUP_HOME"];
+        cmd.env_clear();
+        for name in KEEP {
+            if let Ok(value) = std::env::var(name) {
+                cmd.env(name, value);
+            }
+        }
+    }
+
+    /// `--sandbox`'s dependency pin: `cargo build --offline --locked` refuses
+    /// to run at all without a `Cargo.lock` already sitting next to the
+    /// manifest, so a fresh `build_dir` needs one copied in before the
+    /// scrubbed, network-less build below can succeed. Every generated
+    /// project's `[dependencies]` table is the same fixed template (see
+    /// `write_cargo_toml`) - `apply_crate_directives` refuses to let
+    /// untrusted Python source add to it while sandboxed, so only
+    /// `adrenaline.toml` can - so a single lockfile cached
+    /// under `adrenaline_home/sandbox/Cargo.lock` and refreshed after each
+    /// successful sandboxed build covers every project. The very first
+    /// sandboxed build on a machine with no cache yet and no local registry
+    /// still needs one non-sandboxed build to seed it, the same way
+    /// `cargo build --locked` needs a committed lockfile in CI.
+    fn pin_lockfile(&self, build_dir: &Path) -> Result<()> {
+        let adrenaline_home = dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".adrenaline");
+        let cache_dir = adrenaline_home.join("sandbox");
+        let cached_lock = cache_dir.join("Cargo.lock");
+        let project_lock = build_dir.join("Cargo.lock");
+        if !project_lock.exists() && cached_lock.exists() {
+            fs::create_dir_all(build_dir)?;
+            fs::copy(&cached_lock, &project_lock)?;
+        }
+        Ok(())
+    }
+
+    /// Refreshes `pin_lockfile`'s cache after a sandboxed build actually
+    /// succeeded, so the next one (possibly for a different source file)
+    /// can reuse a lockfile that's known to resolve.
+    fn cache_lockfile(build_dir: &Path) -> Result<()> {
+        let adrenaline_home = dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".adrenaline");
+        let cache_dir = adrenaline_home.join("sandbox");
+        let project_lock = build_dir.join("Cargo.lock");
+        if project_lock.exists() {
+            fs::create_dir_all(&cache_dir)?;
+            fs::copy(&project_lock, cache_dir.join("Cargo.lock"))?;
+        }
+        Ok(())
+    }
+
+    /// `--deterministic`'s self-check: rebuilds the same generated crate a
+    /// second time and compares the resulting binary's hash against the
+    /// first, since the flags above only *intend* reproducibility - this
+    /// catches anything they missed instead of taking it on faith. A
+    /// mismatch is reported but doesn't fail the build; the first binary is
+    /// still a perfectly good artifact, just not a verified-reproducible one.
+    #[allow(clippy::too_many_arguments)]
+    fn verify_reproducible(
+        &self,
+        source_path: &Path,
+        main_rs: &Path,
+        cargo_toml: &Path,
+        build_dir: &Path,
+        target_dir: &Path,
+        rust_code: &str,
+        opt_level: OptLevel,
+        first_binary: &Path,
+    ) -> Result<()> {
+        let first_hash = Cache::get_hash_bytes(&fs::read(first_binary)?);
+        let rebuilt = if self.can_skip_cargo(rust_code) {
+            self.build_via_rustc(source_path, main_rs, build_dir, rust_code, opt_level)?
+        } else {
+            self.build_via_cargo(source_path, cargo_toml, target_dir, rust_code, opt_level)?
+        };
+        let second_hash = Cache::get_hash_bytes(&fs::read(&rebuilt)?);
+
+        if first_hash == second_hash {
+            print_success("Reproducible: rebuild produced a byte-identical binary");
+        } else {
+            print_error("Not reproducible: a second build produced a different binary");
+        }
+        Ok(())
+    }
+
+    fn build_rust_project(&self, source_path: &Path, rust_code: &str, opt_level: OptLevel, copy_to_source: bool) -> Result<PathBuf> {
+        // Covers the same span `--manifest`'s `build_duration_ms` reports -
+        // everything below, cache-hit or not, since a cache hit still pays
+        // for the actual `rustc`/`cargo` invocation this function makes.
+        let build_start = Instant::now();
+
+        // `panic = "abort"` makes `std::panic::catch_unwind` unable to catch
+        // anything, which would turn a `try`/`except` (or a failing test
+        // under `generate_test_harness`) into a silent crash instead of
+        // running its handler - refuse the build instead of shipping a
+        // binary that quietly does the wrong thing.
+        let profile = self.active_profile(opt_level);
+        if profile.panic_abort && rust_code.contains(CATCH_UNWIND_MARKER) {
+            return Err(anyhow!(
+                "profile \"{}\" sets panic = \"abort\", but this program uses try/except (or has tests), which relies on catching a panic to run its handler - that can't work under panic=abort. Remove panic_abort from this profile, or remove the try/except.",
+                profile.name
+            ));
+        }
+
+        // Use ~/.adrenaline/ for all temporary files
+        let adrenaline_home = dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".adrenaline");
+        
+        fs::create_dir_all(&adrenaline_home)?;
+        
+        // Create unique build directory based on source file hash
+        let source_hash = Cache::get_hash(&fs::read_to_string(source_path)?);
+        let build_dir = adrenaline_home.join(format!("build_{}", &source_hash[..8]));
+        let src_dir = build_dir.join("src");
+
+        // Create directories
+        fs::create_dir_all(&src_dir)?;
+
+        // Write Rust source
+        let main_rs = src_dir.join("main.rs");
+        fs::write(&main_rs, rust_code)?;
+
+        // Best-effort: an unformatted file is still valid input to `cargo
+        // build` below, so a missing/failing `rustfmt` isn't a build error,
+        // just a less readable one for anyone auditing the output.
+        let _ = Command::new("rustfmt").arg(&main_rs).output();
+
+        let runtime_crate_dir = Self::ensure_runtime_crate(&adrenaline_home)?;
+
+        // The Cargo profile depends on the requested opt level, so it's
+        // rewritten on every build rather than only when missing.
+        let cargo_toml = build_dir.join("Cargo.toml");
+        self.write_cargo_toml(&cargo_toml, &runtime_crate_dir, opt_level)?;
+
+        // `#adrenaline:simd` chunks are ordinary scalar arithmetic that
+        // relies on LLVM's SLP vectorizer to pack into real SIMD
+        // instructions - which only pays off once the target supports a
+        // wide enough vector register, so `target-cpu=native` is only
+        // turned on for a build that actually contains a chunk worth it.
+        if rust_code.contains(SIMD_CHUNK_MARKER) {
+            self.write_cargo_config(&build_dir)?;
+        }
+
+        if let Some(target) = &self.project_config.target {
+            Self::verify_target_installed(target)?;
+        }
+
+        let target_dir = Self::shared_target_dir(&adrenaline_home);
+
+        // A script with no external crates to resolve doesn't need cargo at
+        // all - `rustc` directly on `main.rs` skips cargo's dependency
+        // resolution and fingerprinting, which dominates the build time for
+        // a small generated file (see `can_skip_cargo`).
+        let build_binary = if self.can_skip_cargo(rust_code) {
+            self.build_via_rustc(source_path, &main_rs, &build_dir, rust_code, opt_level)?
+        } else {
+            self.build_via_cargo(source_path, &cargo_toml, &target_dir, rust_code, opt_level)?
+        };
+
+        if !build_binary.exists() {
+            return Err(anyhow!("Binary not found after compilation"));
+        }
+
+        if self.deterministic {
+            self.verify_reproducible(
+                source_path,
+                &main_rs,
+                &cargo_toml,
+                &build_dir,
+                &target_dir,
+                rust_code,
+                opt_level,
+                &build_binary,
+            )?;
+        }
+
+        if !copy_to_source {
+            // `compile_ephemeral`'s whole point is to leave nothing next to
+            // `source_path` - the binary stays in `build_dir` (cleaned up by
+            // `adrenaline clean`). `run` still needs the srcmap to translate
+            // a panic back to a Python line, so that alone is written next
+            // to `build_binary` itself; the `.rs` sidecar and `--manifest`
+            // are for inspecting a real build output; skip them here unless
+            // `--manifest` was explicitly requested.
+            let srcmap_path = build_binary.with_extension("srcmap.json");
+            let source_map = IRCodegen::build_source_map(rust_code);
+            fs::write(&srcmap_path, serde_json::to_string_pretty(&source_map)?)?;
+
+            if self.manifest {
+                self.write_manifest(&build_binary, &source_hash, build_start.elapsed())?;
+            }
+
+            print_success(&format!("Compiled (ephemeral): {}", build_binary.display()));
+            return Ok(build_binary);
+        }
+
+        // Copy binary to source file's directory, unless `adrenaline.toml`
+        // names an `output_dir` (resolved relative to the source file's own
+        // directory, same as where it would otherwise land).
+        let source_dir = source_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."));
+        let output_dir = match &self.project_config.output_dir {
+            Some(dir) => {
+                let resolved = source_dir.join(dir);
+                fs::create_dir_all(&resolved)?;
+                resolved
+            }
+            None => source_dir.to_path_buf(),
+        };
+        let source_stem = source_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output");
+
+        // Cross-compiling with `--target` can produce several binaries for
+        // the same source (one per triple) landing in the same
+        // `output_dir`, so the triple is folded into the file name instead
+        // of just `source_stem` - and the extension follows the *target's*
+        // OS, not the host's, so a Windows cross-build actually gets `.exe`
+        // even when built from Linux/macOS.
+        let is_windows_binary = match &self.project_config.target {
+            Some(target) => target.contains("windows"),
+            None => cfg!(target_os = "windows"),
+        };
+        let binary_name = match &self.project_config.target {
+            Some(target) => format!("{source_stem}-{target}"),
+            None => source_stem.to_string(),
+        };
+        let output_binary = output_dir.join(if is_windows_binary {
+            format!("{binary_name}.exe")
+        } else {
+            binary_name
+        });
+
+        fs::copy(&build_binary, &output_binary)?;
+
+        // Written next to the binary the same way `compile_python_extension`
+        // writes its `.pyi` stub next to the `.so` - `run` loads this to
+        // translate a panic/backtrace line in the generated crate back to
+        // the Python line it came from.
+        let srcmap_path = output_binary.with_extension("srcmap.json");
+        let source_map = IRCodegen::build_source_map(rust_code);
+        fs::write(&srcmap_path, serde_json::to_string_pretty(&source_map)?)?;
+
+        // Also written next to the binary (see `--emit`) so the generated
+        // Rust is right there to inspect instead of needing to dig through
+        // `~/.adrenaline`.
+        fs::write(output_binary.with_extension("rs"), rust_code)?;
+
+        if self.manifest {
+            self.write_manifest(&output_binary, &source_hash, build_start.elapsed())?;
+        }
+
+        print_success(&format!("Successfully compiled to {}", output_binary.display()));
+        Ok(output_binary)
+    }
+
+    /// `--manifest`: written next to the binary the same way `build_rust_project`
+    /// already writes the `.srcmap.json`/`.rs` siblings above.
+    fn write_manifest(&self, output_binary: &Path, source_hash: &str, duration: std::time::Duration) -> Result<()> {
+        let manifest = BuildManifest {
+            source_hash: source_hash.to_string(),
+            compiler_version: env!("CARGO_PKG_VERSION"),
+            directives_seen: self.directives_seen.clone(),
+            optimizations: self.remarks.clone(),
+            build_duration_ms: duration.as_millis(),
+            output_path: output_binary.to_path_buf(),
+        };
+        let manifest_path = output_binary.with_extension("manifest.json");
+        fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+        print_success(&format!("Wrote {}", manifest_path.display()));
+        Ok(())
+    }
+
+    /// Sibling of `build_rust_project` for `Artifact::Asm`/`Artifact::LlvmIr`:
+    /// same build-dir/`Cargo.toml` setup, but runs `cargo rustc -- --emit=...`
+    /// instead of `cargo build` and returns the emitted artifact's text
+    /// rather than a binary path.
+    fn build_and_emit_rustc_artifact(
+        &self,
+        source_path: &Path,
+        rust_code: &str,
+        opt_level: OptLevel,
+        rustc_emit: &str,
+        out_ext: &str,
+    ) -> Result<String> {
+        let adrenaline_home = dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".adrenaline");
+        fs::create_dir_all(&adrenaline_home)?;
+
+        let source_hash = Cache::get_hash(&fs::read_to_string(source_path)?);
+        let build_dir = adrenaline_home.join(format!("emit_{}", &source_hash[..8]));
+        let src_dir = build_dir.join("src");
+        fs::create_dir_all(&src_dir)?;
+
+        let main_rs = src_dir.join("main.rs");
+        fs::write(&main_rs, rust_code)?;
+        let _ = Command::new("rustfmt").arg(&main_rs).output();
+
+        let runtime_crate_dir = Self::ensure_runtime_crate(&adrenaline_home)?;
+        let cargo_toml = build_dir.join("Cargo.toml");
+        self.write_cargo_toml(&cargo_toml, &runtime_crate_dir, opt_level)?;
+
+        if let Some(target) = &self.project_config.target {
+            Self::verify_target_installed(target)?;
+        }
+
+        let artifact_path = build_dir.join(format!("main.{out_ext}"));
+        let mut cmd = Command::new("cargo");
+        if self.sandbox {
+            Self::scrub_env(&mut cmd);
+        }
+        cmd.arg("rustc").arg("--manifest-path").arg(&cargo_toml);
+        cmd.env("CARGO_TARGET_DIR", Self::shared_target_dir(&adrenaline_home));
+        if self.active_profile(opt_level).dir == "release" {
+            cmd.arg("--release");
+        }
+        if let Some(target) = &self.project_config.target {
+            cmd.arg("--target").arg(target);
+        }
+        if self.sandbox {
+            self.pin_lockfile(&build_dir)?;
+            cmd.arg("--offline").arg("--locked");
+        }
+        cmd.arg("--").arg(format!("--emit={rustc_emit}={}", artifact_path.display()));
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let source_map = IRCodegen::build_source_map(rust_code);
+            let annotated = Self::translate_generated_lines(&stderr, source_path, &source_map);
+            print_error(&format!("Rust compilation failed:\n{}", annotated));
+            return Err(anyhow!(annotated));
+        }
+
+        if self.sandbox {
+            Self::cache_lockfile(&build_dir)?;
+        }
+
+        Ok(fs::read_to_string(&artifact_path)?)
+    }
+
+    /// Rewrites `src/main.rs:LINE[:COL]` references in a `rustc`/`cargo`
+    /// stderr into a pointer at the Python line they came from, using the
+    /// rust-line -> python-line map `IRCodegen::generate` built from its own
+    /// `// from file:line` provenance comments (see
+    /// `IRCodegen::build_source_map`). A line with no entry in the map (e.g.
+    /// inside the synthesized `main`) is left untranslated.
+    fn translate_generated_lines(stderr: &str, source_path: &Path, source_map: &[SourceMapEntry]) -> String {
+        let mut translated = String::new();
+        for line in stderr.lines() {
+            if let Some(rust_line) = Self::extract_main_rs_line(line) {
+                if let Some(entry) = source_map.iter().find(|e| e.rust_line == rust_line) {
+                    translated.push_str(line);
+                    let _ = write!(
+                        translated,
+                        " (from {}:{})",
+                        source_path.display(),
+                        entry.python_line
+                    );
+                    translated.push('\n');
+                    continue;
+                }
+            }
+            translated.push_str(line);
+            translated.push('\n');
+        }
+        translated
+    }
+
+    /// Pulls the line number out of a `src/main.rs:LINE:COL`-style reference,
+    /// as `rustc` emits both in diagnostics and in `RUST_BACKTRACE` frames.
+    fn extract_main_rs_line(line: &str) -> Option<usize> {
+        let rest = line.split("src/main.rs:").nth(1)?;
+        rest.split(':').next()?.parse().ok()
+    }
+
+    /// Materializes the `adrenaline-runtime` support crate (Python-parity
+    /// helpers like `py_bool`, see `ir_codegen::IRCodegen::emit_print`) at a
+    /// single shared location under `adrenaline_home`, so every generated
+    /// project depends on the same on-disk crate by path instead of each
+    /// carrying its own inlined copy of the helper. Sharing `target_dir`
+    /// (see `shared_target_dir`) between all of them means cargo compiles
+    /// it once and every later build just reuses the cached artifact,
+    /// rather than rebuilding it per source hash. Rewritten on every build,
+    /// same as `write_cargo_toml` - it's a handful of lines, so
+    /// unconditional overwrite is simpler than tracking whether it's stale.
+    fn ensure_runtime_crate(adrenaline_home: &Path) -> Result<PathBuf> {
+        let crate_dir = adrenaline_home.join("adrenaline-runtime");
+        let src_dir = crate_dir.join("src");
+        fs::create_dir_all(&src_dir)?;
+
+        fs::write(
+            crate_dir.join("Cargo.toml"),
+            r#"[package]
+name = "adrenaline-runtime"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+name = "adrenaline_runtime"
+path = "src/lib.rs"
+
+[dependencies]
+pyo3 = { version = "0.20", features = ["auto-initialize"] }
+serde = { version = "1.0", features = ["derive"] }
+serde_json = "1.0"
+lazy_static = "1.4"
+libc = "0.2"
+"#,
+        )?;
+
+        fs::write(
+            src_dir.join("lib.rs"),
+            r#"//! Support crate for code generated by Adrenaline.
+//!
+//! Most helpers here exist because Python and Rust disagree on how a value
+//! prints, not because the operation itself needs a library - each one
+//! backs a single `IRCodegen` emission site. The `py_call_fallback*` family
+//! is the exception: it backs every `#adrenaline:no-compile` function,
+//! running its original source through an embedded CPython interpreter
+//! instead of the (possibly unsupported) lowered Rust body. `profiling`/
+//! `profile_function!` are the other exception: they back
+//! `--profile-instrument`, recording real per-function call counts and
+//! timings instead of nothing at all. `alloc_profiling` is the same idea
+//! for `--profile-alloc`, attributing allocations to whichever
+//! `profile_function!`-wrapped function is currently running. `line_profiling`
+//! is `--profile-lines`'s hit-count store, keyed by Python source line
+//! instead of by function. `hw_counters` is `--profile-hwcounters`'s
+//! per-function cache-miss/branch-miss/instruction counter, read straight
+//! from the kernel via `perf_event_open` (Linux only).
+
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+/// Python's `str(True)`/`str(False)`, used wherever generated code prints a
+/// `bool` (Rust's own `{}` formatting would print `true`/`false` instead).
+pub fn py_bool(value: bool) -> &'static str {
+    if value {
+        "True"
+    } else {
+        "False"
+    }
+}
+
+/// Runs `func_name` from `source` (the exact Python text of a
+/// `#adrenaline:no-compile` function - see `Compiler::attach_python_fallbacks`)
+/// through an embedded CPython interpreter and extracts its return value.
+/// Recompiles `source` on every call rather than caching the module,
+/// trading a bit of speed for not needing any global interpreter state.
+pub fn py_call_fallback<A, R>(source: &str, func_name: &str, args: A) -> R
+where
+    A: IntoPy<Py<PyTuple>>,
+    R: for<'p> FromPyObject<'p>,
+{
+    Python::with_gil(|py| {
+        call_fallback(py, source, func_name, args)
+            .extract()
+            .unwrap_or_else(|e| panic!("embedded Python fallback `{func_name}` returned unexpected type: {e}"))
+    })
+}
+
+/// Like `py_call_fallback`, but for a function whose Python body never
+/// returns a meaningful value - skips the extract step, which would
+/// otherwise need `(): FromPyObject`.
+pub fn py_call_fallback_unit<A>(source: &str, func_name: &str, args: A)
+where
+    A: IntoPy<Py<PyTuple>>,
+{
+    Python::with_gil(|py| {
+        call_fallback(py, source, func_name, args);
+    })
+}
+
+/// Like `py_call_fallback`, but for a function that takes no arguments -
+/// PyO3's `IntoPy<Py<PyTuple>>` isn't implemented for `()`, so a zero-arg
+/// call goes through `call0` instead of `call1`.
+pub fn py_call_fallback0<R>(source: &str, func_name: &str) -> R
+where
+    R: for<'p> FromPyObject<'p>,
+{
+    Python::with_gil(|py| {
+        call_fallback0(py, source, func_name)
+            .extract()
+            .unwrap_or_else(|e| panic!("embedded Python fallback `{func_name}` returned unexpected type: {e}"))
+    })
+}
+
+/// `py_call_fallback0` combined with `py_call_fallback_unit`'s skipped
+/// extract step, for a no-argument function with no meaningful return value.
+pub fn py_call_fallback0_unit(source: &str, func_name: &str) {
+    Python::with_gil(|py| {
+        call_fallback0(py, source, func_name);
+    });
+}
+
+fn call_fallback<'p, A: IntoPy<Py<PyTuple>>>(py: Python<'p>, source: &str, func_name: &str, args: A) -> &'p PyAny {
+    load_fallback_fn(py, source, func_name)
+        .call1(args)
+        .unwrap_or_else(|e| panic!("embedded Python fallback `{func_name}` raised: {e}"))
+}
+
+fn call_fallback0<'p>(py: Python<'p>, source: &str, func_name: &str) -> &'p PyAny {
+    load_fallback_fn(py, source, func_name)
+        .call0()
+        .unwrap_or_else(|e| panic!("embedded Python fallback `{func_name}` raised: {e}"))
+}
+
+fn load_fallback_fn<'p>(py: Python<'p>, source: &str, func_name: &str) -> &'p PyAny {
+    let module = PyModule::from_code(py, source, "adrenaline_fallback.py", "adrenaline_fallback")
+        .unwrap_or_else(|e| panic!("embedded Python fallback for `{func_name}` failed to compile: {e}"));
+    module
+        .getattr(func_name)
+        .unwrap_or_else(|e| panic!("embedded Python fallback `{func_name}` not found: {e}"))
+}
+
+/// Backs `--profile-instrument` (see `IRCodegen::generate_function`'s
+/// `profile_function!` wrapping): per-function call counters and timers,
+/// plus `write_report`, which dumps them in the same shape the compiler's
+/// own `profiler::ProfileData` uses - duplicated here rather than shared,
+/// since this crate can't depend back on the `adrenaline` binary crate.
+pub mod profiling {
+    use serde::Serialize;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex;
+
+    struct Counters {
+        call_count: AtomicU64,
+        total_time_ns: AtomicU64,
+    }
+
+    lazy_static::lazy_static! {
+        static ref COUNTERS: Mutex<HashMap<&'static str, Counters>> = Mutex::new(HashMap::new());
+    }
+
+    #[derive(Serialize)]
+    struct ProfileEntry {
+        function: String,
+        call_count: u64,
+        total_time_ms: f64,
+        avg_time_us: f64,
+        alloc_count: u64,
+        alloc_bytes: u64,
+        instructions: u64,
+        cache_misses: u64,
+        branch_misses: u64,
+    }
+
+    /// Called once a `profile_function!`-wrapped body returns normally -
+    /// records one call to `name` and how long it took.
+    pub fn record_call(name: &'static str, duration: std::time::Duration) {
+        let mut counters = COUNTERS.lock().unwrap();
+        let entry = counters.entry(name).or_insert_with(|| Counters {
+            call_count: AtomicU64::new(0),
+            total_time_ns: AtomicU64::new(0),
+        });
+        entry.call_count.fetch_add(1, Ordering::Relaxed);
+        entry.total_time_ns.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Called once a `profile_function_counts_only!`-wrapped body returns
+    /// normally - bumps `name`'s call count without touching `total_time_ns`,
+    /// so an `#adrenaline:profile-counts-only` function never pays for an
+    /// `Instant::now()` at all. Its `avg_time_us`/`total_time_ms` in the
+    /// written report stay `0.0` accordingly.
+    pub fn record_call_only(name: &'static str) {
+        let mut counters = COUNTERS.lock().unwrap();
+        let entry = counters.entry(name).or_insert_with(|| Counters {
+            call_count: AtomicU64::new(0),
+            total_time_ns: AtomicU64::new(0),
+        });
+        entry.call_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Builds one `ProfileEntry` per function `COUNTERS` has seen so far -
+    /// shared by `write_report`'s one-shot dump and `maybe_serve_live`'s
+    /// repeated snapshots.
+    fn snapshot() -> Vec<ProfileEntry> {
+        let counters = COUNTERS.lock().unwrap();
+        counters
+            .iter()
+            .map(|(name, c)| {
+                let call_count = c.call_count.load(Ordering::Relaxed);
+                let total_time_ns = c.total_time_ns.load(Ordering::Relaxed);
+                let total_time_ms = total_time_ns as f64 / 1_000_000.0;
+                let avg_time_us = if call_count > 0 {
+                    (total_time_ns as f64) / (call_count as f64 * 1_000.0)
+                } else {
+                    0.0
+                };
+                let (alloc_count, alloc_bytes) = crate::alloc_profiling::stats_for(name);
+                let (instructions, cache_misses, branch_misses) = crate::hw_counters::stats_for(name);
+                ProfileEntry {
+                    function: name.to_string(),
+                    call_count,
+                    total_time_ms,
+                    avg_time_us,
+                    alloc_count,
+                    alloc_bytes,
+                    instructions,
+                    cache_misses,
+                    branch_misses,
+                }
+            })
+            .collect()
+    }
+
+    /// Writes every counter collected so far to `ADRENALINE_PROFILE_PATH`,
+    /// or `adrenaline_profile.json` in the current directory if that's
+    /// unset - called once, right at the end of an instrumented `main` (see
+    /// `IRCodegen::generate_function`), so a plain `cargo run`/direct
+    /// invocation of the binary reports something without needing
+    /// `adrenaline profile` at all. `alloc_count`/`alloc_bytes` stay `0`
+    /// unless the binary was also built with `--profile-alloc` (see
+    /// `alloc_profiling`) - likewise `instructions`/`cache_misses`/
+    /// `branch_misses` stay `0` unless it was built with
+    /// `--profile-hwcounters` (see `hw_counters`).
+    pub fn write_report() {
+        let path = std::env::var("ADRENALINE_PROFILE_PATH")
+            .unwrap_or_else(|_| "adrenaline_profile.json".to_string());
+        if let Ok(json) = serde_json::to_string_pretty(&snapshot()) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+
+    /// Backs `adrenaline profile --live` (see `Compiler::live_profile`):
+    /// spawns a background thread that binds a Unix domain socket at
+    /// `ADRENALINE_LIVE_PROFILE_ADDR` and, for each client that connects,
+    /// writes a fresh JSON `snapshot()` every half second until it
+    /// disconnects. A no-op unless that variable is set - `IRCodegen::
+    /// generate_function` only emits the call at all when `--live` was
+    /// requested, but the check is kept here too so linking this crate in
+    /// never changes behavior for anyone who isn't using it. Unix only;
+    /// Windows has no Unix domain sockets, so it's a no-op there.
+    #[cfg(unix)]
+    pub fn maybe_serve_live() {
+        let Ok(addr) = std::env::var("ADRENALINE_LIVE_PROFILE_ADDR") else {
+            return;
+        };
+        std::thread::spawn(move || {
+            use std::io::Write;
+            use std::os::unix::net::UnixListener;
+            let _ = std::fs::remove_file(&addr);
+            let listener = match UnixListener::bind(&addr) {
+                Ok(listener) => listener,
+                Err(_) => return,
+            };
+            for stream in listener.incoming().flatten() {
+                let mut stream = stream;
+                loop {
+                    let Ok(json) = serde_json::to_string(&snapshot()) else {
+                        break;
+                    };
+                    if writeln!(stream, "{json}").is_err() {
+                        break;
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    pub fn maybe_serve_live() {}
+}
+
+/// Backs `--profile-alloc`: a `GlobalAlloc` wrapper (`CountingAllocator`)
+/// that attributes every allocation/reallocation to whichever
+/// `profile_function!`-wrapped function is currently executing on this
+/// thread, tracked as a fixed-size, allocation-free stack so pushing a
+/// frame never itself triggers a nested allocation. `record_alloc`'s
+/// `RECORDING` guard exists for the same reason: growing `STATS`' hash map
+/// does allocate, and without the guard that would recurse straight back
+/// into `CountingAllocator::alloc` and deadlock on `STATS`'s own mutex.
+pub mod alloc_profiling {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    const MAX_TRACKED_DEPTH: usize = 64;
+
+    struct FunctionStack {
+        frames: [&'static str; MAX_TRACKED_DEPTH],
+        depth: usize,
+    }
+
+    struct FunctionAllocStats {
+        alloc_count: u64,
+        alloc_bytes: u64,
+    }
+
+    thread_local! {
+        static CURRENT_FUNCTION: RefCell<FunctionStack> = RefCell::new(FunctionStack {
+            frames: [""; MAX_TRACKED_DEPTH],
+            depth: 0,
+        });
+        static RECORDING: Cell<bool> = const { Cell::new(false) };
+    }
+
+    lazy_static::lazy_static! {
+        static ref STATS: Mutex<HashMap<&'static str, FunctionAllocStats>> = Mutex::new(HashMap::new());
+    }
+
+    /// Pushed by `profile_function!` on entry to a wrapped function - frames
+    /// past `MAX_TRACKED_DEPTH` are silently dropped from attribution rather
+    /// than growing the stack, since growing it would allocate.
+    pub fn enter_function(name: &'static str) {
+        CURRENT_FUNCTION.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            let depth = stack.depth;
+            if depth < MAX_TRACKED_DEPTH {
+                stack.frames[depth] = name;
+            }
+            stack.depth += 1;
+        });
+    }
+
+    /// Inverse of `enter_function`, called once `profile_function!`'s body
+    /// returns normally - like `record_call`, an early `return` inside the
+    /// body skips this, the same known limitation the timing side already has.
+    pub fn exit_function() {
+        CURRENT_FUNCTION.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            stack.depth = stack.depth.saturating_sub(1);
+        });
+    }
+
+    fn current_function() -> &'static str {
+        CURRENT_FUNCTION.with(|stack| {
+            let stack = stack.borrow();
+            if stack.depth == 0 || stack.depth > MAX_TRACKED_DEPTH {
+                "<no function>"
+            } else {
+                stack.frames[stack.depth - 1]
+            }
+        })
+    }
+
+    fn record_alloc(bytes: usize) {
+        if RECORDING.with(|r| r.replace(true)) {
+            return;
+        }
+        let name = current_function();
+        if let Ok(mut stats) = STATS.try_lock() {
+            let entry = stats.entry(name).or_insert_with(|| FunctionAllocStats { alloc_count: 0, alloc_bytes: 0 });
+            entry.alloc_count += 1;
+            entry.alloc_bytes += bytes as u64;
+        }
+        RECORDING.with(|r| r.set(false));
+    }
+
+    /// `profiling::write_report`'s hook into this module - `(0, 0)` for any
+    /// function that never allocated, or if `--profile-alloc` wasn't passed
+    /// at all (in which case nothing ever calls `record_alloc`).
+    pub fn stats_for(name: &str) -> (u64, u64) {
+        STATS
+            .try_lock()
+            .ok()
+            .and_then(|stats| stats.get(name).map(|s| (s.alloc_count, s.alloc_bytes)))
+            .unwrap_or((0, 0))
+    }
+
+    /// Installed via `#[global_allocator]` in the generated binary's own
+    /// crate root when `--profile-alloc` is passed (see
+    /// `IRCodegen::generate`) - every call just forwards to `System` after
+    /// recording it, so this changes what gets measured, not how allocation
+    /// actually behaves.
+    pub struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            record_alloc(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            record_alloc(new_size.saturating_sub(layout.size()));
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+}
+
+/// Backs `--profile-hwcounters`: cache-miss, branch-miss, and instruction
+/// counts per function, read straight from the kernel via `perf_event_open`
+/// rather than sampled the way `adrenaline profile --sample` is - each
+/// `profile_function!` call reads all three counters once on entry and once
+/// on exit, and the delta is attributed to that call the same way
+/// `profiling::record_call` attributes a duration. Linux-only, since
+/// `perf_event_open` doesn't exist elsewhere - every function here is a
+/// no-op off Linux, so a `--profile-hwcounters` build still compiles and
+/// runs there, it just never has anything to report.
+pub mod hw_counters {
+    #[cfg(target_os = "linux")]
+    mod imp {
+        use std::collections::HashMap;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Mutex;
+
+        static ENABLED: AtomicBool = AtomicBool::new(false);
+
+        /// Called once, from the top of generated `main`, when
+        /// `--profile-hwcounters` was passed - every other function here
+        /// checks this first, so a plain `--profile-instrument` build never
+        /// touches a perf fd at all.
+        pub fn enable() {
+            ENABLED.store(true, Ordering::Relaxed);
+        }
+
+        // The kernel ABI struct `perf_event_open(2)` expects - `libc` doesn't
+        // declare this one, so it's reproduced here field-for-field. `size`
+        // tells the kernel exactly how much of it we filled in, so leaving
+        // everything past `read_format` zeroed is safe on any kernel version.
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        struct PerfEventAttr {
+            type_: u32,
+            size: u32,
+            config: u64,
+            sample_period_or_freq: u64,
+            sample_type: u64,
+            read_format: u64,
+            flags: u64,
+            wakeup_events_or_watermark: u32,
+            bp_type: u32,
+            config1_or_bp_addr: u64,
+            config2_or_bp_len: u64,
+            branch_sample_type: u64,
+            sample_regs_user: u64,
+            sample_stack_user: u32,
+            clockid: i32,
+            sample_regs_intr: u64,
+            aux_watermark: u32,
+            sample_max_stack: u16,
+            __reserved_2: u16,
+            aux_sample_size: u32,
+            __reserved_3: u32,
+            sig_data: u64,
+        }
+
+        const PERF_TYPE_HARDWARE: u32 = 0;
+        const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+        const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+        const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+        const PERF_ATTR_FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+        const PERF_ATTR_FLAG_EXCLUDE_HV: u64 = 1 << 6;
+        // `perf_event_open(2)`'s `PERF_EVENT_IOC_*` ioctl numbers - not in
+        // `libc` either, but fixed by the kernel ABI (`_IO('$', n)`).
+        const PERF_EVENT_IOC_RESET: libc::c_ulong = 0x2403;
+        const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+
+        fn open_counter(config: u64) -> Option<i32> {
+            let mut attr: PerfEventAttr = unsafe { std::mem::zeroed() };
+            attr.type_ = PERF_TYPE_HARDWARE;
+            attr.size = std::mem::size_of::<PerfEventAttr>() as u32;
+            attr.config = config;
+            attr.flags = PERF_ATTR_FLAG_EXCLUDE_KERNEL | PERF_ATTR_FLAG_EXCLUDE_HV;
+            // pid = 0 (this thread), cpu = -1 (any), group_fd = -1 (own group).
+            let fd = unsafe { libc::syscall(libc::SYS_perf_event_open, &attr, 0, -1, -1, 0) };
+            if fd < 0 {
+                return None;
+            }
+            let fd = fd as i32;
+            unsafe {
+                libc::ioctl(fd, PERF_EVENT_IOC_RESET, 0);
+                libc::ioctl(fd, PERF_EVENT_IOC_ENABLE, 0);
+            }
+            Some(fd)
+        }
+
+        fn read_counter(fd: i32) -> u64 {
+            let mut value: u64 = 0;
+            let read = unsafe { libc::read(fd, &mut value as *mut u64 as *mut libc::c_void, 8) };
+            if read == 8 {
+                value
+            } else {
+                0
+            }
+        }
+
+        struct PerfFds {
+            instructions: i32,
+            cache_misses: i32,
+            branch_misses: i32,
+        }
+
+        thread_local! {
+            // Opened lazily (once per thread) rather than eagerly in `enable`,
+            // since `enable` runs on `main`'s thread but a `rayon` worker
+            // thread needs its own fds - `None` once and for all if opening
+            // failed (e.g. no `CAP_PERFMON`/`perf_event_paranoid` blocks it),
+            // so a failure is only ever paid for once per thread.
+            static FDS: Option<PerfFds> = {
+                match (
+                    open_counter(PERF_COUNT_HW_INSTRUCTIONS),
+                    open_counter(PERF_COUNT_HW_CACHE_MISSES),
+                    open_counter(PERF_COUNT_HW_BRANCH_MISSES),
+                ) {
+                    (Some(instructions), Some(cache_misses), Some(branch_misses)) => {
+                        Some(PerfFds { instructions, cache_misses, branch_misses })
+                    }
+                    _ => None,
+                }
+            };
+        }
+
+        struct HwStats {
+            instructions: u64,
+            cache_misses: u64,
+            branch_misses: u64,
+        }
+
+        lazy_static::lazy_static! {
+            static ref STATS: Mutex<HashMap<&'static str, HwStats>> = Mutex::new(HashMap::new());
+        }
+
+        /// Reads all three counters' current cumulative values - `None` if
+        /// `--profile-hwcounters` wasn't passed, or if this thread couldn't
+        /// open the counters at all. `profile_function!` calls this once on
+        /// entry and once on exit and hands both to `record`.
+        pub fn read() -> Option<(u64, u64, u64)> {
+            if !ENABLED.load(Ordering::Relaxed) {
+                return None;
+            }
+            FDS.with(|fds| {
+                fds.as_ref().map(|fds| {
+                    (
+                        read_counter(fds.instructions),
+                        read_counter(fds.cache_misses),
+                        read_counter(fds.branch_misses),
+                    )
+                })
+            })
+        }
+
+        /// Adds `after - before` (per counter) to `name`'s running total.
+        pub fn record(name: &'static str, before: (u64, u64, u64), after: (u64, u64, u64)) {
+            if let Ok(mut stats) = STATS.try_lock() {
+                let entry = stats.entry(name).or_insert_with(|| HwStats {
+                    instructions: 0,
+                    cache_misses: 0,
+                    branch_misses: 0,
+                });
+                entry.instructions += after.0.saturating_sub(before.0);
+                entry.cache_misses += after.1.saturating_sub(before.1);
+                entry.branch_misses += after.2.saturating_sub(before.2);
+            }
+        }
+
+        /// `profiling::write_report`'s hook into this module - `(0, 0, 0)`
+        /// for any function that was never called, or if
+        /// `--profile-hwcounters` wasn't passed at all.
+        pub fn stats_for(name: &str) -> (u64, u64, u64) {
+            STATS
+                .try_lock()
+                .ok()
+                .and_then(|stats| stats.get(name).map(|s| (s.instructions, s.cache_misses, s.branch_misses)))
+                .unwrap_or((0, 0, 0))
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    mod imp {
+        pub fn enable() {}
+        pub fn read() -> Option<(u64, u64, u64)> {
+            None
+        }
+        pub fn record(_name: &'static str, _before: (u64, u64, u64), _after: (u64, u64, u64)) {}
+        pub fn stats_for(_name: &str) -> (u64, u64, u64) {
+            (0, 0, 0)
+        }
+    }
+
+    pub use imp::{enable, read, record, stats_for};
+}
+
+/// Backs `--profile-lines`: a hit-count store keyed by Python source line
+/// number, incremented by the `adrenaline_runtime::line_profiling::record_line`
+/// call `IRCodegen::emit_instruction` renders for every
+/// `IRInstruction::LineMarker` (i.e. every Python statement, however deeply
+/// nested - see `IRLowering::lower_statement`). Simpler than
+/// `alloc_profiling`: `record_line` is never called from inside a
+/// `GlobalAlloc` impl, so there's no reentrancy to guard against.
+pub mod line_profiling {
+    use serde::Serialize;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    lazy_static::lazy_static! {
+        static ref HITS: Mutex<HashMap<usize, u64>> = Mutex::new(HashMap::new());
+    }
+
+    #[derive(Serialize)]
+    struct LineEntry {
+        line: usize,
+        hit_count: u64,
+    }
+
+    /// Called once per statement executed - increments `line`'s hit count.
+    pub fn record_line(line: usize) {
+        if let Ok(mut hits) = HITS.try_lock() {
+            *hits.entry(line).or_insert(0) += 1;
+        }
+    }
+
+    /// Writes every line's hit count collected so far to
+    /// `ADRENALINE_LINE_PROFILE_PATH`, or `adrenaline_line_profile.json` in
+    /// the current directory if that's unset - called once, right at the end
+    /// of `main` when `--profile-lines` is on (see
+    /// `IRCodegen::generate_function`), mirroring `profiling::write_report`.
+    pub fn write_report() {
+        let path = std::env::var("ADRENALINE_LINE_PROFILE_PATH")
+            .unwrap_or_else(|_| "adrenaline_line_profile.json".to_string());
+        let hits = HITS.lock().unwrap();
+        let mut report: Vec<LineEntry> = hits
+            .iter()
+            .map(|(line, hit_count)| LineEntry { line: *line, hit_count: *hit_count })
+            .collect();
+        report.sort_by_key(|entry| entry.line);
+        if let Ok(json) = serde_json::to_string_pretty(&report) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+}
+
+/// Wraps `$body` with a call counter and timer recorded under `$name` (see
+/// `profiling::record_call`), plus an `alloc_profiling` frame so an
+/// allocation inside `$body` is attributed to it, and a `hw_counters` read
+/// on either side so any cache-miss/branch-miss/instruction delta is too -
+/// injected by `IRCodegen::generate_function` into every instrumented
+/// function. `hw_counters::read()` is `None` (so `record` is skipped
+/// entirely) unless `--profile-hwcounters` was passed and this thread could
+/// open its perf fds, keeping the cost to two atomic loads otherwise. An
+/// early `return` inside `$body` skips the recording, the same known
+/// limitation as the compiler's own developer-facing `profile_function!` in
+/// `profiler.rs`.
+#[macro_export]
+macro_rules! profile_function {
+    ($name:expr, $body:block) => {{
+        $crate::alloc_profiling::enter_function($name);
+        let __adrenaline_hw_start = $crate::hw_counters::read();
+        let __adrenaline_profile_start = std::time::Instant::now();
+        let __adrenaline_profile_result = $body;
+        $crate::profiling::record_call($name, __adrenaline_profile_start.elapsed());
+        if let Some(hw_start) = __adrenaline_hw_start {
+            if let Some(hw_end) = $crate::hw_counters::read() {
+                $crate::hw_counters::record($name, hw_start, hw_end);
+            }
+        }
+        $crate::alloc_profiling::exit_function();
+        __adrenaline_profile_result
+    }};
+}
+
+/// The `#adrenaline:profile-coarse-timing` variant of `profile_function!`:
+/// still a call counter and a wall-clock timer under `$name` (see
+/// `profiling::record_call`), but skips the `alloc_profiling` enter/exit
+/// frame and the `hw_counters` reads on either side - for a function whose
+/// timing matters but whose per-call allocation/hardware-counter
+/// attribution isn't worth the extra bookkeeping. Injected by
+/// `IRCodegen::generate_function` in place of `profile_function!` when
+/// `resolved_profile_overhead_mode` is `ProfileOverheadMode::CoarseTiming`.
+#[macro_export]
+macro_rules! profile_function_coarse {
+    ($name:expr, $body:block) => {{
+        let __adrenaline_profile_start = std::time::Instant::now();
+        let __adrenaline_profile_result = $body;
+        $crate::profiling::record_call($name, __adrenaline_profile_start.elapsed());
+        __adrenaline_profile_result
+    }};
+}
+
+/// The `#adrenaline:profile-counts-only` variant of `profile_function!`:
+/// just a call counter under `$name` (see `profiling::record_call_only`) -
+/// no `Instant::now()`, `alloc_profiling` frame, or `hw_counters` reads at
+/// all. For a microsecond-scale function called often enough that even
+/// reading the clock twice per call would dominate what's being measured.
+/// Injected by `IRCodegen::generate_function` in place of
+/// `profile_function!` when `resolved_profile_overhead_mode` is
+/// `ProfileOverheadMode::CountsOnly`.
+#[macro_export]
+macro_rules! profile_function_counts_only {
+    ($name:expr, $body:block) => {{
+        let __adrenaline_profile_result = $body;
+        $crate::profiling::record_call_only($name);
+        __adrenaline_profile_result
+    }};
+}
+"#,
+        )?;
+
+        Ok(crate_dir)
+    }
+
+    fn write_cargo_toml(&self, path: &Path, runtime_crate_dir: &Path, opt_level: OptLevel) -> Result<()> {
+        let mut content = format!(
+            r#"[package]
+name = "adrenaline-generated"
+version = "0.1.0"
+edition = "2021"
+
+[[bin]]
+name = "main"
+path = "src/main.rs"
+
+[dependencies]
+rayon = "1.7"
+num-bigint = "0.4"
+num-traits = "0.2"
+num-integer = "0.1"
+rand = "0.8"
+once_cell = "1"
+adrenaline-runtime = {{ path = {:?} }}
+"#,
+            runtime_crate_dir
+        );
+
+        // Extra `[dependencies]` from `adrenaline.toml`, on top of the ones
+        // every generated crate needs regardless of project config. Sorted
+        // by name rather than iterated straight off the `HashMap`, so the
+        // generated `Cargo.toml` (and `--deterministic`'s hash check) don't
+        // depend on that map's iteration order.
+        let mut extra_deps: Vec<_> = self.project_config.dependencies.iter().collect();
+        extra_deps.sort_by_key(|(name, _)| name.as_str());
+        for (name, version) in extra_deps {
+            let _ = writeln!(content, "{} = \"{}\"", name, version);
+        }
+
+        let (dev, release) = self.resolve_build_profiles(opt_level);
+        dev.write_toml_section(&mut content);
+        release.write_toml_section(&mut content);
+
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// `dev()`/`release()` with `adrenaline.toml`'s matching `[profile.dev]`/
+    /// `[profile.release]` override, if any, layered on top.
+    fn resolve_build_profiles(&self, opt_level: OptLevel) -> (BuildProfileSettings, BuildProfileSettings) {
+        let mut dev = BuildProfileSettings::dev();
+        if let Some(over) = self.project_config.profile.get("dev") {
+            dev = dev.apply_override(over);
+        }
+        let mut release = BuildProfileSettings::release(opt_level);
+        if let Some(over) = self.project_config.profile.get("release") {
+            release = release.apply_override(over);
+        }
+        (dev, release)
+    }
+
+    /// Which of `resolve_build_profiles`'s two profiles actually builds -
+    /// `--profile` (`self.build_profile`) if given, else `-O0` maps to
+    /// `dev` and everything else to `release`, matching the behavior from
+    /// before `--profile` existed.
+    fn active_profile(&self, opt_level: OptLevel) -> BuildProfileSettings {
+        let (dev, release) = self.resolve_build_profiles(opt_level);
+        match self.build_profile.as_deref() {
+            Some("dev") => dev,
+            Some("release") => release,
+            _ if opt_level.cargo_profile() == "debug" => dev,
+            _ => release,
+        }
+    }
+
+    /// Mirrors `build_rust_project`, but writes `src/lib.rs` plus a `cdylib`
+    /// manifest instead of `src/main.rs` plus a `[[bin]]` one, and copies out
+    /// the resulting shared library instead of an executable.
+    fn build_python_extension(
+        &self,
+        source_path: &Path,
+        rust_code: &str,
+        module_name: &str,
+        opt_level: OptLevel,
+    ) -> Result<PathBuf> {
+        let adrenaline_home = dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".adrenaline");
+
+        fs::create_dir_all(&adrenaline_home)?;
+
+        let source_hash = Cache::get_hash(&fs::read_to_string(source_path)?);
+        let build_dir = adrenaline_home.join(format!("pylib_{}", &source_hash[..8]));
+        let src_dir = build_dir.join("src");
+        fs::create_dir_all(&src_dir)?;
+
+        fs::write(src_dir.join("lib.rs"), rust_code)?;
+
+        let runtime_crate_dir = Self::ensure_runtime_crate(&adrenaline_home)?;
+        let cargo_toml = build_dir.join("Cargo.toml");
+        self.write_extension_cargo_toml(&cargo_toml, module_name, &runtime_crate_dir, opt_level)?;
+
+        let target_dir = Self::shared_target_dir(&adrenaline_home);
+
+        let profile = self.active_profile(opt_level);
+        let mut cmd = Command::new("cargo");
+        if self.sandbox {
+            Self::scrub_env(&mut cmd);
+        }
+        cmd.arg("build").arg("--manifest-path").arg(&cargo_toml);
+        cmd.env("CARGO_TARGET_DIR", &target_dir);
+        if profile.dir == "release" {
+            cmd.arg("--release");
+        }
+        if self.sandbox {
+            self.pin_lockfile(&build_dir)?;
+            cmd.arg("--offline").arg("--locked");
+        }
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            print_error(&format!("Rust compilation failed:\n{}", stderr));
+            return Err(anyhow!(stderr));
+        }
+
+        if self.sandbox {
+            Self::cache_lockfile(&build_dir)?;
+        }
+
+        let source_dir = source_path.parent().unwrap_or_else(|| Path::new("."));
+
+        let lib_prefix = if cfg!(target_os = "windows") { "" } else { "lib" };
+        let lib_ext = if cfg!(target_os = "windows") {
+            "dll"
+        } else if cfg!(target_os = "macos") {
+            "dylib"
+        } else {
+            "so"
+        };
+
+        let build_lib = target_dir
+            .join(profile.dir)
+            .join(format!("{}{}.{}", lib_prefix, module_name, lib_ext));
+
+        if !build_lib.exists() {
+            return Err(anyhow!("Extension module not found after compilation"));
+        }
+
+        // Python imports a native extension by its `.so`/`.pyd` extension
+        // regardless of platform, not the platform's usual dynamic library
+        // suffix - so the copy is renamed to `.so` even on macOS/Windows.
+        let output_lib = source_dir.join(format!("{}.so", module_name));
+        fs::copy(&build_lib, &output_lib)?;
+        print_success(&format!("Successfully built Python extension module {}", output_lib.display()));
+        Ok(output_lib)
+    }
+
+    fn write_extension_cargo_toml(
+        &self,
+        path: &Path,
+        module_name: &str,
+        runtime_crate_dir: &Path,
+        opt_level: OptLevel,
+    ) -> Result<()> {
+        let mut content = format!(
+            r#"[package]
+name = "adrenaline-generated"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+name = "{}"
+crate-type = ["cdylib"]
+path = "src/lib.rs"
+
+[dependencies]
+pyo3 = {{ version = "0.20", features = ["extension-module"] }}
+num-bigint = "0.4"
+num-traits = "0.2"
+num-integer = "0.1"
+once_cell = "1"
+adrenaline-runtime = {{ path = {:?} }}
+"#,
+            module_name, runtime_crate_dir,
+        );
+
+        let (dev, release) = self.resolve_build_profiles(opt_level);
+        dev.write_toml_section(&mut content);
+        release.write_toml_section(&mut content);
+
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Mirrors `build_python_extension`, but writes a `["staticlib",
+    /// "rlib"]` manifest instead of a `cdylib` one, and copies out both the
+    /// `.a` and the `.rlib` (plus the `.h` header from `generate_c_header`)
+    /// instead of a single shared library - a static archive is what a C++
+    /// build links against, an `.rlib` is what another Rust crate's
+    /// `Cargo.toml` `path` dependency needs.
+    fn build_lib_project(
+        &self,
+        source_path: &Path,
+        rust_code: &str,
+        c_header: &str,
+        lib_name: &str,
+        opt_level: OptLevel,
+    ) -> Result<PathBuf> {
+        let adrenaline_home = dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".adrenaline");
+
+        fs::create_dir_all(&adrenaline_home)?;
+
+        let source_hash = Cache::get_hash(&fs::read_to_string(source_path)?);
+        let build_dir = adrenaline_home.join(format!("lib_{}", &source_hash[..8]));
+        let src_dir = build_dir.join("src");
+        fs::create_dir_all(&src_dir)?;
+
+        fs::write(src_dir.join("lib.rs"), rust_code)?;
+
+        let runtime_crate_dir = Self::ensure_runtime_crate(&adrenaline_home)?;
+        let cargo_toml = build_dir.join("Cargo.toml");
+        self.write_lib_cargo_toml(&cargo_toml, lib_name, &runtime_crate_dir, opt_level)?;
+
+        let target_dir = Self::shared_target_dir(&adrenaline_home);
+
+        let profile = self.active_profile(opt_level);
+        let mut cmd = Command::new("cargo");
+        if self.sandbox {
+            Self::scrub_env(&mut cmd);
+        }
+        cmd.arg("build").arg("--manifest-path").arg(&cargo_toml);
+        cmd.env("CARGO_TARGET_DIR", &target_dir);
+        if profile.dir == "release" {
+            cmd.arg("--release");
+        }
+        if self.sandbox {
+            self.pin_lockfile(&build_dir)?;
+            cmd.arg("--offline").arg("--locked");
+        }
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            print_error(&format!("Rust compilation failed:\n{}", stderr));
+            return Err(anyhow!(stderr));
+        }
+
+        if self.sandbox {
+            Self::cache_lockfile(&build_dir)?;
+        }
+
+        let source_dir = source_path.parent().unwrap_or_else(|| Path::new("."));
+        let profile_dir = target_dir.join(profile.dir);
+
+        let static_prefix = if cfg!(target_os = "windows") { "" } else { "lib" };
+        let static_ext = if cfg!(target_os = "windows") { "lib" } else { "a" };
+        let build_staticlib = profile_dir.join(format!("{}{}.{}", static_prefix, lib_name, static_ext));
+        let build_rlib = profile_dir.join(format!("lib{}.rlib", lib_name));
+
+        if !build_staticlib.exists() {
+            return Err(anyhow!("Static library not found after compilation"));
+        }
+
+        let output_staticlib = source_dir.join(format!("{}{}.{}", static_prefix, lib_name, static_ext));
+        fs::copy(&build_staticlib, &output_staticlib)?;
+        if build_rlib.exists() {
+            fs::copy(&build_rlib, source_dir.join(format!("lib{}.rlib", lib_name)))?;
+        }
+
+        let header_path = source_dir.join(format!("{}.h", lib_name));
+        fs::write(&header_path, c_header)?;
+
+        print_success(&format!("Successfully built library {}", output_staticlib.display()));
+        Ok(output_staticlib)
+    }
+
+    fn write_lib_cargo_toml(
+        &self,
+        path: &Path,
+        lib_name: &str,
+        runtime_crate_dir: &Path,
+        opt_level: OptLevel,
+    ) -> Result<()> {
+        let mut content = format!(
+            r#"[package]
+name = "adrenaline-generated"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+name = "{}"
+crate-type = ["staticlib", "rlib"]
+path = "src/lib.rs"
+
+[dependencies]
+num-bigint = "0.4"
+num-traits = "0.2"
+num-integer = "0.1"
+once_cell = "1"
+adrenaline-runtime = {{ path = {:?} }}
+"#,
+            lib_name, runtime_crate_dir,
+        );
+
+        let (dev, release) = self.resolve_build_profiles(opt_level);
+        dev.write_toml_section(&mut content);
+        release.write_toml_section(&mut content);
+
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Enables `-C target-cpu=native` for the generated crate's build, so
+    /// the SIMD chunks `IRCodegen` emits as plain scalar arithmetic get
+    /// packed into real vector instructions by LLVM's SLP vectorizer
+    /// instead of staying on the compiler's conservative baseline target.
+    fn write_cargo_config(&self, build_dir: &Path) -> Result<()> {
+        let cargo_dir = build_dir.join(".cargo");
+        fs::create_dir_all(&cargo_dir)?;
+        fs::write(
+            cargo_dir.join("config.toml"),
+            "[build]\nrustflags = [\"-C\", \"target-cpu=native\"]\n",
+        )?;
+        Ok(())
+    }
+
+    pub fn run(&self, binary: &Path, args: &[String]) -> Result<()> {
+        let mut cmd = Command::new(binary);
+        cmd.args(args);
+        cmd.stderr(std::process::Stdio::piped());
+
+        let mut child = cmd.spawn()?;
+        let mut stderr_output = String::new();
+        child
+            .stderr
+            .take()
+            .expect("stderr was piped")
+            .read_to_string(&mut stderr_output)?;
+        let status = child.wait()?;
+
+        // A panic/backtrace frame in the generated crate points at
+        // `src/main.rs`, which means nothing to someone who only ever wrote
+        // Python - translate it back using the sidecar `build_rust_project`
+        // wrote next to `binary`, if one is there.
+        let srcmap_path = binary.with_extension("srcmap.json");
+        if let Ok(srcmap_json) = fs::read_to_string(&srcmap_path) {
+            if let Ok(source_map) = serde_json::from_str::<Vec<SourceMapEntry>>(&srcmap_json) {
+                // `build_rust_project` copies the binary out under the
+                // original source's own stem, so the `.py` it was compiled
+                // from - if it's still there - is just an extension swap
+                // away.
+                let source_path = binary.with_extension("py");
+                stderr_output = Self::translate_generated_lines(&stderr_output, &source_path, &source_map);
+            }
+        }
+        eprint!("{}", stderr_output);
+
+        if !status.success() {
+            return Err(anyhow!("Execution failed"));
+        }
+
+        Ok(())
+    }
+
+    /// `adrenaline profile <file>`: builds `source_path` the normal way,
+    /// then runs the binary `iterations` times, timing each run and
+    /// recording it in `self.profiler`. One full run is the profiled unit
+    /// here - real per-function granularity means rebuilding with
+    /// `--profile-instrument` instead (see `IRCodegen::generate_function`),
+    /// which reports call counts and timings straight from the binary
+    /// itself rather than from wrapping it externally. `--flamegraph <path>`
+    /// additionally samples one more run's stacks and renders them at
+    /// `flamegraph_path`, see `record_flamegraph`. `--sample` replaces the
+    /// iterations loop with a single run driven by a system sampling
+    /// profiler instead, see `sample_profile`. `--output <format>` renders
+    /// the final report as JSON/CSV/callgrind instead of the default table,
+    /// see `render_profile_report`.
+    pub fn profile(
+        &mut self,
+        source_path: &Path,
+        opt_level: u8,
+        iterations: usize,
+        sample: bool,
+        flamegraph_path: Option<&Path>,
+        output: Option<crate::profiler::ReportFormat>,
+    ) -> Result<()> {
+        let binary = self.compile(source_path, opt_level)?;
+
+        if sample {
+            let report = Self::sample_profile(source_path, &binary)?;
+            if let Some(svg_path) = flamegraph_path {
+                Self::record_flamegraph(source_path, &binary, svg_path)?;
+            }
+            return Self::render_profile_report(report, output);
+        }
+
+        let function_name = source_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("main")
+            .to_string();
+        self.profiler.register_function(&function_name);
+
+        print_info(&format!("Running {} for {iterations} iterations...", binary.display()));
+        for _ in 0..iterations {
+            let start = Instant::now();
+            let status = Command::new(&binary).stdout(Stdio::null()).stderr(Stdio::null()).status()?;
+            if !status.success() {
+                return Err(anyhow!("Execution failed during profiling"));
+            }
+            self.profiler.record_call(&function_name, start.elapsed());
+        }
+
+        if let Some(svg_path) = flamegraph_path {
+            Self::record_flamegraph(source_path, &binary, svg_path)?;
+        }
+
+        Self::render_profile_report(self.profiler.report(), output)
+    }
+
+    /// `adrenaline profile <file> --live`: rebuilds `source_path` with
+    /// `--profile-instrument` plus `enable_profile_live`, launches it as a
+    /// background child (rather than waiting for it to exit, like `profile`'s
+    /// iterations loop does) with `ADRENALINE_LIVE_PROFILE_ADDR` pointing at
+    /// a fresh Unix domain socket, then connects and redraws a top-like
+    /// table from each JSON snapshot `adrenaline_runtime::profiling::
+    /// maybe_serve_live` sends until the child exits - for a long-running
+    /// simulation where waiting for exit to see a report isn't practical.
+    /// Unix only, since there's no portable equivalent of a Unix domain
+    /// socket to attach through.
+    #[cfg(unix)]
+    pub fn live_profile(&mut self, source_path: &Path, opt_level: u8) -> Result<()> {
+        use std::io::BufRead;
+        use std::os::unix::net::UnixStream;
+
+        self.enable_profile_live();
+        let binary = self.compile(source_path, opt_level)?;
+
+        let socket_path = std::env::temp_dir().join(format!("adrenaline_live_{}.sock", std::process::id()));
+        let _ = fs::remove_file(&socket_path);
+
+        let mut child = Command::new(&binary)
+            .env("ADRENALINE_LIVE_PROFILE_ADDR", &socket_path)
+            .spawn()?;
+
+        // The child needs a moment to reach `main` and bind the socket -
+        // retry the connection rather than failing outright, but give up
+        // once the child itself has already exited (nothing will ever bind).
+        let stream = loop {
+            if let Ok(stream) = UnixStream::connect(&socket_path) {
+                break Some(stream);
+            }
+            if child.try_wait()?.is_some() {
+                break None;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        };
+
+        if let Some(stream) = stream {
+            let mut lines = std::io::BufReader::new(stream).lines();
+            while child.try_wait()?.is_none() {
+                let Some(Ok(line)) = lines.next() else {
+                    break;
+                };
+                let Ok(report) = serde_json::from_str::<Vec<crate::profiler::ProfileData>>(&line) else {
+                    continue;
+                };
+                Self::render_live_table(&report);
+            }
+        }
+
+        let _ = child.wait();
+        let _ = fs::remove_file(&socket_path);
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn live_profile(&mut self, _source_path: &Path, _opt_level: u8) -> Result<()> {
+        Err(anyhow!("`adrenaline profile --live` needs a Unix domain socket, which this platform doesn't have"))
+    }
+
+    /// Clears the terminal and redraws `report` as a top-like table, hottest
+    /// function first - used by `live_profile` once per snapshot instead of
+    /// `render_profile_report`'s one-shot table, since that one doesn't
+    /// repaint in place.
+    #[cfg(unix)]
+    fn render_live_table(report: &[crate::profiler::ProfileData]) {
+        let mut sorted = report.to_vec();
+        sorted.sort_by(|a, b| b.total_time_ms.partial_cmp(&a.total_time_ms).unwrap());
+
+        // ANSI clear screen + move cursor home, so each snapshot repaints in
+        // place instead of scrolling - the same trick `indicatif`'s spinners
+        // use under the hood, just spelled out directly since this is a
+        // whole table rather than one line.
+        print!("\x1B[2J\x1B[H");
+        println!("{:<30} {:<10} {:<12} {:<12}", "Function", "Calls", "Total(ms)", "Avg(us)");
+        println!("{}", "-".repeat(66));
+        for data in &sorted {
+            println!(
+                "{:<30} {:<10} {:<12.2} {:<12.2}",
+                data.function, data.call_count, data.total_time_ms, data.avg_time_us
+            );
+        }
+        let _ = std::io::stdout().flush();
+    }
+
+    /// `adrenaline profile <file> --annotate`: rebuilds `source_path` with
+    /// `--profile-lines`, runs the binary once, then prints the Python
+    /// source back with each line's hit count from
+    /// `line_profiling::write_report` prefixed - like `line_profiler`, but
+    /// for the compiled code rather than the interpreter. Reports hit
+    /// counts only, not per-line time: attributing time would mean wrapping
+    /// every statement in its own timer pair, a much bigger codegen change
+    /// than the counter `IRInstruction::LineMarker` already gives us for
+    /// free.
+    pub fn annotate(&mut self, source_path: &Path, opt_level: u8) -> Result<()> {
+        self.enable_profile_lines();
+        let binary = self.compile(source_path, opt_level)?;
+
+        let report_path = std::env::temp_dir().join(format!(
+            "adrenaline_line_profile_{}.json",
+            std::process::id()
+        ));
+        let status = Command::new(&binary)
+            .env("ADRENALINE_LINE_PROFILE_PATH", &report_path)
+            .status()?;
+        if !status.success() {
+            return Err(anyhow!("Execution failed while collecting line profile"));
+        }
+
+        let hits: HashMap<usize, u64> = fs::read_to_string(&report_path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Vec<LineHit>>(&json).ok())
+            .map(|entries| entries.into_iter().map(|e| (e.line, e.hit_count)).collect())
+            .unwrap_or_default();
+        let _ = fs::remove_file(&report_path);
+
+        let source = fs::read_to_string(source_path)?;
+        for (idx, text) in source.lines().enumerate() {
+            let line_no = idx + 1;
+            match hits.get(&line_no) {
+                Some(count) => println!("{count:>8}  {line_no:>4} | {text}"),
+                None => println!("{:>8}  {line_no:>4} | {text}", ""),
+            }
+        }
+        Ok(())
+    }
+
+    /// `adrenaline profile --compare <old> <new>`: loads two reports saved
+    /// by `--profile-instrument`/`profile --output json`, prints each
+    /// function's call-count/time delta, and returns an error if any
+    /// function's total time regressed beyond `threshold` (a fraction of
+    /// its old total time, e.g. `0.1` flags anything 10% slower) - a `Result`
+    /// error surfaces as a non-zero exit from `main`, which is what makes
+    /// this usable as a CI performance gate.
+    pub fn compare_profiles(old_path: &Path, new_path: &Path, threshold: f64) -> Result<()> {
+        let old = Profiler::load_from_file(old_path)?;
+        let new = Profiler::load_from_file(new_path)?;
+        let old_by_name: HashMap<&str, &crate::profiler::ProfileData> =
+            old.iter().map(|d| (d.function.as_str(), d)).collect();
+
+        println!("\n{:<30} {:<12} {:<12} {:<10}", "Function", "Old (ms)", "New (ms)", "Delta");
+        println!("{}", "-".repeat(68));
+
+        let mut regressed = Vec::new();
+        for data in &new {
+            let old_time = old_by_name.get(data.function.as_str()).map(|d| d.total_time_ms);
+            let delta = match old_time {
+                Some(old_ms) if old_ms > 0.0 => {
+                    let ratio = (data.total_time_ms - old_ms) / old_ms;
+                    if ratio > threshold {
+                        regressed.push((data.function.clone(), ratio));
+                    }
+                    format!("{:+.1}%", ratio * 100.0)
+                }
+                Some(_) => "n/a".to_string(),
+                None => "new".to_string(),
+            };
+            println!(
+                "{:<30} {:<12} {:<12.2} {:<10}",
+                data.function,
+                old_time.map(|t| format!("{t:.2}")).unwrap_or_else(|| "-".to_string()),
+                data.total_time_ms,
+                delta,
+            );
+        }
+
+        if regressed.is_empty() {
+            Ok(())
+        } else {
+            for (name, ratio) in &regressed {
+                print_error(&format!(
+                    "{name} regressed {:.1}% (beyond --threshold {:.0}%)",
+                    ratio * 100.0,
+                    threshold * 100.0
+                ));
+            }
+            Err(anyhow!("{} function(s) regressed beyond --threshold", regressed.len()))
+        }
+    }
+
+    /// `adrenaline optimize <file>`: closes the `--profile-instrument`/
+    /// `--profile-use` loop into one command - builds `source_path`
+    /// instrumented, runs it once to collect real per-function call counts,
+    /// then rebuilds with that report fed straight into `--profile-use`
+    /// (skipping the round trip through a saved JSON file the CLI flags
+    /// require) and prints whichever functions `IROptimizer::apply_profile`
+    /// promoted to `Aggressive`/`Extreme`. A function already at that level
+    /// (from a `#adrenaline:hot` directive, say) isn't reported again, since
+    /// nothing about this build actually changed for it.
+    pub fn optimize(&mut self, source_path: &Path, opt_level: u8) -> Result<()> {
+        self.enable_profile_instrument();
+        let binary = self.compile(source_path, opt_level)?;
+
+        let report_path = std::env::temp_dir().join(format!(
+            "adrenaline_profile_{}.json",
+            std::process::id()
+        ));
+        print_info(&format!("Running {} once to find hot functions...", binary.display()));
+        let status = Command::new(&binary)
+            .env("ADRENALINE_PROFILE_PATH", &report_path)
+            .status()?;
+        if !status.success() {
+            let _ = fs::remove_file(&report_path);
+            return Err(anyhow!("Execution failed while collecting profile"));
+        }
+
+        self.set_profile_use(report_path.clone());
+        print_info("Recompiling with hot functions promoted...");
+        self.compile(source_path, opt_level)?;
+        let _ = fs::remove_file(&report_path);
+
+        let promoted: Vec<&OptimizationRemark> = self
+            .remarks
+            .iter()
+            .filter(|r| r.message.starts_with("promoted to"))
+            .collect();
+        if promoted.is_empty() {
+            print_success("No functions needed promotion");
+        } else {
+            for remark in promoted {
+                print_success(&format!("{}: {}", remark.function, remark.message));
+            }
+        }
+        Ok(())
+    }
+
+    /// `adrenaline profile <file> --sample`: an alternative to the
+    /// iterations loop above and to `--profile-instrument` - drives a
+    /// platform sampling profiler around a single run of `binary` instead of
+    /// requiring it to be rebuilt with counters compiled in, for low-overhead
+    /// profiling of a long-running program. Linux drives `perf record`/
+    /// `perf script`, reusing `record_flamegraph`'s collapse-and-demangle
+    /// pipeline to turn sampled stacks into Python-name-keyed counts; macOS
+    /// drives `dtrace`'s pid provider instead, since that's the tool this
+    /// platform actually offers (see `sample_profile_dtrace`'s doc comment
+    /// for why it counts calls rather than time-sampling stacks). Neither
+    /// tool being on `PATH` degrades to an empty report with a warning, the
+    /// same pattern `record_flamegraph` uses for a missing `perf`.
+    fn sample_profile(source_path: &Path, binary: &Path) -> Result<Vec<crate::profiler::ProfileData>> {
+        if cfg!(target_os = "macos") {
+            Self::sample_profile_dtrace(source_path, binary)
+        } else {
+            Self::sample_profile_perf(source_path, binary)
+        }
+    }
+
+    /// `sample_profile`'s Linux path: samples `binary` at 997Hz with `perf
+    /// record -g`, collapses the result with `inferno` (same as
+    /// `record_flamegraph`), and turns each folded stack's leaf frame into a
+    /// call count - `total_time_ms`/`avg_time_us` are estimated from the
+    /// fixed 997Hz sampling period, since a sample only says a function was
+    /// on-CPU at that instant, not how long any one call actually took.
+    fn sample_profile_perf(source_path: &Path, binary: &Path) -> Result<Vec<crate::profiler::ProfileData>> {
+        if Command::new("perf").arg("--version").output().is_err() {
+            print_warning("`perf` not found on PATH - skipping --sample (system sampling needs it)");
+            return Ok(Vec::new());
+        }
+
+        print_info("Sampling stacks with perf...");
+        let sample_hz = 997.0;
+        let perf_data = binary.with_extension("perf.data");
+        let record_status = Command::new("perf")
+            .args(["record", "-F", "997", "-g", "-o"])
+            .arg(&perf_data)
+            .arg("--")
+            .arg(binary)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        if !record_status.success() {
+            let _ = fs::remove_file(&perf_data);
+            return Err(anyhow!("perf record failed"));
+        }
+
+        let script_output = Command::new("perf").args(["script", "-i"]).arg(&perf_data).output()?;
+        let _ = fs::remove_file(&perf_data);
+        if !script_output.status.success() {
+            return Err(anyhow!("perf script failed"));
+        }
+
+        let known_functions = Self::known_function_names(source_path)?;
+        let mut folded = Vec::new();
+        inferno::collapse::perf::Folder::default()
+            .collapse(script_output.stdout.as_slice(), &mut folded)
+            .map_err(|e| anyhow!("collapsing perf samples: {e}"))?;
+        let folded = String::from_utf8_lossy(&folded);
+
+        let sample_period_ms = 1000.0 / sample_hz;
+        let mut counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        for line in folded.lines() {
+            let Some((stack, count)) = line.rsplit_once(' ') else {
+                continue;
+            };
+            let Some(leaf) = stack.split(';').next_back() else {
+                continue;
+            };
+            let Ok(count) = count.parse::<usize>() else {
+                continue;
+            };
+            let name = Self::simplify_frame_symbol(leaf, &known_functions);
+            *counts.entry(name).or_insert(0) += count;
+        }
+
+        Ok(counts
+            .into_iter()
+            .map(|(function, call_count)| crate::profiler::ProfileData {
+                function,
+                call_count,
+                total_time_ms: call_count as f64 * sample_period_ms,
+                avg_time_us: sample_period_ms * 1000.0,
+                alloc_count: 0,
+                alloc_bytes: 0,
+                instructions: 0,
+                cache_misses: 0,
+                branch_misses: 0,
+            })
+            .collect())
+    }
+
+    /// `sample_profile`'s macOS path. Real periodic ustack sampling under
+    /// `dtrace` needs kernel-level stack-walking permissions that aren't
+    /// reliably available outside a real, non-virtualized macOS host, so
+    /// this drives the pid provider's function-entry probes instead
+    /// (`pid$target:::entry`), counting calls per symbol rather than
+    /// time-sampling stacks - still a real, low-overhead system trace, just
+    /// event-counted like `perf stat` rather than time-sampled like `perf
+    /// record`. `total_time_ms`/`avg_time_us` are left at `0.0`, since an
+    /// entry probe alone doesn't measure how long a call took.
+    fn sample_profile_dtrace(source_path: &Path, binary: &Path) -> Result<Vec<crate::profiler::ProfileData>> {
+        if Command::new("dtrace").arg("-V").output().is_err() {
+            print_warning("`dtrace` not found on PATH - skipping --sample (system sampling needs it)");
+            return Ok(Vec::new());
+        }
+
+        print_info("Counting calls with dtrace...");
+        let script = "pid$target:::entry { @counts[probefunc] = count(); } END { printa(\"%s %@u\\n\", @counts); }";
+        let output = Command::new("dtrace")
+            .args(["-q", "-n", script, "-c"])
+            .arg(binary)
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow!("dtrace failed: {}", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        let known_functions = Self::known_function_names(source_path)?;
+        let mut counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some((symbol, count)) = line.trim().rsplit_once(' ') else {
+                continue;
+            };
+            let Ok(count) = count.parse::<usize>() else {
+                continue;
+            };
+            let name = Self::simplify_frame_symbol(symbol, &known_functions);
+            *counts.entry(name).or_insert(0) += count;
+        }
+
+        Ok(counts
+            .into_iter()
+            .map(|(function, call_count)| crate::profiler::ProfileData {
+                function,
+                call_count,
+                total_time_ms: 0.0,
+                avg_time_us: 0.0,
+                alloc_count: 0,
+                alloc_bytes: 0,
+                instructions: 0,
+                cache_misses: 0,
+                branch_misses: 0,
+            })
+            .collect())
+    }
+
+    /// `--flamegraph <path>`'s worker: samples `binary`'s running stacks with
+    /// `perf record`/`perf script` (skipped, with a warning, if `perf` isn't
+    /// on `PATH` - the same optional-external-tool pattern `time_run` uses
+    /// for `/usr/bin/time -v`), collapses them with `inferno`, and renders
+    /// the result at `svg_path`. Every generated function is already named
+    /// after the Python function it came from (see `IRCodegen::generate_function`
+    /// and `build_source_map`'s per-line version of the same idea), so
+    /// "mapping symbols back to Python function names" here means demangling
+    /// each sampled frame and, when its plain name matches one of
+    /// `source_path`'s own functions, showing that bare name instead of the
+    /// full `crate::path::to::it` a Rust symbol would otherwise carry -
+    /// runtime/libc frames that don't match anything Python wrote keep their
+    /// full demangled form so they're still identifiable as non-Python.
+    fn record_flamegraph(source_path: &Path, binary: &Path, svg_path: &Path) -> Result<()> {
+        if Command::new("perf").arg("--version").output().is_err() {
+            print_warning("`perf` not found on PATH - skipping --flamegraph (stack sampling needs it)");
+            return Ok(());
+        }
+
+        print_info("Sampling stacks with perf...");
+        let perf_data = binary.with_extension("perf.data");
+        let record_status = Command::new("perf")
+            .args(["record", "-F", "997", "-g", "--call-graph=dwarf", "-o"])
+            .arg(&perf_data)
+            .arg("--")
+            .arg(binary)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        if !record_status.success() {
+            let _ = fs::remove_file(&perf_data);
+            return Err(anyhow!("perf record failed"));
+        }
+
+        let script_output = Command::new("perf").args(["script", "-i"]).arg(&perf_data).output()?;
+        let _ = fs::remove_file(&perf_data);
+        if !script_output.status.success() {
+            return Err(anyhow!("perf script failed"));
+        }
+
+        let known_functions = Self::known_function_names(source_path)?;
+        let mut folded = Vec::new();
+        inferno::collapse::perf::Folder::default()
+            .collapse(script_output.stdout.as_slice(), &mut folded)
+            .map_err(|e| anyhow!("collapsing perf samples: {e}"))?;
+        let folded = String::from_utf8_lossy(&folded);
+        let simplified: Vec<String> = folded
+            .lines()
+            .map(|line| Self::simplify_folded_stack_line(line, &known_functions))
+            .collect();
+
+        let mut options = inferno::flamegraph::Options::default();
+        options.title = format!("{} (adrenaline profile)", source_path.display());
+        let svg_file = fs::File::create(svg_path)?;
+        inferno::flamegraph::from_lines(&mut options, simplified.iter().map(|s| s.as_str()), svg_file)
+            .map_err(|e| anyhow!("rendering flamegraph: {e}"))?;
+
+        print_success(&format!("Wrote flamegraph to {}", svg_path.display()));
+        Ok(())
+    }
+
+    /// The names of every function `source_path` (and whatever it locally
+    /// imports) lowers to - `record_flamegraph`'s stand-in for a real
+    /// symbol-level source map, since a demangled perf frame that matches one
+    /// of these is, by `IRCodegen::generate_function`'s naming convention,
+    /// that Python function.
+    fn known_function_names(source_path: &Path) -> Result<std::collections::HashSet<String>> {
+        let source = fs::read_to_string(source_path)?;
+        let mut program = AdrenalineParser::parse(&source)?;
+        Self::resolve_local_imports(source_path, &mut program, &mut std::collections::HashSet::new())?;
+        let module = IRLowering::lower_program(&program);
+        Ok(module.functions.into_iter().map(|f| f.name).collect())
+    }
+
+    /// One folded `stack;of;frames count` line: demangles every frame in
+    /// `stack` and, when a frame's plain name is one of `known_functions`,
+    /// replaces it with that bare name - see `record_flamegraph`.
+    fn simplify_folded_stack_line(line: &str, known_functions: &std::collections::HashSet<String>) -> String {
+        let Some((stack, count)) = line.rsplit_once(' ') else {
+            return line.to_string();
+        };
+        let simplified: Vec<String> = stack
+            .split(';')
+            .map(|frame| Self::simplify_frame_symbol(frame, known_functions))
+            .collect();
+        format!("{} {}", simplified.join(";"), count)
+    }
+
+    fn simplify_frame_symbol(frame: &str, known_functions: &std::collections::HashSet<String>) -> String {
+        let demangled = format!("{:#}", rustc_demangle::demangle(frame));
+        match demangled.rsplit("::").next() {
+            Some(name) if known_functions.contains(name) => name.to_string(),
+            _ => demangled,
+        }
+    }
+
+    /// `adrenaline bench <file>`: builds `source_path` the normal way, runs
+    /// it and `python3 <file>` `iterations` times each, and reports
+    /// wall-time, speedup, peak memory (via `/usr/bin/time -v`, when
+    /// present - `None` otherwise rather than a fabricated number), and
+    /// whether the two produced the same stdout.
+    pub fn bench(&mut self, source_path: &Path, opt_level: u8, iterations: usize) -> Result<()> {
+        let binary = self.compile(source_path, opt_level)?;
+
+        print_info(&format!("Benchmarking against python3 ({iterations} iterations each)..."));
+
+        let mut python_runs = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            python_runs.push(Self::time_run(Path::new("python3"), &[source_path.as_os_str()])?);
+        }
+
+        let mut binary_runs = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            binary_runs.push(Self::time_run(&binary, &[] as &[&std::ffi::OsStr])?);
+        }
+
+        let python_avg = Self::average_duration(python_runs.iter().map(|r| r.wall));
+        let binary_avg = Self::average_duration(binary_runs.iter().map(|r| r.wall));
+        let speedup = python_avg.as_secs_f64() / binary_avg.as_secs_f64().max(f64::EPSILON);
+        let outputs_match = python_runs[0].stdout == binary_runs[0].stdout;
+
+        if json_mode() {
+            emit_json(&serde_json::json!({
+                "event": "bench",
+                "success": true,
+                "python_wall_ms": python_avg.as_secs_f64() * 1000.0,
+                "adrenaline_wall_ms": binary_avg.as_secs_f64() * 1000.0,
+                "speedup": speedup,
+                "python_peak_rss_kb": Self::average_rss(&python_runs),
+                "adrenaline_peak_rss_kb": Self::average_rss(&binary_runs),
+                "outputs_match": outputs_match,
+            }));
+            return Ok(());
+        }
+
+        println!("\n{:<20} {:<15} {:<15}", "", "python3", "adrenaline");
+        println!("{}", "-".repeat(50));
+        println!(
+            "{:<20} {:<15} {:<15}",
+            "Wall time (ms)",
+            format!("{:.2}", python_avg.as_secs_f64() * 1000.0),
+            format!("{:.2}", binary_avg.as_secs_f64() * 1000.0)
+        );
+        println!(
+            "{:<20} {:<15} {:<15}",
+            "Peak RSS (KB)",
+            Self::format_rss(&python_runs),
+            Self::format_rss(&binary_runs)
+        );
+        println!("\nSpeedup: {speedup:.2}x");
+
+        if outputs_match {
+            print_success("Outputs match");
+        } else {
+            print_warning("Outputs differ between python3 and the compiled binary");
+        }
+
+        Ok(())
+    }
+
+    /// `bench`'s single-process timer: runs `program` under `/usr/bin/time
+    /// -v` when it exists (to also capture peak RSS) and directly otherwise,
+    /// since GNU `time`'s `-v` flag isn't available on every platform.
+    fn time_run(program: &Path, args: &[&std::ffi::OsStr]) -> Result<BenchRun> {
+        let use_gnu_time = Path::new("/usr/bin/time").exists();
+        let start = Instant::now();
+        let output = if use_gnu_time {
+            let mut cmd = Command::new("/usr/bin/time");
+            cmd.arg("-v").arg(program).args(args);
+            cmd.output()?
+        } else {
+            let mut cmd = Command::new(program);
+            cmd.args(args);
+            cmd.output()?
+        };
+        let wall = start.elapsed();
+
+        let peak_rss_kb = use_gnu_time
+            .then(|| String::from_utf8_lossy(&output.stderr).into_owned())
+            .and_then(|stderr| {
+                stderr.lines().find_map(|line| {
+                    line.trim()
+                        .strip_prefix("Maximum resident set size (kbytes):")
+                        .and_then(|v| v.trim().parse::<u64>().ok())
+                })
+            });
+
+        Ok(BenchRun {
+            wall,
+            peak_rss_kb,
+            stdout: output.stdout,
+        })
+    }
+
+    fn average_duration(durations: impl Iterator<Item = std::time::Duration>) -> std::time::Duration {
+        let (total, count) = durations.fold((std::time::Duration::ZERO, 0u32), |(sum, n), d| (sum + d, n + 1));
+        if count == 0 {
+            std::time::Duration::ZERO
+        } else {
+            total / count
+        }
+    }
+
+    fn average_rss(runs: &[BenchRun]) -> Option<u64> {
+        let samples: Vec<u64> = runs.iter().filter_map(|r| r.peak_rss_kb).collect();
+        if samples.is_empty() {
+            None
+        } else {
+            Some(samples.iter().sum::<u64>() / samples.len() as u64)
+        }
+    }
+
+    fn format_rss(runs: &[BenchRun]) -> String {
+        Self::average_rss(runs).map_or_else(|| "N/A".to_string(), |kb| kb.to_string())
+    }
+
+    /// Runs `source_path` under `python3` and the freshly-compiled binary
+    /// with the same `args`/`stdin`, then diffs stdout and exit code - for
+    /// spotting a behavioral divergence compilation introduced (e.g.
+    /// integer floor-division rounding, `i64` wraparound) rather than one
+    /// caught by eye. When stdout differs, also scans the sidecar `.rs`
+    /// `build_rust_project` writes next to the binary for constructs known
+    /// to diverge from CPython and, via the sidecar `.srcmap.json`, reports
+    /// the Python source line each one came from.
+    pub fn diff(&mut self, source_path: &Path, opt_level: u8, args: &[String], stdin: &[u8]) -> Result<()> {
+        let binary = self.compile(source_path, opt_level)?;
+
+        print_info("Running under python3...");
+        let python_run = Self::run_capturing(Path::new("python3"), &[source_path.as_os_str()], args, stdin)?;
+
+        print_info("Running the compiled binary...");
+        let binary_run = Self::run_capturing(&binary, &[] as &[&std::ffi::OsStr], args, stdin)?;
+
+        if python_run.exit_code == binary_run.exit_code && python_run.stdout == binary_run.stdout {
+            print_success("No divergence: stdout and exit code match");
+            return Ok(());
+        }
+
+        if python_run.exit_code != binary_run.exit_code {
+            print_warning(&format!(
+                "Exit code differs: python3={:?}, adrenaline={:?}",
+                python_run.exit_code, binary_run.exit_code
+            ));
+        }
+
+        if python_run.stdout != binary_run.stdout {
+            print_warning("stdout differs:");
+            Self::print_diff_lines(&python_run.stdout, &binary_run.stdout);
+
+            let suspects = Self::divergence_suspects(&binary);
+            if !suspects.is_empty() {
+                print_info("Source lines using constructs that commonly diverge from CPython:");
+                for (line, reason) in suspects {
+                    println!("  {}:{line}: {reason}", source_path.display());
+                }
+            }
+        }
+
+        Err(anyhow!("behavioral divergence detected"))
+    }
+
+    /// `diff`'s single-process runner: pipes `stdin_data` in, captures
+    /// stdout, and forwards stderr straight through so a real crash is
+    /// still visible to the user - only stdout and the exit code are
+    /// compared.
+    fn run_capturing(program: &Path, base_args: &[&std::ffi::OsStr], args: &[String], stdin_data: &[u8]) -> Result<DiffRun> {
+        let mut cmd = Command::new(program);
+        cmd.args(base_args);
+        cmd.args(args);
+        cmd.stdin(Stdio::piped());
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::inherit());
+
+        let mut child = cmd.spawn()?;
+        {
+            use std::io::Write;
+            child.stdin.take().expect("stdin was piped").write_all(stdin_data)?;
+        }
+        let output = child.wait_with_output()?;
+
+        Ok(DiffRun {
+            stdout: output.stdout,
+            exit_code: output.status.code(),
+        })
+    }
+
+    /// Prints every stdout line where `python_stdout`/`binary_stdout`
+    /// disagree, 1-indexed the way a source line number would be.
+    fn print_diff_lines(python_stdout: &[u8], binary_stdout: &[u8]) {
+        let python_text = String::from_utf8_lossy(python_stdout);
+        let binary_text = String::from_utf8_lossy(binary_stdout);
+        let python_lines: Vec<&str> = python_text.lines().collect();
+        let binary_lines: Vec<&str> = binary_text.lines().collect();
+
+        for i in 0..python_lines.len().max(binary_lines.len()) {
+            let python_line = python_lines.get(i).copied().unwrap_or("<no output>");
+            let binary_line = binary_lines.get(i).copied().unwrap_or("<no output>");
+            if python_line != binary_line {
+                println!("  line {}: python3:     {python_line:?}", i + 1);
+                println!("  line {}: adrenaline:  {binary_line:?}", i + 1);
+            }
+        }
+    }
+
+    /// Best-effort source-line hints for a `diff` divergence: scans the
+    /// generated Rust next to `binary` for constructs known to behave
+    /// differently from CPython, then uses the sidecar `.srcmap.json` (see
+    /// `build_rust_project`) to translate each one back to the Python line
+    /// it was generated from. Empty if either sidecar file is missing.
+    fn divergence_suspects(binary: &Path) -> Vec<(usize, String)> {
+        let Ok(rust_code) = fs::read_to_string(binary.with_extension("rs")) else {
+            return Vec::new();
+        };
+        let Ok(srcmap_json) = fs::read_to_string(binary.with_extension("srcmap.json")) else {
+            return Vec::new();
+        };
+        let Ok(source_map) = serde_json::from_str::<Vec<SourceMapEntry>>(&srcmap_json) else {
+            return Vec::new();
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        let mut suspects = Vec::new();
+        for (idx, line) in rust_code.lines().enumerate() {
+            let rust_line = idx + 1;
+            let reason = if line.contains("div_euclid") || line.contains("rem_euclid") {
+                "floor division/modulo (rounding can differ from Python for negative operands)"
+            } else if line.contains(".powi(") || line.contains(".powf(") {
+                "exponentiation (float rounding can differ from Python)"
+            } else if line.contains("wrapping_add") || line.contains("wrapping_sub") || line.contains("wrapping_mul") {
+                "wrapping arithmetic (Python integers don't overflow; i64 does)"
+            } else {
+                continue;
+            };
+
+            if let Some(entry) = source_map.iter().find(|e| e.rust_line == rust_line) {
+                if seen.insert(entry.python_line) {
+                    suspects.push((entry.python_line, reason.to_string()));
+                }
+            }
+        }
+        suspects.sort_by_key(|(line, _)| *line);
+        suspects
+    }
+
+    /// Shared table/JSON/CSV/callgrind rendering for a `Vec<ProfileData>`,
+    /// whichever produced it - `profile`'s own `self.profiler` (the
+    /// iteration loop or `--profile-instrument`) or `sample_profile`'s
+    /// system-sampler report. `output` is `--output`'s explicit choice,
+    /// which wins over the default table even under the global `--format
+    /// json` (`ReportFormat::Json` there is redundant with it, but a caller
+    /// that wants CSV/callgrind while leaving every other command's output
+    /// alone still needs `--output` to win).
+    fn render_profile_report(
+        report: Vec<crate::profiler::ProfileData>,
+        output: Option<crate::profiler::ReportFormat>,
+    ) -> Result<()> {
+        use crate::profiler::{ProfileData, ReportFormat};
+
+        match output {
+            Some(ReportFormat::Json) => {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+            Some(ReportFormat::Csv) => {
+                print!("{}", ProfileData::to_csv(&report));
+                return Ok(());
+            }
+            Some(ReportFormat::Callgrind) => {
+                print!("{}", ProfileData::to_callgrind(&report));
+                return Ok(());
+            }
+            None => {}
+        }
+
+        if json_mode() {
+            emit_json(&serde_json::json!({
+                "event": "profile",
+                "success": true,
+                "functions": report,
+            }));
+            return Ok(());
+        }
+
+        if report.is_empty() {
+            print_info("No profiling data available");
+            return Ok(());
+        }
+
+        // `--profile-alloc`/`--profile-hwcounters` are the only sources of
+        // non-zero alloc/hardware-counter data - most reports have neither,
+        // so those columns only show up when there's something in them to
+        // see.
+        let has_alloc_data = report.iter().any(|d| d.alloc_count > 0 || d.alloc_bytes > 0);
+        let has_hw_data = report.iter().any(|d| d.instructions > 0 || d.cache_misses > 0 || d.branch_misses > 0);
+
+        match (has_alloc_data, has_hw_data) {
+            (false, false) => {
+                println!(
+                    "\n{:<30} {:<12} {:<12} {:<12}",
+                    "Function", "Calls", "Total (ms)", "Avg (μs)"
+                );
+                println!("{}", "-".repeat(66));
+
+                for data in report {
+                    println!(
+                        "{:<30} {:<12} {:<12.2} {:<12.2}",
+                        data.function, data.call_count, data.total_time_ms, data.avg_time_us
+                    );
+                }
+            }
+            (true, false) => {
+                println!(
+                    "\n{:<30} {:<12} {:<12} {:<12} {:<10} {:<14}",
+                    "Function", "Calls", "Total (ms)", "Avg (μs)", "Allocs", "Alloc bytes"
+                );
+                println!("{}", "-".repeat(90));
+                for data in report {
+                    println!(
+                        "{:<30} {:<12} {:<12.2} {:<12.2} {:<10} {:<14}",
+                        data.function, data.call_count, data.total_time_ms, data.avg_time_us, data.alloc_count, data.alloc_bytes
+                    );
+                }
+            }
+            (false, true) => {
+                println!(
+                    "\n{:<30} {:<12} {:<12} {:<12} {:<14} {:<14} {:<14}",
+                    "Function", "Calls", "Total (ms)", "Avg (μs)", "Instructions", "Cache misses", "Branch misses"
+                );
+                println!("{}", "-".repeat(114));
+                for data in report {
+                    println!(
+                        "{:<30} {:<12} {:<12.2} {:<12.2} {:<14} {:<14} {:<14}",
+                        data.function,
+                        data.call_count,
+                        data.total_time_ms,
+                        data.avg_time_us,
+                        data.instructions,
+                        data.cache_misses,
+                        data.branch_misses
+                    );
+                }
+            }
+            (true, true) => {
+                println!(
+                    "\n{:<30} {:<12} {:<12} {:<12} {:<10} {:<14} {:<14} {:<14} {:<14}",
+                    "Function",
+                    "Calls",
+                    "Total (ms)",
+                    "Avg (μs)",
+                    "Allocs",
+                    "Alloc bytes",
+                    "Instructions",
+                    "Cache misses",
+                    "Branch misses"
+                );
+                println!("{}", "-".repeat(148));
+                for data in report {
+                    println!(
+                        "{:<30} {:<12} {:<12.2} {:<12.2} {:<10} {:<14} {:<14} {:<14} {:<14}",
+                        data.function,
+                        data.call_count,
+                        data.total_time_ms,
+                        data.avg_time_us,
+                        data.alloc_count,
+                        data.alloc_bytes,
+                        data.instructions,
+                        data.cache_misses,
+                        data.branch_misses
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints the remarks collected by the IR optimizer during the most
+    /// recent `compile()` call.
+    pub fn print_remarks(&self, json: bool) -> Result<()> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&self.remarks)?);
+        } else if self.remarks.is_empty() {
+            print_info("No optimization remarks");
+        } else {
+            for remark in &self.remarks {
+                println!("[{:?}] {}: {}", remark.kind, remark.function, remark.message);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn clear_cache(&self) -> Result<()> {
+        self.cache.clear()?;
+        print_success("Cache cleared");
+        Ok(())
+    }
+
+    pub fn cache_size(&self) -> Result<()> {
+        let bytes = self.cache.size()?;
+        print_info(&format!("Cache size: {}", Self::format_bytes(bytes)));
+        Ok(())
+    }
+
+    /// `adrenaline cache list`: one row per whole-file cache entry, newest
+    /// first - source file (if its `.meta.json` sidecar is still there),
+    /// age, and size.
+    pub fn cache_list(&self) -> Result<()> {
+        let entries = self.cache.list_entries()?;
+        if entries.is_empty() {
+            print_info("Cache is empty");
+            return Ok(());
+        }
+
+        println!("{:<14} {:<50} {:<8} {:<10}", "HASH", "SOURCE", "AGE", "SIZE");
+        let now = std::time::SystemTime::now();
+        for entry in &entries {
+            let age = now.duration_since(entry.modified).unwrap_or_default();
+            println!(
+                "{:<14} {:<50} {:<8} {:<10}",
+                &entry.hash[..entry.hash.len().min(12)],
+                entry.source_path.as_deref().unwrap_or("<unknown>"),
+                Self::format_age(age),
+                Self::format_bytes(entry.size_bytes),
+            );
+        }
+        Ok(())
+    }
+
+    /// `adrenaline cache stats`.
+    pub fn cache_stats(&self) -> Result<()> {
+        let stats = self.cache.stats();
+        let total = stats.hits + stats.misses;
+        let hit_rate = if total > 0 { stats.hits as f64 / total as f64 * 100.0 } else { 0.0 };
+        println!("Hits:            {}", stats.hits);
+        println!("Misses:          {}", stats.misses);
+        println!("Hit rate:        {:.1}%", hit_rate);
+        println!("Bytes served:    {}", Self::format_bytes(stats.bytes_served));
+        println!("Time saved:      {} (estimated)", Self::format_duration(stats.time_saved));
+        Ok(())
+    }
+
+    /// `adrenaline cache export <path>`.
+    pub fn cache_export(&self, archive_path: &Path) -> Result<()> {
+        self.cache.export(archive_path)?;
+        print_success(&format!("Exported cache to {}", archive_path.display()));
+        Ok(())
+    }
+
+    /// `adrenaline cache import <path>`.
+    pub fn cache_import(&self, archive_path: &Path) -> Result<()> {
+        self.cache.import(archive_path)?;
+        print_success(&format!("Imported cache from {}", archive_path.display()));
+        Ok(())
+    }
+
+    /// `adrenaline cache prune --older-than <DAYS>`.
+    pub fn cache_prune(&self, older_than: std::time::Duration) -> Result<()> {
+        let removed = self.cache.prune(older_than)?;
+        print_success(&format!(
+            "Pruned {removed} cache entr{} older than {}",
+            if removed == 1 { "y" } else { "ies" },
+            Self::format_age(older_than)
+        ));
+        Ok(())
+    }
+
+    /// `adrenaline clean [--all] [--older-than N]`: removes stale
+    /// `~/.adrenaline/{build,cbuild,lib,pylib,emit}_<hash>` directories (see
+    /// `build_rust_project`, `build_c_project`, `compile_lib`,
+    /// `build_python_extension`, `emit_artifact`) and reports space
+    /// reclaimed. Distinct from `cache clear`/`cache prune`, which only
+    /// touch the `.cache` compilation cache - and leaves `sandbox/` and the
+    /// shared `target/` (see `shared_target_dir`) alone, since those are
+    /// reused across builds rather than being one-off per-source dirs.
+    pub fn clean(&self, all: bool, older_than: std::time::Duration) -> Result<()> {
+        let adrenaline_home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?.join(".adrenaline");
+        if !adrenaline_home.exists() {
+            print_success("Nothing to clean");
+            return Ok(());
+        }
+
+        let now = std::time::SystemTime::now();
+        let mut removed = 0usize;
+        let mut reclaimed = 0u64;
+        for entry in fs::read_dir(&adrenaline_home)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if !metadata.is_dir() {
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+            if !Self::is_build_dir_name(name) {
+                continue;
+            }
+            if !all {
+                let age = now.duration_since(metadata.modified()?).unwrap_or_default();
+                if age <= older_than {
+                    continue;
+                }
+            }
+            reclaimed += Self::dir_size(&path);
+            fs::remove_dir_all(&path)?;
+            removed += 1;
+        }
+
+        if removed == 0 {
+            print_success("Nothing to clean");
+        } else {
+            print_success(&format!(
+                "Removed {removed} build director{} ({} reclaimed)",
+                if removed == 1 { "y" } else { "ies" },
+                Self::format_bytes(reclaimed),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether `name` is one of `clean`'s per-source build directories -
+    /// every prefix `build_rust_project`/`build_c_project`/`compile_lib`/
+    /// `build_python_extension`/`emit_artifact` create their `build_dir`
+    /// under, each followed by an 8-character source hash.
+    fn is_build_dir_name(name: &str) -> bool {
+        const PREFIXES: &[&str] = &["build_", "cbuild_", "lib_", "pylib_", "emit_"];
+        PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+    }
+
+    /// Best-effort recursive directory size, in bytes - used for `clean`'s
+    /// reclaimed-space report, where an unreadable subentry just doesn't
+    /// count rather than failing the whole command.
+    fn dir_size(path: &Path) -> u64 {
+        let Ok(entries) = fs::read_dir(path) else { return 0 };
+        entries
+            .flatten()
+            .map(|entry| match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => Self::dir_size(&entry.path()),
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            })
+            .sum()
+    }
+
+    fn format_bytes(bytes: u64) -> String {
+        if bytes >= 1_048_576 {
+            format!("{:.1} MB", bytes as f64 / 1_048_576.0)
+        } else if bytes >= 1024 {
+            format!("{:.1} KB", bytes as f64 / 1024.0)
+        } else {
+            format!("{bytes} B")
+        }
+    }
+
+    /// Sibling of `format_age`, for a duration short enough that whole
+    /// seconds would round away the number entirely - a single cache hit's
+    /// share of "time saved" is typically well under a second.
+    fn format_duration(duration: std::time::Duration) -> String {
+        let ms = duration.as_secs_f64() * 1000.0;
+        if ms >= 1000.0 {
+            format!("{:.1}s", ms / 1000.0)
+        } else {
+            format!("{:.0}ms", ms)
+        }
+    }
+
+    fn format_age(age: std::time::Duration) -> String {
+        let secs = age.as_secs();
+        if secs < 60 {
+            format!("{secs}s")
+        } else if secs < 3600 {
+            format!("{}m", secs / 60)
+        } else if secs < 86400 {
+            format!("{}h", secs / 3600)
+        } else {
+            format!("{}d", secs / 86400)
+        }
+    }
+
+    /// `adrenaline doctor`: most first-run failures reported in issues
+    /// aren't compiler bugs, they're a missing `rustc`/`cargo`, a
+    /// not-yet-installed cross target, or an unwritable `~/.adrenaline` -
+    /// this runs every check independently and prints a fix-it suggestion
+    /// per failure instead of bailing on the first one, so a fresh install
+    /// gets the whole list at once rather than one error per re-run.
+    pub fn doctor(&self) -> Result<()> {
+        print_info("Running environment checks...");
+        let mut failures = 0usize;
+
+        match Self::doctor_tool_version("cargo") {
+            Some(version) => print_success(&format!("cargo found: {version}")),
+            None => {
+                print_error("cargo not found on PATH");
+                print_info("  fix: install Rust via https://rustup.rs");
+                failures += 1;
+            }
+        }
+
+        match Self::doctor_tool_version("rustc") {
+            Some(version) => print_success(&format!("rustc found: {version}")),
+            None => {
+                print_error("rustc not found on PATH");
+                print_info("  fix: install Rust via https://rustup.rs");
+                failures += 1;
+            }
+        }
+
+        match Self::doctor_host_target() {
+            Some(host) => match Self::verify_target_installed(&host) {
+                Ok(()) => print_success(&format!("target installed: {host}")),
+                Err(e) => {
+                    print_error(&e.to_string());
+                    failures += 1;
+                }
+            },
+            None => print_warning("could not determine host target (`rustc -vV` failed)"),
+        }
+
+        match Self::doctor_tool_version("python3") {
+            Some(version) => print_success(&format!("python3 found: {version}")),
+            None => {
+                print_warning("python3 not found on PATH");
+                print_info("  fix: install python3 - needed for `diff` and `#adrenaline:no-compile` fallback comparisons");
+            }
+        }
+
+        match Self::doctor_home_writable() {
+            Ok(home) => {
+                print_success(&format!("{} is writable", home.display()));
+                match Self::doctor_free_space_mb(&home) {
+                    Some(mb) if mb < 500 => {
+                        print_warning(&format!("only {mb} MB free at {}", home.display()));
+                        print_info(
+                            "  fix: a cold build of the runtime crate and its pyo3 dependency needs a few hundred MB - free up space or set CARGO_TARGET_DIR elsewhere",
+                        );
+                    }
+                    Some(mb) => print_success(&format!("{mb} MB free at {}", home.display())),
+                    None => print_info("could not determine free disk space (`df` unavailable)"),
+                }
+            }
+            Err(e) => {
+                print_error(&format!("~/.adrenaline is not writable: {e}"));
+                print_info("  fix: check permissions on your home directory");
+                failures += 1;
+            }
+        }
+
+        if failures == 0 {
+            print_success("All checks passed");
+            Ok(())
+        } else {
+            Err(anyhow!("{failures} check(s) failed"))
+        }
+    }
+
+    /// Runs `tool --version` and returns its trimmed first line of output,
+    /// or `None` if the tool isn't on `PATH` or exits non-zero.
+    fn doctor_tool_version(tool: &str) -> Option<String> {
+        let output = Command::new(tool).arg("--version").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout).lines().next().map(|s| s.trim().to_string())
+    }
+
+    /// The triple `rustc` itself was built for, parsed from `rustc -vV`'s
+    /// `host: <triple>` line - what `verify_target_installed` should check
+    /// is installed even when no `--target` was passed explicitly.
+    fn doctor_host_target() -> Option<String> {
+        let output = Command::new("rustc").arg("-vV").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.strip_prefix("host: "))
+            .map(|s| s.to_string())
+    }
+
+    /// Creates `~/.adrenaline` if needed and proves it's actually writable
+    /// (not just present) by writing and removing a throwaway file - every
+    /// build depends on this directory for its cache, runtime crate, and
+    /// shared `target/`.
+    fn doctor_home_writable() -> Result<PathBuf> {
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("could not determine home directory"))?.join(".adrenaline");
+        fs::create_dir_all(&home)?;
+        let probe = home.join(".doctor_write_test");
+        fs::write(&probe, b"ok")?;
+        fs::remove_file(&probe)?;
+        Ok(home)
+    }
+
+    /// Free space at `path` in MB via `df`, best-effort like `rustfmt`
+    /// above - `None` if `df` isn't on `PATH` or its output doesn't parse,
+    /// rather than failing the whole check over a missing tool.
+    fn doctor_free_space_mb(path: &Path) -> Option<u64> {
+        let output = Command::new("df").args(["-Pk", &path.to_string_lossy()]).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        let fields: Vec<&str> = text.lines().last()?.split_whitespace().collect();
+        let available_kb: u64 = fields.get(3)?.parse().ok()?;
+        Some(available_kb / 1024)
     }
 }
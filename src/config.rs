@@ -0,0 +1,96 @@
+/// `adrenaline.toml` project configuration
+/// Lets a team commit shared build settings - default opt level, per-glob
+/// directives, extra Cargo dependencies, target triple, output directory,
+/// and cache behavior - instead of passing a long CLI flag list to every
+/// `adrenaline build` invocation. Discovered from `--project`, the same
+/// directory `Compiler::new`/`Cache::new` already treat as the project
+/// root.
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// `--opt-level`'s default when the flag isn't passed explicitly.
+    pub opt_level: Option<u8>,
+    /// Extra `#adrenaline:...` directives applied to every function whose
+    /// name matches a glob key, on top of whatever directive comments it
+    /// already has - see `ProjectConfig::directives_for` and
+    /// `DirectiveSet::from_strings`. Example:
+    /// `[directives]` / `"hot_*" = ["hot", "inline"]`.
+    pub directives: HashMap<String, Vec<String>>,
+    /// Extra `[dependencies]` entries merged into the generated crate's
+    /// `Cargo.toml`, name -> version requirement (e.g. `regex = "1"`).
+    pub dependencies: HashMap<String, String>,
+    /// `cargo build --target <triple>`, for cross-compiling the generated
+    /// crate.
+    pub target: Option<String>,
+    /// Where the finished binary is copied, relative to the project
+    /// directory - defaults to the source file's own directory, same as
+    /// with no config file at all.
+    pub output_dir: Option<PathBuf>,
+    /// Whether compiled output is cached by source hash (see `cache.rs`) -
+    /// `false` forces every build to recompile from scratch. Defaults to
+    /// enabled.
+    pub cache: Option<bool>,
+    /// `[profile.dev]`/`[profile.release]` overrides for the generated
+    /// crate's `Cargo.toml`, keyed the same way Cargo itself names them -
+    /// see `compiler::BuildProfileSettings` for the defaults these apply
+    /// on top of, and `--profile` for picking which one actually runs.
+    pub profile: HashMap<String, ProfileOverride>,
+}
+
+/// One `[profile.dev]`/`[profile.release]` table in `adrenaline.toml` -
+/// every field mirrors a real Cargo profile key so nothing here needs its
+/// own vocabulary. Unset fields fall back to `BuildProfileSettings`'s
+/// built-in defaults for that profile.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProfileOverride {
+    pub lto: Option<bool>,
+    pub codegen_units: Option<u32>,
+    pub panic_abort: Option<bool>,
+    pub debug: Option<bool>,
+}
+
+impl ProjectConfig {
+    /// Loads `adrenaline.toml` from `project_dir`, if present - a project
+    /// with no config file gets every CLI default untouched.
+    pub fn load(project_dir: &Path) -> anyhow::Result<Self> {
+        let config_path = project_dir.join("adrenaline.toml");
+        if !config_path.is_file() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&config_path)?;
+        toml::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("invalid {}: {e}", config_path.display()))
+    }
+
+    /// Whether compiled output should be cached, honoring `cache = false`;
+    /// defaults to `true` with no config file or no `cache` key.
+    pub fn cache_enabled(&self) -> bool {
+        self.cache.unwrap_or(true)
+    }
+
+    /// The extra directives `function_name` picks up from `[directives]`,
+    /// across every glob key it matches - only a single trailing `*`
+    /// wildcard is supported (`"test_*"`), matching the narrow style
+    /// already used elsewhere in this compiler (see
+    /// `IRLowering::is_stdlib_module` for a similarly narrow, hardcoded
+    /// match rather than a full glob engine).
+    pub fn directives_for(&self, function_name: &str) -> Vec<String> {
+        self.directives
+            .iter()
+            .filter(|(glob, _)| Self::glob_matches(glob, function_name))
+            .flat_map(|(_, directives)| directives.iter().cloned())
+            .collect()
+    }
+
+    fn glob_matches(glob: &str, name: &str) -> bool {
+        match glob.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => glob == name,
+        }
+    }
+}
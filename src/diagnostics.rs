@@ -1,10 +1,14 @@
 use miette::{Diagnostic, NamedSource, SourceSpan};
+use std::sync::atomic::{AtomicBool, Ordering};
 use thiserror::Error;
 
 #[derive(Debug, Error, Diagnostic)]
 pub enum CompileError {
     #[error("Parse error")]
     #[diagnostic(code = "E0001")]
+    // No constructor builds this variant yet - parse failures currently
+    // surface as `anyhow::Error` from `PythonParser`, not a `CompileError`.
+    #[allow(dead_code)]
     ParseError {
         #[source_code]
         src: NamedSource<String>,
@@ -46,6 +50,18 @@ pub enum CompileError {
 pub struct DiagnosticBuilder;
 
 impl DiagnosticBuilder {
+    /// Byte offset of `line` (1-based, matching `FunctionDef::line`), `col`
+    /// (0-based) in `source` - real line lengths, not a fixed-width guess,
+    /// since a wrong span is worse than none for a `check` diagnostic.
+    fn offset(source: &str, line: usize, col: usize) -> usize {
+        source
+            .lines()
+            .take(line.saturating_sub(1))
+            .map(|l| l.len() + 1)
+            .sum::<usize>()
+            + col
+    }
+
     pub fn unsupported_feature(
         source: &str,
         line: usize,
@@ -53,7 +69,7 @@ impl DiagnosticBuilder {
         feature: &str,
         suggestion: Option<String>,
     ) -> CompileError {
-        let offset: usize = line.saturating_mul(80).saturating_add(col);
+        let offset = Self::offset(source, line, col);
         let span = SourceSpan::new(offset.into(), 1usize);
         CompileError::UnsupportedFeature {
             src: NamedSource::new("input.py", source.to_string()),
@@ -63,6 +79,19 @@ impl DiagnosticBuilder {
         }
     }
 
+    pub fn type_error_at(source: &str, line: usize, message: &str) -> CompileError {
+        let offset = Self::offset(source, line, 0);
+        let span = SourceSpan::new(offset.into(), 1usize);
+        CompileError::TypeError {
+            src: NamedSource::new("input.py", source.to_string()),
+            span,
+            message: message.to_string(),
+        }
+    }
+
+    // No caller reaches for a type error with a default 10-byte span yet -
+    // every current caller goes through `type_error_at` with a real line.
+    #[allow(dead_code)]
     pub fn type_error(source: &str, message: &str) -> CompileError {
         let span = SourceSpan::new(0usize.into(), 10usize);
         CompileError::TypeError {
@@ -79,18 +108,70 @@ impl DiagnosticBuilder {
     }
 }
 
+/// Set once at startup from the global `--format` flag (see
+/// `cli::OutputFormatFlag`). While enabled, `print_success`/`print_info`/
+/// `print_warning`/`print_error` become no-ops - `build`/`check`/`profile`/
+/// `bench` emit one `emit_json` event apiece instead, and everything else
+/// keeps calling the `print_*` helpers unchanged, it just goes quiet.
+static JSON_MODE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json_mode(enabled: bool) {
+    JSON_MODE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn json_mode() -> bool {
+    JSON_MODE.load(Ordering::Relaxed)
+}
+
+/// Set from `--quiet` (see `cli::Cli::execute`). Unlike `JSON_MODE`, this
+/// only silences `print_info` - the progress chatter `--quiet` exists to
+/// suppress - not `print_success`/`print_warning`/`print_error`, since a
+/// quiet build should still report its final result.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet_mode(enabled: bool) {
+    QUIET.store(enabled, Ordering::Relaxed);
+}
+
+pub fn quiet_mode() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
 pub fn print_success(message: &str) {
+    if json_mode() {
+        return;
+    }
     println!("✓ {}", message);
 }
 
 pub fn print_info(message: &str) {
+    if json_mode() || quiet_mode() {
+        return;
+    }
     println!("ℹ {}", message);
 }
 
 pub fn print_warning(message: &str) {
+    if json_mode() {
+        return;
+    }
     eprintln!("⚠ {}", message);
 }
 
 pub fn print_error(message: &str) {
+    if json_mode() {
+        return;
+    }
     eprintln!("✗ {}", message);
 }
+
+/// One line of compact JSON for `--format json` - `build`/`check`/
+/// `profile`/`bench` each emit exactly one of these summarizing the run
+/// (diagnostics, timings, artifact paths) instead of their usual
+/// `print_*`/`println!` output, so editors and CI can parse a single
+/// well-formed event per invocation.
+pub fn emit_json<T: serde::Serialize>(value: &T) {
+    if let Ok(line) = serde_json::to_string(value) {
+        println!("{line}");
+    }
+}
@@ -1,8 +1,10 @@
 /// Directive system for compile-time hints
-/// Supports directives: no-compile, hot, inline, parallel, simd, cache
+/// Supports directives: no-compile, hot, inline, parallel, simd, cache, memoize, fast-math, bigint,
+/// overflow-wrap, overflow-checked, overflow-bigint, profile-counts-only, profile-coarse-timing,
+/// profile-full-timing
 use std::collections::HashSet;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum Directive {
     NoCompile,
     Hot,
@@ -10,6 +12,15 @@ pub enum Directive {
     Parallel,
     Simd,
     Cache,
+    Memoize,
+    FastMath,
+    BigInt,
+    OverflowWrap,
+    OverflowChecked,
+    OverflowBigint,
+    ProfileCountsOnly,
+    ProfileCoarseTiming,
+    ProfileFullTiming,
 }
 
 impl Directive {
@@ -21,12 +32,84 @@ impl Directive {
             "parallel" => Some(Directive::Parallel),
             "simd" => Some(Directive::Simd),
             "cache" => Some(Directive::Cache),
+            "memoize" => Some(Directive::Memoize),
+            "fast-math" => Some(Directive::FastMath),
+            "bigint" => Some(Directive::BigInt),
+            "overflow-wrap" => Some(Directive::OverflowWrap),
+            "overflow-checked" => Some(Directive::OverflowChecked),
+            "overflow-bigint" => Some(Directive::OverflowBigint),
+            "profile-counts-only" => Some(Directive::ProfileCountsOnly),
+            "profile-coarse-timing" => Some(Directive::ProfileCoarseTiming),
+            "profile-full-timing" => Some(Directive::ProfileFullTiming),
             _ => None,
         }
     }
+
+    /// Inverse of `from_string` - the exact text that follows
+    /// `#adrenaline:` for this directive. Used by `Compiler::advise` to
+    /// write a suggestion back into a `#adrenaline:` comment.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Directive::NoCompile => "no-compile",
+            Directive::Hot => "hot",
+            Directive::Inline => "inline",
+            Directive::Parallel => "parallel",
+            Directive::Simd => "simd",
+            Directive::Cache => "cache",
+            Directive::Memoize => "memoize",
+            Directive::FastMath => "fast-math",
+            Directive::BigInt => "bigint",
+            Directive::OverflowWrap => "overflow-wrap",
+            Directive::OverflowChecked => "overflow-checked",
+            Directive::OverflowBigint => "overflow-bigint",
+            Directive::ProfileCountsOnly => "profile-counts-only",
+            Directive::ProfileCoarseTiming => "profile-coarse-timing",
+            Directive::ProfileFullTiming => "profile-full-timing",
+        }
+    }
+}
+
+/// Integer-arithmetic overflow policy for plain (non-bigint) `+`/`-`/`*` -
+/// see `DirectiveSet::overflow_mode` and `IRCodegen`'s per-function
+/// `overflow_mode`. Selected per-function by an `#adrenaline:overflow-*`
+/// directive, or codegen-wide by `--overflow` / the opt-level's default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowMode {
+    /// Silently wraps on overflow (Rust's `wrapping_*` ops) - fastest, but
+    /// can corrupt results without any warning.
+    Wrap,
+    /// Panics on overflow via Rust's `checked_*` ops, regardless of build
+    /// profile - the default for a `-O0` build, where surfacing a bug early
+    /// is worth more than the cycles saved by not checking.
+    Checked,
+    /// Promotes to `num_bigint::BigInt`, the same machinery
+    /// `#adrenaline:bigint` already opts a function into - overflow becomes
+    /// impossible rather than merely detected.
+    Bigint,
 }
 
-#[derive(Debug, Clone, Default)]
+/// `--profile-instrument`'s per-function overhead level - see
+/// `DirectiveSet::profile_overhead_mode` and `IRCodegen`'s choice of which
+/// `profile_function!` variant to emit. Selected per-function by an
+/// `#adrenaline:profile-*` directive; codegen-wide default is `FullTiming`
+/// (today's behavior) when none is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileOverheadMode {
+    /// Just a call counter, no `Instant::now()` at all - for a
+    /// microsecond-scale function called often enough that even reading the
+    /// clock twice per call would dominate what's being measured.
+    CountsOnly,
+    /// Call count and wall-clock duration, but skips `alloc_profiling`'s
+    /// enter/exit stack bookkeeping and `hw_counters`'s counter reads - the
+    /// middle ground when timing itself matters but the extra attribution
+    /// doesn't.
+    CoarseTiming,
+    /// Everything `--profile-alloc`/`--profile-hwcounters` can attribute,
+    /// on top of the call count and duration - today's only, most, behavior.
+    FullTiming,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct DirectiveSet {
     directives: HashSet<Directive>,
 }
@@ -52,6 +135,10 @@ impl DirectiveSet {
         self.directives.contains(&directive)
     }
 
+    // No caller mutates a `Directives` set after parsing it from source
+    // yet - every current use reads directives with `has`/the per-kind
+    // accessors below.
+    #[allow(dead_code)]
     pub fn add(&mut self, directive: Directive) {
         self.directives.insert(directive);
     }
@@ -60,6 +147,9 @@ impl DirectiveSet {
         !self.has(Directive::NoCompile)
     }
 
+    // No caller checks `#adrenaline:hot` directly yet - the profiler infers
+    // hotness from measured call counts instead (see `profiler.rs`).
+    #[allow(dead_code)]
     pub fn is_hot(&self) -> bool {
         self.has(Directive::Hot)
     }
@@ -76,10 +166,66 @@ impl DirectiveSet {
         self.has(Directive::Simd)
     }
 
+    // No caller checks `#adrenaline:cache` directly yet - see `cache.rs`,
+    // whose own caching decisions don't currently consult this directive.
+    #[allow(dead_code)]
     pub fn use_cache(&self) -> bool {
         self.has(Directive::Cache)
     }
 
+    pub fn should_memoize(&self) -> bool {
+        self.has(Directive::Memoize)
+    }
+
+    pub fn fast_math(&self) -> bool {
+        self.has(Directive::FastMath)
+    }
+
+    /// Opts a function's integer arithmetic into `num-bigint` instead of
+    /// `i64` - see `IRCodegen`'s bigint mode, which also enables this
+    /// automatically for a function whose range analysis can't prove `Pow`
+    /// fits (i.e. any use of `**`).
+    pub fn use_bigint(&self) -> bool {
+        self.has(Directive::BigInt)
+    }
+
+    /// The overflow policy explicitly requested via `#adrenaline:overflow-*`,
+    /// if any - `None` means "use the codegen-wide default" (see
+    /// `IRCodegen::set_overflow_mode`). Checked in order of most to least
+    /// safe, so a function that (accidentally) carries more than one of
+    /// these picks the safer reading rather than an arbitrary one.
+    pub fn overflow_mode(&self) -> Option<OverflowMode> {
+        if self.has(Directive::OverflowBigint) {
+            Some(OverflowMode::Bigint)
+        } else if self.has(Directive::OverflowChecked) {
+            Some(OverflowMode::Checked)
+        } else if self.has(Directive::OverflowWrap) {
+            Some(OverflowMode::Wrap)
+        } else {
+            None
+        }
+    }
+
+    /// The profiler overhead level explicitly requested via
+    /// `#adrenaline:profile-*`, if any - `None` means "use the codegen-wide
+    /// default" (see `IRCodegen::set_profile_overhead_mode`). Checked
+    /// cheapest-first, so a function that (accidentally) carries more than
+    /// one of these picks the cheapest reading rather than an arbitrary one.
+    pub fn profile_overhead_mode(&self) -> Option<ProfileOverheadMode> {
+        if self.has(Directive::ProfileCountsOnly) {
+            Some(ProfileOverheadMode::CountsOnly)
+        } else if self.has(Directive::ProfileCoarseTiming) {
+            Some(ProfileOverheadMode::CoarseTiming)
+        } else if self.has(Directive::ProfileFullTiming) {
+            Some(ProfileOverheadMode::FullTiming)
+        } else {
+            None
+        }
+    }
+
+    // No caller needs the full directive set yet - every current use asks
+    // about one specific directive via `has`/the per-kind accessors above.
+    #[allow(dead_code)]
     pub fn all(&self) -> Vec<Directive> {
         self.directives.iter().copied().collect()
     }
@@ -0,0 +1,627 @@
+/// Tree-walking interpreter for REPL input.
+///
+/// `adrenaline` as a whole is an ahead-of-time compiler - `IRLowering`/
+/// `IRCodegen` exist to turn a whole program into Rust once, not to run one
+/// statement immediately. The REPL is the one place that's needed, so it
+/// gets its own small evaluator that walks `ast_types` directly instead of
+/// round-tripping every line through a temp file and a full build. See
+/// `Repl::execute_expression`, which parses REPL input with
+/// `AdrenalineParser` and feeds the result to `Interpreter::eval_program`.
+use crate::ast_types::*;
+use anyhow::{anyhow, bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    None,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{n}"),
+            Value::Float(n) => write!(f, "{n}"),
+            Value::Bool(b) => write!(f, "{}", if *b { "True" } else { "False" }),
+            Value::Str(s) => write!(f, "{s}"),
+            Value::List(items) => write!(f, "[{}]", items.iter().map(Value::repr).collect::<Vec<_>>().join(", ")),
+            Value::Tuple(items) => {
+                let inner = items.iter().map(Value::repr).collect::<Vec<_>>().join(", ");
+                if items.len() == 1 {
+                    write!(f, "({inner},)")
+                } else {
+                    write!(f, "({inner})")
+                }
+            }
+            Value::None => write!(f, "None"),
+        }
+    }
+}
+
+impl Value {
+    /// `repr()` rather than `str()` of a value - used for elements nested
+    /// inside a `List`/`Tuple`'s own `Display`, where Python quotes a string
+    /// element (`['a', 'b']`) even though `str(['a', 'b'][0])` wouldn't.
+    fn repr(&self) -> String {
+        match self {
+            Value::Str(s) => format!("{s:?}"),
+            other => other.to_string(),
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Int(n) => *n != 0,
+            Value::Float(n) => *n != 0.0,
+            Value::Bool(b) => *b,
+            Value::Str(s) => !s.is_empty(),
+            Value::List(items) | Value::Tuple(items) => !items.is_empty(),
+            Value::None => false,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Str(_) => "str",
+            Value::List(_) => "list",
+            Value::Tuple(_) => "tuple",
+            Value::None => "NoneType",
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+}
+
+/// Why `eval_block` stopped before reaching the end of a body - not an
+/// error, just a different reason execution of the current block ended.
+/// Propagated up through nested `if`/`while`/`for` the same way it would in
+/// a real Python interpreter's frame stack.
+enum Flow {
+    Return(Value),
+    Break,
+    Continue,
+}
+
+/// One function call's local variables, plus which names it `global`s -
+/// see `Interpreter::read_name`/`write_name`. The module scope itself
+/// (`Interpreter::globals`) isn't a `Frame`; top-level code always writes
+/// there directly.
+#[derive(Default)]
+struct Frame {
+    locals: HashMap<String, Value>,
+    globals_declared: HashSet<String>,
+}
+
+/// Evaluates a `Program` (or a single REPL line's worth of one) against a
+/// persistent module-level environment carried across calls - so `x = 1` in
+/// one REPL entry is visible to `x + 1` in the next, matching how a real
+/// Python REPL's `__main__` module scope works.
+pub struct Interpreter {
+    globals: HashMap<String, Value>,
+    functions: HashMap<String, FunctionDef>,
+    call_stack: Vec<Frame>,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self {
+            globals: HashMap::new(),
+            functions: HashMap::new(),
+            call_stack: Vec::new(),
+        }
+    }
+
+    /// Runs every statement in `program` against the persistent environment,
+    /// returning the value of the last bare expression statement (what the
+    /// REPL echoes back) if the program ended with one.
+    pub fn eval_program(&mut self, program: &Program) -> Result<Option<Value>> {
+        let mut last = None;
+        for stmt in &program.statements {
+            last = match stmt {
+                Statement::ExprStatement(expr, _) => Some(self.eval_expr(expr)?),
+                _ => {
+                    if let Some(flow) = self.eval_statement(stmt)? {
+                        match flow {
+                            Flow::Return(_) => bail!("'return' outside function"),
+                            Flow::Break => bail!("'break' outside loop"),
+                            Flow::Continue => bail!("'continue' outside loop"),
+                        }
+                    }
+                    None
+                }
+            };
+        }
+        Ok(last)
+    }
+
+    fn eval_block(&mut self, body: &[Statement]) -> Result<Option<Flow>> {
+        for stmt in body {
+            if let Some(flow) = self.eval_statement(stmt)? {
+                return Ok(Some(flow));
+            }
+        }
+        Ok(None)
+    }
+
+    fn eval_statement(&mut self, stmt: &Statement) -> Result<Option<Flow>> {
+        match stmt {
+            Statement::FunctionDef(f) => {
+                self.functions.insert(f.name.clone(), f.clone());
+                Ok(None)
+            }
+            Statement::Assign(a) => {
+                let value = self.eval_expr(&a.value)?;
+                for target in &a.targets {
+                    self.write_name(target, value.clone());
+                }
+                Ok(None)
+            }
+            Statement::AugAssign(a) => {
+                let current = self.read_name(&a.target)?;
+                let rhs = self.eval_expr(&a.value)?;
+                let result = Self::apply_binop(a.op, &current, &rhs)?;
+                self.write_name(&a.target, result);
+                Ok(None)
+            }
+            Statement::If(i) => {
+                if self.eval_expr(&i.condition)?.truthy() {
+                    self.eval_block(&i.then_body)
+                } else if let Some(else_body) = &i.else_body {
+                    self.eval_block(else_body)
+                } else {
+                    Ok(None)
+                }
+            }
+            Statement::While(w) => {
+                while self.eval_expr(&w.condition)?.truthy() {
+                    match self.eval_block(&w.body)? {
+                        Some(Flow::Break) => break,
+                        Some(Flow::Continue) | None => {}
+                        Some(flow @ Flow::Return(_)) => return Ok(Some(flow)),
+                    }
+                }
+                Ok(None)
+            }
+            Statement::For(fl) => {
+                for item in self.eval_iterable(&fl.iter)? {
+                    self.write_name(&fl.target, item);
+                    match self.eval_block(&fl.body)? {
+                        Some(Flow::Break) => break,
+                        Some(Flow::Continue) | None => {}
+                        Some(flow @ Flow::Return(_)) => return Ok(Some(flow)),
+                    }
+                }
+                Ok(None)
+            }
+            Statement::Return(expr, _) => {
+                let value = match expr {
+                    Some(e) => self.eval_expr(e)?,
+                    None => Value::None,
+                };
+                Ok(Some(Flow::Return(value)))
+            }
+            Statement::Break(_) => Ok(Some(Flow::Break)),
+            Statement::Continue(_) => Ok(Some(Flow::Continue)),
+            Statement::Pass(_) => Ok(None),
+            Statement::ExprStatement(expr, _) => {
+                self.eval_expr(expr)?;
+                Ok(None)
+            }
+            Statement::Global(names, _) => {
+                if let Some(frame) = self.call_stack.last_mut() {
+                    frame.globals_declared.extend(names.iter().cloned());
+                }
+                Ok(None)
+            }
+            Statement::ClassDef(c) => bail!("classes aren't supported in the REPL yet ({})", c.name),
+            Statement::Try(_) => bail!("try/except isn't supported in the REPL yet"),
+            Statement::Yield(_, _) => bail!("yield isn't supported in the REPL yet"),
+        }
+    }
+
+    /// Reads `name`, checking the innermost function frame (if any) before
+    /// falling back to module scope - a function can read a global without
+    /// declaring it, matching Python, but see `write_name` for why writing
+    /// is different.
+    fn read_name(&self, name: &str) -> Result<Value> {
+        if let Some(frame) = self.call_stack.last() {
+            if !frame.globals_declared.contains(name) {
+                if let Some(value) = frame.locals.get(name) {
+                    return Ok(value.clone());
+                }
+            }
+        }
+        self.globals.get(name).cloned().ok_or_else(|| anyhow!("name '{name}' is not defined"))
+    }
+
+    /// Writes `name`, into the innermost function frame (if any) unless it
+    /// was declared `global` there, else module scope - an assignment inside
+    /// a function is local by default in Python; `global` is what opts it
+    /// into writing through to module scope instead.
+    fn write_name(&mut self, name: &str, value: Value) {
+        if let Some(frame) = self.call_stack.last_mut() {
+            if !frame.globals_declared.contains(name) {
+                frame.locals.insert(name.to_string(), value);
+                return;
+            }
+        }
+        self.globals.insert(name.to_string(), value);
+    }
+
+    /// `for x in <iter>`: `range(...)` is expanded directly (matching
+    /// `IRLowering::lower_for_range`'s special-casing of it); anything else
+    /// evaluates to a `List`/`Tuple`/`Str` and is iterated by value.
+    fn eval_iterable(&mut self, expr: &Expression) -> Result<Vec<Value>> {
+        if let Expression::Call(callee, args) = expr {
+            if matches!(callee.as_ref(), Expression::Identifier(name) if name == "range") {
+                return self.eval_range(args);
+            }
+        }
+        match self.eval_expr(expr)? {
+            Value::List(items) | Value::Tuple(items) => Ok(items),
+            Value::Str(s) => Ok(s.chars().map(|c| Value::Str(c.to_string())).collect()),
+            other => bail!("'{}' object is not iterable", other.type_name()),
+        }
+    }
+
+    fn eval_range(&mut self, args: &[Expression]) -> Result<Vec<Value>> {
+        let values = args.iter().map(|a| self.eval_expr(a)).collect::<Result<Vec<_>>>()?;
+        let as_int = |v: &Value| match v {
+            Value::Int(n) => Ok(*n),
+            other => bail!("range() argument must be an int, not '{}'", other.type_name()),
+        };
+        let (start, end, step) = match values.as_slice() {
+            [end] => (0, as_int(end)?, 1),
+            [start, end] => (as_int(start)?, as_int(end)?, 1),
+            [start, end, step] => (as_int(start)?, as_int(end)?, as_int(step)?),
+            _ => bail!("range() expected 1 to 3 arguments, got {}", values.len()),
+        };
+        if step == 0 {
+            bail!("range() arg 3 must not be zero");
+        }
+        let mut out = Vec::new();
+        let mut i = start;
+        while (step > 0 && i < end) || (step < 0 && i > end) {
+            out.push(Value::Int(i));
+            i += step;
+        }
+        Ok(out)
+    }
+
+    fn eval_expr(&mut self, expr: &Expression) -> Result<Value> {
+        match expr {
+            Expression::IntLit(n) => Ok(Value::Int(*n)),
+            Expression::FloatLit(n) => Ok(Value::Float(*n)),
+            Expression::BoolLit(b) => Ok(Value::Bool(*b)),
+            Expression::StringLit(s) => Ok(Value::Str(s.clone())),
+            Expression::Identifier(name) if name == "None" => Ok(Value::None),
+            Expression::Identifier(name) => self.read_name(name),
+            Expression::BinOp(lhs, op, rhs) => {
+                // `and`/`or` short-circuit, so their right side must not be
+                // evaluated eagerly like every other `BinOp`.
+                match op {
+                    BinOp::And => {
+                        let left = self.eval_expr(lhs)?;
+                        if !left.truthy() {
+                            return Ok(left);
+                        }
+                        self.eval_expr(rhs)
+                    }
+                    BinOp::Or => {
+                        let left = self.eval_expr(lhs)?;
+                        if left.truthy() {
+                            return Ok(left);
+                        }
+                        self.eval_expr(rhs)
+                    }
+                    _ => {
+                        let left = self.eval_expr(lhs)?;
+                        let right = self.eval_expr(rhs)?;
+                        Self::apply_binop(*op, &left, &right)
+                    }
+                }
+            }
+            Expression::UnaryOp(op, operand) => {
+                let value = self.eval_expr(operand)?;
+                Self::apply_unaryop(*op, &value)
+            }
+            Expression::Conditional(cond, then_expr, else_expr) => {
+                if self.eval_expr(cond)?.truthy() {
+                    self.eval_expr(then_expr)
+                } else {
+                    self.eval_expr(else_expr)
+                }
+            }
+            Expression::List(items) => Ok(Value::List(
+                items.iter().map(|e| self.eval_expr(e)).collect::<Result<Vec<_>>>()?,
+            )),
+            Expression::Tuple(items) => Ok(Value::Tuple(
+                items.iter().map(|e| self.eval_expr(e)).collect::<Result<Vec<_>>>()?,
+            )),
+            Expression::Index(target, index) => {
+                let target = self.eval_expr(target)?;
+                let index = self.eval_expr(index)?;
+                self.eval_index(&target, &index)
+            }
+            Expression::FString(parts) => {
+                let mut out = String::new();
+                for part in parts {
+                    match part {
+                        FStringPart::Literal(text) => out.push_str(text),
+                        FStringPart::Expr(expr, _spec) => out.push_str(&self.eval_expr(expr)?.to_string()),
+                    }
+                }
+                Ok(Value::Str(out))
+            }
+            Expression::Call(callee, args) => self.eval_call(callee, args),
+            Expression::Kwarg(_, value) => self.eval_expr(value),
+            Expression::Slice(..) | Expression::Attribute(..) | Expression::Dict(..) | Expression::Lambda(..) => {
+                bail!("this expression form isn't supported in the REPL yet")
+            }
+        }
+    }
+
+    fn eval_index(&self, target: &Value, index: &Value) -> Result<Value> {
+        let items: &[Value] = match target {
+            Value::List(items) | Value::Tuple(items) => items,
+            _ => bail!("'{}' object is not subscriptable", target.type_name()),
+        };
+        let Value::Int(i) = index else {
+            bail!("indices must be integers, not '{}'", index.type_name());
+        };
+        let len = items.len() as i64;
+        let resolved = if *i < 0 { i + len } else { *i };
+        items
+            .get(resolved as usize)
+            .cloned()
+            .ok_or_else(|| anyhow!("index out of range"))
+    }
+
+    fn eval_call(&mut self, callee: &Expression, args: &[Expression]) -> Result<Value> {
+        let Expression::Identifier(name) = callee else {
+            bail!("this call form isn't supported in the REPL yet");
+        };
+        let values = args.iter().map(|a| self.eval_expr(a)).collect::<Result<Vec<_>>>()?;
+        if let Some(result) = self.eval_builtin(name, &values)? {
+            return Ok(result);
+        }
+        let Some(func) = self.functions.get(name).cloned() else {
+            bail!("name '{name}' is not defined");
+        };
+        self.call_function(&func, values)
+    }
+
+    /// The handful of builtins `ir_lowering`'s codegen path also special-cases
+    /// (`print`, `len`, `range`) plus the basic type constructors - `None` if
+    /// `name` isn't one of these, so `eval_call` falls through to a
+    /// user-defined function of the same name.
+    fn eval_builtin(&self, name: &str, args: &[Value]) -> Result<Option<Value>> {
+        Ok(Some(match name {
+            "print" => {
+                let text = args.iter().map(Value::to_string).collect::<Vec<_>>().join(" ");
+                println!("{text}");
+                Value::None
+            }
+            "len" => match args.first() {
+                Some(Value::List(items) | Value::Tuple(items)) => Value::Int(items.len() as i64),
+                Some(Value::Str(s)) => Value::Int(s.chars().count() as i64),
+                Some(other) => bail!("object of type '{}' has no len()", other.type_name()),
+                None => bail!("len() expected 1 argument, got 0"),
+            },
+            "abs" => match args.first() {
+                Some(Value::Int(n)) => Value::Int(n.abs()),
+                Some(Value::Float(n)) => Value::Float(n.abs()),
+                Some(other) => bail!("bad operand type for abs(): '{}'", other.type_name()),
+                None => bail!("abs() expected 1 argument, got 0"),
+            },
+            "int" => match args.first() {
+                Some(Value::Int(n)) => Value::Int(*n),
+                Some(Value::Float(n)) => Value::Int(*n as i64),
+                Some(Value::Bool(b)) => Value::Int(if *b { 1 } else { 0 }),
+                Some(Value::Str(s)) => {
+                    Value::Int(s.trim().parse().map_err(|_| anyhow!("invalid literal for int() with base 10: {s:?}"))?)
+                }
+                Some(other) => bail!("int() argument must be a string or a number, not '{}'", other.type_name()),
+                None => Value::Int(0),
+            },
+            "float" => match args.first() {
+                Some(v @ (Value::Int(_) | Value::Float(_) | Value::Bool(_))) => Value::Float(v.as_f64().unwrap()),
+                Some(Value::Str(s)) => {
+                    Value::Float(s.trim().parse().map_err(|_| anyhow!("could not convert string to float: {s:?}"))?)
+                }
+                Some(other) => bail!("float() argument must be a string or a number, not '{}'", other.type_name()),
+                None => Value::Float(0.0),
+            },
+            "str" => Value::Str(args.first().map(Value::to_string).unwrap_or_default()),
+            "bool" => Value::Bool(args.first().map(Value::truthy).unwrap_or(false)),
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Binds `args` positionally against `func.params` (falling back to
+    /// declared defaults for any trailing ones omitted, the same rule
+    /// `IRLowering::lower_call_args` uses for a real compiled call), pushes a
+    /// fresh `Frame`, and runs the body - `Flow::Break`/`Flow::Continue`
+    /// escaping the body is a bug in the parsed program (a `break`/`continue`
+    /// outside any loop), not something a function call should ever see.
+    fn call_function(&mut self, func: &FunctionDef, args: Vec<Value>) -> Result<Value> {
+        if args.len() > func.params.len() {
+            bail!("{}() takes {} positional arguments but {} were given", func.name, func.params.len(), args.len());
+        }
+        let mut frame = Frame::default();
+        for (i, param) in func.params.iter().enumerate() {
+            let value = match args.get(i) {
+                Some(v) => v.clone(),
+                None => match &param.default {
+                    Some(default_expr) => self.eval_expr(default_expr)?,
+                    None => bail!("{}() missing required argument: '{}'", func.name, param.name),
+                },
+            };
+            frame.locals.insert(param.name.clone(), value);
+        }
+        self.call_stack.push(frame);
+        let result = self.eval_block(&func.body);
+        self.call_stack.pop();
+        match result? {
+            Some(Flow::Return(value)) => Ok(value),
+            Some(Flow::Break) => bail!("'break' outside loop"),
+            Some(Flow::Continue) => bail!("'continue' outside loop"),
+            None => Ok(Value::None),
+        }
+    }
+
+    fn apply_unaryop(op: UnaryOp, value: &Value) -> Result<Value> {
+        match (op, value) {
+            (UnaryOp::Not, v) => Ok(Value::Bool(!v.truthy())),
+            (UnaryOp::Neg, Value::Int(n)) => Ok(Value::Int(-n)),
+            (UnaryOp::Neg, Value::Float(n)) => Ok(Value::Float(-n)),
+            (UnaryOp::Pos, Value::Int(n)) => Ok(Value::Int(*n)),
+            (UnaryOp::Pos, Value::Float(n)) => Ok(Value::Float(*n)),
+            (UnaryOp::Invert, Value::Int(n)) => Ok(Value::Int(!n)),
+            _ => bail!("bad operand type for unary operator: '{}'", value.type_name()),
+        }
+    }
+
+    /// Numeric arithmetic promotes `Int`/`Bool` to `Float` the moment either
+    /// side is a `Float`, matching Python's numeric tower - everything else
+    /// (string concatenation, comparisons, bitwise ops) requires both sides
+    /// to already agree on type.
+    fn apply_binop(op: BinOp, left: &Value, right: &Value) -> Result<Value> {
+        use BinOp::*;
+        if let (BinOp::Add, Value::Str(a), Value::Str(b)) = (op, left, right) {
+            return Ok(Value::Str(format!("{a}{b}")));
+        }
+        if let (BinOp::Add, Value::List(a), Value::List(b)) = (op, left, right) {
+            return Ok(Value::List(a.iter().chain(b).cloned().collect()));
+        }
+        if matches!(op, Eq | NotEq) {
+            let equal = left == right || (left.as_f64().is_some() && right.as_f64().is_some() && left.as_f64() == right.as_f64());
+            return Ok(Value::Bool(if op == Eq { equal } else { !equal }));
+        }
+        if let (Value::Int(a), Value::Int(b)) = (left, right) {
+            return Self::apply_int_binop(op, *a, *b);
+        }
+        match (left.as_f64(), right.as_f64()) {
+            (Some(a), Some(b)) => Self::apply_float_binop(op, a, b),
+            _ => bail!(
+                "unsupported operand type(s) for {op:?}: '{}' and '{}'",
+                left.type_name(),
+                right.type_name()
+            ),
+        }
+    }
+
+    /// Python's `//` rounds toward negative infinity, unlike `div_euclid`
+    /// (which is Euclidean and only agrees with floor division for a
+    /// positive divisor - see `Optimizer::floor_div`, which has the same
+    /// split for the values it can fold at compile time). `div_euclid`
+    /// undershoots by exactly one whenever the divisor is negative and the
+    /// division isn't exact, so that's the only case needing a correction.
+    fn floor_div(a: i64, b: i64) -> i64 {
+        let q = a.div_euclid(b);
+        if b < 0 && a.rem_euclid(b) != 0 {
+            q - 1
+        } else {
+            q
+        }
+    }
+
+    /// Companion to `floor_div`: Python's `%` takes the sign of the
+    /// divisor, while `rem_euclid` is always non-negative. Shifting the
+    /// remainder by the divisor in the same negative-divisor case
+    /// `floor_div` corrects for keeps the two in sync
+    /// (`a == floor_div(a, b) * b + floor_mod(a, b)`).
+    fn floor_mod(a: i64, b: i64) -> i64 {
+        let rem = a.rem_euclid(b);
+        if b < 0 && rem != 0 {
+            rem + b
+        } else {
+            rem
+        }
+    }
+
+    fn apply_int_binop(op: BinOp, a: i64, b: i64) -> Result<Value> {
+        use BinOp::*;
+        Ok(match op {
+            Add => Value::Int(a + b),
+            Sub => Value::Int(a - b),
+            Mult => Value::Int(a * b),
+            Div => Value::Float(a as f64 / b as f64),
+            FloorDiv => {
+                if b == 0 {
+                    bail!("integer division or modulo by zero");
+                }
+                Value::Int(Self::floor_div(a, b))
+            }
+            Mod => {
+                if b == 0 {
+                    bail!("integer division or modulo by zero");
+                }
+                Value::Int(Self::floor_mod(a, b))
+            }
+            Pow => Value::Int(a.pow(b.try_into().unwrap_or(0))),
+            LShift => Value::Int(a << b),
+            RShift => Value::Int(a >> b),
+            BitOr => Value::Int(a | b),
+            BitXor => Value::Int(a ^ b),
+            BitAnd => Value::Int(a & b),
+            Lt => Value::Bool(a < b),
+            LtE => Value::Bool(a <= b),
+            Gt => Value::Bool(a > b),
+            GtE => Value::Bool(a >= b),
+            Eq | NotEq => unreachable!("handled by apply_binop before dispatching here"),
+            Is => Value::Bool(a == b),
+            IsNot => Value::Bool(a != b),
+            And | Or => unreachable!("short-circuited by eval_expr before reaching apply_binop"),
+            In | NotIn => bail!("'in' isn't supported between integers"),
+        })
+    }
+
+    fn apply_float_binop(op: BinOp, a: f64, b: f64) -> Result<Value> {
+        use BinOp::*;
+        Ok(match op {
+            Add => Value::Float(a + b),
+            Sub => Value::Float(a - b),
+            Mult => Value::Float(a * b),
+            Div => Value::Float(a / b),
+            FloorDiv => Value::Float((a / b).floor()),
+            // Python's float `%` takes the sign of the divisor, same as the
+            // int case above; `a - b * (a / b).floor()` gives exactly that
+            // (unlike `rem_euclid`, which is always non-negative).
+            Mod => Value::Float(a - b * (a / b).floor()),
+            Pow => Value::Float(a.powf(b)),
+            Lt => Value::Bool(a < b),
+            LtE => Value::Bool(a <= b),
+            Gt => Value::Bool(a > b),
+            GtE => Value::Bool(a >= b),
+            Eq | NotEq => unreachable!("handled by apply_binop before dispatching here"),
+            Is => Value::Bool(a == b),
+            IsNot => Value::Bool(a != b),
+            And | Or => unreachable!("short-circuited by eval_expr before reaching apply_binop"),
+            LShift | RShift | BitOr | BitXor | BitAnd => bail!("bitwise operators require integer operands"),
+            In | NotIn => bail!("'in' isn't supported between floats"),
+        })
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
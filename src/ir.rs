@@ -7,10 +7,25 @@ use crate::directives::DirectiveSet;
 pub struct IRModule {
     pub functions: Vec<IRFunction>,
     pub globals: Vec<IRGlobal>,
+    pub structs: Vec<IRStruct>,
     pub hot_functions: Vec<String>, // Profiled hot functions
 }
 
+/// A Rust `struct` lowered from a "dataclass-like" Python class - see
+/// `IRLowering::lower_class`. Has no notion of inheritance; a class's
+/// `bases` are parsed but never consulted here.
 #[derive(Debug, Clone)]
+pub struct IRStruct {
+    pub name: String,
+    pub fields: Vec<IRParam>,
+    /// `Some(item)` when this struct backs a `IRLowering::lower_generator`
+    /// generator - `IRCodegen` then wraps its `next` method (see
+    /// `IRFunction::is_generator_next`) in `impl Iterator for {name}`
+    /// instead of a plain inherent `impl`.
+    pub item_type: Option<Type>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct IRFunction {
     pub name: String,
     pub params: Vec<IRParam>,
@@ -18,15 +33,43 @@ pub struct IRFunction {
     pub blocks: Vec<BasicBlock>,
     pub directives: DirectiveSet,
     pub optimization_level: OptimizationLevel,
+    /// The class this function is a method of, if any - `IRCodegen` renders
+    /// it inside that class's `impl` block, taking `&mut self`, instead of
+    /// as a free top-level function.
+    pub owner: Option<String>,
+    /// True for the `next` method `IRLowering::lower_generator` builds -
+    /// `IRCodegen` gives it an `Option<Item>` return type and wraps its
+    /// `Return`s in `Some`/`None` instead of emitting `return_type` and the
+    /// return value verbatim.
+    pub is_generator_next: bool,
+    /// 1-based line of the original `def` in the Python source, carried
+    /// from `ast::FunctionDef::line` - `None` for functions with no single
+    /// source line of their own (e.g. the synthesized `main`). Used by
+    /// `IRCodegen::generate` for provenance comments.
+    pub source_line: Option<usize>,
+    /// Names bound by a `global` statement anywhere in this function's body
+    /// (see `ast::Statement::Global`) - only these names' assignments write
+    /// straight through to the module-level global; any other assignment to
+    /// a same-named identifier is an ordinary local shadow, matching
+    /// Python's rule that `global` is required for a *write* to bind to
+    /// module scope (a read never needs it). See `IRCodegen::assign`.
+    pub global_names: Vec<String>,
+    /// The exact Python source of this function, present only when it
+    /// carries `#adrenaline:no-compile` - filled in after lowering by
+    /// `Compiler::attach_python_fallbacks`, since nothing upstream of it
+    /// keeps the original text alongside the AST. `IRCodegen::generate_function`
+    /// embeds this and routes calls through `adrenaline_runtime::py_call_fallback`
+    /// instead of translating the (possibly unsupported) lowered body.
+    pub python_source: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct IRParam {
     pub name: String,
     pub typ: Type,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum OptimizationLevel {
     None,
     Basic,
@@ -34,14 +77,14 @@ pub enum OptimizationLevel {
     Extreme,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct BasicBlock {
     pub id: usize,
     pub instructions: Vec<IRInstruction>,
     pub successors: Vec<usize>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum IRInstruction {
     // Arithmetic
     BinOp {
@@ -56,6 +99,17 @@ pub enum IRInstruction {
         operand: IRValue,
     },
 
+    /// A fused `a * b + c`, produced only under fast-math (see
+    /// `IROptimizer::fast_math_transform`) since fusing the multiply and add
+    /// into a single rounding step is observable for floats even though it
+    /// isn't for this IR's integer-only constants today.
+    Fma {
+        result: IRValue,
+        a: IRValue,
+        b: IRValue,
+        c: IRValue,
+    },
+
     // Memory
     Assign {
         target: IRValue,
@@ -80,6 +134,31 @@ pub enum IRInstruction {
         value: IRValue,
     },
 
+    /// Reads `cache[keys...]` for `IROptimizer::memoize_pure_functions` -
+    /// a real `HashMap` keyed on the full argument tuple rather than
+    /// `Index`'s plain `Vec` indexing, since a memoized function's
+    /// arguments aren't a dense range of small integers. `found` is set to
+    /// whether `keys` was present; `value` to the cached value on a hit, or
+    /// `value_type`'s default otherwise. `IRCodegen` declares `cache`
+    /// itself, the first time it sees a `CacheLookup` naming it, so there's
+    /// nothing upstream of this instruction that needs to emit a separate
+    /// "new cache" instruction.
+    CacheLookup {
+        found: IRValue,
+        value: IRValue,
+        cache: String,
+        keys: Vec<IRValue>,
+        key_types: Vec<Type>,
+        value_type: Type,
+    },
+    /// Writes `value` into `cache[keys...]`, populating the `HashMap` a
+    /// matching `CacheLookup` reads from.
+    CacheStore {
+        cache: String,
+        keys: Vec<IRValue>,
+        value: IRValue,
+    },
+
     // Control flow
     Branch {
         condition: IRValue,
@@ -93,6 +172,29 @@ pub enum IRInstruction {
         value: Option<IRValue>,
     },
 
+    /// Terminates a block the same way `Branch` does, but for a `try`
+    /// instead of an `if`: runs `try_block`, and if it panics, runs
+    /// `except_block` instead of propagating - `IRCodegen` renders this as
+    /// `std::panic::catch_unwind` around `try_block`, matched on `Err` to
+    /// pick `except_block`. `error_binding` is the local Python's
+    /// `except E as name` binds the caught panic's message to.
+    TryExcept {
+        try_block: usize,
+        except_block: usize,
+        error_binding: Option<String>,
+    },
+
+    /// Builds a `Name { field: value, ... }` struct literal - used only for
+    /// a lowered `__init__`'s trailing `Self { .. }` (see
+    /// `IRLowering::lower_constructor`). `name` is the literal class name
+    /// rather than always `Self`, since both are valid Rust here and the
+    /// class name reads better in the generated output.
+    NewStruct {
+        result: IRValue,
+        name: String,
+        fields: Vec<(String, IRValue)>,
+    },
+
     // Function calls
     Call {
         result: IRValue,
@@ -100,11 +202,50 @@ pub enum IRInstruction {
         args: Vec<IRValue>,
     },
 
+    /// `print(...)` - rendered by `IRCodegen` to match CPython's formatting
+    /// (space-separated args, `True`/`False`, Python list syntax) rather
+    /// than through the generic `Call` path, which has no way to express
+    /// that per-argument formatting or the `sep`/`end` separators.
+    Print {
+        args: Vec<(IRValue, PrintArgKind)>,
+        sep: String,
+        end: String,
+    },
+
+    /// A call whose receiver is a value rather than a free function name,
+    /// e.g. `list.append(x)`, or the `in`/`not in` operators lowered to
+    /// `list.contains(x)`.
+    MethodCall {
+        result: IRValue,
+        receiver: IRValue,
+        method: String,
+        args: Vec<IRValue>,
+    },
+
+    // Collections
+    /// Allocates a new, empty `Vec` for a Python list literal. `capacity`
+    /// mirrors `Vec::with_capacity` when the final size is known up front
+    /// (always true for a literal, since every element is pushed right
+    /// after), so the generated code doesn't reallocate while filling it in.
+    NewList {
+        result: IRValue,
+        capacity: Option<IRValue>,
+    },
+
+    /// An f-string, built at codegen time into a single Rust `format!` call
+    /// rather than a chain of `StrConcat`s, so each interpolation's format
+    /// spec (`{x:.2f}`, alignment, width, ...) has somewhere to attach.
+    FormatString {
+        result: IRValue,
+        parts: Vec<FormatPart>,
+    },
+
     // Loops (for optimization passes)
     LoopStart {
         iterator: IRValue,
         body_block: usize,
         exit_block: usize,
+        metadata: LoopMetadata,
     },
     LoopEnd,
 
@@ -113,9 +254,29 @@ pub enum IRInstruction {
     Parallelizable,
     CanElideCheck,
     Pure, // Function has no side effects
+
+    /// A batch of independent same-op binary operations rewritten to run as
+    /// one SIMD instruction over `lanes.len()` elements, replacing the
+    /// scalar `BinOp`s that fed the `Vectorizable` hint.
+    SimdBinOp {
+        op: BinOpIR,
+        lanes: Vec<(IRValue, IRValue, IRValue)>, // (result, left, right) per lane
+    },
+
+    /// Marks a `Parallelizable` block as a recognized associative reduction
+    /// into `target` via `op`, so parallel codegen can accumulate per-thread
+    /// partial results instead of racing on a shared write.
+    Reduction { target: IRValue, op: BinOpIR },
+
+    /// The Python source line `IRLowering::lower_statement` was lowering the
+    /// rest of this instruction run from - emitted once per statement,
+    /// including nested ones, unconditionally (cheap: just a struct in a
+    /// `Vec`). Only rendered into a real call by `IRCodegen` when
+    /// `--profile-lines` is on - see `IRCodegen::set_profile_lines`.
+    LineMarker { line: usize },
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum BinOpIR {
     Add,
     Sub,
@@ -135,23 +296,125 @@ pub enum BinOpIR {
     LtE,
     Gt,
     GtE,
+
+    /// `str + str`: rendered via `format!` rather than Rust's `+` operator,
+    /// since `String: Add` only accepts a `&str` right-hand side and would
+    /// need the codegen to know which operand owns its buffer.
+    StrConcat,
+    /// `str * int` (Python string repetition) - `BinOpIR::Mul` stays
+    /// numeric-only, so this gets its own opcode rather than overloading it.
+    StrRepeat,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// One fragment of a `FormatString`: either literal text carried straight
+/// into the generated format string, or a value with its (already
+/// Rust-translated) format spec - see `IRCodegen::translate_format_spec`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum FormatPart {
+    Literal(String),
+    Value(IRValue, Option<String>),
+}
+
+/// How one `Print` argument should be rendered to match CPython's `print`,
+/// decided structurally at lowering time from the argument's own AST shape
+/// (see `IRLowering::print_arg_kind`) - a value passed through a variable
+/// still renders with plain `Display`, the same best-effort limit as the
+/// rest of this compiler's type tracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PrintArgKind {
+    /// `{}` (Display) - already Python-compatible for strings, ints, and
+    /// floats.
+    Plain,
+    /// A `bool` literal - Display renders `true`/`false`, but Python's
+    /// `print` wants `True`/`False`.
+    Bool,
+    /// A list literal - `{:?}` (Debug) on a `Vec` already renders with
+    /// Python-compatible `[a, b, c]` syntax.
+    List,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum UnaryOpIR {
     Neg,
     Not,
     BitNot,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Trip count analysis result for a loop, shared by unrolling, vectorization,
+/// and parallelization so they don't each re-derive it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TripCount {
+    /// Iteration count is a compile-time constant.
+    Known(u64),
+    /// Iteration count is not constant, but a static analysis produced a guess
+    /// (e.g. from profiling data or a heuristic on the loop bound expression).
+    Estimated(u64),
+    Unknown,
+}
+
+impl TripCount {
+    pub fn value(&self) -> Option<u64> {
+        match self {
+            TripCount::Known(n) | TripCount::Estimated(n) => Some(*n),
+            TripCount::Unknown => None,
+        }
+    }
+
+    // No caller distinguishes `Known` from `Estimated` yet - every current
+    // consumer goes through `value()`, which treats both the same.
+    #[allow(dead_code)]
+    pub fn is_known(&self) -> bool {
+        matches!(self, TripCount::Known(_))
+    }
+}
+
+/// A variable that changes by a fixed stride on every iteration of a loop.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct InductionVariable {
+    pub value: IRValue,
+    pub start: IRConstant,
+    pub stride: i64,
+}
+
+/// Analysis results attached to a `LoopStart`, computed once and reused by
+/// every later pass (unrolling, vectorization, parallelization) instead of
+/// each pass re-deriving trip counts and induction variables on its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LoopMetadata {
+    pub trip_count: Option<TripCount>,
+    pub induction_variables: Vec<InductionVariable>,
+}
+
+impl LoopMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // No caller builds a `LoopMetadata` through this builder yet -
+    // `analyze_loops` sets `trip_count` directly on the struct.
+    #[allow(dead_code)]
+    pub fn with_trip_count(mut self, trip_count: TripCount) -> Self {
+        self.trip_count = Some(trip_count);
+        self
+    }
+
+    pub fn add_induction_variable(&mut self, iv: InductionVariable) {
+        self.induction_variables.push(iv);
+    }
+
+    pub fn primary_induction_variable(&self) -> Option<&InductionVariable> {
+        self.induction_variables.first()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum IRValue {
     Const(IRConstant),
     Local(String),
     Temporary(usize),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum IRConstant {
     Int(i64),
     Bool(bool),
@@ -159,11 +422,48 @@ pub enum IRConstant {
     Null,
 }
 
+/// A module-level `name = literal`, lowered from a top-level
+/// `ast::Statement::Assign` - see `IRLowering::lower_globals`. Rendered by
+/// `IRCodegen::generate_globals` as a `const`, an atomic `static` if
+/// `mutable`, or a `once_cell::sync::Lazy` for a lookup-table list.
 #[derive(Debug, Clone)]
 pub struct IRGlobal {
     pub name: String,
     pub typ: Type,
-    pub initializer: Option<IRValue>,
+    pub initializer: IRGlobalInit,
+    /// True when some function's `global` declaration mutates this name.
+    /// Only ever set for `Type::Int`/`Type::Bool`, the two types
+    /// `IRCodegen` can back with a `std::sync::atomic` static - a `global`
+    /// mutation of anything else (a float, a string, a list) still parses
+    /// but falls back to an ordinary local shadow inside that function,
+    /// same as before this global existed.
+    pub mutable: bool,
+}
+
+/// The literal a `IRGlobal` was initialized from - narrower than
+/// `IRValue`/`IRConstant` since a global also needs to represent a lookup
+/// table (`IntList`/`FloatList`), and never needs to represent a temporary.
+#[derive(Debug, Clone)]
+pub enum IRGlobalInit {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    IntList(Vec<i64>),
+    FloatList(Vec<f64>),
+}
+
+impl IRGlobalInit {
+    pub fn value_type(&self) -> Type {
+        match self {
+            IRGlobalInit::Int(_) => Type::Int,
+            IRGlobalInit::Float(_) => Type::Float,
+            IRGlobalInit::Bool(_) => Type::Bool,
+            IRGlobalInit::String(_) => Type::String,
+            IRGlobalInit::IntList(_) => Type::List(Box::new(Type::Int)),
+            IRGlobalInit::FloatList(_) => Type::List(Box::new(Type::Float)),
+        }
+    }
 }
 
 impl IRModule {
@@ -171,6 +471,7 @@ impl IRModule {
         Self {
             functions: Vec::new(),
             globals: Vec::new(),
+            structs: Vec::new(),
             hot_functions: Vec::new(),
         }
     }
@@ -0,0 +1,2283 @@
+/// Rust codegen from optimized IR
+/// Replaces the old AST-driven `codegen::RustCodegen` (which never saw
+/// anything `IROptimizer` did) with a generator that walks the possibly
+/// restructured basic-block graph directly, so unrolled loops, folded
+/// branches, and fused arithmetic all show up in the emitted source.
+use crate::ast_types::Type;
+use crate::cache::Cache;
+use crate::directives::{OverflowMode, ProfileOverheadMode};
+use crate::ir::*;
+use crate::optimizer::{OptimizationRemark, RemarkKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write as _;
+
+/// Emitted as a comment ahead of every `SimdBinOp` chunk, and grepped for by
+/// `Compiler::build_rust_project` to decide whether the generated crate is
+/// worth building with `-C target-cpu=native` - LLVM's SLP vectorizer only
+/// turns these independent same-op lanes into real vector instructions once
+/// the target supports a wide enough register, which the compiler's default
+/// conservative baseline target usually doesn't.
+pub const SIMD_CHUNK_MARKER: &str = "adrenaline:simd-chunk";
+
+/// Substring of the `std::panic::catch_unwind` call `emit_instruction`
+/// renders for `IRInstruction::TryExcept`, and also of the one
+/// `Compiler::generate_test_harness` writes around each test function -
+/// grepped for by `Compiler::build_rust_project` to refuse a build whose
+/// profile sets `panic = "abort"`, under which `catch_unwind` can't catch
+/// anything, so a `try`/`except` (or a failing test) would silently never
+/// run its handler instead of failing loudly at build time.
+pub const CATCH_UNWIND_MARKER: &str = "catch_unwind";
+
+/// The three blocks that make up a structured `while` loop, bundled so
+/// `emit_while` doesn't need to take each one as its own argument.
+struct WhileShape {
+    header: usize,
+    body: usize,
+    exit: usize,
+}
+
+pub struct IRCodegen {
+    output: String,
+    indent: usize,
+    /// Names already bound with `let mut` in the current function, so a
+    /// second write to the same local reuses it instead of shadowing it -
+    /// shadowing silently discarded every earlier iteration of a loop body
+    /// that reassigned a variable.
+    declared: HashSet<String>,
+    /// `#adrenaline:memoize` cache names already given their `let mut ...:
+    /// HashMap<..> = HashMap::new();` declaration in the current function -
+    /// see `emit_cache_lookup`. Separate from `declared` since a cache name
+    /// is never itself an `IRValue::Local`/`Temporary` that `assign` would
+    /// otherwise track.
+    declared_caches: HashSet<String>,
+    /// Whether the function currently being emitted renders `Type::Int` as
+    /// `num_bigint::BigInt` instead of `i64` - set per-function in
+    /// `generate_function`, see `function_needs_bigint`.
+    bigint: bool,
+    /// Names of every function in the module that needs bigint mode,
+    /// computed once up front so `emit_call` can tell when a call crosses
+    /// from a plain i64 caller into a bigint callee and needs its arguments
+    /// converted at the call site.
+    bigint_functions: HashSet<String>,
+    /// Whether the function currently being emitted is a
+    /// `IRLowering::lower_generator`-built `next` - set per-function in
+    /// `generate_function`, it makes `emit_seq`'s `Return` handling wrap the
+    /// value in `Some`/`None` instead of returning it bare.
+    is_generator_next: bool,
+    /// Set by `set_provenance` before `generate()` - the original Python
+    /// file and the optimizer's own remarks, so every emitted function/loop
+    /// can cite where it came from and what was done to it. `None`/empty
+    /// outside `generate()` (the PyO3 and C backends skip these comments).
+    source_file: Option<String>,
+    remarks: Vec<OptimizationRemark>,
+    /// Codegen-wide default set by `set_overflow_mode` (from `--overflow` /
+    /// the opt-level's default) - overridden per-function by an
+    /// `#adrenaline:overflow-*` directive, see `overflow_mode`.
+    default_overflow_mode: OverflowMode,
+    /// Resolved for whichever function is currently being emitted - set in
+    /// `generate_function`, next to `self.bigint`. Only consulted for plain
+    /// `+`/`-`/`*` on `i64`; bigint mode is already overflow-proof, and
+    /// `**`/`/`/`//`/`%` already have their own fixed semantics.
+    overflow_mode: OverflowMode,
+    /// Codegen-wide default when a function carries no `#adrenaline:
+    /// profile-*` directive - always `FullTiming` (today's only behavior
+    /// before this field existed), since there's no `--profile-*-timing`/
+    /// `--profile-counts-only` CLI flag to change it. See
+    /// `resolved_profile_overhead_mode` and `generate_function`'s choice of
+    /// which `profile_function!` variant to emit.
+    default_profile_overhead_mode: ProfileOverheadMode,
+    /// Names of every `IRGlobal` rendered as a `std::sync::atomic` static
+    /// (an `Int`/`Bool` global some function's `global` declaration
+    /// mutates), mapped to the atomic type used - computed once up front in
+    /// `generate_globals` so `value_to_rust`/`assign` can tell a bare read
+    /// or write of the name apart from an ordinary local.
+    atomic_globals: std::collections::HashMap<String, &'static str>,
+    /// The currently-emitted function's own `global_names`, restricted to
+    /// ones that are actually atomic - set in `generate_function`. `assign`
+    /// only routes a write through `.store(...)` for a name in this set;
+    /// anything else assigns an ordinary (possibly same-named, shadowing)
+    /// local instead, matching Python's rule that `global` is required for
+    /// a write, but not a read, to bind to module scope.
+    function_global_writes: HashSet<String>,
+    /// Set by `generate_lib` right before each top-level function's
+    /// `generate_function` call - `"pub "` for a plain Rust API export, or
+    /// `"pub extern \"C\" "` (with a preceding `#[no_mangle]` line) for one
+    /// whose signature is representable in a C header (see
+    /// `generate_c_header`'s `c_signature`). Left empty everywhere else, so
+    /// `generate`/`generate_pyo3`/`generate_c`'s always-private functions
+    /// for a `main`-driven binary are unaffected.
+    fn_export_prefix: String,
+    /// Set by `set_profile_instrument` (from `--profile-instrument`) -
+    /// `generate_function` wraps every non-fallback function body in
+    /// `adrenaline_runtime::profile_function!`, and `main`'s body
+    /// additionally gets a trailing call to write the collected counters
+    /// out, see `generate_function`.
+    profile_instrument: bool,
+    /// Set by `set_profile_alloc` (from `--profile-alloc`) - `generate`/
+    /// `generate_incremental` emit a `#[global_allocator]` installing
+    /// `adrenaline_runtime::alloc_profiling::CountingAllocator`, which
+    /// attributes each allocation to whichever `profile_function!`-wrapped
+    /// function is currently running.
+    profile_alloc: bool,
+    /// Set by `set_profile_lines` (from `--profile-lines`) - every
+    /// `IRInstruction::LineMarker` renders as a real
+    /// `adrenaline_runtime::line_profiling::record_line` call instead of
+    /// being skipped, and `main` gets a trailing call to write the
+    /// per-line report out alongside `--profile-instrument`'s.
+    profile_lines: bool,
+    /// Set by `set_profile_hwcounters` (from `--profile-hwcounters`) -
+    /// `generate_function` passes this through to the
+    /// `profile_function!` invocation wrapping each function body, so it
+    /// also reads cache-miss/branch-miss/instruction counts from
+    /// `adrenaline_runtime::hw_counters` (Linux-only) around the call,
+    /// alongside `profile_instrument`'s call count and timing.
+    profile_hwcounters: bool,
+    /// Set by `set_profile_live` (from `adrenaline profile --live`) -
+    /// `generate_function` has `main` call
+    /// `adrenaline_runtime::profiling::maybe_serve_live` at startup, so a
+    /// client can attach to its counters over a Unix domain socket while it
+    /// runs, alongside `profile_instrument`'s call count and timing.
+    profile_live: bool,
+    /// Set per-function in `generate_function`, true only for the
+    /// synthesized top-level `main` - `emit_seq`'s terminal
+    /// `Return { value: None }` case (the one `main` always falls through
+    /// to, per its own doc comment) writes any enabled report(s) in place
+    /// of a bare `return;` there instead of after the function's closing
+    /// brace, since a statement placed after that trailing `return;` would
+    /// be unreachable dead code.
+    emit_main_reports: bool,
+}
+
+/// One line of Rust generated by `IRCodegen::generate` mapped back to the
+/// Python line it came from, so `Compiler::build_rust_project` and `run` can
+/// translate a `rustc`/panic line number in the generated crate back to
+/// something the user actually wrote - see `Self::build_source_map`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceMapEntry {
+    pub rust_line: usize,
+    pub python_line: usize,
+}
+
+impl IRCodegen {
+    pub fn new() -> Self {
+        Self {
+            output: String::new(),
+            indent: 0,
+            declared: HashSet::new(),
+            declared_caches: HashSet::new(),
+            bigint: false,
+            bigint_functions: HashSet::new(),
+            is_generator_next: false,
+            source_file: None,
+            remarks: Vec::new(),
+            default_overflow_mode: OverflowMode::Wrap,
+            overflow_mode: OverflowMode::Wrap,
+            default_profile_overhead_mode: ProfileOverheadMode::FullTiming,
+            atomic_globals: std::collections::HashMap::new(),
+            function_global_writes: HashSet::new(),
+            fn_export_prefix: String::new(),
+            profile_instrument: false,
+            profile_alloc: false,
+            profile_lines: false,
+            profile_hwcounters: false,
+            profile_live: false,
+            emit_main_reports: false,
+        }
+    }
+
+    /// Sets the codegen-wide default overflow policy - `Compiler::compile`
+    /// calls this with either an explicit `--overflow` flag or a
+    /// profile-based default before `generate()`.
+    pub fn set_overflow_mode(&mut self, mode: OverflowMode) {
+        self.default_overflow_mode = mode;
+    }
+
+    /// Sets `--profile-instrument`'s codegen-wide switch - `Compiler::compile`
+    /// calls this before `generate()`/`generate_incremental()` when the flag
+    /// was passed, see `generate_function`.
+    pub fn set_profile_instrument(&mut self, enabled: bool) {
+        self.profile_instrument = enabled;
+    }
+
+    /// Sets `--profile-alloc`'s codegen-wide switch - `Compiler::compile`
+    /// calls this (alongside `set_profile_instrument`, which allocation
+    /// attribution depends on) before `generate()`/`generate_incremental()`
+    /// when the flag was passed.
+    pub fn set_profile_alloc(&mut self, enabled: bool) {
+        self.profile_alloc = enabled;
+    }
+
+    /// Sets `--profile-lines`'s codegen-wide switch - `Compiler::compile`
+    /// calls this before `generate()`/`generate_incremental()` when the flag
+    /// was passed, see `emit_instruction`'s `IRInstruction::LineMarker` arm.
+    pub fn set_profile_lines(&mut self, enabled: bool) {
+        self.profile_lines = enabled;
+    }
+
+    /// Sets `--profile-hwcounters`'s codegen-wide switch - `Compiler::compile`
+    /// calls this (alongside `set_profile_instrument`, which hardware-counter
+    /// attribution depends on) before `generate()`/`generate_incremental()`
+    /// when the flag was passed, see `generate_function`.
+    pub fn set_profile_hwcounters(&mut self, enabled: bool) {
+        self.profile_hwcounters = enabled;
+    }
+
+    /// Sets `adrenaline profile --live`'s codegen-wide switch -
+    /// `Compiler::live_profile` calls this (alongside `set_profile_instrument`,
+    /// which live snapshots depend on) before `generate()`/
+    /// `generate_incremental()`, see `generate_function`.
+    pub fn set_profile_live(&mut self, enabled: bool) {
+        self.profile_live = enabled;
+    }
+
+    /// `--profile-alloc`'s one piece of whole-file (rather than
+    /// per-function) codegen - installs `CountingAllocator` as the
+    /// generated binary's global allocator, called once at the top of
+    /// `generate`/`generate_incremental`. A no-op when the flag wasn't set.
+    fn emit_global_allocator(&mut self) {
+        if !self.profile_alloc {
+            return;
+        }
+        writeln!(self.output, "#[global_allocator]").ok();
+        writeln!(
+            self.output,
+            "static ADRENALINE_ALLOC: adrenaline_runtime::alloc_profiling::CountingAllocator = adrenaline_runtime::alloc_profiling::CountingAllocator;"
+        )
+        .ok();
+    }
+
+    /// Reconstructs a rust-line -> python-line map from the `// from
+    /// file:line` / `// from file (synthesized)` comments `generate_function`
+    /// writes ahead of every function - every line from one such comment to
+    /// the next inherits that function's Python line, since nothing more
+    /// precise than function granularity is tracked (see
+    /// `IRFunction::source_line`). Lines under a "(synthesized)" function
+    /// (no line of Python to point to) are left unmapped.
+    pub fn build_source_map(rust_code: &str) -> Vec<SourceMapEntry> {
+        let mut map = Vec::new();
+        let mut current_python_line: Option<usize> = None;
+        for (idx, line) in rust_code.lines().enumerate() {
+            let rust_line = idx + 1;
+            if let Some(location) = line.trim_start().strip_prefix("// from ") {
+                current_python_line = location.rsplit_once(':').and_then(|(_, n)| n.parse().ok());
+                continue;
+            }
+            if let Some(python_line) = current_python_line {
+                map.push(SourceMapEntry { rust_line, python_line });
+            }
+        }
+        map
+    }
+
+    /// Feeds `generate()`'s provenance comments - the source file each
+    /// function/loop is cited against, and the optimizer remarks (from
+    /// `IROptimizer::remarks`) describing what was done to it.
+    pub fn set_provenance(&mut self, source_file: &str, remarks: &[OptimizationRemark]) {
+        self.source_file = Some(source_file.to_string());
+        self.remarks = remarks.to_vec();
+    }
+
+    /// Every remark the optimizer recorded for `function`, most useful ones
+    /// first - `Applied` before `NotApplied`, since a directive that fired
+    /// is more interesting to an auditor than one that didn't.
+    fn remarks_for(&self, function: &str) -> Vec<&OptimizationRemark> {
+        let mut matches: Vec<&OptimizationRemark> =
+            self.remarks.iter().filter(|r| r.function == function).collect();
+        matches.sort_by_key(|r| !matches!(r.kind, RemarkKind::Applied));
+        matches
+    }
+
+    /// A one-line `// from file:line - ...` comment citing the original
+    /// Python source and, if the optimizer left any remarks for `function`,
+    /// summarizing what was applied - so a reader auditing the generated
+    /// Rust can trace it straight back to the Python that produced it,
+    /// without needing `--remarks` separately.
+    fn provenance_comment(&self, function: &IRFunction) -> Option<String> {
+        let file = self.source_file.as_deref()?;
+        let location = match function.source_line {
+            Some(line) => format!("{file}:{line}"),
+            None => format!("{file} (synthesized)"),
+        };
+        let remarks = self.remarks_for(&function.name);
+        if remarks.is_empty() {
+            Some(format!("// from {location}"))
+        } else {
+            let summary = remarks.iter().map(|r| r.message.as_str()).collect::<Vec<_>>().join("; ");
+            Some(format!("// from {location} - {summary}"))
+        }
+    }
+
+    pub fn generate(&mut self, module: &IRModule) -> String {
+        self.output.clear();
+        writeln!(self.output, "// Generated by Adrenaline Python → Rust (via IR)").ok();
+        self.emit_global_allocator();
+
+        self.generate_globals(&module.globals);
+
+        self.bigint_functions = module
+            .functions
+            .iter()
+            .filter(|f| self.function_needs_bigint(f))
+            .map(|f| f.name.clone())
+            .collect();
+
+        for ir_struct in &module.structs {
+            self.generate_struct(ir_struct);
+        }
+        for ir_struct in &module.structs {
+            self.generate_impl(module, ir_struct);
+        }
+
+        for function in module.functions.iter().filter(|f| f.owner.is_none()) {
+            self.generate_function(function);
+        }
+
+        self.output.clone()
+    }
+
+    /// Like `generate`, but reuses `cache`'s per-function entry (see
+    /// `Cache::get_cached_function`) for any top-level function whose
+    /// `function_cache_key` hasn't changed since the last compile, instead
+    /// of re-running `generate_function` for it - so a one-function edit in
+    /// a large file only regenerates that function's Rust. Only top-level
+    /// (non-method) functions are considered, matching `generate`'s own
+    /// split between the struct/impl loop above and this one; methods stay
+    /// on the always-regenerate path since `generate_impl` doesn't call
+    /// `generate_function` directly.
+    pub fn generate_incremental(&mut self, module: &IRModule, cache: &crate::cache::Cache) -> String {
+        self.output.clear();
+        writeln!(self.output, "// Generated by Adrenaline Python → Rust (via IR)").ok();
+        self.emit_global_allocator();
+
+        self.generate_globals(&module.globals);
+
+        self.bigint_functions = module
+            .functions
+            .iter()
+            .filter(|f| self.function_needs_bigint(f))
+            .map(|f| f.name.clone())
+            .collect();
+
+        for ir_struct in &module.structs {
+            self.generate_struct(ir_struct);
+        }
+        for ir_struct in &module.structs {
+            self.generate_impl(module, ir_struct);
+        }
+
+        for function in module.functions.iter().filter(|f| f.owner.is_none()) {
+            let function_hash = Cache::get_hash(&self.function_cache_key(function));
+            if let Ok(cached) = cache.get_cached_function(&function_hash) {
+                self.output.push_str(&cached);
+                continue;
+            }
+            let start = self.output.len();
+            self.generate_function(function);
+            let _ = cache.cache_function(&function_hash, &self.output[start..]);
+        }
+
+        self.output.clone()
+    }
+
+    /// Everything that can change what `generate_function` emits for
+    /// `function`, hashed together as the cache key `generate_incremental`
+    /// looks a previous run's snippet up by: the crate's own version (a
+    /// codegen change between adrenaline releases must never serve an old
+    /// release's cached snippet for the same IR), the function's own IR (its
+    /// body/params/return type/directives, already `function` since
+    /// `IRLowering` folds a def's source and `TypeInference`'s inferred
+    /// types into it), the provenance comment ahead of it (source file +
+    /// this function's optimizer remarks), which globals it writes that
+    /// happen to be atomic, which *other* module functions need bigint
+    /// call-site conversion (see `emit_call`), and `--profile-instrument`
+    /// (an instrumented and a plain build must never share a cached
+    /// snippet) - so an edit anywhere that would change this function's own
+    /// emitted text, even indirectly through a sibling function or a
+    /// global, is a cache miss rather than stale reused code.
+    fn function_cache_key(&self, function: &IRFunction) -> String {
+        let mut bigint_functions: Vec<&String> = self.bigint_functions.iter().collect();
+        bigint_functions.sort();
+        let global_writes: Vec<&String> = function
+            .global_names
+            .iter()
+            .filter(|name| self.atomic_globals.contains_key(*name))
+            .collect();
+        format!(
+            "{}|{}|{}|{:?}|{:?}|{:?}|{:?}|{}",
+            env!("CARGO_PKG_VERSION"),
+            self.profile_instrument,
+            self.profile_lines,
+            function,
+            bigint_functions,
+            global_writes,
+            self.default_overflow_mode,
+            self.provenance_comment(function).unwrap_or_default(),
+        )
+    }
+
+    /// A `// loop: ...` comment citing the `LoopStart` that owns `body_block`
+    /// (see `IROptimizer::analyze_loops`) - trip count and induction
+    /// variable(s) if known, plus whether this body carries a
+    /// `Vectorizable`/`Parallelizable`/`Reduction` hint, so a reader can see
+    /// why a loop did or didn't get the fast-path treatment without cross
+    /// referencing `--remarks` output.
+    fn loop_provenance_comment(&self, function: &IRFunction, body_block: usize) -> Option<String> {
+        self.source_file.as_deref()?;
+        let metadata = function.blocks.iter().flat_map(|b| &b.instructions).find_map(|i| match i {
+            IRInstruction::LoopStart { body_block: b, metadata, .. } if *b == body_block => Some(metadata),
+            _ => None,
+        })?;
+
+        let mut parts = Vec::new();
+        match metadata.trip_count {
+            Some(TripCount::Known(n)) => parts.push(format!("trip count {n}")),
+            Some(TripCount::Estimated(n)) => parts.push(format!("estimated trip count {n}")),
+            Some(TripCount::Unknown) | None => {}
+        }
+        for iv in &metadata.induction_variables {
+            if let IRValue::Local(name) = &iv.value {
+                parts.push(format!("induction `{name}` (stride {})", iv.stride));
+            }
+        }
+        if let Some(body) = Self::block(function, body_block) {
+            if body.instructions.iter().any(|i| matches!(i, IRInstruction::Vectorizable)) {
+                parts.push("vectorized".to_string());
+            }
+            if body.instructions.iter().any(|i| matches!(i, IRInstruction::Parallelizable)) {
+                parts.push("parallelized".to_string());
+            }
+        }
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("// loop: {}", parts.join(", ")))
+        }
+    }
+
+    /// Generates a PyO3 extension module instead of a `main`-driven binary
+    /// (see `Compiler::compile_python_extension`) - every top-level function
+    /// gets a `#[pyfunction]` wrapper and is registered in a `#[pymodule]`
+    /// init function named `module_name`, so `import {module_name}` from
+    /// Python exposes them directly. Classes aren't wrapped (PyO3's `#[pyclass]`
+    /// has its own constraints - no borrowed fields, `Send`, etc. - that
+    /// this compiler's structs aren't checked against), and the synthesized
+    /// `main` (see `IRLowering::lower_program`) is skipped since it isn't a
+    /// real user function.
+    pub fn generate_pyo3(&mut self, module: &IRModule, module_name: &str) -> String {
+        self.output.clear();
+        writeln!(self.output, "// Generated by Adrenaline Python → Rust (via IR)").ok();
+        writeln!(self.output, "use pyo3::prelude::*;").ok();
+
+        self.generate_globals(&module.globals);
+
+        self.bigint_functions = module
+            .functions
+            .iter()
+            .filter(|f| self.function_needs_bigint(f))
+            .map(|f| f.name.clone())
+            .collect();
+
+        let exported: Vec<&IRFunction> = module
+            .functions
+            .iter()
+            .filter(|f| f.owner.is_none() && f.name != "main")
+            .collect();
+
+        for function in &exported {
+            writeln!(self.output, "#[pyfunction]").ok();
+            self.generate_function(function);
+        }
+
+        writeln!(self.output, "#[pymodule]").ok();
+        writeln!(self.output, "fn {}(_py: Python, m: &PyModule) -> PyResult<()> {{", module_name).ok();
+        self.indent += 1;
+        let indent = "    ".repeat(self.indent);
+        for function in &exported {
+            writeln!(self.output, "{}m.add_function(wrap_pyfunction!({}, m)?)?;", indent, function.name).ok();
+        }
+        writeln!(self.output, "{}Ok(())", indent).ok();
+        self.indent -= 1;
+        writeln!(self.output, "}}").ok();
+
+        self.output.clone()
+    }
+
+    /// Generates a plain Rust API for a `staticlib`/`rlib` build (see
+    /// `Compiler::compile_lib`) instead of a `main`-driven binary. Every
+    /// top-level function `generate` would otherwise emit privately is
+    /// exported `pub` so an existing Rust crate can link against the
+    /// compiled `.rlib`/`.a` and call it directly; one whose signature is
+    /// representable in a C header (see `generate_c_header`) additionally
+    /// gets `#[no_mangle] pub extern "C"`, so the same build is callable
+    /// from C/C++ too. Skips the synthesized `main` (see
+    /// `IRLowering::lower_program`), same as `generate_pyo3`/`generate_c`.
+    pub fn generate_lib(&mut self, module: &IRModule) -> String {
+        self.output.clear();
+        writeln!(self.output, "// Generated by Adrenaline Python → Rust (via IR)").ok();
+        self.emit_global_allocator();
+
+        self.generate_globals(&module.globals);
+
+        self.bigint_functions = module
+            .functions
+            .iter()
+            .filter(|f| self.function_needs_bigint(f))
+            .map(|f| f.name.clone())
+            .collect();
+
+        for ir_struct in &module.structs {
+            self.generate_struct(ir_struct);
+        }
+        for ir_struct in &module.structs {
+            self.generate_impl(module, ir_struct);
+        }
+
+        for function in module.functions.iter().filter(|f| f.owner.is_none() && f.name != "main") {
+            if Self::c_signature(function).is_ok() {
+                writeln!(self.output, "#[no_mangle]").ok();
+                self.fn_export_prefix = "pub extern \"C\" ".to_string();
+            } else {
+                self.fn_export_prefix = "pub ".to_string();
+            }
+            self.generate_function(function);
+        }
+        self.fn_export_prefix.clear();
+
+        self.output.clone()
+    }
+
+    /// A C header declaring the subset of `generate_lib`'s top-level
+    /// functions whose signature `generate_c`'s own `c_signature`/`c_type`
+    /// (the same narrow `int`/`bool` subset `compile_c` uses) can represent,
+    /// so the two never disagree about which functions are C-callable -
+    /// this reuses them rather than defining a second notion of "C-safe
+    /// type". A function outside that subset gets a `// skipped` comment
+    /// instead of silently vanishing from the header, so a reader diffing
+    /// it against `compile_lib`'s full Rust API can see why it isn't
+    /// callable from C.
+    pub fn generate_c_header(module: &IRModule, header_guard: &str) -> String {
+        let mut out = String::new();
+        writeln!(out, "// Generated by Adrenaline Python → Rust (via IR)").ok();
+        writeln!(out, "#ifndef {header_guard}").ok();
+        writeln!(out, "#define {header_guard}").ok();
+        writeln!(out).ok();
+        writeln!(out, "#include <stdbool.h>").ok();
+        writeln!(out).ok();
+
+        for function in module.functions.iter().filter(|f| f.owner.is_none() && f.name != "main") {
+            match Self::c_signature(function) {
+                Ok(sig) => {
+                    writeln!(out, "{sig};").ok();
+                }
+                Err(reason) => {
+                    writeln!(out, "// {} skipped: {reason}", function.name).ok();
+                }
+            }
+        }
+
+        writeln!(out).ok();
+        writeln!(out, "#endif // {header_guard}").ok();
+        out
+    }
+
+    /// Generates portable C instead of Rust, for embedding a kernel in a
+    /// build that has no Rust toolchain (see `Compiler::compile_c`). Emits
+    /// plain functions to be linked into someone else's C program, the same
+    /// way `generate_pyo3` exports functions to be imported from Python -
+    /// so it skips the synthesized entry point (see `IRLowering::lower_main`)
+    /// exactly like `generate_pyo3` does, rather than trying to give it a
+    /// `main` of its own.
+    ///
+    /// Only covers a deliberately narrow subset - `int`/`bool` params,
+    /// locals, and returns, arithmetic, comparisons, and calls between
+    /// generated functions - since C has none of `IRCodegen`'s
+    /// structured-control-flow reconstruction to lean on for the rest; every
+    /// block instead becomes a C label and `Branch`/`Jump` become `goto`,
+    /// which C supports directly and the main Rust path has no need for.
+    /// Bails with `Err` on the first instruction or type outside that
+    /// subset (floats, strings, lists, classes, calls into the standard
+    /// library) rather than emitting something subtly wrong.
+    pub fn generate_c(&mut self, module: &IRModule) -> Result<String, String> {
+        self.output.clear();
+        writeln!(self.output, "/* Generated by Adrenaline Python -> C (via IR) */").ok();
+        writeln!(self.output, "#include <stdio.h>\n").ok();
+
+        let exported: Vec<&IRFunction> =
+            module.functions.iter().filter(|f| f.owner.is_none() && f.name != "main").collect();
+        let known: HashSet<String> = exported.iter().map(|f| f.name.clone()).collect();
+
+        for function in &exported {
+            writeln!(self.output, "{};", Self::c_signature(function)?).ok();
+        }
+        writeln!(self.output).ok();
+
+        for function in &exported {
+            self.generate_c_function(function, &known)?;
+        }
+
+        Ok(self.output.clone())
+    }
+
+    fn c_type(typ: &Type) -> Result<&'static str, String> {
+        match typ {
+            Type::Int | Type::Bool => Ok("long long"),
+            Type::NoneType => Ok("void"),
+            other => Err(format!("the C backend doesn't support the `{other:?}` type")),
+        }
+    }
+
+    fn c_signature(function: &IRFunction) -> Result<String, String> {
+        let ret_ty = Self::c_type(&function.return_type)?;
+        let params = function
+            .params
+            .iter()
+            .map(|p| Ok(format!("{} {}", Self::c_type(&p.typ)?, p.name)))
+            .collect::<Result<Vec<_>, String>>()?;
+        let params = if params.is_empty() { "void".to_string() } else { params.join(", ") };
+        Ok(format!("{} {}({})", ret_ty, function.name, params))
+    }
+
+    fn c_value_key(value: &IRValue) -> Option<String> {
+        match value {
+            IRValue::Local(name) => Some(name.clone()),
+            IRValue::Temporary(id) => Some(format!("__t{id}")),
+            IRValue::Const(_) => None,
+        }
+    }
+
+    fn c_value(value: &IRValue) -> Result<String, String> {
+        match value {
+            IRValue::Const(IRConstant::Int(n)) => Ok(n.to_string()),
+            IRValue::Const(IRConstant::Bool(b)) => Ok((*b as i64).to_string()),
+            IRValue::Const(IRConstant::String(_)) | IRValue::Const(IRConstant::Null) => {
+                Err("the C backend doesn't support string/null constants".to_string())
+            }
+            other => Ok(Self::c_value_key(other).unwrap()),
+        }
+    }
+
+    fn c_binop(op: BinOpIR, left: &IRValue, right: &IRValue) -> Result<String, String> {
+        let l = Self::c_value(left)?;
+        let r = Self::c_value(right)?;
+        let op_str = match op {
+            BinOpIR::Add => "+",
+            BinOpIR::Sub => "-",
+            BinOpIR::Mul => "*",
+            BinOpIR::BitAnd => "&",
+            BinOpIR::BitOr => "|",
+            BinOpIR::BitXor => "^",
+            BinOpIR::LShift => "<<",
+            BinOpIR::RShift => ">>",
+            BinOpIR::Eq => "==",
+            BinOpIR::NotEq => "!=",
+            BinOpIR::Lt => "<",
+            BinOpIR::LtE => "<=",
+            BinOpIR::Gt => ">",
+            BinOpIR::GtE => ">=",
+            BinOpIR::Div | BinOpIR::FloorDiv | BinOpIR::Mod | BinOpIR::Pow | BinOpIR::StrConcat | BinOpIR::StrRepeat => {
+                return Err(format!("the C backend doesn't support the `{op:?}` operator"))
+            }
+        };
+        Ok(format!("({l} {op_str} {r})"))
+    }
+
+    /// Every local/temporary the function ever assigns, declared up front C89-style
+    /// so a `goto` never jumps past a declaration - params are excluded since
+    /// they're already declared by the signature itself.
+    fn c_locals(function: &IRFunction) -> HashSet<String> {
+        let param_names: HashSet<&str> = function.params.iter().map(|p| p.name.as_str()).collect();
+        let mut locals = HashSet::new();
+        for instr in function.blocks.iter().flat_map(|b| &b.instructions) {
+            let target = match instr {
+                IRInstruction::Assign { target, .. }
+                | IRInstruction::BinOp { result: target, .. }
+                | IRInstruction::UnaryOp { result: target, .. }
+                | IRInstruction::Load { result: target, .. }
+                | IRInstruction::Call { result: target, .. } => Self::c_value_key(target),
+                IRInstruction::Store { target, .. } => Some(target.clone()),
+                _ => None,
+            };
+            if let Some(name) = target {
+                if !param_names.contains(name.as_str()) {
+                    locals.insert(name);
+                }
+            }
+        }
+        locals
+    }
+
+    fn generate_c_function(&mut self, function: &IRFunction, known: &HashSet<String>) -> Result<(), String> {
+        if function.owner.is_some() {
+            return Err(format!("the C backend doesn't support methods (`{}`)", function.name));
+        }
+
+        writeln!(self.output, "{} {{", Self::c_signature(function)?).ok();
+        self.indent += 1;
+        for name in Self::c_locals(function) {
+            self.writeln(&format!("long long {name};"));
+        }
+
+        for block in &function.blocks {
+            self.writeln(&format!("L{}:", block.id));
+            for instr in &block.instructions {
+                self.emit_c_instruction(instr, known)?;
+            }
+        }
+        self.indent -= 1;
+        writeln!(self.output, "}}\n").ok();
+        Ok(())
+    }
+
+    fn emit_c_instruction(&mut self, instr: &IRInstruction, known: &HashSet<String>) -> Result<(), String> {
+        match instr {
+            IRInstruction::Assign { target, value } => {
+                let key = Self::c_value_key(target).ok_or("cannot assign to a constant")?;
+                let expr = Self::c_value(value)?;
+                self.writeln(&format!("{key} = {expr};"));
+            }
+            IRInstruction::Load { result, source } => {
+                let key = Self::c_value_key(result).ok_or("cannot assign to a constant")?;
+                self.writeln(&format!("{key} = {source};"));
+            }
+            IRInstruction::Store { target, value } => {
+                let expr = Self::c_value(value)?;
+                self.writeln(&format!("{target} = {expr};"));
+            }
+            IRInstruction::BinOp { result, op, left, right } => {
+                let key = Self::c_value_key(result).ok_or("cannot assign to a constant")?;
+                let expr = Self::c_binop(*op, left, right)?;
+                self.writeln(&format!("{key} = {expr};"));
+            }
+            IRInstruction::UnaryOp { result, op, operand } => {
+                let key = Self::c_value_key(result).ok_or("cannot assign to a constant")?;
+                let o = Self::c_value(operand)?;
+                let expr = match op {
+                    UnaryOpIR::Neg => format!("(-{o})"),
+                    UnaryOpIR::BitNot => format!("(~{o})"),
+                    UnaryOpIR::Not => format!("({o} == 0)"),
+                };
+                self.writeln(&format!("{key} = {expr};"));
+            }
+            IRInstruction::Call { result, function, args } => {
+                if !known.contains(function) {
+                    return Err(format!("the C backend can't call `{function}` (not a compiled function)"));
+                }
+                let key = Self::c_value_key(result).ok_or("cannot assign to a constant")?;
+                let args = args.iter().map(Self::c_value).collect::<Result<Vec<_>, String>>()?;
+                self.writeln(&format!("{key} = {function}({});", args.join(", ")));
+            }
+            IRInstruction::Branch { condition, true_block, false_block } => {
+                let cond = Self::c_value(condition)?;
+                self.writeln(&format!("if ({cond}) goto L{true_block}; else goto L{false_block};"));
+            }
+            IRInstruction::Jump { target } => {
+                self.writeln(&format!("goto L{target};"));
+            }
+            IRInstruction::Return { value } => match value {
+                Some(v) => self.writeln(&format!("return {};", Self::c_value(v)?)),
+                None => self.writeln("return;"),
+            },
+            IRInstruction::Pure | IRInstruction::CanElideCheck => {}
+            other => return Err(format!("the C backend doesn't support the `{other:?}` instruction")),
+        }
+        Ok(())
+    }
+
+    /// Emits every `IRGlobal` as a top-level Rust item ahead of the
+    /// functions that use it, and records which ones are atomic-backed (see
+    /// `atomic_globals`) so `value_to_rust`/`assign` know to route through
+    /// `.load`/`.store` instead of a bare identifier. Three shapes, in order
+    /// of how `IRLowering::lower_globals` decides them:
+    /// - `mutable` (`Int`/`Bool` reassigned via `global` somewhere) -> a
+    ///   `std::sync::atomic` static, the only safe way to give a Python
+    ///   module-level variable real cross-call mutation without `unsafe`.
+    /// - a non-empty list literal (a lookup table) -> a
+    ///   `once_cell::sync::Lazy<Vec<_>>`, since a `Vec` can't be built in a
+    ///   `const`/plain `static` initializer.
+    /// - anything else -> a plain `const`.
+    fn generate_globals(&mut self, globals: &[IRGlobal]) {
+        self.atomic_globals.clear();
+        for global in globals {
+            if global.mutable {
+                let atomic_type = match global.typ {
+                    Type::Bool => "std::sync::atomic::AtomicBool",
+                    _ => "std::sync::atomic::AtomicI64",
+                };
+                self.atomic_globals.insert(global.name.clone(), atomic_type);
+                let init = match &global.initializer {
+                    IRGlobalInit::Bool(b) => b.to_string(),
+                    IRGlobalInit::Int(n) => n.to_string(),
+                    // `lower_globals` never marks anything else `mutable`.
+                    _ => unreachable!("only Int/Bool globals are ever marked mutable"),
+                };
+                writeln!(self.output, "static {}: {} = {}::new({});", global.name, atomic_type, atomic_type, init).ok();
+                continue;
+            }
+
+            match &global.initializer {
+                IRGlobalInit::Int(n) => writeln!(self.output, "const {}: i64 = {};", global.name, n).ok(),
+                IRGlobalInit::Float(f) => writeln!(self.output, "const {}: f64 = {};", global.name, f).ok(),
+                IRGlobalInit::Bool(b) => writeln!(self.output, "const {}: bool = {};", global.name, b).ok(),
+                IRGlobalInit::String(s) => writeln!(self.output, "const {}: &str = {:?};", global.name, s).ok(),
+                IRGlobalInit::IntList(items) => writeln!(
+                    self.output,
+                    "static {}: once_cell::sync::Lazy<Vec<i64>> = once_cell::sync::Lazy::new(|| vec![{}]);",
+                    global.name,
+                    items.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")
+                )
+                .ok(),
+                IRGlobalInit::FloatList(items) => writeln!(
+                    self.output,
+                    "static {}: once_cell::sync::Lazy<Vec<f64>> = once_cell::sync::Lazy::new(|| vec![{}]);",
+                    global.name,
+                    items.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(", ")
+                )
+                .ok(),
+            };
+        }
+        if !globals.is_empty() {
+            self.output.push('\n');
+        }
+    }
+
+    fn generate_struct(&mut self, ir_struct: &IRStruct) {
+        writeln!(self.output, "struct {} {{", ir_struct.name).ok();
+        for field in &ir_struct.fields {
+            writeln!(self.output, "    {}: {},", field.name, self.type_to_rust(&field.typ)).ok();
+        }
+        writeln!(self.output, "}}\n").ok();
+    }
+
+    fn generate_impl(&mut self, module: &IRModule, ir_struct: &IRStruct) {
+        let methods: Vec<&IRFunction> = module
+            .functions
+            .iter()
+            .filter(|f| f.owner.as_deref() == Some(ir_struct.name.as_str()))
+            .collect();
+        if methods.is_empty() {
+            return;
+        }
+
+        let inherent: Vec<&IRFunction> =
+            methods.iter().copied().filter(|f| !f.is_generator_next).collect();
+        if !inherent.is_empty() {
+            writeln!(self.output, "impl {} {{", ir_struct.name).ok();
+            self.indent += 1;
+            for method in inherent {
+                self.generate_function(method);
+            }
+            self.indent -= 1;
+            writeln!(self.output, "}}\n").ok();
+        }
+
+        // A generator's `next` (see `IRLowering::lower_generator`) belongs
+        // in an `impl Iterator for {name}`, not the inherent impl above, so
+        // callers can use it with `for`/adaptor methods directly.
+        if let Some(item_type) = &ir_struct.item_type {
+            if let Some(next) = methods.iter().copied().find(|f| f.is_generator_next) {
+                writeln!(self.output, "impl Iterator for {} {{", ir_struct.name).ok();
+                self.indent += 1;
+                let item_indent = "    ".repeat(self.indent);
+                writeln!(self.output, "{}type Item = {};", item_indent, self.type_to_rust(item_type)).ok();
+                self.generate_function(next);
+                self.indent -= 1;
+                writeln!(self.output, "}}\n").ok();
+            }
+        }
+    }
+
+    /// The overflow policy this function actually runs under: its own
+    /// `#adrenaline:overflow-*` directive if it has one, else the
+    /// codegen-wide default set by `set_overflow_mode`.
+    fn resolved_overflow_mode(&self, function: &IRFunction) -> OverflowMode {
+        function.directives.overflow_mode().unwrap_or(self.default_overflow_mode)
+    }
+
+    /// The profiler overhead level this function actually runs under: its
+    /// own `#adrenaline:profile-*` directive if it has one, else the
+    /// codegen-wide default set by `set_profile_overhead_mode`.
+    fn resolved_profile_overhead_mode(&self, function: &IRFunction) -> ProfileOverheadMode {
+        function.directives.profile_overhead_mode().unwrap_or(self.default_profile_overhead_mode)
+    }
+
+    /// A function opts into `num-bigint` arithmetic either explicitly, via
+    /// `#adrenaline:bigint`, when its resolved overflow mode (see
+    /// `resolved_overflow_mode`) is `Bigint` - whether from its own
+    /// `#adrenaline:overflow-bigint` or a codegen-wide `--overflow bigint` -
+    /// or automatically when it uses `**` - there's no range analysis in
+    /// this compiler to bound an exponentiation's result, so it's treated
+    /// the same as "can't prove it fits in i64". Plain `+`/`-`/`*` stay on
+    /// the fast i64 path unless one of those opts them in too, since
+    /// they're far less likely to overflow in practice.
+    fn function_needs_bigint(&self, function: &IRFunction) -> bool {
+        function.directives.use_bigint()
+            || self.resolved_overflow_mode(function) == OverflowMode::Bigint
+            || function
+                .blocks
+                .iter()
+                .flat_map(|b| &b.instructions)
+                .any(|i| matches!(i, IRInstruction::BinOp { op: BinOpIR::Pow, .. }))
+    }
+
+    fn generate_function(&mut self, function: &IRFunction) {
+        self.declared.clear();
+        self.declared_caches.clear();
+        self.bigint = self.function_needs_bigint(function);
+        self.overflow_mode = self.resolved_overflow_mode(function);
+        self.function_global_writes = function
+            .global_names
+            .iter()
+            .filter(|name| self.atomic_globals.contains_key(*name))
+            .cloned()
+            .collect();
+        self.is_generator_next = function.is_generator_next;
+
+        // A lowered `__init__` (see `IRLowering::lower_constructor`) is
+        // always named `new` and is the only method that builds rather than
+        // borrows `Self`, so it's the one case that skips the `&mut self`
+        // receiver and returns `Self` instead of its declared return type.
+        let is_constructor = function.owner.is_some() && function.name == "new";
+        let indent = "    ".repeat(self.indent);
+
+        if let Some(comment) = self.provenance_comment(function) {
+            writeln!(self.output, "{indent}{comment}").ok();
+        }
+        write!(self.output, "{}{}fn {}(", indent, self.fn_export_prefix, function.name).ok();
+        if function.owner.is_some() && !is_constructor {
+            write!(self.output, "&mut self").ok();
+            if !function.params.is_empty() {
+                write!(self.output, ", ").ok();
+            }
+        }
+        for (i, param) in function.params.iter().enumerate() {
+            if i > 0 {
+                write!(self.output, ", ").ok();
+            }
+            write!(self.output, "{}: {}", param.name, self.type_to_rust(&param.typ)).ok();
+            self.declared.insert(param.name.clone());
+        }
+        let return_type = if is_constructor {
+            "Self".to_string()
+        } else if function.is_generator_next {
+            format!("Option<{}>", self.type_to_rust(&function.return_type))
+        } else {
+            self.type_to_rust(&function.return_type)
+        };
+        writeln!(self.output, ") -> {} {{", return_type).ok();
+        self.indent += 1;
+
+        // `--profile-instrument` wraps the body in `profile_function!` so a
+        // generated binary reports real per-function call counts and
+        // timings instead of `Compiler::profile`'s one-full-run-is-the-unit
+        // fallback (see its own doc comment). Skipped for a `#adrenaline:
+        // no-compile` fallback body, which already routes through its own
+        // PyO3 timing on the Python side.
+        let instrument = self.profile_instrument && function.python_source.is_none();
+        if instrument {
+            let body_indent = "    ".repeat(self.indent);
+            // `resolved_profile_overhead_mode` picks which of the three
+            // `profile_function*!` macros to wrap the body in - see
+            // `ProfileOverheadMode`'s own doc comment for what each skips.
+            let macro_name = match self.resolved_profile_overhead_mode(function) {
+                ProfileOverheadMode::CountsOnly => "profile_function_counts_only",
+                ProfileOverheadMode::CoarseTiming => "profile_function_coarse",
+                ProfileOverheadMode::FullTiming => "profile_function",
+            };
+            writeln!(self.output, "{body_indent}adrenaline_runtime::{macro_name}!({:?}, {{", function.name).ok();
+            self.indent += 1;
+        }
+        // `hw_counters::enable()` has to run before anything else the
+        // program does to be reachable from the very first `profile_function!`
+        // call, so it goes at the top of `main`'s body rather than
+        // alongside `emit_main_reports`'s end-of-run writes below.
+        if self.profile_hwcounters && function.owner.is_none() && function.name == "main" {
+            let body_indent = "    ".repeat(self.indent);
+            writeln!(self.output, "{body_indent}adrenaline_runtime::hw_counters::enable();").ok();
+        }
+        // Same reasoning as `hw_counters::enable()` above: has to run before
+        // anything else so the socket is bound and ready for a client to
+        // attach from the very first call onward.
+        if self.profile_live && function.owner.is_none() && function.name == "main" {
+            let body_indent = "    ".repeat(self.indent);
+            writeln!(self.output, "{body_indent}adrenaline_runtime::profiling::maybe_serve_live();").ok();
+        }
+        // `main` is the only function guaranteed to fall through to its own
+        // end rather than an early `return` (see the CFG-terminal `Return`
+        // case in `emit_seq`), so it's the one place a report-writing call
+        // can be placed at that fall-through point and be reliably reached
+        // on a normal, successful run - anywhere after it (e.g. after the
+        // function's closing brace) would be unreachable dead code instead.
+        self.emit_main_reports = function.owner.is_none() && function.name == "main";
+
+        if let Some(source) = &function.python_source {
+            self.emit_python_fallback_body(function, source);
+        } else if let Some(entry) = function.blocks.first().map(|b| b.id) {
+            let mut loop_stack = Vec::new();
+            self.emit_seq(function, entry, None, &mut loop_stack);
+        }
+        self.emit_main_reports = false;
+
+        if instrument {
+            self.indent -= 1;
+            let body_indent = "    ".repeat(self.indent);
+            writeln!(self.output, "{body_indent}}});").ok();
+        }
+
+        self.indent -= 1;
+        writeln!(self.output, "{}}}\n", indent).ok();
+    }
+
+    /// Body for a `#adrenaline:no-compile` function (see
+    /// `Compiler::attach_python_fallbacks`): embeds `source` as a string
+    /// constant and routes the call through the matching
+    /// `adrenaline_runtime::py_call_fallback*` helper, which runs it via an
+    /// embedded CPython interpreter and marshals args/the return value
+    /// across the PyO3 boundary - so one unsupported function doesn't fail
+    /// the whole build. Picks the `0`/non-`0` and `_unit`/non-`_unit`
+    /// variant based on this function's own arity and return type, since
+    /// `()` doesn't implement PyO3's `FromPyObject`.
+    fn emit_python_fallback_body(&mut self, function: &IRFunction, source: &str) {
+        let indent = "    ".repeat(self.indent);
+        let const_name = format!("{}_ADRENALINE_FALLBACK_SRC", function.name.to_uppercase());
+        writeln!(self.output, "{indent}const {const_name}: &str = {:?};", source).ok();
+
+        let is_unit = self.type_to_rust(&function.return_type) == "()";
+        let helper = match (function.params.is_empty(), is_unit) {
+            (true, true) => "py_call_fallback0_unit",
+            (true, false) => "py_call_fallback0",
+            (false, true) => "py_call_fallback_unit",
+            (false, false) => "py_call_fallback",
+        };
+
+        write!(self.output, "{indent}adrenaline_runtime::{helper}({const_name}, {:?}", function.name).ok();
+        if !function.params.is_empty() {
+            write!(self.output, ", (").ok();
+            for (i, param) in function.params.iter().enumerate() {
+                if i > 0 {
+                    write!(self.output, ", ").ok();
+                }
+                write!(self.output, "{}", param.name).ok();
+            }
+            if function.params.len() == 1 {
+                write!(self.output, ",").ok();
+            }
+            write!(self.output, ")").ok();
+        }
+        writeln!(self.output, ")").ok();
+    }
+
+    /// Walks the CFG from `block_id`, stopping once it reaches `stop_at`
+    /// (the merge point the caller will continue emitting from itself)
+    /// rather than recursing into it. `loop_stack` holds the (header, exit)
+    /// pair for every `while` this call is nested inside, so a `Jump` back
+    /// to an enclosing loop's header/exit becomes `continue`/`break` instead
+    /// of being mistaken for a fresh block to structure.
+    fn emit_seq(
+        &mut self,
+        function: &IRFunction,
+        mut block_id: usize,
+        stop_at: Option<usize>,
+        loop_stack: &mut Vec<(usize, usize)>,
+    ) {
+        loop {
+            if Some(block_id) == stop_at {
+                return;
+            }
+            let Some(block) = Self::block(function, block_id) else {
+                return;
+            };
+
+            // A self-referencing `LoopStart` (left behind by unrolling/tiling,
+            // where `body_block` is the block's own id) has no separate
+            // condition block to structure - it just means "repeat the
+            // instructions before this one, `trip_count` times".
+            if let Some(pos) = block.instructions.iter().position(
+                |instr| matches!(instr, IRInstruction::LoopStart { body_block, .. } if *body_block == block_id),
+            ) {
+                let IRInstruction::LoopStart {
+                    exit_block, metadata, ..
+                } = &block.instructions[pos]
+                else {
+                    unreachable!()
+                };
+                let exit_block = *exit_block;
+                match metadata.trip_count.as_ref().and_then(TripCount::value) {
+                    Some(n) => self.writeln(&format!("for _ in 0..{} {{", n)),
+                    None => self.writeln("loop {"),
+                }
+                self.indent += 1;
+                let payload: Vec<IRInstruction> = block.instructions[..pos].to_vec();
+                for instr in &payload {
+                    self.emit_instruction(instr);
+                }
+                self.indent -= 1;
+                self.writeln("}");
+                block_id = exit_block;
+                continue;
+            }
+
+            if block.instructions.is_empty() {
+                return;
+            }
+            let prefix = &block.instructions[..block.instructions.len() - 1];
+
+            match block.instructions.last() {
+                Some(IRInstruction::Return { value }) => {
+                    for instr in prefix {
+                        self.emit_instruction(instr);
+                    }
+                    match (value, self.is_generator_next) {
+                        (Some(v), true) => {
+                            self.writeln(&format!("return Some({});", self.value_to_rust(v)))
+                        }
+                        (Some(v), false) => self.writeln(&format!("return {};", self.value_to_rust(v))),
+                        (None, true) => self.writeln("return None;"),
+                        (None, false) if self.emit_main_reports => {
+                            if self.profile_instrument {
+                                self.writeln("adrenaline_runtime::profiling::write_report();");
+                            }
+                            if self.profile_lines {
+                                self.writeln("adrenaline_runtime::line_profiling::write_report();");
+                            }
+                        }
+                        (None, false) => self.writeln("return;"),
+                    }
+                    return;
+                }
+                Some(IRInstruction::Jump { target }) => {
+                    for instr in prefix {
+                        self.emit_instruction(instr);
+                    }
+                    if Some(*target) == stop_at {
+                        return;
+                    }
+                    if let Some(&(header, exit)) = loop_stack.last() {
+                        if *target == header {
+                            self.writeln("continue;");
+                            return;
+                        }
+                        if *target == exit {
+                            self.writeln("break;");
+                            return;
+                        }
+                    }
+                    block_id = *target;
+                }
+                Some(IRInstruction::Branch {
+                    condition,
+                    true_block,
+                    false_block,
+                }) => {
+                    let condition = condition.clone();
+                    let (true_block, false_block) = (*true_block, *false_block);
+
+                    if self.leads_back(function, true_block, block_id, stop_at) {
+                        if !self.try_emit_parallel_reduction(function, prefix, &condition, true_block) {
+                            let shape = WhileShape { header: block_id, body: true_block, exit: false_block };
+                            self.emit_while(function, prefix, &condition, false, shape, loop_stack);
+                        }
+                        block_id = false_block;
+                    } else if self.leads_back(function, false_block, block_id, stop_at) {
+                        let shape = WhileShape { header: block_id, body: false_block, exit: true_block };
+                        self.emit_while(function, prefix, &condition, true, shape, loop_stack);
+                        block_id = true_block;
+                    } else {
+                        for instr in prefix {
+                            self.emit_instruction(instr);
+                        }
+                        let merge = self.find_merge(function, true_block, false_block, stop_at);
+                        let inner_stop = merge.or(stop_at);
+
+                        self.writeln(&format!("if {} {{", self.value_to_rust(&condition)));
+                        self.indent += 1;
+                        self.emit_seq(function, true_block, inner_stop, loop_stack);
+                        self.indent -= 1;
+                        self.writeln("} else {");
+                        self.indent += 1;
+                        self.emit_seq(function, false_block, inner_stop, loop_stack);
+                        self.indent -= 1;
+                        self.writeln("}");
+
+                        match merge {
+                            Some(m) => block_id = m,
+                            None => return,
+                        }
+                    }
+                }
+                Some(IRInstruction::TryExcept {
+                    try_block,
+                    except_block,
+                    error_binding,
+                }) => {
+                    let try_block = *try_block;
+                    let except_block = *except_block;
+                    let error_binding = error_binding.clone();
+
+                    for instr in prefix {
+                        self.emit_instruction(instr);
+                    }
+                    let merge = self.find_merge(function, try_block, except_block, stop_at);
+                    let inner_stop = merge.or(stop_at);
+
+                    self.writeln("let __adrenaline_try = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {");
+                    self.indent += 1;
+                    self.emit_seq(function, try_block, inner_stop, loop_stack);
+                    self.indent -= 1;
+                    self.writeln("}));");
+                    self.writeln("if let Err(__adrenaline_err) = __adrenaline_try {");
+                    self.indent += 1;
+                    if let Some(name) = &error_binding {
+                        self.writeln(&format!(
+                            "let mut {} = __adrenaline_err.downcast_ref::<&str>().map(|s| s.to_string()).unwrap_or_else(|| String::from(\"exception\"));",
+                            name
+                        ));
+                        self.declared.insert(name.clone());
+                    }
+                    self.emit_seq(function, except_block, inner_stop, loop_stack);
+                    self.indent -= 1;
+                    self.writeln("}");
+
+                    match merge {
+                        Some(m) => block_id = m,
+                        None => return,
+                    }
+                }
+                Some(other) => {
+                    for instr in prefix {
+                        self.emit_instruction(instr);
+                    }
+                    self.emit_instruction(&other.clone());
+                    return;
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Emits `loop { <header instrs>; if !cond { break; } <body> }` and
+    /// continues past it once done. The header's condition-computing
+    /// instructions are re-run every iteration via `continue`, which a
+    /// plain `while cond { .. }` couldn't do since `cond` is itself
+    /// computed by a preceding instruction rather than being a bare
+    /// expression. `negate` is set when the header's true-branch is the
+    /// loop's exit rather than its body.
+    fn emit_while(
+        &mut self,
+        function: &IRFunction,
+        header_prefix: &[IRInstruction],
+        condition: &IRValue,
+        negate: bool,
+        shape: WhileShape,
+        loop_stack: &mut Vec<(usize, usize)>,
+    ) {
+        let cond_text = self.value_to_rust(condition);
+        let break_cond = if negate {
+            cond_text
+        } else {
+            format!("!({})", cond_text)
+        };
+        if let Some(comment) = self.loop_provenance_comment(function, shape.body) {
+            self.writeln(&comment);
+        }
+        self.writeln("loop {");
+        self.indent += 1;
+        for instr in header_prefix {
+            self.emit_instruction(instr);
+        }
+        self.writeln(&format!("if {} {{ break; }}", break_cond));
+        loop_stack.push((shape.header, shape.exit));
+        self.emit_seq(function, shape.body, Some(shape.header), loop_stack);
+        loop_stack.pop();
+        self.indent -= 1;
+        self.writeln("}");
+    }
+
+    /// Attempts to render a `Reduction`-hinted loop body as a Rayon
+    /// `into_par_iter().map(..).reduce(..)` expression instead of the
+    /// sequential `emit_while` loop, returning `false` (leaving the caller
+    /// to fall back to `emit_while`) whenever the body doesn't match the
+    /// one shape this handles: a `for i in range(start, end)` whose only
+    /// per-iteration effect is `target = target <op> i`. The `Safe`
+    /// (index-write) case `analyze_parallel_safety` also detects would need
+    /// `par_iter_mut()` plus rewriting `IndexStore`/`Index` into slot
+    /// references to codegen soundly, so it's left as a sequential loop.
+    fn try_emit_parallel_reduction(
+        &mut self,
+        function: &IRFunction,
+        header_prefix: &[IRInstruction],
+        condition: &IRValue,
+        body_block: usize,
+    ) -> bool {
+        if self.bigint {
+            return false;
+        }
+
+        let Some(body) = Self::block(function, body_block) else {
+            return false;
+        };
+        let Some(IRInstruction::Reduction { target, op }) = body
+            .instructions
+            .iter()
+            .find(|i| matches!(i, IRInstruction::Reduction { .. }))
+        else {
+            return false;
+        };
+        if !body.instructions.iter().any(|i| matches!(i, IRInstruction::Parallelizable)) {
+            return false;
+        }
+        let (op_str, identity) = match op {
+            BinOpIR::Add => ("+", "0"),
+            BinOpIR::Mul => ("*", "1"),
+            BinOpIR::BitAnd => ("&", "-1"),
+            BinOpIR::BitOr => ("|", "0"),
+            BinOpIR::BitXor => ("^", "0"),
+            _ => return false,
+        };
+        let IRValue::Local(target_name) = target else {
+            return false;
+        };
+
+        // The `LoopStart` that owns this body carries the induction
+        // variable analysis computed by `IROptimizer::analyze_loops`.
+        let Some(iv) = function
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .find_map(|i| match i {
+                IRInstruction::LoopStart { body_block: b, metadata, .. } if *b == body_block => {
+                    metadata.primary_induction_variable()
+                }
+                _ => None,
+            })
+        else {
+            return false;
+        };
+        if iv.stride != 1 {
+            return false;
+        }
+        let IRValue::Local(iv_name) = &iv.value else {
+            return false;
+        };
+
+        // The header computes `condition` as `iv < end` just before the
+        // `Branch` - `end` is the loop's upper bound.
+        let Some(end) = header_prefix.iter().find_map(|i| match i {
+            IRInstruction::BinOp { result, op: BinOpIR::Lt, left, right }
+                if result == condition && left == &iv.value =>
+            {
+                Some(self.value_to_rust(right))
+            }
+            _ => None,
+        }) else {
+            return false;
+        };
+
+        // Locate the reduction's own `BinOp` (`target <op> iv`) and the
+        // `Store` that writes it back to `target`, plus the induction
+        // variable's increment - every other instruction in the body must
+        // be loop bookkeeping/hints, or this body has side effects this
+        // simple shape can't safely reorder across threads.
+        let target_val = IRValue::Local(target_name.clone());
+        let mut reduction = None;
+        for (idx, instr) in body.instructions.iter().enumerate() {
+            let IRInstruction::BinOp { result, op: bop, left, right } = instr else {
+                continue;
+            };
+            if bop != op {
+                continue;
+            }
+            let delta = if left == &target_val && right == &iv.value {
+                Some(right.clone())
+            } else if right == &target_val && left == &iv.value {
+                Some(left.clone())
+            } else {
+                None
+            };
+            let Some(delta) = delta else { continue };
+            if let Some(IRInstruction::Store { target: store_target, value }) = body.instructions.get(idx + 1) {
+                if store_target == target_name && value == result {
+                    reduction = Some((idx, idx + 1, delta));
+                }
+            }
+        }
+        let Some((binop_idx, store_idx, delta)) = reduction else {
+            return false;
+        };
+
+        let Some(increment_idx) = body
+            .instructions
+            .iter()
+            .position(|i| matches!(i, IRInstruction::BinOp { result, .. } if result == &iv.value))
+        else {
+            return false;
+        };
+
+        for (idx, instr) in body.instructions.iter().enumerate() {
+            if idx == binop_idx || idx == store_idx || idx == increment_idx {
+                continue;
+            }
+            if !matches!(
+                instr,
+                IRInstruction::LoopEnd
+                    | IRInstruction::Jump { .. }
+                    | IRInstruction::Parallelizable
+                    | IRInstruction::Reduction { .. }
+            ) {
+                return false;
+            }
+        }
+
+        let start = self.value_to_rust(&IRValue::Const(iv.start.clone()));
+        let delta_expr = self.value_to_rust(&delta);
+        for instr in header_prefix {
+            self.emit_instruction(instr);
+        }
+        self.writeln(&format!(
+            "{target} = {target} {op} ({start}..{end}).into_par_iter().map(|{iv}: i64| {delta}).reduce(|| {identity}, |a, b| a {op} b);",
+            target = target_name,
+            op = op_str,
+            start = start,
+            end = end,
+            iv = iv_name,
+            delta = delta_expr,
+            identity = identity,
+        ));
+        true
+    }
+
+    fn block(function: &IRFunction, id: usize) -> Option<&BasicBlock> {
+        function.blocks.iter().find(|b| b.id == id)
+    }
+
+    /// Whether `target` is reachable from `from` by following `successors`
+    /// edges without passing through `boundary` - used to tell a loop's body
+    /// (which jumps back to its own header) apart from its exit, and from a
+    /// nested branch that merely lies somewhere on an *enclosing* loop's
+    /// cycle. `boundary` (the enclosing `stop_at`) is what keeps that
+    /// enclosing cycle from being mistaken for one headed by `target`: the
+    /// search stops there instead of wrapping back around through it.
+    fn leads_back(&self, function: &IRFunction, from: usize, target: usize, boundary: Option<usize>) -> bool {
+        self.reachable_set(function, from, boundary).contains(&target)
+    }
+
+    fn reachable_set(&self, function: &IRFunction, start: usize, boundary: Option<usize>) -> HashSet<usize> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if Some(id) == boundary {
+                continue; // reached the edge of our scope; don't expand past it
+            }
+            if let Some(block) = Self::block(function, id) {
+                for &succ in &block.successors {
+                    if !visited.contains(&succ) {
+                        queue.push_back(succ);
+                    }
+                }
+            }
+        }
+        visited
+    }
+
+    /// The nearest block reachable from both `a` and `b` without passing
+    /// through `boundary`, i.e. where an `if`/`else` reconverges - `None`
+    /// when both arms return/break out on every path instead of rejoining.
+    fn find_merge(&self, function: &IRFunction, a: usize, b: usize, boundary: Option<usize>) -> Option<usize> {
+        let reachable_b = self.reachable_set(function, b, boundary);
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([a]);
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if reachable_b.contains(&id) {
+                return Some(id);
+            }
+            if Some(id) == boundary {
+                continue;
+            }
+            if let Some(block) = Self::block(function, id) {
+                for &succ in &block.successors {
+                    queue.push_back(succ);
+                }
+            }
+        }
+        None
+    }
+
+    fn emit_instruction(&mut self, instr: &IRInstruction) {
+        match instr {
+            IRInstruction::BinOp { result, op, left, right } => {
+                let expr = self.binop_to_rust(*op, left, right);
+                self.assign(result, &expr);
+            }
+            IRInstruction::UnaryOp { result, op, operand } => {
+                let operand = self.value_to_rust(operand);
+                let expr = match op {
+                    UnaryOpIR::Neg => format!("-({})", operand),
+                    UnaryOpIR::Not => format!("!({})", operand),
+                    UnaryOpIR::BitNot => format!("!({})", operand),
+                };
+                self.assign(result, &expr);
+            }
+            IRInstruction::Fma { result, a, b, c } => {
+                let expr = format!(
+                    "{} * {} + {}",
+                    self.value_to_rust(a),
+                    self.value_to_rust(b),
+                    self.value_to_rust(c)
+                );
+                self.assign(result, &expr);
+            }
+            IRInstruction::Assign { target, value } => {
+                let expr = self.value_to_rust(value);
+                self.assign(target, &expr);
+            }
+            IRInstruction::Load { result, source } => {
+                let expr = source.clone();
+                self.assign(result, &expr);
+            }
+            IRInstruction::Store { target, value } => {
+                let expr = self.value_to_rust(value);
+                self.assign(&IRValue::Local(target.clone()), &expr);
+            }
+            IRInstruction::Index { result, array, index } => {
+                // `sys.argv` (see `value_to_rust`) substitutes a fresh
+                // `Vec<String>` at every use site, so indexing it needs a
+                // `.clone()` to get an owned `String` out rather than moving
+                // an element out of a temporary - same reasoning as any
+                // other `Vec<String>` indexed by value.
+                let is_argv = matches!(array, IRValue::Local(name) if name == "sys.argv");
+                let expr = format!(
+                    "{}[{} as usize]{}",
+                    self.value_to_rust(array),
+                    self.value_to_rust(index),
+                    if is_argv { ".clone()" } else { "" }
+                );
+                self.assign(result, &expr);
+            }
+            IRInstruction::IndexStore { array, index, value } => {
+                let line = format!(
+                    "{}[{} as usize] = {};",
+                    self.value_to_rust(array),
+                    self.value_to_rust(index),
+                    self.value_to_rust(value)
+                );
+                self.writeln(&line);
+            }
+            IRInstruction::CacheLookup {
+                found,
+                value,
+                cache,
+                keys,
+                key_types,
+                value_type,
+            } => {
+                self.emit_cache_lookup(found, value, cache, keys, key_types, value_type);
+            }
+            IRInstruction::CacheStore { cache, keys, value } => {
+                self.emit_cache_store(cache, keys, value);
+            }
+            IRInstruction::Call { result, function, args } => {
+                self.emit_call(result, function, args);
+            }
+            IRInstruction::Print { args, sep, end } => {
+                self.emit_print(args, sep, end);
+            }
+            IRInstruction::MethodCall { result, receiver, method, args } => {
+                self.emit_method_call(result, receiver, method, args);
+            }
+            IRInstruction::NewList { result, capacity } => {
+                let expr = match capacity {
+                    Some(c) => format!("Vec::with_capacity({} as usize)", self.value_to_rust(c)),
+                    None => "Vec::new()".to_string(),
+                };
+                self.assign(result, &expr);
+            }
+            IRInstruction::NewStruct { result, name, fields } => {
+                let field_text: Vec<String> = fields
+                    .iter()
+                    .map(|(field, value)| format!("{}: {}", field, self.value_to_rust(value)))
+                    .collect();
+                let expr = format!("{} {{ {} }}", name, field_text.join(", "));
+                self.assign(result, &expr);
+            }
+            IRInstruction::FormatString { result, parts } => {
+                let expr = self.format_string_to_rust(parts);
+                self.assign(result, &expr);
+            }
+            // A chunk of independent same-op lanes, rendered as ordinary
+            // adjacent scalar arithmetic - real vector-register packing
+            // needs `std::simd`, which isn't stable yet. The marker
+            // comment is what actually earns the speedup: it's what
+            // `Compiler::build_rust_project` greps for to decide whether
+            // to pass `-C target-cpu=native`, which is what lets LLVM's own
+            // SLP vectorizer pack this straight-line code into real SIMD
+            // instructions.
+            IRInstruction::SimdBinOp { op, lanes } => {
+                self.writeln(&format!("// {SIMD_CHUNK_MARKER}: {} lanes of {op:?}", lanes.len()));
+                let lanes = lanes.clone();
+                for (result, left, right) in &lanes {
+                    let expr = self.binop_to_rust(*op, left, right);
+                    self.assign(result, &expr);
+                }
+            }
+            // Loop bookkeeping already consumed by `emit_seq`, and hints
+            // that have no textual representation in generated Rust.
+            IRInstruction::LoopStart { .. }
+            | IRInstruction::LoopEnd
+            | IRInstruction::Vectorizable
+            | IRInstruction::Parallelizable
+            | IRInstruction::CanElideCheck
+            | IRInstruction::Pure
+            | IRInstruction::Reduction { .. } => {}
+            // Terminators are handled by `emit_seq` and never reach here
+            // except via its own defensive fallback.
+            IRInstruction::Branch { .. }
+            | IRInstruction::Jump { .. }
+            | IRInstruction::Return { .. }
+            | IRInstruction::TryExcept { .. } => {}
+            // Present in every function's IR unconditionally (see
+            // `IRLowering::lower_statement`), but only worth a real call
+            // when `--profile-lines` asked for the per-line hit counts it
+            // backs.
+            IRInstruction::LineMarker { line } => {
+                if self.profile_lines {
+                    self.writeln(&format!("adrenaline_runtime::line_profiling::record_line({line});"));
+                }
+            }
+        }
+    }
+
+    /// Builds the Rust key expression/type for a `CacheLookup`/`CacheStore`'s
+    /// `keys` - a bare value for a single-argument cache, or a tuple for a
+    /// multi-argument one, since `HashMap<(A,), V>` is valid but needlessly
+    /// ugly next to `HashMap<A, V>`.
+    fn cache_key_expr(&self, keys: &[IRValue]) -> String {
+        if keys.len() == 1 {
+            self.value_to_rust(&keys[0])
+        } else {
+            format!(
+                "({})",
+                keys.iter().map(|k| self.value_to_rust(k)).collect::<Vec<_>>().join(", ")
+            )
+        }
+    }
+
+    fn cache_key_type(&self, key_types: &[Type]) -> String {
+        if key_types.len() == 1 {
+            self.type_to_rust(&key_types[0])
+        } else {
+            format!(
+                "({})",
+                key_types.iter().map(|t| self.type_to_rust(t)).collect::<Vec<_>>().join(", ")
+            )
+        }
+    }
+
+    fn emit_cache_lookup(
+        &mut self,
+        found: &IRValue,
+        value: &IRValue,
+        cache: &str,
+        keys: &[IRValue],
+        key_types: &[Type],
+        value_type: &Type,
+    ) {
+        if self.declared_caches.insert(cache.to_string()) {
+            self.writeln(&format!(
+                "let mut {}: std::collections::HashMap<{}, {}> = std::collections::HashMap::new();",
+                cache,
+                self.cache_key_type(key_types),
+                self.type_to_rust(value_type)
+            ));
+        }
+        let key_expr = self.cache_key_expr(keys);
+        self.assign(found, &format!("{}.contains_key(&{})", cache, key_expr));
+        self.assign(value, &format!("{}.get(&{}).cloned().unwrap_or_default()", cache, key_expr));
+    }
+
+    fn emit_cache_store(&mut self, cache: &str, keys: &[IRValue], value: &IRValue) {
+        let key_expr = self.cache_key_expr(keys);
+        let value_expr = self.value_to_rust(value);
+        self.writeln(&format!("{}.insert({}, {});", cache, key_expr, value_expr));
+    }
+
+    fn emit_call(&mut self, result: &IRValue, function: &str, args: &[IRValue]) {
+        let arg_text: Vec<String> = args.iter().map(|a| self.value_to_rust(a)).collect();
+        match function {
+            "println" => {
+                self.writeln(&format!("println!(\"{{:?}}\", {});", arg_text.join(", ")));
+            }
+            "len" => {
+                let expr = format!("({}.len() as i64)", arg_text[0]);
+                self.assign(result, &expr);
+            }
+            // A prompt argument (if any) is printed unbuffered before the
+            // read, matching CPython; the line is stripped of exactly its
+            // trailing newline (`\r\n` or `\n`), matching CPython's own
+            // `input()` rather than trimming all whitespace.
+            "input" => {
+                if let Some(prompt) = arg_text.first() {
+                    self.writeln(&format!("print!(\"{{}}\", {});", prompt));
+                    self.writeln("std::io::Write::flush(&mut std::io::stdout()).ok();");
+                }
+                let expr = "{ let mut __line = String::new(); \
+                    std::io::stdin().read_line(&mut __line).unwrap(); \
+                    __line.trim_end_matches('\\n').trim_end_matches('\\r').to_string() }"
+                    .to_string();
+                self.assign(result, &expr);
+            }
+            // The mode is read off the second argument's literal value at
+            // codegen time (the same structural, best-effort approach as
+            // `PrintArgKind` - see `ir::PrintArgKind`) rather than branching
+            // on it at runtime; a missing or non-literal mode defaults to
+            // read, matching Python's own `open(path)` default.
+            "open" => {
+                let path = &arg_text[0];
+                let mode = match args.get(1) {
+                    Some(IRValue::Const(IRConstant::String(m))) => m.as_str(),
+                    _ => "r",
+                };
+                let expr = if mode.contains('a') {
+                    format!("std::fs::OpenOptions::new().append(true).create(true).open({}).unwrap()", path)
+                } else if mode.contains('w') {
+                    format!("std::fs::File::create({}).unwrap()", path)
+                } else {
+                    format!("std::fs::File::open({}).unwrap()", path)
+                };
+                self.assign(result, &expr);
+            }
+            // `math`/`random`/`time` calls (see
+            // `IRLowering::is_stdlib_module`) - mapped straight to their
+            // `std`/`rand` equivalents rather than through a generated free
+            // function, since none of these names exist as Rust functions.
+            "math.sqrt" => {
+                let expr = format!("(({}) as f64).sqrt()", arg_text[0]);
+                self.assign(result, &expr);
+            }
+            "math.sin" => {
+                let expr = format!("(({}) as f64).sin()", arg_text[0]);
+                self.assign(result, &expr);
+            }
+            "math.floor" => {
+                let expr = format!("((({}) as f64).floor() as i64)", arg_text[0]);
+                self.assign(result, &expr);
+            }
+            "random.random" => {
+                let expr = "rand::random::<f64>()".to_string();
+                self.assign(result, &expr);
+            }
+            "random.randint" => {
+                let expr = format!(
+                    "rand::Rng::gen_range(&mut rand::thread_rng(), ({} as i64)..=({} as i64))",
+                    arg_text[0], arg_text[1]
+                );
+                self.assign(result, &expr);
+            }
+            // `rand`'s default `thread_rng()` isn't reseedable, and every
+            // other `random.*` call here draws from it independently - so a
+            // seed can't actually be threaded through without a much larger
+            // rewrite (a shared `StdRng` passed to every call site). Known,
+            // narrow gap: the call is honored for parse/compile purposes but
+            // has no effect on the generated program's randomness.
+            "random.seed" => {
+                self.writeln("// random.seed(...) has no effect: thread_rng() isn't reseedable");
+            }
+            "time.time" | "time.perf_counter" => {
+                let expr = "std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs_f64()".to_string();
+                self.assign(result, &expr);
+            }
+            _ => {
+                // A caller that isn't itself in bigint mode (e.g. the
+                // synthesized `main`) still passes plain `i64`/literal
+                // arguments - convert them at the call site so a bigint
+                // callee's `num_bigint::BigInt` parameters type-check. A
+                // bigint caller already has `BigInt`-valued arguments, so no
+                // conversion is needed the other way around.
+                let arg_text: Vec<String> = if !self.bigint && self.bigint_functions.contains(function) {
+                    arg_text
+                        .into_iter()
+                        .map(|a| format!("num_bigint::BigInt::from({})", a))
+                        .collect()
+                } else {
+                    arg_text
+                };
+                let expr = format!("{}({})", function, arg_text.join(", "));
+                self.assign(result, &expr);
+            }
+        }
+    }
+
+    /// Renders `print(...)` matching CPython: positional args joined by
+    /// `sep`, terminated by `end`, with each argument formatted per its
+    /// `PrintArgKind` (see `ir::PrintArgKind`) since a single Rust format
+    /// spec can't be right for bools, lists, and everything else at once.
+    fn emit_print(&mut self, args: &[(IRValue, PrintArgKind)], sep: &str, end: &str) {
+        let mut format_str = String::new();
+        let mut format_args = Vec::new();
+
+        for (i, (value, kind)) in args.iter().enumerate() {
+            if i > 0 {
+                format_str.push_str(&Self::escape_format_literal(sep));
+            }
+            match kind {
+                PrintArgKind::Plain => {
+                    format_str.push_str("{}");
+                    format_args.push(self.value_to_rust(value));
+                }
+                PrintArgKind::Bool => {
+                    format_str.push_str("{}");
+                    format_args.push(format!(
+                        "adrenaline_runtime::py_bool({})",
+                        self.value_to_rust(value)
+                    ));
+                }
+                PrintArgKind::List => {
+                    format_str.push_str("{:?}");
+                    format_args.push(self.value_to_rust(value));
+                }
+            }
+        }
+        format_str.push_str(&Self::escape_format_literal(end));
+
+        let line = if format_args.is_empty() {
+            format!("print!(\"{}\");", format_str)
+        } else {
+            format!("print!(\"{}\", {});", format_str, format_args.join(", "))
+        };
+        self.writeln(&line);
+    }
+
+    /// Escapes `text` for splicing as literal characters into a generated
+    /// `format!`/`print!` string, reusing Rust's own `Debug` escaping for
+    /// quotes/backslashes/control characters rather than hand-rolling it,
+    /// then additionally escaping `{`/`}` so the text can't be mistaken for
+    /// a format placeholder.
+    fn escape_format_literal(text: &str) -> String {
+        let quoted = format!("{:?}", text);
+        let inner = &quoted[1..quoted.len() - 1];
+        inner.replace('{', "{{").replace('}', "}}")
+    }
+
+    /// Renders a call whose receiver is a value (`list.append(x)`,
+    /// `list.contains(x)`, ...) rather than a free function name.
+    fn emit_method_call(&mut self, result: &IRValue, receiver: &IRValue, method: &str, args: &[IRValue]) {
+        let recv = self.value_to_rust(receiver);
+        let arg_text: Vec<String> = args.iter().map(|a| self.value_to_rust(a)).collect();
+        match method {
+            // `Vec::push` returns `()`, so there's nothing to assign - the
+            // result temporary this was given exists only so `MethodCall`
+            // always has somewhere to put one, and is left unused here.
+            "append" => {
+                self.writeln(&format!("{}.push({});", recv, arg_text.join(", ")));
+            }
+            "pop" => {
+                let expr = format!("{}.pop().unwrap_or_default()", recv);
+                self.assign(result, &expr);
+            }
+            "contains" => {
+                let expr = format!("{}.contains(&{})", recv, arg_text.join(", "));
+                self.assign(result, &expr);
+            }
+            // Python indexes a string by character, but `String` has no
+            // `Index<usize>` impl - `chars().nth(..)` is the direct
+            // translation, and the result is re-wrapped as a one-character
+            // `String` to match Python's `s[i]` returning a length-1 `str`
+            // rather than a `char`.
+            "char_at" => {
+                let expr = format!(
+                    "{}.chars().nth(({}) as usize).unwrap().to_string()",
+                    recv,
+                    arg_text.join(", ")
+                );
+                self.assign(result, &expr);
+            }
+            "upper" => {
+                let expr = format!("{}.to_uppercase()", recv);
+                self.assign(result, &expr);
+            }
+            "lower" => {
+                let expr = format!("{}.to_lowercase()", recv);
+                self.assign(result, &expr);
+            }
+            "strip" => {
+                let expr = format!("{}.trim().to_string()", recv);
+                self.assign(result, &expr);
+            }
+            "startswith" => {
+                let expr = format!("{}.starts_with(&{})", recv, arg_text.join(", "));
+                self.assign(result, &expr);
+            }
+            // Python's no-argument `split()` splits on runs of whitespace;
+            // `split(sep)` splits on the literal separator instead.
+            "split" => {
+                let expr = if arg_text.is_empty() {
+                    format!(
+                        "{}.split_whitespace().map(|s| s.to_string()).collect::<Vec<String>>()",
+                        recv
+                    )
+                } else {
+                    format!(
+                        "{}.split({}.as_str()).map(|s| s.to_string()).collect::<Vec<String>>()",
+                        recv,
+                        arg_text[0]
+                    )
+                };
+                self.assign(result, &expr);
+            }
+            // `sep.join(items)` in Python has the separator as the receiver
+            // and the list as the argument - the reverse of Rust's
+            // `items.join(sep)` - so receiver and argument swap places here.
+            "join" => {
+                let expr = format!("{}.join({}.as_str())", arg_text[0], recv);
+                self.assign(result, &expr);
+            }
+            // Reads a file opened by `open()` (see `emit_call`) to
+            // completion, matching Python's no-argument `read()`.
+            "read" => {
+                let expr = format!(
+                    "{{ let mut __buf = String::new(); std::io::Read::read_to_string(&mut {}, &mut __buf).unwrap(); __buf }}",
+                    recv
+                );
+                self.assign(result, &expr);
+            }
+            // Python's `write()` returns the character count, but scripts
+            // almost always call it for the side effect alone - like
+            // `append`, the result temporary is left unused.
+            "write" => {
+                self.writeln(&format!(
+                    "std::io::Write::write_all(&mut {}, {}.as_bytes()).unwrap();",
+                    recv,
+                    arg_text.join(", ")
+                ));
+            }
+            // Every line keeps a trailing `\n` to match Python's
+            // `readlines()` - the one known gap is a final line lacking a
+            // newline in the source file, which this still terminates with
+            // one.
+            "readlines" => {
+                let expr = format!(
+                    "std::io::BufRead::lines(std::io::BufReader::new({})).map(|l| l.unwrap() + \"\\n\").collect::<Vec<String>>()",
+                    recv
+                );
+                self.assign(result, &expr);
+            }
+            _ => {
+                let expr = format!("{}.{}({})", recv, method, arg_text.join(", "));
+                self.assign(result, &expr);
+            }
+        }
+    }
+
+    /// Assembles an f-string's parts into a single `format!(...)` call -
+    /// literal runs go straight into the format string (with `{`/`}`
+    /// escaped, since they're literal braces there, not placeholders), and
+    /// each interpolation becomes a `{}`/`{:spec}` placeholder plus its
+    /// value as a trailing argument.
+    fn format_string_to_rust(&self, parts: &[FormatPart]) -> String {
+        let mut fmt = String::new();
+        let mut args = Vec::new();
+        for part in parts {
+            match part {
+                FormatPart::Literal(text) => {
+                    fmt.push_str(&text.replace('{', "{{").replace('}', "}}"));
+                }
+                FormatPart::Value(value, spec) => {
+                    match spec {
+                        Some(spec) => write!(fmt, "{{:{}}}", Self::translate_format_spec(spec)).ok(),
+                        None => write!(fmt, "{{}}").ok(),
+                    };
+                    args.push(self.value_to_rust(value));
+                }
+            }
+        }
+        if args.is_empty() {
+            format!("{:?}.to_string()", fmt)
+        } else {
+            format!("format!({:?}, {})", fmt, args.join(", "))
+        }
+    }
+
+    /// Translates a Python format spec into Rust's, which share almost the
+    /// same grammar (`[[fill]align][sign][#][0][width][.precision]`) - the
+    /// only real difference is the trailing type character (`f`, `d`, `s`,
+    /// `%`), which Rust infers from the argument's type instead of reading
+    /// from the spec, so it's stripped. `x`/`o`/`b`/`e`/`E` are left as-is;
+    /// Rust uses them the same way Python does.
+    fn translate_format_spec(spec: &str) -> String {
+        match spec.strip_suffix(['f', 'd', 's', '%']) {
+            Some(rest) => rest.to_string(),
+            None => spec.to_string(),
+        }
+    }
+
+    /// Bigint-mode arithmetic - see `function_needs_bigint`. Trait methods
+    /// are called through their fully-qualified path rather than a `use` at
+    /// the top of the generated file, matching this codegen's existing
+    /// habit of never emitting `use` statements. `+`/`-`/`*` take references
+    /// so a value used more than once (e.g. an accumulator) isn't moved out
+    /// from under itself.
+    fn bigint_binop_to_rust(op: BinOpIR, l: &str, r: &str) -> String {
+        match op {
+            BinOpIR::Add => format!("(&({}) + &({}))", l, r),
+            BinOpIR::Sub => format!("(&({}) - &({}))", l, r),
+            BinOpIR::Mul => format!("(&({}) * &({}))", l, r),
+            // The exponent is itself a `BigInt` here (every `Type::Int` is,
+            // in bigint mode), and `as u32` only converts between
+            // primitives, so it goes through `ToPrimitive` instead of the
+            // plain cast the i64 path uses.
+            BinOpIR::Pow => format!(
+                "num_traits::pow::Pow::pow(&({}), num_traits::ToPrimitive::to_u32(&({})).unwrap())",
+                l, r
+            ),
+            // Python's `/` is true division even for two ints - converting
+            // through `f64` loses precision for huge values, a narrower gap
+            // here than on the fast i64 path, but there's no arbitrary-
+            // precision rational type in play to do better.
+            BinOpIR::Div => format!(
+                "(num_traits::ToPrimitive::to_f64(&({})).unwrap() / num_traits::ToPrimitive::to_f64(&({})).unwrap())",
+                l, r
+            ),
+            BinOpIR::FloorDiv => format!("num_integer::Integer::div_floor(&({}), &({}))", l, r),
+            BinOpIR::Mod => format!("num_integer::Integer::mod_floor(&({}), &({}))", l, r),
+            _ => unreachable!(),
+        }
+    }
+
+    fn binop_to_rust(&self, op: BinOpIR, left: &IRValue, right: &IRValue) -> String {
+        let l = self.value_to_rust(left);
+        let r = self.value_to_rust(right);
+        if self.bigint
+            && matches!(
+                op,
+                BinOpIR::Add
+                    | BinOpIR::Sub
+                    | BinOpIR::Mul
+                    | BinOpIR::Pow
+                    | BinOpIR::Div
+                    | BinOpIR::FloorDiv
+                    | BinOpIR::Mod
+            )
+        {
+            return Self::bigint_binop_to_rust(op, &l, &r);
+        }
+        // Rust's own `+`/`-`/`*` on `i64` panic on overflow in a debug build
+        // and silently wrap in release - an accident of the build profile,
+        // not a choice. `overflow_mode` makes the choice explicit and
+        // profile-independent instead.
+        if matches!(op, BinOpIR::Add | BinOpIR::Sub | BinOpIR::Mul) {
+            let method = match op {
+                BinOpIR::Add => "add",
+                BinOpIR::Sub => "sub",
+                BinOpIR::Mul => "mul",
+                _ => unreachable!(),
+            };
+            // Cast to a concrete `i64` first, the same way the `Div` case
+            // above casts to `f64` - `wrapping_add`/`checked_add` are method
+            // calls, not operators, so unlike plain `+` they need a
+            // concrete receiver type up front rather than leaving it to
+            // fall out of unrelated inference elsewhere in the function.
+            match self.overflow_mode {
+                OverflowMode::Wrap => {
+                    return format!("(({}) as i64).wrapping_{}(({}) as i64)", l, method, r)
+                }
+                OverflowMode::Checked => {
+                    return format!(
+                        "(({}) as i64).checked_{}(({}) as i64).expect(\"integer overflow\")",
+                        l, method, r
+                    )
+                }
+                // Reached only if a caller runs a non-bigint function with a
+                // `Bigint` overflow mode, which `generate_function` never
+                // does (see `function_needs_bigint`) - fall back to a plain
+                // op rather than a silent behavior change.
+                OverflowMode::Bigint => {}
+            }
+        }
+        if op == BinOpIR::Pow {
+            return format!("({}).pow({} as u32)", l, r);
+        }
+        if op == BinOpIR::StrConcat {
+            return format!("format!(\"{{}}{{}}\", {}, {})", l, r);
+        }
+        if op == BinOpIR::StrRepeat {
+            return format!("{}.repeat(({}) as usize)", l, r);
+        }
+        // Python's `/` is true division - always a float, even for two ints
+        // - unlike Rust's `/` on integers, which truncates.
+        if op == BinOpIR::Div {
+            return format!("(({}) as f64 / ({}) as f64)", l, r);
+        }
+        // Python's `//` and `%` round toward negative infinity and take the
+        // sign of the divisor; Rust's `/`/`%` truncate toward zero instead,
+        // giving different answers for negative operands. `div_floor`/
+        // `mod_floor` implement exactly this floor semantics (unlike
+        // `div_euclid`/`rem_euclid`, which are Euclidean and only agree with
+        // Python for a positive divisor) - the same trait the bigint path
+        // above already uses, so both paths agree on negative-operand
+        // behavior.
+        if op == BinOpIR::FloorDiv {
+            return format!("num_integer::Integer::div_floor(&({}), &({}))", l, r);
+        }
+        if op == BinOpIR::Mod {
+            return format!("num_integer::Integer::mod_floor(&({}), &({}))", l, r);
+        }
+        let op_str = match op {
+            BinOpIR::Add => "+",
+            BinOpIR::Sub => "-",
+            BinOpIR::Mul => "*",
+            BinOpIR::BitAnd => "&",
+            BinOpIR::BitOr => "|",
+            BinOpIR::BitXor => "^",
+            BinOpIR::LShift => "<<",
+            BinOpIR::RShift => ">>",
+            BinOpIR::Eq => "==",
+            BinOpIR::NotEq => "!=",
+            BinOpIR::Lt => "<",
+            BinOpIR::LtE => "<=",
+            BinOpIR::Gt => ">",
+            BinOpIR::GtE => ">=",
+            BinOpIR::Pow
+            | BinOpIR::StrConcat
+            | BinOpIR::StrRepeat
+            | BinOpIR::Div
+            | BinOpIR::FloorDiv
+            | BinOpIR::Mod => unreachable!(),
+        };
+        format!("({} {} {})", l, op_str, r)
+    }
+
+    fn value_to_rust(&self, value: &IRValue) -> String {
+        match value {
+            IRValue::Const(IRConstant::Int(n)) if self.bigint => {
+                format!("num_bigint::BigInt::from({}i64)", n)
+            }
+            IRValue::Const(IRConstant::Int(n)) => n.to_string(),
+            IRValue::Const(IRConstant::Bool(b)) => b.to_string(),
+            IRValue::Const(IRConstant::String(s)) => format!("{:?}.to_string()", s),
+            IRValue::Const(IRConstant::Null) => "Default::default()".to_string(),
+            // `sys.argv` reaches here as a plain dotted identifier (see
+            // `parser.rs`'s bare-dotted-name fallback, the same mechanism
+            // `self.x` uses) - substituted with the real thing at every use
+            // site rather than a synthesized global, so a benchmark reading
+            // `sys.argv[1]` just works without `IRModule` needing a new kind
+            // of top-level item.
+            IRValue::Local(name) if name == "sys.argv" => {
+                "std::env::args().collect::<Vec<String>>()".to_string()
+            }
+            // `math.pi` reaches here the same dotted-identifier way as
+            // `sys.argv` above.
+            IRValue::Local(name) if name == "math.pi" => "std::f64::consts::PI".to_string(),
+            // An atomic global reads through `.load`, unless this function
+            // has already shadowed it with a genuine local of the same name
+            // (tracked in `declared` the same way an ordinary local is) -
+            // see `atomic_globals` and `assign`.
+            IRValue::Local(name) if self.atomic_globals.contains_key(name) && !self.declared.contains(name) => {
+                format!("{}.load(std::sync::atomic::Ordering::SeqCst)", name)
+            }
+            IRValue::Local(name) => name.clone(),
+            IRValue::Temporary(id) => format!("__t{}", id),
+        }
+    }
+
+    /// Declares `target` with `let mut` the first time it's written in this
+    /// function, and plainly reassigns it afterward.
+    fn assign(&mut self, target: &IRValue, expr: &str) {
+        let name = match target {
+            IRValue::Local(name) => name.clone(),
+            IRValue::Temporary(id) => format!("__t{}", id),
+            IRValue::Const(_) => return, // never a valid assignment target
+        };
+
+        // A field write (`self.x = ...`) is a plain reassignment, never a
+        // fresh Rust binding - `self` is already declared as a `&mut self`
+        // receiver, and `let mut self.x = ...` isn't even valid syntax.
+        if name.contains('.') {
+            self.writeln(&format!("{} = {};", name, expr));
+            return;
+        }
+
+        // A `global name` in this function routes the write straight
+        // through to the atomic static instead of shadowing it with a
+        // fresh local - and deliberately skips `declared`, so every later
+        // read/write of `name` in this function keeps going through the
+        // atomic too (see `value_to_rust`).
+        if self.function_global_writes.contains(&name) {
+            self.writeln(&format!("{}.store({}, std::sync::atomic::Ordering::SeqCst);", name, expr));
+            return;
+        }
+
+        if self.declared.insert(name.clone()) {
+            self.writeln(&format!("let mut {} = {};", name, expr));
+        } else {
+            self.writeln(&format!("{} = {};", name, expr));
+        }
+    }
+
+    fn writeln(&mut self, line: &str) {
+        writeln!(self.output, "{}{}", "    ".repeat(self.indent), line).ok();
+    }
+
+    /// Generates a `.pyi` stub alongside `generate_pyo3`'s extension module,
+    /// so IDEs and `mypy` see real signatures for the compiled functions
+    /// instead of treating an untyped `.so` import as `Any`. Mirrors
+    /// `generate_pyo3`'s own filtering (skip methods, skip the synthesized
+    /// `main`) so the stub always matches what actually got exported.
+    pub fn generate_pyi(module: &IRModule) -> String {
+        let mut out = String::new();
+        for function in module.functions.iter().filter(|f| f.owner.is_none() && f.name != "main") {
+            let params: Vec<String> = function
+                .params
+                .iter()
+                .map(|p| format!("{}: {}", p.name, Self::type_to_python_hint(&p.typ)))
+                .collect();
+            writeln!(
+                out,
+                "def {}({}) -> {}: ...",
+                function.name,
+                params.join(", "),
+                Self::type_to_python_hint(&function.return_type)
+            )
+            .ok();
+        }
+        out
+    }
+
+    /// The `.pyi` counterpart to `type_to_rust` - maps an inferred `Type` to
+    /// the Python type hint spelling a caller would write by hand.
+    fn type_to_python_hint(typ: &Type) -> String {
+        match typ {
+            Type::Int => "int".to_string(),
+            Type::Float => "float".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::String => "str".to_string(),
+            Type::List(elem) => format!("list[{}]", Self::type_to_python_hint(elem)),
+            Type::NoneType => "None".to_string(),
+            _ => "None".to_string(),
+        }
+    }
+
+    fn type_to_rust(&self, typ: &Type) -> String {
+        match typ {
+            Type::Int if self.bigint => "num_bigint::BigInt".to_string(),
+            Type::Int => "i64".to_string(),
+            Type::Float => "f64".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::String => "String".to_string(),
+            Type::List(elem) => format!("Vec<{}>", self.type_to_rust(elem)),
+            Type::NoneType => "()".to_string(),
+            _ => "()".to_string(),
+        }
+    }
+}
+
+impl Default for IRCodegen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,1797 @@
+/// AST → IR lowering
+/// Turns the parsed program into an `IRModule` so `IROptimizer` runs before
+/// Rust is generated, instead of `Compiler::compile` handing the AST
+/// straight to codegen and never touching the IR layer at all.
+use crate::ast_types as ast;
+use crate::ast_types::Type;
+use crate::directives::DirectiveSet;
+use crate::ir::*;
+use std::collections::HashMap;
+
+pub struct IRLowering {
+    next_temp: usize,
+    next_block: usize,
+    blocks: Vec<BasicBlock>,
+    current: usize,
+    /// (loop header, loop exit) for each loop `lower_statement` is currently
+    /// inside, innermost last - `break`/`continue` jump to the top entry.
+    loop_stack: Vec<(usize, usize)>,
+    /// A best-effort, local-only guess at which names currently hold a
+    /// `String`, used to pick string-flavored codegen for `+`/`*`/indexing
+    /// (see `is_string_expr`). This isn't real type inference - it only
+    /// looks at the most recent assignment to a name, so it can be fooled by
+    /// a variable reused for two different types, but that's rare enough in
+    /// practice to not be worth a real type checker yet.
+    locals: HashMap<String, Type>,
+    /// Every class name declared anywhere in the program - a `Call` whose
+    /// callee is one of these is a constructor call, not a call to a
+    /// same-named free function (see `lower_expression`'s `Call` arm).
+    known_classes: std::collections::HashSet<String>,
+    /// Every top-level function's parameter list, keyed by name - a `Call`
+    /// to one of these has its arguments reordered/defaulted against the
+    /// real signature (see `lower_call_args`) instead of passed straight
+    /// through positionally.
+    signatures: HashMap<String, Vec<ast::Parameter>>,
+}
+
+impl IRLowering {
+    /// Lowers every top-level function and class, plus a `main` for
+    /// `IROptimizer::dead_function_elimination` (which traces reachability
+    /// from `"main"`) to start from. A script's real top-level statements -
+    /// the `if __name__ == "__main__":` body, or bare statements above it -
+    /// become `main`'s body verbatim, in program order (see
+    /// `lower_script_main`); a module with none of those (just `def`s, no
+    /// script body at all) falls back to a synthesized `main` that calls
+    /// the first free function, mirroring what the old AST codegen's
+    /// `generate_main` produced.
+    pub fn lower_program(program: &ast::Program) -> IRModule {
+        let program = Self::rename_conflicting_main(program);
+        let program = &program;
+        let known_classes: std::collections::HashSet<String> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                ast::Statement::ClassDef(class_def) => Some(class_def.name.clone()),
+                _ => None,
+            })
+            .collect();
+        let signatures: HashMap<String, Vec<ast::Parameter>> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                ast::Statement::FunctionDef(func) => Some((func.name.clone(), func.params.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let mut module = IRModule::new();
+        module.globals = Self::lower_globals(program);
+
+        for stmt in &program.statements {
+            match stmt {
+                ast::Statement::FunctionDef(func) if Self::contains_yield(&func.body) => {
+                    match Self::lower_generator(func, &known_classes, &signatures) {
+                        Some((ir_struct, methods)) => {
+                            module.structs.push(ir_struct);
+                            module.functions.extend(methods);
+                        }
+                        // Shape not recognized - fall back to lowering it
+                        // like an ordinary function, where `yield` drops its
+                        // value (see `lower_statement`) instead of streaming
+                        // it through an `Iterator`.
+                        None => {
+                            module.functions.push(Self::lower_function(func, None, &known_classes, &signatures));
+                        }
+                    }
+                }
+                ast::Statement::FunctionDef(func) => {
+                    module.functions.push(Self::lower_function(func, None, &known_classes, &signatures));
+                }
+                ast::Statement::ClassDef(class_def) => {
+                    let (ir_struct, methods) = Self::lower_class(class_def, &known_classes, &signatures);
+                    module.structs.push(ir_struct);
+                    module.functions.extend(methods);
+                }
+                _ => {}
+            }
+        }
+
+        let script_body = Self::script_body_statements(program);
+        if script_body.is_empty() {
+            let entry = module
+                .functions
+                .iter()
+                .find(|f| f.owner.is_none())
+                .map(|f| (f.name.clone(), f.params.clone(), f.return_type.clone()));
+            if let Some((name, params, return_type)) = entry {
+                module
+                    .functions
+                    .push(Self::lower_main(&name, &params, &return_type));
+            }
+        } else {
+            module
+                .functions
+                .push(Self::lower_script_main(&script_body, &known_classes, &signatures));
+        }
+
+        module
+    }
+
+    /// Every top-level statement that's actual script execution, in
+    /// program order - everything except `def`/`class` (already lowered as
+    /// functions/structs above) and the assignments `lower_globals` already
+    /// turned into `const`/`static` items (re-running those in `main` would
+    /// either be a redundant re-store or, for an immutable `const`, invalid
+    /// Rust).
+    fn script_body_statements(program: &ast::Program) -> Vec<ast::Statement> {
+        program
+            .statements
+            .iter()
+            .filter(|stmt| {
+                !matches!(
+                    stmt,
+                    ast::Statement::FunctionDef(_) | ast::Statement::ClassDef(_)
+                )
+            })
+            .filter(|stmt| match stmt {
+                ast::Statement::Assign(assign) if assign.targets.len() == 1 => {
+                    Self::lower_global_init(&assign.value).is_none()
+                }
+                _ => true,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Lowers a script's real top-level statements into `main`, the same
+    /// way `lower_function` lowers a function body - just with no
+    /// parameters and nothing to return, since a Python script's top level
+    /// doesn't have either.
+    fn lower_script_main(
+        body: &[ast::Statement],
+        known_classes: &std::collections::HashSet<String>,
+        signatures: &HashMap<String, Vec<ast::Parameter>>,
+    ) -> IRFunction {
+        let mut lowering = IRLowering::new(known_classes.clone(), signatures.clone());
+        for stmt in body {
+            lowering.lower_statement(stmt);
+        }
+        if !lowering.terminated() {
+            lowering.push(IRInstruction::Return { value: None });
+        }
+        let mut global_names = std::collections::HashSet::new();
+        Self::collect_global_names(body, &mut global_names);
+        let mut lowered = lowering.finish(
+            "main".to_string(),
+            Vec::new(),
+            Type::NoneType,
+            DirectiveSet::new(),
+            None,
+            None,
+        );
+        lowered.global_names = global_names.into_iter().collect();
+        lowered
+    }
+
+    /// A script that both defines `def main():` and actually runs top-level
+    /// code (`if __name__ == "__main__": main()`, or the equivalent bare
+    /// statements) needs its script body compiled as Rust's own `fn main` -
+    /// but Python's `main` and the module/`__main__` split have no Rust
+    /// equivalent, so the user's `main` would collide with it in Rust's flat
+    /// function namespace. Renaming the user's `main` (and every call to it)
+    /// out of the way sidesteps the clash; scripts that don't hit it are
+    /// returned unchanged.
+    fn rename_conflicting_main(program: &ast::Program) -> ast::Program {
+        let has_user_main = program.statements.iter().any(
+            |stmt| matches!(stmt, ast::Statement::FunctionDef(f) if f.name == "main"),
+        );
+        if !has_user_main || Self::script_body_statements(program).is_empty() {
+            return program.clone();
+        }
+
+        const RENAMED: &str = "__py_main";
+        let mut renamed = program.clone();
+        for stmt in &mut renamed.statements {
+            Self::rename_main_in_statement(stmt, "main", RENAMED);
+        }
+        renamed
+    }
+
+    fn rename_main_in_statement(stmt: &mut ast::Statement, old: &str, new: &str) {
+        match stmt {
+            ast::Statement::FunctionDef(f) => {
+                if f.name == old {
+                    f.name = new.to_string();
+                }
+                for s in &mut f.body {
+                    Self::rename_main_in_statement(s, old, new);
+                }
+            }
+            ast::Statement::ClassDef(c) => {
+                for s in &mut c.body {
+                    Self::rename_main_in_statement(s, old, new);
+                }
+            }
+            ast::Statement::Assign(a) => Self::rename_main_in_expr(&mut a.value, old, new),
+            ast::Statement::AugAssign(a) => Self::rename_main_in_expr(&mut a.value, old, new),
+            ast::Statement::For(f) => {
+                Self::rename_main_in_expr(&mut f.iter, old, new);
+                for s in &mut f.body {
+                    Self::rename_main_in_statement(s, old, new);
+                }
+            }
+            ast::Statement::While(w) => {
+                Self::rename_main_in_expr(&mut w.condition, old, new);
+                for s in &mut w.body {
+                    Self::rename_main_in_statement(s, old, new);
+                }
+            }
+            ast::Statement::If(i) => {
+                Self::rename_main_in_expr(&mut i.condition, old, new);
+                for s in &mut i.then_body {
+                    Self::rename_main_in_statement(s, old, new);
+                }
+                if let Some(else_body) = &mut i.else_body {
+                    for s in else_body {
+                        Self::rename_main_in_statement(s, old, new);
+                    }
+                }
+            }
+            ast::Statement::Try(t) => {
+                for s in &mut t.body {
+                    Self::rename_main_in_statement(s, old, new);
+                }
+                for handler in &mut t.handlers {
+                    for s in &mut handler.body {
+                        Self::rename_main_in_statement(s, old, new);
+                    }
+                }
+                for s in &mut t.finalbody {
+                    Self::rename_main_in_statement(s, old, new);
+                }
+            }
+            ast::Statement::Return(Some(e), _) => Self::rename_main_in_expr(e, old, new),
+            ast::Statement::Yield(e, _) => Self::rename_main_in_expr(e, old, new),
+            ast::Statement::ExprStatement(e, _) => Self::rename_main_in_expr(e, old, new),
+            ast::Statement::Return(None, _)
+            | ast::Statement::Pass(_)
+            | ast::Statement::Break(_)
+            | ast::Statement::Continue(_)
+            | ast::Statement::Global(_, _) => {}
+        }
+    }
+
+    fn rename_main_in_expr(expr: &mut ast::Expression, old: &str, new: &str) {
+        match expr {
+            ast::Expression::IntLit(_)
+            | ast::Expression::FloatLit(_)
+            | ast::Expression::BoolLit(_)
+            | ast::Expression::StringLit(_)
+            | ast::Expression::Identifier(_) => {}
+            ast::Expression::BinOp(l, _, r) => {
+                Self::rename_main_in_expr(l, old, new);
+                Self::rename_main_in_expr(r, old, new);
+            }
+            ast::Expression::UnaryOp(_, e) => Self::rename_main_in_expr(e, old, new),
+            ast::Expression::Call(callee, args) => {
+                match callee.as_mut() {
+                    ast::Expression::Identifier(name) if name == old => *name = new.to_string(),
+                    other => Self::rename_main_in_expr(other, old, new),
+                }
+                for arg in args {
+                    Self::rename_main_in_expr(arg, old, new);
+                }
+            }
+            ast::Expression::Index(a, b) => {
+                Self::rename_main_in_expr(a, old, new);
+                Self::rename_main_in_expr(b, old, new);
+            }
+            ast::Expression::Slice(base, start, stop, step) => {
+                Self::rename_main_in_expr(base, old, new);
+                for part in [start, stop, step].into_iter().flatten() {
+                    Self::rename_main_in_expr(part, old, new);
+                }
+            }
+            ast::Expression::Attribute(e, _) => Self::rename_main_in_expr(e, old, new),
+            ast::Expression::List(items) | ast::Expression::Tuple(items) => {
+                for item in items {
+                    Self::rename_main_in_expr(item, old, new);
+                }
+            }
+            ast::Expression::Dict(pairs) => {
+                for (k, v) in pairs {
+                    Self::rename_main_in_expr(k, old, new);
+                    Self::rename_main_in_expr(v, old, new);
+                }
+            }
+            ast::Expression::Lambda(_, body) => Self::rename_main_in_expr(body, old, new),
+            ast::Expression::Conditional(c, t, f) => {
+                Self::rename_main_in_expr(c, old, new);
+                Self::rename_main_in_expr(t, old, new);
+                Self::rename_main_in_expr(f, old, new);
+            }
+            ast::Expression::FString(parts) => {
+                for part in parts {
+                    if let ast::FStringPart::Expr(e, _) = part {
+                        Self::rename_main_in_expr(e, old, new);
+                    }
+                }
+            }
+            ast::Expression::Kwarg(_, v) => Self::rename_main_in_expr(v, old, new),
+        }
+    }
+
+    /// Lowers every top-level `name = literal` into an `IRGlobal` - see
+    /// `IRCodegen::generate_globals`. A top-level assignment whose value
+    /// isn't one of the literal shapes `lower_global_init` recognizes (a
+    /// call, an expression referencing another name, ...) is silently
+    /// skipped, same as every other top-level statement always has been.
+    fn lower_globals(program: &ast::Program) -> Vec<IRGlobal> {
+        let mutated = Self::globally_mutated_names(program);
+        program
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                ast::Statement::Assign(assign) if assign.targets.len() == 1 => {
+                    let initializer = Self::lower_global_init(&assign.value)?;
+                    let name = assign.targets[0].clone();
+                    let typ = initializer.value_type();
+                    let mutable = mutated.contains(&name) && matches!(typ, Type::Int | Type::Bool);
+                    Some(IRGlobal { name, typ, initializer, mutable })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn lower_global_init(expr: &ast::Expression) -> Option<IRGlobalInit> {
+        match expr {
+            ast::Expression::IntLit(n) => Some(IRGlobalInit::Int(*n)),
+            ast::Expression::FloatLit(f) => Some(IRGlobalInit::Float(*f)),
+            ast::Expression::BoolLit(b) => Some(IRGlobalInit::Bool(*b)),
+            ast::Expression::StringLit(s) => Some(IRGlobalInit::String(s.clone())),
+            ast::Expression::List(items) if !items.is_empty() => {
+                if items.iter().all(|e| matches!(e, ast::Expression::IntLit(_))) {
+                    Some(IRGlobalInit::IntList(
+                        items
+                            .iter()
+                            .map(|e| match e {
+                                ast::Expression::IntLit(n) => *n,
+                                _ => unreachable!(),
+                            })
+                            .collect(),
+                    ))
+                } else if items.iter().all(|e| matches!(e, ast::Expression::FloatLit(_))) {
+                    Some(IRGlobalInit::FloatList(
+                        items
+                            .iter()
+                            .map(|e| match e {
+                                ast::Expression::FloatLit(f) => *f,
+                                _ => unreachable!(),
+                            })
+                            .collect(),
+                    ))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Every name any top-level function's body binds via `global` -
+    /// consulted only to decide whether an eligible (`Int`/`Bool`) global
+    /// needs the atomic treatment instead of a plain `const` (see
+    /// `lower_globals`); the per-function `global_names` computed in
+    /// `lower_function` is what actually gates codegen for a given call.
+    fn globally_mutated_names(program: &ast::Program) -> std::collections::HashSet<String> {
+        let mut names = std::collections::HashSet::new();
+        for stmt in &program.statements {
+            if let ast::Statement::FunctionDef(func) = stmt {
+                Self::collect_global_names(&func.body, &mut names);
+            }
+        }
+        names
+    }
+
+    /// Recursively collects every name in a `global` statement anywhere in
+    /// `stmts`, including inside nested `if`/`while`/`for`/`try` bodies -
+    /// mirrors `contains_yield`'s walk.
+    fn collect_global_names(stmts: &[ast::Statement], names: &mut std::collections::HashSet<String>) {
+        for stmt in stmts {
+            match stmt {
+                ast::Statement::Global(globals, _) => names.extend(globals.iter().cloned()),
+                ast::Statement::For(f) => Self::collect_global_names(&f.body, names),
+                ast::Statement::While(w) => Self::collect_global_names(&w.body, names),
+                ast::Statement::If(i) => {
+                    Self::collect_global_names(&i.then_body, names);
+                    if let Some(else_body) = &i.else_body {
+                        Self::collect_global_names(else_body, names);
+                    }
+                }
+                ast::Statement::Try(t) => {
+                    Self::collect_global_names(&t.body, names);
+                    for handler in &t.handlers {
+                        Self::collect_global_names(&handler.body, names);
+                    }
+                    Self::collect_global_names(&t.finalbody, names);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn new(
+        known_classes: std::collections::HashSet<String>,
+        signatures: HashMap<String, Vec<ast::Parameter>>,
+    ) -> Self {
+        Self {
+            next_temp: 0,
+            next_block: 1,
+            blocks: vec![BasicBlock {
+                id: 0,
+                instructions: Vec::new(),
+                successors: Vec::new(),
+            }],
+            current: 0,
+            loop_stack: Vec::new(),
+            locals: HashMap::new(),
+            known_classes,
+            signatures,
+        }
+    }
+
+    fn lower_function(
+        func: &ast::FunctionDef,
+        owner: Option<String>,
+        known_classes: &std::collections::HashSet<String>,
+        signatures: &HashMap<String, Vec<ast::Parameter>>,
+    ) -> IRFunction {
+        let mut lowering = IRLowering::new(known_classes.clone(), signatures.clone());
+        // `self` has no Rust equivalent among the parameter list - a method
+        // takes it as the `&mut self` receiver `IRCodegen` prepends instead
+        // (see `owner`), so it's dropped here rather than lowered like any
+        // other parameter.
+        let params: Vec<IRParam> = func
+            .params
+            .iter()
+            .filter(|p| p.name != "self")
+            .map(|p| IRParam {
+                name: p.name.clone(),
+                typ: p.typ.clone(),
+            })
+            .collect();
+        for param in &params {
+            if param.typ == Type::String {
+                lowering.locals.insert(param.name.clone(), Type::String);
+            }
+        }
+
+        for stmt in &func.body {
+            lowering.lower_statement(stmt);
+        }
+
+        // Every path that falls off the end of the function needs a
+        // `Return`, since the CFG walk in `IRCodegen` has no other way to
+        // know the function body ended - `if`/`while`/`for` bodies that
+        // return early already terminated their own block and are left
+        // alone here.
+        if !lowering.terminated() {
+            let value = (func.return_type != Type::NoneType)
+                .then(|| Self::default_value(&func.return_type));
+            lowering.push(IRInstruction::Return { value });
+        }
+
+        let directives = DirectiveSet::from_strings(&func.directives);
+        let mut lowered = lowering.finish(func.name.clone(), params, func.return_type.clone(), directives, owner, Some(func.line));
+        let mut global_names = std::collections::HashSet::new();
+        Self::collect_global_names(&func.body, &mut global_names);
+        lowered.global_names = global_names.into_iter().collect();
+        lowered
+    }
+
+    /// Lowers a "dataclass-like" class into an `IRStruct` plus one
+    /// `IRFunction` per method - `__init__` becomes an associated `new`
+    /// (see `lower_constructor`), and every other method is lowered exactly
+    /// like a free function, `owner`-tagged so `IRCodegen` groups it into
+    /// the class's `impl` block and gives it a `&mut self` receiver.
+    /// Fields come only from `__init__`'s top-level `self.field = ...`
+    /// assignments - nothing else in the class body (a class variable, a
+    /// field assigned outside `__init__`) contributes one, matching the
+    /// "common dataclass shape" this targets rather than general Python
+    /// classes.
+    fn lower_class(
+        class_def: &ast::ClassDef,
+        known_classes: &std::collections::HashSet<String>,
+        signatures: &HashMap<String, Vec<ast::Parameter>>,
+    ) -> (IRStruct, Vec<IRFunction>) {
+        let init = class_def.body.iter().find_map(|stmt| match stmt {
+            ast::Statement::FunctionDef(f) if f.name == "__init__" => Some(f),
+            _ => None,
+        });
+
+        let fields: Vec<IRParam> = init
+            .map(|init| {
+                init.body
+                    .iter()
+                    .filter_map(|stmt| match stmt {
+                        ast::Statement::Assign(assign) if assign.targets.len() == 1 => {
+                            assign.targets[0].strip_prefix("self.").map(|field| IRParam {
+                                name: field.to_string(),
+                                typ: Self::infer_field_type(&assign.value, &init.params),
+                            })
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut functions = Vec::new();
+        if let Some(init) = init {
+            functions.push(Self::lower_constructor(class_def, init, known_classes, signatures));
+        }
+        for stmt in &class_def.body {
+            if let ast::Statement::FunctionDef(f) = stmt {
+                if f.name != "__init__" {
+                    functions.push(Self::lower_function(f, Some(class_def.name.clone()), known_classes, signatures));
+                }
+            }
+        }
+
+        (
+            IRStruct {
+                name: class_def.name.clone(),
+                fields,
+                item_type: None,
+            },
+            functions,
+        )
+    }
+
+    /// A cheap guess at a field's type from how `__init__` assigns it: the
+    /// type of the matching parameter for the common `self.x = x` shape,
+    /// the type a literal RHS obviously has, or `Type::Int` as the same
+    /// fallback `parse_parameters` uses for an unannotated parameter.
+    fn infer_field_type(value: &ast::Expression, params: &[ast::Parameter]) -> Type {
+        match value {
+            ast::Expression::Identifier(name) => params
+                .iter()
+                .find(|p| &p.name == name)
+                .map(|p| p.typ.clone())
+                .unwrap_or(Type::Int),
+            ast::Expression::FloatLit(_) => Type::Float,
+            ast::Expression::StringLit(_) | ast::Expression::FString(_) => Type::String,
+            ast::Expression::BoolLit(_) => Type::Bool,
+            _ => Type::Int,
+        }
+    }
+
+    /// `__init__(self, ...)` becomes an associated `new(...)` function:
+    /// every top-level `self.field = expr` in its body contributes one
+    /// field to a trailing `Self { .. }` struct literal instead of a
+    /// `Store` (there's no `self` local to store into yet - it's what
+    /// `new` is building), and everything else lowers normally.
+    fn lower_constructor(
+        class_def: &ast::ClassDef,
+        init: &ast::FunctionDef,
+        known_classes: &std::collections::HashSet<String>,
+        signatures: &HashMap<String, Vec<ast::Parameter>>,
+    ) -> IRFunction {
+        let mut lowering = IRLowering::new(known_classes.clone(), signatures.clone());
+        let params: Vec<IRParam> = init
+            .params
+            .iter()
+            .filter(|p| p.name != "self")
+            .map(|p| IRParam {
+                name: p.name.clone(),
+                typ: p.typ.clone(),
+            })
+            .collect();
+
+        let mut fields = Vec::new();
+        for stmt in &init.body {
+            match stmt {
+                ast::Statement::Assign(assign) if assign.targets.len() == 1 => {
+                    match assign.targets[0].strip_prefix("self.") {
+                        Some(field) => {
+                            let value = lowering.lower_expression(&assign.value);
+                            fields.push((field.to_string(), value));
+                        }
+                        None => lowering.lower_statement(stmt),
+                    }
+                }
+                _ => lowering.lower_statement(stmt),
+            }
+        }
+
+        let result = lowering.fresh_temp();
+        lowering.push(IRInstruction::NewStruct {
+            result: result.clone(),
+            name: class_def.name.clone(),
+            fields,
+        });
+        lowering.push(IRInstruction::Return { value: Some(result) });
+
+        let directives = DirectiveSet::from_strings(&init.directives);
+        lowering.finish(
+            "new".to_string(),
+            params,
+            Type::Unknown,
+            directives,
+            Some(class_def.name.clone()),
+            Some(init.line),
+        )
+    }
+
+    /// Whether `stmts` contains a `yield` anywhere, including nested inside
+    /// `if`/`while`/`for`/`try` bodies - used to decide whether a top-level
+    /// function needs `lower_generator` instead of `lower_function` at all.
+    fn contains_yield(stmts: &[ast::Statement]) -> bool {
+        stmts.iter().any(|stmt| match stmt {
+            ast::Statement::Yield(_, _) => true,
+            ast::Statement::For(f) => Self::contains_yield(&f.body),
+            ast::Statement::While(w) => Self::contains_yield(&w.body),
+            ast::Statement::If(i) => {
+                Self::contains_yield(&i.then_body)
+                    || i.else_body.as_ref().is_some_and(|b| Self::contains_yield(b))
+            }
+            ast::Statement::Try(t) => {
+                Self::contains_yield(&t.body)
+                    || t.handlers.iter().any(|h| Self::contains_yield(&h.body))
+                    || Self::contains_yield(&t.finalbody)
+            }
+            _ => false,
+        })
+    }
+
+    /// Recognizes the narrow "zero or more setup assignments, then a single
+    /// tail `for ... in range(...)` loop whose only statement is a `yield`"
+    /// shape and compiles it straight into a struct implementing `Iterator`,
+    /// so consuming it doesn't require materializing a full list first. Any
+    /// generator function that doesn't match this shape returns `None`, and
+    /// the caller falls back to `lower_function` instead (see
+    /// `lower_program`), where `yield` just drops its value.
+    fn lower_generator(
+        func: &ast::FunctionDef,
+        known_classes: &std::collections::HashSet<String>,
+        signatures: &HashMap<String, Vec<ast::Parameter>>,
+    ) -> Option<(IRStruct, Vec<IRFunction>)> {
+        let (last, setup) = func.body.split_last()?;
+        let for_loop = match last {
+            ast::Statement::For(for_loop) => for_loop,
+            _ => return None,
+        };
+        if !setup.iter().all(|s| matches!(s, ast::Statement::Assign(_))) {
+            return None;
+        }
+        let yield_expr = match for_loop.body.as_slice() {
+            [ast::Statement::Yield(expr, _)] => expr,
+            _ => return None,
+        };
+        let (start, end) = match &for_loop.iter {
+            ast::Expression::Call(callee, args)
+                if matches!(callee.as_ref(), ast::Expression::Identifier(n) if n == "range")
+                    && (args.len() == 1 || args.len() == 2) =>
+            {
+                if args.len() == 2 {
+                    (args[0].clone(), args[1].clone())
+                } else {
+                    (ast::Expression::IntLit(0), args[0].clone())
+                }
+            }
+            _ => return None,
+        };
+
+        // Every captured name - function params, setup locals, and the loop
+        // target - becomes a struct field; `new` and `next` reference them
+        // as `self.<name>`, reusing the same dotted-name convention
+        // `lower_class` established for ordinary class fields.
+        let mut field_names: Vec<String> = func.params.iter().map(|p| p.name.clone()).collect();
+        for stmt in setup {
+            if let ast::Statement::Assign(assign) = stmt {
+                for target in &assign.targets {
+                    if !field_names.contains(target) {
+                        field_names.push(target.clone());
+                    }
+                }
+            }
+        }
+        field_names.push(for_loop.target.clone());
+        let end_field = format!("__{}_end", for_loop.target);
+        field_names.push(end_field.clone());
+
+        let item_type = Self::infer_field_type(yield_expr, &func.params);
+        let constructor = Self::lower_generator_constructor(
+            &func.name, func, setup, &for_loop.target, &start, &end, &end_field,
+            &field_names, known_classes, signatures,
+        );
+        let next = Self::lower_generator_next(
+            &func.name, &item_type, &for_loop.target, &end_field, yield_expr,
+            &field_names, known_classes, signatures,
+        );
+
+        Some((
+            IRStruct {
+                name: func.name.clone(),
+                // A captured field's type is its matching parameter's type
+                // when it has one - a setup local or the loop bound has no
+                // annotation to draw from, so it falls back to `Type::Int`
+                // the same way `infer_field_type` does for a class field.
+                fields: field_names
+                    .iter()
+                    .map(|name| IRParam {
+                        name: name.clone(),
+                        typ: func
+                            .params
+                            .iter()
+                            .find(|p| &p.name == name)
+                            .map(|p| p.typ.clone())
+                            .unwrap_or(Type::Int),
+                    })
+                    .collect(),
+                item_type: Some(item_type),
+            },
+            vec![constructor, next],
+        ))
+    }
+
+    /// Rewrites every `Identifier` in `names` into `self.<name>`, for
+    /// splicing a generator's setup/yield expressions into `next()`, which
+    /// reads captured state off `self` rather than plain locals. Only the
+    /// expression forms the "simple generator" shape can actually contain
+    /// are handled - anything else is passed through unchanged.
+    fn prefix_self(expr: &ast::Expression, names: &[String]) -> ast::Expression {
+        use ast::Expression::*;
+        match expr {
+            Identifier(name) if names.contains(name) => Identifier(format!("self.{}", name)),
+            BinOp(left, op, right) => BinOp(
+                Box::new(Self::prefix_self(left, names)),
+                *op,
+                Box::new(Self::prefix_self(right, names)),
+            ),
+            UnaryOp(op, operand) => UnaryOp(*op, Box::new(Self::prefix_self(operand, names))),
+            Call(callee, args) => Call(
+                Box::new(Self::prefix_self(callee, names)),
+                args.iter().map(|a| Self::prefix_self(a, names)).collect(),
+            ),
+            Index(base, index) => Index(
+                Box::new(Self::prefix_self(base, names)),
+                Box::new(Self::prefix_self(index, names)),
+            ),
+            Conditional(cond, then_e, else_e) => Conditional(
+                Box::new(Self::prefix_self(cond, names)),
+                Box::new(Self::prefix_self(then_e, names)),
+                Box::new(Self::prefix_self(else_e, names)),
+            ),
+            _ => expr.clone(),
+        }
+    }
+
+    /// The generator's `new(...)`: runs the setup assignments and the loop
+    /// bound expressions as plain locals (there's no `self` yet - it's what
+    /// this builds), then captures every field into a trailing `Self { .. }`
+    /// literal exactly like `lower_constructor` does for `__init__`.
+    #[allow(clippy::too_many_arguments)]
+    fn lower_generator_constructor(
+        struct_name: &str,
+        func: &ast::FunctionDef,
+        setup: &[ast::Statement],
+        target: &str,
+        start: &ast::Expression,
+        end: &ast::Expression,
+        end_field: &str,
+        field_names: &[String],
+        known_classes: &std::collections::HashSet<String>,
+        signatures: &HashMap<String, Vec<ast::Parameter>>,
+    ) -> IRFunction {
+        let mut lowering = IRLowering::new(known_classes.clone(), signatures.clone());
+        let params: Vec<IRParam> = func
+            .params
+            .iter()
+            .map(|p| IRParam { name: p.name.clone(), typ: p.typ.clone() })
+            .collect();
+
+        for stmt in setup {
+            lowering.lower_statement(stmt);
+        }
+        let start_value = lowering.lower_expression(start);
+        lowering.push(IRInstruction::Store { target: target.to_string(), value: start_value });
+        let end_value = lowering.lower_expression(end);
+        lowering.push(IRInstruction::Store { target: end_field.to_string(), value: end_value });
+
+        let fields: Vec<(String, IRValue)> = field_names
+            .iter()
+            .map(|name| (name.clone(), IRValue::Local(name.clone())))
+            .collect();
+        let result = lowering.fresh_temp();
+        lowering.push(IRInstruction::NewStruct {
+            result: result.clone(),
+            name: struct_name.to_string(),
+            fields,
+        });
+        lowering.push(IRInstruction::Return { value: Some(result) });
+
+        let directives = DirectiveSet::from_strings(&func.directives);
+        lowering.finish("new".to_string(), params, Type::Unknown, directives, Some(struct_name.to_string()), Some(func.line))
+    }
+
+    /// The generator's `next(&mut self) -> Option<Item>`: `if self.<target>
+    /// < self.<end_field> { advance and return Some(the yielded value) }
+    /// else { None }`, hand-built rather than lowered from the original
+    /// `for`/`yield` statements since there's no general "resume after a
+    /// yield" machinery in this IR - only this one loop shape is supported.
+    #[allow(clippy::too_many_arguments)]
+    fn lower_generator_next(
+        struct_name: &str,
+        item_type: &Type,
+        target: &str,
+        end_field: &str,
+        yield_expr: &ast::Expression,
+        field_names: &[String],
+        known_classes: &std::collections::HashSet<String>,
+        signatures: &HashMap<String, Vec<ast::Parameter>>,
+    ) -> IRFunction {
+        let mut lowering = IRLowering::new(known_classes.clone(), signatures.clone());
+        let entry = lowering.current;
+        let then_id = lowering.new_block();
+        let else_id = lowering.new_block();
+
+        let condition = lowering.fresh_temp();
+        lowering.push(IRInstruction::BinOp {
+            result: condition.clone(),
+            op: BinOpIR::Lt,
+            left: IRValue::Local(format!("self.{}", target)),
+            right: IRValue::Local(format!("self.{}", end_field)),
+        });
+        lowering.push(IRInstruction::Branch { condition, true_block: then_id, false_block: else_id });
+        lowering.set_successors(entry, vec![then_id, else_id]);
+
+        lowering.current = then_id;
+        let prefixed_expr = Self::prefix_self(yield_expr, field_names);
+        let value = lowering.lower_expression(&prefixed_expr);
+        let saved = lowering.fresh_temp();
+        lowering.push(IRInstruction::Assign { target: saved.clone(), value });
+        let advanced = lowering.fresh_temp();
+        lowering.push(IRInstruction::BinOp {
+            result: advanced.clone(),
+            op: BinOpIR::Add,
+            left: IRValue::Local(format!("self.{}", target)),
+            right: IRValue::Const(IRConstant::Int(1)),
+        });
+        lowering.push(IRInstruction::Assign {
+            target: IRValue::Local(format!("self.{}", target)),
+            value: advanced,
+        });
+        lowering.push(IRInstruction::Return { value: Some(saved) });
+
+        lowering.current = else_id;
+        lowering.push(IRInstruction::Return { value: None });
+
+        let mut next = lowering.finish(
+            "next".to_string(),
+            Vec::new(),
+            item_type.clone(),
+            DirectiveSet::new(),
+            Some(struct_name.to_string()),
+            None,
+        );
+        next.is_generator_next = true;
+        next
+    }
+
+    /// A placeholder `main` calling the entry function with default-valued
+    /// arguments and printing its result, the same behavior
+    /// `RustCodegen::generate_main` used to hard-code from the AST.
+    fn lower_main(name: &str, params: &[IRParam], return_type: &Type) -> IRFunction {
+        let mut lowering = IRLowering::new(std::collections::HashSet::new(), HashMap::new());
+        let mut args = Vec::new();
+        for param in params {
+            let value = Self::default_value(&param.typ);
+            lowering.push(IRInstruction::Store {
+                target: param.name.clone(),
+                value,
+            });
+            args.push(IRValue::Local(param.name.clone()));
+        }
+
+        let result = lowering.fresh_temp();
+        lowering.push(IRInstruction::Call {
+            result: result.clone(),
+            function: name.to_string(),
+            args,
+        });
+
+        if *return_type != Type::NoneType {
+            let print_result = lowering.fresh_temp();
+            lowering.push(IRInstruction::Call {
+                result: print_result,
+                function: "println".to_string(),
+                args: vec![result],
+            });
+        }
+
+        lowering.push(IRInstruction::Return { value: None });
+        lowering.finish("main".to_string(), Vec::new(), Type::NoneType, DirectiveSet::new(), None, None)
+    }
+
+    /// A representative value for a parameter/return type with no literal
+    /// expression to draw from (main's placeholder call arguments, or a
+    /// function falling off its end without an explicit `return`).
+    fn default_value(typ: &Type) -> IRValue {
+        match typ {
+            Type::Int => IRValue::Const(IRConstant::Int(0)),
+            Type::Bool => IRValue::Const(IRConstant::Bool(false)),
+            Type::String => IRValue::Const(IRConstant::String(String::new())),
+            // Float has no IR constant yet, and List/Dict/Tuple/Array have no
+            // literal representation in IR at all - `Null` becomes Rust's
+            // `Default::default()`, which resolves correctly for all of
+            // these via type inference at the call site.
+            _ => IRValue::Const(IRConstant::Null),
+        }
+    }
+
+    fn fresh_temp(&mut self) -> IRValue {
+        let id = self.next_temp;
+        self.next_temp += 1;
+        IRValue::Temporary(id)
+    }
+
+    fn new_block(&mut self) -> usize {
+        let id = self.next_block;
+        self.next_block += 1;
+        self.blocks.push(BasicBlock {
+            id,
+            instructions: Vec::new(),
+            successors: Vec::new(),
+        });
+        id
+    }
+
+    fn block_index(&self, id: usize) -> usize {
+        self.blocks
+            .iter()
+            .position(|b| b.id == id)
+            .expect("lowering never references a block id it hasn't created")
+    }
+
+    fn push(&mut self, instr: IRInstruction) {
+        let idx = self.block_index(self.current);
+        self.blocks[idx].instructions.push(instr);
+    }
+
+    fn set_successors(&mut self, block: usize, successors: Vec<usize>) {
+        let idx = self.block_index(block);
+        self.blocks[idx].successors = successors;
+    }
+
+    /// Whether the current block already ends in `Return`/`Jump`/`Branch` -
+    /// once true, any further statements in the same source block are
+    /// unreachable (e.g. code after a `return`) and are dropped rather than
+    /// appended after a terminator.
+    fn terminated(&self) -> bool {
+        let idx = self.block_index(self.current);
+        matches!(
+            self.blocks[idx].instructions.last(),
+            Some(IRInstruction::Return { .. })
+                | Some(IRInstruction::Jump { .. })
+                | Some(IRInstruction::Branch { .. })
+        )
+    }
+
+    fn jump_to(&mut self, target: usize) {
+        if !self.terminated() {
+            self.push(IRInstruction::Jump { target });
+            self.set_successors(self.current, vec![target]);
+        }
+    }
+
+    fn finish(
+        self,
+        name: String,
+        params: Vec<IRParam>,
+        return_type: Type,
+        directives: DirectiveSet,
+        owner: Option<String>,
+        source_line: Option<usize>,
+    ) -> IRFunction {
+        IRFunction {
+            name,
+            params,
+            return_type,
+            blocks: self.blocks,
+            directives,
+            optimization_level: OptimizationLevel::Basic,
+            owner,
+            is_generator_next: false,
+            source_line,
+            // Only `lower_function` fills this in, since `global` only
+            // makes sense against a plain top-level function - see there.
+            global_names: Vec::new(),
+            // Only `Compiler::attach_python_fallbacks` fills this in, once
+            // lowering is done and the original source text is available.
+            python_source: None,
+        }
+    }
+
+    fn lower_statement(&mut self, stmt: &ast::Statement) {
+        if self.terminated() {
+            return;
+        }
+
+        // Every statement, including ones nested inside a loop/`if`/`try`
+        // body, funnels through here exactly once - the one place fine
+        // enough grained to back `--profile-lines`'s per-Python-line hit
+        // counts (see `IRInstruction::LineMarker`).
+        self.push(IRInstruction::LineMarker { line: stmt.line() });
+
+        match stmt {
+            ast::Statement::Assign(assign) => {
+                let is_string = self.is_string_expr(&assign.value);
+                let value = self.lower_expression(&assign.value);
+                for target in &assign.targets {
+                    if is_string {
+                        self.locals.insert(target.clone(), Type::String);
+                    } else {
+                        self.locals.remove(target);
+                    }
+                    self.push(IRInstruction::Store {
+                        target: target.clone(),
+                        value: value.clone(),
+                    });
+                }
+            }
+            ast::Statement::AugAssign(aug) => {
+                let is_string_concat = matches!(aug.op, ast::BinOp::Add)
+                    && (matches!(self.locals.get(&aug.target), Some(Type::String))
+                        || self.is_string_expr(&aug.value));
+                let rhs = self.lower_expression(&aug.value);
+                let op = if is_string_concat {
+                    self.locals.insert(aug.target.clone(), Type::String);
+                    BinOpIR::StrConcat
+                } else {
+                    Self::lower_binop(aug.op)
+                };
+                self.push(IRInstruction::BinOp {
+                    result: IRValue::Local(aug.target.clone()),
+                    op,
+                    left: IRValue::Local(aug.target.clone()),
+                    right: rhs,
+                });
+            }
+            ast::Statement::Return(expr, _) => {
+                let value = expr.as_ref().map(|e| self.lower_expression(e));
+                self.push(IRInstruction::Return { value });
+            }
+            // Only `lower_generator`'s narrow "single tail-yield loop" shape
+            // compiles a `yield` into a real produced value - anywhere else
+            // (a shape `lower_generator` didn't recognize) the yielded
+            // expression is still lowered for its side effects, but the
+            // value itself is dropped rather than streamed anywhere.
+            ast::Statement::Yield(expr, _) => {
+                self.lower_expression(expr);
+            }
+            ast::Statement::Pass(_) => {}
+            ast::Statement::Break(_) => self.lower_break(),
+            ast::Statement::Continue(_) => self.lower_continue(),
+            ast::Statement::ExprStatement(expr, _) => {
+                self.lower_expression(expr);
+            }
+            ast::Statement::If(if_stmt) => self.lower_if(if_stmt),
+            ast::Statement::Try(try_stmt) => self.lower_try(try_stmt),
+            ast::Statement::While(while_loop) => self.lower_while(while_loop),
+            ast::Statement::For(for_loop) => self.lower_for(for_loop),
+            // The parser never nests a `def`/`class` inside a function body,
+            // so these can't currently occur here.
+            ast::Statement::FunctionDef(_) | ast::Statement::ClassDef(_) => {}
+            // Already collected up front by `lower_function` (see
+            // `collect_global_names`) before any statement is lowered -
+            // nothing to emit for the declaration itself.
+            ast::Statement::Global(_, _) => {}
+        }
+    }
+
+    fn lower_break(&mut self) {
+        if let Some(&(_, exit)) = self.loop_stack.last() {
+            self.jump_to(exit);
+        }
+    }
+
+    fn lower_continue(&mut self) {
+        if let Some(&(header, _)) = self.loop_stack.last() {
+            self.jump_to(header);
+        }
+    }
+
+    fn lower_if(&mut self, if_stmt: &ast::IfStatement) {
+        let condition = self.lower_expression(&if_stmt.condition);
+        let then_id = self.new_block();
+        let else_id = if_stmt.else_body.as_ref().map(|_| self.new_block());
+        let merge_id = self.new_block();
+        let false_target = else_id.unwrap_or(merge_id);
+
+        self.push(IRInstruction::Branch {
+            condition,
+            true_block: then_id,
+            false_block: false_target,
+        });
+        self.set_successors(self.current, vec![then_id, false_target]);
+
+        self.current = then_id;
+        for stmt in &if_stmt.then_body {
+            self.lower_statement(stmt);
+        }
+        self.jump_to(merge_id);
+
+        if let (Some(else_id), Some(else_body)) = (else_id, &if_stmt.else_body) {
+            self.current = else_id;
+            for stmt in else_body {
+                self.lower_statement(stmt);
+            }
+            self.jump_to(merge_id);
+        }
+
+        self.current = merge_id;
+    }
+
+    /// Lowers a `try`/`except`/`finally` the same way `lower_if` lowers an
+    /// `if`/`else`: `try_block`/`except_block` are separate CFG blocks off a
+    /// `TryExcept` terminator, and both jump into a shared `finally_block`
+    /// (falling straight through to `merge_id` when there's no `finally`) so
+    /// `IRCodegen::find_merge` discovers it as the reconvergence point the
+    /// same way it would an `if`/`else`'s merge block, with no extra
+    /// machinery needed for "run this whether or not it panicked".
+    ///
+    /// Only the *first* `except` clause is lowered - Rust's `catch_unwind`
+    /// gives no structured exception type to dispatch further handlers on
+    /// with the plain panic-based codegen this compiler generates, so
+    /// picking between `except ValueError` and `except IndexError` isn't
+    /// something this pass can do soundly yet.
+    fn lower_try(&mut self, try_stmt: &ast::TryStatement) {
+        let try_id = self.new_block();
+        let except_id = self.new_block();
+        let merge_id = self.new_block();
+        let finally_id = (!try_stmt.finalbody.is_empty()).then(|| self.new_block());
+        let after_body = finally_id.unwrap_or(merge_id);
+
+        let error_binding = try_stmt.handlers.first().and_then(|h| h.name.clone());
+        self.push(IRInstruction::TryExcept {
+            try_block: try_id,
+            except_block: except_id,
+            error_binding,
+        });
+        self.set_successors(self.current, vec![try_id, except_id]);
+
+        self.current = try_id;
+        for stmt in &try_stmt.body {
+            self.lower_statement(stmt);
+        }
+        self.jump_to(after_body);
+
+        self.current = except_id;
+        if let Some(handler) = try_stmt.handlers.first() {
+            for stmt in &handler.body {
+                self.lower_statement(stmt);
+            }
+        }
+        self.jump_to(after_body);
+
+        if let Some(finally_id) = finally_id {
+            self.current = finally_id;
+            for stmt in &try_stmt.finalbody {
+                self.lower_statement(stmt);
+            }
+            self.jump_to(merge_id);
+        }
+
+        self.current = merge_id;
+    }
+
+    fn lower_while(&mut self, while_loop: &ast::WhileLoop) {
+        let header_id = self.new_block();
+        let body_id = self.new_block();
+        let exit_id = self.new_block();
+
+        self.jump_to(header_id);
+
+        self.current = header_id;
+        let condition = self.lower_expression(&while_loop.condition);
+        self.push(IRInstruction::Branch {
+            condition,
+            true_block: body_id,
+            false_block: exit_id,
+        });
+        self.set_successors(header_id, vec![body_id, exit_id]);
+
+        self.loop_stack.push((header_id, exit_id));
+        self.current = body_id;
+        for stmt in &while_loop.body {
+            self.lower_statement(stmt);
+        }
+        self.jump_to(header_id);
+        self.loop_stack.pop();
+
+        self.current = exit_id;
+    }
+
+    fn lower_for(&mut self, for_loop: &ast::ForLoop) {
+        if let ast::Expression::Call(callee, args) = &for_loop.iter {
+            if matches!(callee.as_ref(), ast::Expression::Identifier(n) if n == "range") {
+                self.lower_for_range(for_loop, args);
+                return;
+            }
+        }
+        self.lower_for_generic(for_loop);
+    }
+
+    /// `for i in range(...)`: a counted loop whose preheader initializes the
+    /// iterator and records a `LoopStart` hint, so `IROptimizer::analyze_loop`
+    /// can recover the induction variable's start and stride the same way it
+    /// would for a hand-written counting `while` loop.
+    fn lower_for_range(&mut self, for_loop: &ast::ForLoop, args: &[ast::Expression]) {
+        let start = if args.len() >= 2 {
+            self.lower_expression(&args[0])
+        } else {
+            IRValue::Const(IRConstant::Int(0))
+        };
+        let end = self.lower_expression(&args[args.len() - 1]);
+
+        self.push(IRInstruction::Store {
+            target: for_loop.target.clone(),
+            value: start,
+        });
+
+        let header_id = self.new_block();
+        let body_id = self.new_block();
+        let exit_id = self.new_block();
+
+        self.push(IRInstruction::LoopStart {
+            iterator: IRValue::Local(for_loop.target.clone()),
+            body_block: body_id,
+            exit_block: exit_id,
+            metadata: LoopMetadata::new(),
+        });
+        self.jump_to(header_id);
+
+        self.current = header_id;
+        let condition = self.fresh_temp();
+        self.push(IRInstruction::BinOp {
+            result: condition.clone(),
+            op: BinOpIR::Lt,
+            left: IRValue::Local(for_loop.target.clone()),
+            right: end,
+        });
+        self.push(IRInstruction::Branch {
+            condition,
+            true_block: body_id,
+            false_block: exit_id,
+        });
+        self.set_successors(header_id, vec![body_id, exit_id]);
+
+        self.loop_stack.push((header_id, exit_id));
+        self.current = body_id;
+        for stmt in &for_loop.body {
+            self.lower_statement(stmt);
+        }
+        if !self.terminated() {
+            self.push(IRInstruction::BinOp {
+                result: IRValue::Local(for_loop.target.clone()),
+                op: BinOpIR::Add,
+                left: IRValue::Local(for_loop.target.clone()),
+                right: IRValue::Const(IRConstant::Int(1)),
+            });
+            self.push(IRInstruction::LoopEnd);
+        }
+        self.jump_to(header_id);
+        self.loop_stack.pop();
+
+        self.current = exit_id;
+    }
+
+    /// `for x in <iterable>` over anything other than `range(...)`, desugared
+    /// into an index-counted `while` since the IR has no generic iterator
+    /// instruction yet - `x = <iterable>[i]` each pass, `i` counted against
+    /// `len(<iterable>)`. Producing a real Rust `for x in iterable` here is
+    /// left to whatever eventually gives IR a proper collection type.
+    fn lower_for_generic(&mut self, for_loop: &ast::ForLoop) {
+        let iterable = self.lower_expression(&for_loop.iter);
+        let index_name = format!("__idx{}", self.next_block);
+        self.push(IRInstruction::Store {
+            target: index_name.clone(),
+            value: IRValue::Const(IRConstant::Int(0)),
+        });
+
+        let header_id = self.new_block();
+        let body_id = self.new_block();
+        let exit_id = self.new_block();
+
+        self.jump_to(header_id);
+
+        self.current = header_id;
+        let length = self.fresh_temp();
+        self.push(IRInstruction::Call {
+            result: length.clone(),
+            function: "len".to_string(),
+            args: vec![iterable.clone()],
+        });
+        let condition = self.fresh_temp();
+        self.push(IRInstruction::BinOp {
+            result: condition.clone(),
+            op: BinOpIR::Lt,
+            left: IRValue::Local(index_name.clone()),
+            right: length,
+        });
+        self.push(IRInstruction::Branch {
+            condition,
+            true_block: body_id,
+            false_block: exit_id,
+        });
+        self.set_successors(header_id, vec![body_id, exit_id]);
+
+        self.loop_stack.push((header_id, exit_id));
+        self.current = body_id;
+        let element = self.fresh_temp();
+        self.push(IRInstruction::Index {
+            result: element.clone(),
+            array: iterable,
+            index: IRValue::Local(index_name.clone()),
+        });
+        self.push(IRInstruction::Store {
+            target: for_loop.target.clone(),
+            value: element,
+        });
+        for stmt in &for_loop.body {
+            self.lower_statement(stmt);
+        }
+        if !self.terminated() {
+            self.push(IRInstruction::BinOp {
+                result: IRValue::Local(index_name.clone()),
+                op: BinOpIR::Add,
+                left: IRValue::Local(index_name),
+                right: IRValue::Const(IRConstant::Int(1)),
+            });
+        }
+        self.jump_to(header_id);
+        self.loop_stack.pop();
+
+        self.current = exit_id;
+    }
+
+    fn lower_expression(&mut self, expr: &ast::Expression) -> IRValue {
+        match expr {
+            ast::Expression::IntLit(n) => IRValue::Const(IRConstant::Int(*n)),
+            // No float constant exists in the IR yet (see `ir::IRConstant`);
+            // truncating here is a known, narrow gap until it does.
+            ast::Expression::FloatLit(f) => IRValue::Const(IRConstant::Int(*f as i64)),
+            ast::Expression::BoolLit(b) => IRValue::Const(IRConstant::Bool(*b)),
+            ast::Expression::StringLit(s) => IRValue::Const(IRConstant::String(s.clone())),
+            ast::Expression::Identifier(name) => IRValue::Local(name.clone()),
+            // Only `lower_print` looks for `sep=`/`end=` specifically -
+            // anywhere else a kwarg's name is meaningless to this compiler,
+            // so just lower the value it wraps.
+            ast::Expression::Kwarg(_, value) => self.lower_expression(value),
+            ast::Expression::UnaryOp(ast::UnaryOp::Pos, operand) => self.lower_expression(operand),
+            ast::Expression::UnaryOp(op, operand) => {
+                let value = self.lower_expression(operand);
+                let result = self.fresh_temp();
+                self.push(IRInstruction::UnaryOp {
+                    result: result.clone(),
+                    op: Self::lower_unaryop(*op),
+                    operand: value,
+                });
+                result
+            }
+            // `in`/`not in` lower to `list.contains(item)` rather than
+            // through `lower_binop`, since `BinOpIR` has no membership-test
+            // opcode - a `Vec::contains` call is the only place this needs
+            // to be expressed.
+            ast::Expression::BinOp(left, ast::BinOp::In, right)
+            | ast::Expression::BinOp(left, ast::BinOp::NotIn, right) => {
+                let item = self.lower_expression(left);
+                let list = self.lower_expression(right);
+                let contains = self.fresh_temp();
+                self.push(IRInstruction::MethodCall {
+                    result: contains.clone(),
+                    receiver: list,
+                    method: "contains".to_string(),
+                    args: vec![item],
+                });
+                if matches!(expr, ast::Expression::BinOp(_, ast::BinOp::NotIn, _)) {
+                    let result = self.fresh_temp();
+                    self.push(IRInstruction::UnaryOp {
+                        result: result.clone(),
+                        op: UnaryOpIR::Not,
+                        operand: contains,
+                    });
+                    result
+                } else {
+                    contains
+                }
+            }
+            // `+`/`*` need different Rust when a string is involved: `String`
+            // doesn't implement the `Add`/`Mul` that plain infix `l op r`
+            // assumes, and Python's `s * n` repeats a string rather than
+            // multiplying two numbers. Only `<string> * <int>` is recognized
+            // here (not the reversed `<int> * <string>`), matching how this
+            // is written in practice - a known, narrow gap like the others
+            // in this function.
+            ast::Expression::BinOp(left, ast::BinOp::Add, right)
+                if self.is_string_expr(left) || self.is_string_expr(right) =>
+            {
+                let left = self.lower_expression(left);
+                let right = self.lower_expression(right);
+                let result = self.fresh_temp();
+                self.push(IRInstruction::BinOp {
+                    result: result.clone(),
+                    op: BinOpIR::StrConcat,
+                    left,
+                    right,
+                });
+                result
+            }
+            ast::Expression::BinOp(left, ast::BinOp::Mult, right) if self.is_string_expr(left) => {
+                let left = self.lower_expression(left);
+                let right = self.lower_expression(right);
+                let result = self.fresh_temp();
+                self.push(IRInstruction::BinOp {
+                    result: result.clone(),
+                    op: BinOpIR::StrRepeat,
+                    left,
+                    right,
+                });
+                result
+            }
+            ast::Expression::BinOp(left, op, right) => {
+                let left = self.lower_expression(left);
+                let right = self.lower_expression(right);
+                let result = self.fresh_temp();
+                self.push(IRInstruction::BinOp {
+                    result: result.clone(),
+                    op: Self::lower_binop(*op),
+                    left,
+                    right,
+                });
+                result
+            }
+            // `math.sqrt(x)`, `random.randint(a, b)`, `time.time()`, ... -
+            // these modules have no real value behind their name (unlike an
+            // actual object receiver), so a call into one is mapped straight
+            // through by its dotted name (see `IRCodegen::emit_call`)
+            // instead of becoming a `MethodCall` on a nonexistent `math`/
+            // `random`/`time` local.
+            ast::Expression::Call(callee, args)
+                if matches!(callee.as_ref(), ast::Expression::Attribute(receiver, _)
+                    if matches!(receiver.as_ref(), ast::Expression::Identifier(name) if Self::is_stdlib_module(name))) =>
+            {
+                let ast::Expression::Attribute(receiver, method) = callee.as_ref() else {
+                    unreachable!()
+                };
+                let ast::Expression::Identifier(module) = receiver.as_ref() else {
+                    unreachable!()
+                };
+                let function = format!("{module}.{method}");
+                let args = args.iter().map(|a| self.lower_expression(a)).collect();
+                let result = self.fresh_temp();
+                self.push(IRInstruction::Call {
+                    result: result.clone(),
+                    function,
+                    args,
+                });
+                result
+            }
+            // `obj.method(args)`: the receiver is itself an operand, unlike
+            // a free function call, so it gets its own instruction rather
+            // than being folded into `Call`'s `args`.
+            ast::Expression::Call(callee, args) if matches!(callee.as_ref(), ast::Expression::Attribute(..)) => {
+                let ast::Expression::Attribute(receiver, method) = callee.as_ref() else {
+                    unreachable!()
+                };
+                let receiver = self.lower_expression(receiver);
+                let args = args.iter().map(|a| self.lower_expression(a)).collect();
+                let result = self.fresh_temp();
+                self.push(IRInstruction::MethodCall {
+                    result: result.clone(),
+                    receiver,
+                    method: method.clone(),
+                    args,
+                });
+                result
+            }
+            // `print(...)` gets CPython-matching formatting (see
+            // `lower_print`) instead of becoming a generic `Call` to a
+            // free function literally named `print`, which doesn't exist
+            // in generated Rust.
+            ast::Expression::Call(callee, args)
+                if matches!(callee.as_ref(), ast::Expression::Identifier(name) if name == "print") =>
+            {
+                self.lower_print(args)
+            }
+            ast::Expression::Call(callee, args) => {
+                let function = match callee.as_ref() {
+                    // `Point(3, 4)` constructs a `Point`, not a call to a
+                    // free function named `Point` - `IRCodegen`'s call
+                    // codegen needs no further help once the callee reads
+                    // `Point::new`, since that's already valid Rust for an
+                    // associated function.
+                    ast::Expression::Identifier(name) if self.known_classes.contains(name) => {
+                        format!("{}::new", name)
+                    }
+                    ast::Expression::Identifier(name) => name.clone(),
+                    _ => "unknown".to_string(),
+                };
+                // A call to a known free function has its arguments
+                // reordered and defaulted against the real parameter list
+                // (see `lower_call_args`), so a keyword-reordered or
+                // default-omitting call still passes the right value in
+                // the right slot; anything else (builtins, constructors,
+                // an unresolved forward reference) keeps the simple
+                // positional-with-kwargs-dropped behavior it always had.
+                let params = match callee.as_ref() {
+                    ast::Expression::Identifier(name) => self.signatures.get(name).cloned(),
+                    _ => None,
+                };
+                let args = match params {
+                    Some(params) => self.lower_call_args(&params, args),
+                    None => args.iter().map(|a| self.lower_expression(a)).collect(),
+                };
+                let result = self.fresh_temp();
+                self.push(IRInstruction::Call {
+                    result: result.clone(),
+                    function,
+                    args,
+                });
+                result
+            }
+            // `String` has no `Index<usize>` impl, so indexing a string needs
+            // its own instruction (character access via `chars().nth(..)`)
+            // rather than the generic array `Index` below - see
+            // `IRCodegen::emit_method_call`'s `char_at` arm. Slicing by a
+            // range (`s[a:b]`) isn't parsed yet, so only single-character
+            // access is covered here.
+            ast::Expression::Index(array, index) if self.is_string_expr(array) => {
+                let receiver = self.lower_expression(array);
+                let index = self.lower_expression(index);
+                let result = self.fresh_temp();
+                self.push(IRInstruction::MethodCall {
+                    result: result.clone(),
+                    receiver,
+                    method: "char_at".to_string(),
+                    args: vec![index],
+                });
+                result
+            }
+            ast::Expression::Index(array, index) => {
+                let array = self.lower_expression(array);
+                let index = self.lower_expression(index);
+                let result = self.fresh_temp();
+                self.push(IRInstruction::Index {
+                    result: result.clone(),
+                    array,
+                    index,
+                });
+                result
+            }
+            // A list literal's size is always known up front, so the `Vec`
+            // is allocated with exactly that capacity and filled by
+            // `append`ing each element rather than reallocating as it
+            // grows.
+            ast::Expression::List(items) => {
+                let result = self.fresh_temp();
+                self.push(IRInstruction::NewList {
+                    result: result.clone(),
+                    capacity: Some(IRValue::Const(IRConstant::Int(items.len() as i64))),
+                });
+                for item in items {
+                    let value = self.lower_expression(item);
+                    let discard = self.fresh_temp();
+                    self.push(IRInstruction::MethodCall {
+                        result: discard,
+                        receiver: result.clone(),
+                        method: "append".to_string(),
+                        args: vec![value],
+                    });
+                }
+                result
+            }
+            // f-string: each interpolation is lowered on its own, and its
+            // (still Python-flavored) format spec is carried through
+            // unchanged - `IRCodegen` is what translates it into a Rust
+            // spec, since that's also where the literal `format!` string
+            // gets assembled.
+            ast::Expression::FString(fparts) => {
+                let parts = fparts
+                    .iter()
+                    .map(|part| match part {
+                        ast::FStringPart::Literal(text) => FormatPart::Literal(text.clone()),
+                        ast::FStringPart::Expr(expr, spec) => {
+                            FormatPart::Value(self.lower_expression(expr), spec.clone())
+                        }
+                    })
+                    .collect();
+                let result = self.fresh_temp();
+                self.push(IRInstruction::FormatString {
+                    result: result.clone(),
+                    parts,
+                });
+                result
+            }
+            // Tuples and everything else below have no IR value
+            // representation yet; side effects in their sub-expressions are
+            // still lowered (and so still run), but the expression itself
+            // becomes `Null` (Rust `Default::default()`).
+            ast::Expression::Tuple(items) => {
+                for item in items {
+                    self.lower_expression(item);
+                }
+                IRValue::Const(IRConstant::Null)
+            }
+            _ => IRValue::Const(IRConstant::Null),
+        }
+    }
+
+    /// Builds a call's final, fully-ordered argument list against a known
+    /// callee's real parameters: each parameter takes the next unconsumed
+    /// positional argument, falling back to a same-named `Kwarg` among
+    /// `call_args`, then the parameter's own default expression, then
+    /// `default_value` as a last resort for a call missing that argument
+    /// entirely. Generated Rust calls stay purely positional, so this is
+    /// what makes a keyword-reordered or default-omitting Python call
+    /// compile without the caller ever seeing a mismatch.
+    fn lower_call_args(
+        &mut self,
+        params: &[ast::Parameter],
+        call_args: &[ast::Expression],
+    ) -> Vec<IRValue> {
+        let mut positional = call_args
+            .iter()
+            .filter(|a| !matches!(a, ast::Expression::Kwarg(..)));
+        params
+            .iter()
+            .map(|param| {
+                if let Some(arg) = positional.next() {
+                    return self.lower_expression(arg);
+                }
+                let kwarg = call_args.iter().find_map(|a| match a {
+                    ast::Expression::Kwarg(name, value) if name == &param.name => {
+                        Some(value.as_ref())
+                    }
+                    _ => None,
+                });
+                match kwarg.or(param.default.as_ref()) {
+                    Some(value) => self.lower_expression(value),
+                    None => Self::default_value(&param.typ),
+                }
+            })
+            .collect()
+    }
+
+    /// Lowers a `print(...)` call into a dedicated `Print` instruction (see
+    /// `ir::IRInstruction::Print`) instead of a `Call` to a free function
+    /// named `print`, which doesn't exist in generated Rust. `sep`/`end`
+    /// are read off `Kwarg` arguments when given as string literals - a
+    /// dynamic `sep`/`end` (e.g. a variable) is a known, narrow gap and
+    /// falls back to the Python defaults, still lowering the value for its
+    /// side effects.
+    fn lower_print(&mut self, args: &[ast::Expression]) -> IRValue {
+        let mut sep = " ".to_string();
+        let mut end = "\n".to_string();
+        let mut positional = Vec::new();
+
+        for arg in args {
+            match arg {
+                ast::Expression::Kwarg(name, value) if name == "sep" => {
+                    if let ast::Expression::StringLit(s) = value.as_ref() {
+                        sep = s.clone();
+                    } else {
+                        self.lower_expression(value);
+                    }
+                }
+                ast::Expression::Kwarg(name, value) if name == "end" => {
+                    if let ast::Expression::StringLit(s) = value.as_ref() {
+                        end = s.clone();
+                    } else {
+                        self.lower_expression(value);
+                    }
+                }
+                ast::Expression::Kwarg(_, value) => {
+                    self.lower_expression(value);
+                }
+                _ => {
+                    let kind = Self::print_arg_kind(arg);
+                    let value = self.lower_expression(arg);
+                    positional.push((value, kind));
+                }
+            }
+        }
+
+        self.push(IRInstruction::Print {
+            args: positional,
+            sep,
+            end,
+        });
+        IRValue::Const(IRConstant::Null)
+    }
+
+    /// How a `print` argument should be rendered, decided structurally from
+    /// its own AST shape - see `ir::PrintArgKind`.
+    fn print_arg_kind(expr: &ast::Expression) -> PrintArgKind {
+        match expr {
+            ast::Expression::BoolLit(_) => PrintArgKind::Bool,
+            ast::Expression::List(_) => PrintArgKind::List,
+            _ => PrintArgKind::Plain,
+        }
+    }
+
+    /// A cheap, syntactic guess at whether `expr` produces a `String`,
+    /// backed by `self.locals` for identifiers. Not sound (it can't see
+    /// through function calls, parameters aren't re-checked past their
+    /// declared type, etc.) but covers the common shapes well enough to
+    /// choose string-flavored codegen for `+`, `*`, and indexing.
+    /// Whether `name` is one of the handful of stdlib modules `IRCodegen`
+    /// maps by dotted call name (see `IRCodegen::emit_call`) rather than
+    /// treating as a real receiver value.
+    fn is_stdlib_module(name: &str) -> bool {
+        matches!(name, "math" | "random" | "time")
+    }
+
+    fn is_string_expr(&self, expr: &ast::Expression) -> bool {
+        match expr {
+            ast::Expression::StringLit(_) | ast::Expression::FString(_) => true,
+            ast::Expression::Identifier(name) => matches!(self.locals.get(name), Some(Type::String)),
+            ast::Expression::BinOp(left, ast::BinOp::Add, right) => {
+                self.is_string_expr(left) || self.is_string_expr(right)
+            }
+            ast::Expression::Call(callee, _) => matches!(
+                callee.as_ref(),
+                ast::Expression::Attribute(_, method)
+                    if matches!(method.as_str(), "upper" | "lower" | "strip" | "join")
+            ),
+            _ => false,
+        }
+    }
+
+    fn lower_binop(op: ast::BinOp) -> BinOpIR {
+        match op {
+            ast::BinOp::Add => BinOpIR::Add,
+            ast::BinOp::Sub => BinOpIR::Sub,
+            ast::BinOp::Mult => BinOpIR::Mul,
+            ast::BinOp::Div => BinOpIR::Div,
+            ast::BinOp::FloorDiv => BinOpIR::FloorDiv,
+            ast::BinOp::Mod => BinOpIR::Mod,
+            ast::BinOp::Pow => BinOpIR::Pow,
+            ast::BinOp::LShift => BinOpIR::LShift,
+            ast::BinOp::RShift => BinOpIR::RShift,
+            ast::BinOp::BitOr => BinOpIR::BitOr,
+            ast::BinOp::BitXor => BinOpIR::BitXor,
+            ast::BinOp::BitAnd => BinOpIR::BitAnd,
+            ast::BinOp::Eq => BinOpIR::Eq,
+            ast::BinOp::NotEq => BinOpIR::NotEq,
+            ast::BinOp::Lt => BinOpIR::Lt,
+            ast::BinOp::LtE => BinOpIR::LtE,
+            ast::BinOp::Gt => BinOpIR::Gt,
+            ast::BinOp::GtE => BinOpIR::GtE,
+            // The parser doesn't produce `and`/`or`/`is` yet, but the match
+            // must stay exhaustive - approximate with the closest IR opcode
+            // rather than panicking if it ever does. `In`/`NotIn` are
+            // handled earlier in `lower_expression` (as `list.contains`)
+            // and never reach here; kept here only for exhaustiveness.
+            ast::BinOp::Is => BinOpIR::Eq,
+            ast::BinOp::IsNot => BinOpIR::NotEq,
+            ast::BinOp::In => BinOpIR::Eq,
+            ast::BinOp::NotIn => BinOpIR::NotEq,
+            ast::BinOp::And => BinOpIR::BitAnd,
+            ast::BinOp::Or => BinOpIR::BitOr,
+        }
+    }
+
+    fn lower_unaryop(op: ast::UnaryOp) -> UnaryOpIR {
+        match op {
+            ast::UnaryOp::Neg => UnaryOpIR::Neg,
+            ast::UnaryOp::Not => UnaryOpIR::Not,
+            ast::UnaryOp::Invert => UnaryOpIR::BitNot,
+            // Handled before reaching here (`lower_expression` special-cases
+            // `Pos` as the identity function).
+            ast::UnaryOp::Pos => UnaryOpIR::Neg,
+        }
+    }
+}
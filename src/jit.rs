@@ -0,0 +1,343 @@
+/// In-process Cranelift JIT backend
+/// An alternative to `Compiler::compile`'s cargo path: lowers a single
+/// simple, all-integer `IRFunction` straight to machine code in this
+/// process and calls it directly, skipping `rustc`/cargo entirely for the
+/// sub-second turnaround `run --jit` needs. Only a narrow subset of the IR
+/// is supported (integer/bool arithmetic, comparisons, and structured
+/// control flow) - anything wider (calls, strings, lists, floats) reports
+/// `Err` so the caller can fall back to `Compiler::compile`'s full cargo
+/// build, which handles all of it.
+use crate::ast_types::Type;
+use crate::ir::{BinOpIR, IRConstant, IRFunction, IRInstruction, IRModule, IRValue, UnaryOpIR};
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{types, AbiParam, Block as ClifBlock, InstBuilder, Value};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_codegen::Context;
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{Linkage, Module};
+use std::collections::HashMap;
+
+/// Every local/temporary in a jitted function is a plain `i64` Cranelift
+/// `Variable` - there's no need for a richer value representation since
+/// unsupported types (strings, lists, floats) bail out of the whole
+/// compilation before any code is emitted.
+pub struct JitBackend {
+    module: JITModule,
+    ctx: Context,
+    builder_ctx: FunctionBuilderContext,
+}
+
+/// Entry point for `adrenaline run --jit`: JIT-compiles and executes the
+/// program's synthesized `main` function (see `IRLowering::lower_program`)
+/// directly, with no cargo build step. Only succeeds for scripts whose
+/// entire body falls inside this backend's narrow integer/bool subset -
+/// anything using `print`, strings, lists, or calls to other functions
+/// reports `Err` so the caller falls back to `Compiler::compile`.
+pub fn try_run(module: &IRModule) -> Result<i64, String> {
+    let function = module
+        .get_function("main")
+        .ok_or_else(|| "no `main` entry point to JIT".to_string())?;
+    let mut backend = JitBackend::new()?;
+    backend.run(function, &[])
+}
+
+impl JitBackend {
+    pub fn new() -> Result<Self, String> {
+        let mut flag_builder = settings::builder();
+        flag_builder.set("use_colocated_libcalls", "false").map_err(|e| e.to_string())?;
+        flag_builder.set("is_pic", "false").map_err(|e| e.to_string())?;
+        let isa_builder = cranelift_native::builder().map_err(|e| e.to_string())?;
+        let isa = isa_builder
+            .finish(settings::Flags::new(flag_builder))
+            .map_err(|e| e.to_string())?;
+        let jit_builder = JITBuilder::with_isa(isa, cranelift_module::default_libcall_names());
+        let module = JITModule::new(jit_builder);
+        let ctx = module.make_context();
+        Ok(Self {
+            module,
+            ctx,
+            builder_ctx: FunctionBuilderContext::new(),
+        })
+    }
+
+    /// Whether `function` is within the subset this backend can compile -
+    /// all-integer/bool params and return, and only the instructions
+    /// `lower_instruction` below knows how to translate.
+    fn is_jittable(function: &IRFunction) -> bool {
+        let scalar = |t: &Type| matches!(t, Type::Int | Type::Bool);
+        function.owner.is_none()
+            && function.params.iter().all(|p| scalar(&p.typ))
+            && (scalar(&function.return_type) || function.return_type == Type::NoneType)
+            && function.blocks.iter().flat_map(|b| &b.instructions).all(|i| {
+                matches!(
+                    i,
+                    IRInstruction::BinOp { op, .. } if !matches!(
+                        op,
+                        BinOpIR::Div
+                            | BinOpIR::FloorDiv
+                            | BinOpIR::Mod
+                            | BinOpIR::Pow
+                            | BinOpIR::StrConcat
+                            | BinOpIR::StrRepeat
+                    )
+                ) || matches!(
+                    i,
+                    IRInstruction::UnaryOp { .. }
+                        | IRInstruction::Assign { .. }
+                        | IRInstruction::Load { .. }
+                        | IRInstruction::Store { .. }
+                        | IRInstruction::Branch { .. }
+                        | IRInstruction::Jump { .. }
+                        | IRInstruction::Return { .. }
+                        | IRInstruction::Pure
+                        | IRInstruction::CanElideCheck
+                )
+            })
+    }
+
+    /// Compiles `function` and calls it with `args`, returning its `i64`
+    /// result (a `bool` return is `0`/`1`). Fails fast with a description of
+    /// what's unsupported rather than emitting incorrect code - the caller
+    /// is expected to fall back to `Compiler::compile` in that case.
+    pub fn run(&mut self, function: &IRFunction, args: &[i64]) -> Result<i64, String> {
+        if !Self::is_jittable(function) {
+            return Err(format!(
+                "function `{}` uses a feature this JIT backend doesn't support yet",
+                function.name
+            ));
+        }
+        if args.len() != function.params.len() {
+            return Err(format!(
+                "`{}` expects {} argument(s), got {}",
+                function.name,
+                function.params.len(),
+                args.len()
+            ));
+        }
+
+        let func_id = self.compile(function)?;
+        self.module.finalize_definitions().map_err(|e| e.to_string())?;
+        let code_ptr = self.module.get_finalized_function(func_id);
+
+        // Cranelift doesn't know the argument count at the FFI boundary -
+        // only a handful of arities are supported, matching what this
+        // backend is realistically used for (small helper functions, not
+        // wide APIs).
+        let result = unsafe {
+            match args.len() {
+                0 => {
+                    let f: extern "C" fn() -> i64 = std::mem::transmute(code_ptr);
+                    f()
+                }
+                1 => {
+                    let f: extern "C" fn(i64) -> i64 = std::mem::transmute(code_ptr);
+                    f(args[0])
+                }
+                2 => {
+                    let f: extern "C" fn(i64, i64) -> i64 = std::mem::transmute(code_ptr);
+                    f(args[0], args[1])
+                }
+                3 => {
+                    let f: extern "C" fn(i64, i64, i64) -> i64 = std::mem::transmute(code_ptr);
+                    f(args[0], args[1], args[2])
+                }
+                4 => {
+                    let f: extern "C" fn(i64, i64, i64, i64) -> i64 = std::mem::transmute(code_ptr);
+                    f(args[0], args[1], args[2], args[3])
+                }
+                n => return Err(format!("JIT backend supports at most 4 arguments, got {n}")),
+            }
+        };
+        Ok(result)
+    }
+
+    fn compile(&mut self, function: &IRFunction) -> Result<cranelift_module::FuncId, String> {
+        self.module.clear_context(&mut self.ctx);
+
+        for _ in &function.params {
+            self.ctx.func.signature.params.push(AbiParam::new(types::I64));
+        }
+        self.ctx.func.signature.returns.push(AbiParam::new(types::I64));
+
+        let func_id = self
+            .module
+            .declare_function(&function.name, Linkage::Export, &self.ctx.func.signature)
+            .map_err(|e| e.to_string())?;
+
+        let target_config = self.module.target_config();
+        {
+            let mut builder = FunctionBuilder::new(&mut self.ctx.func, &mut self.builder_ctx);
+            let mut vars = HashMap::new();
+            let mut clif_blocks = HashMap::new();
+
+            for block in &function.blocks {
+                clif_blocks.insert(block.id, builder.create_block());
+            }
+
+            let entry = clif_blocks[&function.blocks[0].id];
+            builder.append_block_params_for_function_params(entry);
+            builder.switch_to_block(entry);
+            for (i, param) in function.params.iter().enumerate() {
+                let var = builder.declare_var(types::I64);
+                let value = builder.block_params(entry)[i];
+                builder.def_var(var, value);
+                vars.insert(param.name.clone(), var);
+            }
+
+            for block in &function.blocks {
+                let clif_block = clif_blocks[&block.id];
+                if clif_block != entry {
+                    builder.switch_to_block(clif_block);
+                }
+                for instr in &block.instructions {
+                    Self::lower_instruction(&mut builder, instr, &mut vars, &clif_blocks)?;
+                }
+                builder.seal_block(clif_block);
+            }
+
+            builder.finalize(target_config);
+        }
+
+        self.module
+            .define_function(func_id, &mut self.ctx)
+            .map_err(|e| e.to_string())?;
+        Ok(func_id)
+    }
+
+    /// Looks up (declaring on first use) the Cranelift `Variable` backing an
+    /// IR local/temporary - every jitted value lives in one, matching
+    /// `IRCodegen::assign`'s "declare once, reassign after" convention.
+    fn var_for(builder: &mut FunctionBuilder, vars: &mut HashMap<String, Variable>, key: &str) -> Variable {
+        match vars.get(key) {
+            Some(var) => *var,
+            None => {
+                let var = builder.declare_var(types::I64);
+                vars.insert(key.to_string(), var);
+                var
+            }
+        }
+    }
+
+    fn value_key(value: &IRValue) -> Option<String> {
+        match value {
+            IRValue::Local(name) => Some(name.clone()),
+            IRValue::Temporary(id) => Some(format!("__t{id}")),
+            IRValue::Const(_) => None,
+        }
+    }
+
+    fn read_value(builder: &mut FunctionBuilder, vars: &mut HashMap<String, Variable>, value: &IRValue) -> Value {
+        match value {
+            IRValue::Const(IRConstant::Int(n)) => builder.ins().iconst(types::I64, *n),
+            IRValue::Const(IRConstant::Bool(b)) => builder.ins().iconst(types::I64, *b as i64),
+            IRValue::Const(IRConstant::String(_)) | IRValue::Const(IRConstant::Null) => {
+                // Never reached: `is_jittable` rejects any function whose
+                // instructions could produce one of these as an operand.
+                builder.ins().iconst(types::I64, 0)
+            }
+            _ => {
+                let key = Self::value_key(value).unwrap();
+                let var = Self::var_for(builder, vars, &key);
+                builder.use_var(var)
+            }
+        }
+    }
+
+    fn lower_instruction(
+        builder: &mut FunctionBuilder,
+        instr: &IRInstruction,
+        vars: &mut HashMap<String, Variable>,
+        clif_blocks: &HashMap<usize, ClifBlock>,
+    ) -> Result<(), String> {
+        match instr {
+            IRInstruction::Assign { target, value } => {
+                let v = Self::read_value(builder, vars, value);
+                let key = Self::value_key(target).ok_or("cannot assign to a constant")?;
+                let var = Self::var_for(builder, vars, &key);
+                builder.def_var(var, v);
+            }
+            IRInstruction::BinOp { result, op, left, right } => {
+                let l = Self::read_value(builder, vars, left);
+                let r = Self::read_value(builder, vars, right);
+                let v = Self::binop(builder, *op, l, r)?;
+                let key = Self::value_key(result).ok_or("cannot assign to a constant")?;
+                let var = Self::var_for(builder, vars, &key);
+                builder.def_var(var, v);
+            }
+            IRInstruction::UnaryOp { result, op, operand } => {
+                let o = Self::read_value(builder, vars, operand);
+                let v = match op {
+                    UnaryOpIR::Neg => builder.ins().ineg(o),
+                    UnaryOpIR::BitNot => builder.ins().bnot(o),
+                    UnaryOpIR::Not => {
+                        let zero = builder.ins().iconst(types::I64, 0);
+                        let cmp = builder.ins().icmp(IntCC::Equal, o, zero);
+                        builder.ins().uextend(types::I64, cmp)
+                    }
+                };
+                let key = Self::value_key(result).ok_or("cannot assign to a constant")?;
+                let var = Self::var_for(builder, vars, &key);
+                builder.def_var(var, v);
+            }
+            IRInstruction::Load { result, source } => {
+                let var = Self::var_for(builder, vars, source);
+                let v = builder.use_var(var);
+                let key = Self::value_key(result).ok_or("cannot assign to a constant")?;
+                let dest = Self::var_for(builder, vars, &key);
+                builder.def_var(dest, v);
+            }
+            IRInstruction::Store { target, value } => {
+                let v = Self::read_value(builder, vars, value);
+                let var = Self::var_for(builder, vars, target);
+                builder.def_var(var, v);
+            }
+            IRInstruction::Branch { condition, true_block, false_block } => {
+                let cond = Self::read_value(builder, vars, condition);
+                builder
+                    .ins()
+                    .brif(cond, clif_blocks[true_block], &[], clif_blocks[false_block], &[]);
+            }
+            IRInstruction::Jump { target } => {
+                builder.ins().jump(clif_blocks[target], &[]);
+            }
+            IRInstruction::Return { value } => {
+                let v = match value {
+                    Some(v) => Self::read_value(builder, vars, v),
+                    None => builder.ins().iconst(types::I64, 0),
+                };
+                builder.ins().return_(&[v]);
+            }
+            IRInstruction::Pure | IRInstruction::CanElideCheck => {}
+            _ => return Err(format!("unsupported IR instruction: {instr:?}")),
+        }
+        Ok(())
+    }
+
+    fn binop(builder: &mut FunctionBuilder, op: BinOpIR, l: Value, r: Value) -> Result<Value, String> {
+        Ok(match op {
+            BinOpIR::Add => builder.ins().iadd(l, r),
+            BinOpIR::Sub => builder.ins().isub(l, r),
+            BinOpIR::Mul => builder.ins().imul(l, r),
+            BinOpIR::BitAnd => builder.ins().band(l, r),
+            BinOpIR::BitOr => builder.ins().bor(l, r),
+            BinOpIR::BitXor => builder.ins().bxor(l, r),
+            BinOpIR::LShift => builder.ins().ishl(l, r),
+            BinOpIR::RShift => builder.ins().sshr(l, r),
+            BinOpIR::Eq => Self::bool_to_i64(builder, IntCC::Equal, l, r),
+            BinOpIR::NotEq => Self::bool_to_i64(builder, IntCC::NotEqual, l, r),
+            BinOpIR::Lt => Self::bool_to_i64(builder, IntCC::SignedLessThan, l, r),
+            BinOpIR::LtE => Self::bool_to_i64(builder, IntCC::SignedLessThanOrEqual, l, r),
+            BinOpIR::Gt => Self::bool_to_i64(builder, IntCC::SignedGreaterThan, l, r),
+            BinOpIR::GtE => Self::bool_to_i64(builder, IntCC::SignedGreaterThanOrEqual, l, r),
+            BinOpIR::Div | BinOpIR::FloorDiv | BinOpIR::Mod | BinOpIR::Pow | BinOpIR::StrConcat | BinOpIR::StrRepeat => {
+                return Err(format!("unsupported binary op: {op:?}"))
+            }
+        })
+    }
+
+    fn bool_to_i64(builder: &mut FunctionBuilder, cc: IntCC, l: Value, r: Value) -> Value {
+        let cmp = builder.ins().icmp(cc, l, r);
+        builder.ins().uextend(types::I64, cmp)
+    }
+}
@@ -1,11 +1,15 @@
 mod ast_types;
 mod cache;
 mod cli;
-mod codegen;
 mod compiler;
+mod config;
 mod diagnostics;
 mod directives;
+mod interpreter;
 mod ir;
+mod ir_codegen;
+mod ir_lowering;
+mod jit;
 mod optimizer;
 mod parser;
 mod profiler;
@@ -1,16 +1,178 @@
 /// Optimizer module
 /// Applies aggressive optimizations: loop unrolling, constant folding, SIMD, etc.
+use crate::ast_types::Type;
 use crate::ir::*;
-use std::collections::HashMap;
+use crate::profiler::ProfileData;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Default ceiling on a hot function's own instruction count while its loop
+/// passes (fusion/interchange/tiling/unrolling) are still iterating to a
+/// fixed point - generous enough that ordinary hot loops never hit it, but
+/// low enough to catch the nested-loop-unrolling blowup that would
+/// otherwise make `rustc` take minutes on the result. See `optimize_function`.
+const DEFAULT_INSTRUCTION_BUDGET: usize = 50_000;
+
+/// Default wall-clock ceiling on the same fixed-point loop, alongside
+/// `DEFAULT_INSTRUCTION_BUDGET` - either one tripping backs the function off
+/// to `Basic` for the rest of this build.
+const DEFAULT_STAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A function called at least this many times in a saved profile is
+/// promoted to `Aggressive`; at `EXTREME_CALL_THRESHOLD` calls or more, to
+/// `Extreme`. These are arbitrary but generous, since a false-positive
+/// promotion only costs compile time, not correctness.
+const AGGRESSIVE_CALL_THRESHOLD: usize = 1_000;
+const EXTREME_CALL_THRESHOLD: usize = 100_000;
+
+/// Whether a pass ended up applying its transform, so a remark can explain
+/// what happened either way - not just why an optimization *did* fire, but
+/// why one that a directive asked for didn't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemarkKind {
+    Applied,
+    NotApplied,
+}
+
+/// A structured explanation of one optimizer decision, printable with
+/// `--remarks` as text or JSON so a `#adrenaline:simd` that had no effect
+/// isn't a silent no-op.
+#[derive(Debug, Clone, Serialize)]
+pub struct OptimizationRemark {
+    /// The function the decision was made in - `IRCodegen`'s provenance
+    /// comments (see `IRCodegen::set_provenance`) resolve this to a source
+    /// line via `IRFunction::source_line`, but remarks themselves are only
+    /// ever attributed at function granularity.
+    pub function: String,
+    pub kind: RemarkKind,
+    pub message: String,
+}
 
 pub struct IROptimizer {
     unroll_threshold: usize,
+    remarks: Vec<OptimizationRemark>,
+    /// Set by `--fast-math` (or, per-function, by `#adrenaline:fast-math`):
+    /// allows `fast_math_transform` to reassociate arithmetic and fuse
+    /// multiply-add chains into a single `Fma`, both of which change
+    /// rounding behavior and so are unsafe to apply by default.
+    fast_math: bool,
+    /// Set when compiling to a backend (like the C backend) that exports
+    /// every top-level function as a callable entry point instead of
+    /// running only through the synthesized `main` - see
+    /// `Compiler::compile_c`. Keeps `dead_function_elimination` from
+    /// pruning functions that are unreachable from `main` but are still
+    /// meant to be exported.
+    keep_all_functions: bool,
+    /// Ceiling on a function's own instruction count while its loop passes
+    /// iterate - see `optimize_function` and `DEFAULT_INSTRUCTION_BUDGET`.
+    instruction_budget: usize,
+    /// Wall-clock ceiling on the same loop - see `optimize_function` and
+    /// `DEFAULT_STAGE_TIMEOUT`.
+    stage_timeout: Duration,
+}
+
+/// Outcome of a loop's dependence test, used to decide whether
+/// `detect_parallel_opportunities` may mark it `Parallelizable`.
+enum ParallelSafety {
+    /// No two iterations touch the same memory; safe to run in any order.
+    Safe,
+    /// Safe once the write is treated as an associative reduction rather
+    /// than a plain shared write.
+    Reduction { target: IRValue, op: BinOpIR },
+    /// A loop-carried dependency was found; must stay sequential.
+    Unsafe,
 }
 
 impl IROptimizer {
     pub fn new() -> Self {
         Self {
             unroll_threshold: 8,
+            remarks: Vec::new(),
+            fast_math: false,
+            keep_all_functions: false,
+            instruction_budget: DEFAULT_INSTRUCTION_BUDGET,
+            stage_timeout: DEFAULT_STAGE_TIMEOUT,
+        }
+    }
+
+    /// Enables fast-math for every function, regardless of whether it carries
+    /// `#adrenaline:fast-math` - set from the `--fast-math` CLI flag.
+    pub fn enable_fast_math(&mut self) {
+        self.fast_math = true;
+    }
+
+    /// Overrides `DEFAULT_INSTRUCTION_BUDGET` - set from `--opt-budget`.
+    pub fn set_instruction_budget(&mut self, budget: usize) {
+        self.instruction_budget = budget;
+    }
+
+    /// Overrides `DEFAULT_STAGE_TIMEOUT` - set from `--opt-timeout`.
+    pub fn set_stage_timeout(&mut self, timeout: Duration) {
+        self.stage_timeout = timeout;
+    }
+
+    /// Keeps every top-level function reachable, even ones the synthesized
+    /// `main` never calls - for backends like C that export the whole
+    /// module as a library instead of running only through `main`.
+    pub fn keep_all_functions(&mut self) {
+        self.keep_all_functions = true;
+    }
+
+    /// Remarks collected across every pass since this optimizer was
+    /// created, in the order they were emitted.
+    pub fn remarks(&self) -> &[OptimizationRemark] {
+        &self.remarks
+    }
+
+    fn remark(&mut self, function: &str, kind: RemarkKind, message: impl Into<String>) {
+        self.remarks.push(OptimizationRemark {
+            function: function.to_string(),
+            kind,
+            message: message.into(),
+        });
+    }
+
+    /// Promotes functions to `Aggressive`/`Extreme` based on a saved
+    /// profiler report (see `Profiler::save_to_file`/`load_from_file`),
+    /// and records them in `IRModule.hot_functions` - so the next build of
+    /// the same program spends its optimization budget where the last run
+    /// actually spent its time, instead of everywhere uniformly.
+    pub fn apply_profile(&mut self, module: &mut IRModule, profile: &[ProfileData]) {
+        for data in profile {
+            let already = module
+                .get_function(&data.function)
+                .map(|f| f.optimization_level)
+                .unwrap_or(OptimizationLevel::None);
+            let Some(function) = module.get_function_mut(&data.function) else {
+                continue;
+            };
+
+            let promoted = if data.call_count >= EXTREME_CALL_THRESHOLD {
+                function.optimization_level = OptimizationLevel::Extreme;
+                already != OptimizationLevel::Extreme
+            } else if data.call_count >= AGGRESSIVE_CALL_THRESHOLD {
+                if function.optimization_level != OptimizationLevel::Extreme {
+                    function.optimization_level = OptimizationLevel::Aggressive;
+                }
+                already != OptimizationLevel::Aggressive && already != OptimizationLevel::Extreme
+            } else {
+                continue;
+            };
+
+            if promoted {
+                self.remark(
+                    &data.function,
+                    RemarkKind::Applied,
+                    format!(
+                        "promoted to {:?} ({} calls in profile)",
+                        function.optimization_level, data.call_count
+                    ),
+                );
+            }
+
+            module.mark_hot_function(&data.function);
         }
     }
 
@@ -18,15 +180,127 @@ impl IROptimizer {
         for function in &mut module.functions {
             self.optimize_function(function);
         }
+
+        // Inlining needs the call graph across the whole module, so it runs
+        // as a module-level pass after each function has been optimized on
+        // its own, then callers get one more local pass over the code that
+        // was just spliced in.
+        self.inline_functions(module);
+        for function in &mut module.functions {
+            self.optimize_function(function);
+        }
+
+        // Run last: inlining may have left a callee referenced only by the
+        // copy that got spliced into its caller, so this catches functions
+        // that only became dead once the rest of the pipeline ran.
+        self.dead_function_elimination(module);
+    }
+
+    /// Like `optimize`, but reuses `cache`'s per-function optimized-IR entry
+    /// (see `Cache::get_cached_function_ir`) for the first `optimize_function`
+    /// pass over each function, keyed on the function's own pre-optimization
+    /// IR - so a function that hasn't changed since the last compile skips
+    /// loop analysis, CSE, unrolling, and the rest of that pass entirely,
+    /// not just the codegen step `IRCodegen::generate_incremental` already
+    /// caches. Inlining, the second `optimize_function` pass it feeds (a
+    /// spliced-in callee's body just changed, cache key and all), and dead
+    /// function elimination all stay on the always-run path, since they
+    /// depend on the whole call graph rather than any one function alone.
+    pub fn optimize_incremental(&mut self, module: &mut IRModule, cache: &crate::cache::Cache) {
+        for function in &mut module.functions {
+            // Versioned so an optimizer change between adrenaline releases
+            // can't serve a previous release's cached IR for an unchanged
+            // function - see `IRCodegen::function_cache_key`, which does the
+            // same for the generated-Rust tier.
+            let pre_hash =
+                crate::cache::Cache::get_hash(&format!("{}|{:?}", env!("CARGO_PKG_VERSION"), function));
+            if let Ok(cached) = cache.get_cached_function_ir(&pre_hash) {
+                *function = cached;
+                continue;
+            }
+            self.optimize_function(function);
+            let _ = cache.cache_function_ir(&pre_hash, function);
+        }
+
+        self.inline_functions(module);
+        for function in &mut module.functions {
+            self.optimize_function(function);
+        }
+
+        self.dead_function_elimination(module);
+    }
+
+    /// Removes IR functions that can never be reached from `main`, so a
+    /// large utility module that only exercises a handful of its functions
+    /// doesn't carry the rest into the generated crate and binary.
+    fn dead_function_elimination(&self, module: &mut IRModule) {
+        if self.keep_all_functions {
+            return;
+        }
+        if module.get_function("main").is_none() {
+            return; // no entry point to trace reachability from
+        }
+
+        let mut reachable: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut worklist = vec!["main".to_string()];
+
+        while let Some(name) = worklist.pop() {
+            if !reachable.insert(name.clone()) {
+                continue;
+            }
+            let Some(function) = module.get_function(&name) else {
+                continue;
+            };
+            for block in &function.blocks {
+                for instr in &block.instructions {
+                    if let IRInstruction::Call {
+                        function: callee, ..
+                    } = instr
+                    {
+                        if !reachable.contains(callee) {
+                            worklist.push(callee.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        // Method reachability isn't traced above (only `Call`, never
+        // `MethodCall`, walks the worklist), and a constructor's own name
+        // (`new`) never matches the `ClassName::new` callee string used at
+        // its call sites - so every class method and constructor is kept
+        // unconditionally rather than risking deleting all of them the
+        // moment a class exists.
+        module
+            .functions
+            .retain(|f| f.owner.is_some() || reachable.contains(&f.name));
     }
 
     fn optimize_function(&mut self, function: &mut IRFunction) {
+        // Loop analysis feeds unrolling, vectorization, and parallelization,
+        // so it runs once up front and the result is shared by all of them.
+        self.analyze_loops(function);
+
+        // Turn self-recursive tail calls into a jump back to the function's
+        // entry block so deep recursion doesn't grow the call stack.
+        self.tail_call_optimization(function);
+
+        // Purity feeds memoization: only wrap a function in a cache if
+        // calling it twice with the same arguments is provably safe.
+        self.detect_purity(function);
+        self.memoize_pure_functions(function);
+
         // Apply multiple optimization passes
+        let stage_start = Instant::now();
         loop {
             let before_count = self.count_instructions(function);
 
+            self.copy_propagation(function);
             self.dead_code_elimination(function);
+            self.algebraic_simplification(function);
             self.constant_folding(function);
+            self.fast_math_transform(function);
+            self.branch_folding(function);
             self.bounds_check_elimination(function);
             self.common_subexpression_elimination(function);
 
@@ -34,8 +308,34 @@ impl IROptimizer {
             if function.optimization_level == OptimizationLevel::Aggressive
                 || function.optimization_level == OptimizationLevel::Extreme
             {
-                self.loop_unrolling(function);
-                self.inline_small_functions(function);
+                // Nested loops each fully unrolled inside the next compound
+                // multiplicatively, and a function that big is what makes
+                // `rustc` take minutes instead of seconds on it. Once this
+                // function's own instruction count or the time spent in
+                // this loop crosses its budget, it drops back to `Basic` so
+                // none of these passes run on it again for the rest of this
+                // build, instead of letting the fixed-point loop above keep
+                // growing it unbounded.
+                if before_count > self.instruction_budget || stage_start.elapsed() > self.stage_timeout {
+                    self.remark(
+                        &function.name.clone(),
+                        RemarkKind::NotApplied,
+                        format!(
+                            "backed off from {:?} to Basic: {before_count} instructions / {:.1}s into loop optimization (budget {}, timeout {:.1}s)",
+                            function.optimization_level,
+                            stage_start.elapsed().as_secs_f64(),
+                            self.instruction_budget,
+                            self.stage_timeout.as_secs_f64(),
+                        ),
+                    );
+                    function.optimization_level = OptimizationLevel::Basic;
+                } else {
+                    self.loop_fusion(function);
+                    self.loop_interchange(function);
+                    self.loop_tiling(function);
+                    self.loop_unrolling(function);
+                    self.strength_reduction(function);
+                }
             }
 
             let after_count = self.count_instructions(function);
@@ -49,18 +349,122 @@ impl IROptimizer {
         self.detect_parallel_opportunities(function);
     }
 
+    /// Removes stores to temporaries that liveness analysis shows are never
+    /// read anywhere in the function. Named locals are always kept, since
+    /// they may be observed outside the instructions we can see here.
     fn dead_code_elimination(&self, function: &mut IRFunction) {
+        let live = self.compute_live_values(function);
+
         for block in &mut function.blocks {
-            block.instructions.retain(|instr| match instr {
-                IRInstruction::Assign { target, .. } => {
-                    // Keep assignments to non-temporary values
-                    !matches!(target, IRValue::Temporary(_))
-                }
-                _ => true,
+            block.instructions.retain(|instr| {
+                let Some(result) = Self::dead_store_target(instr) else {
+                    return true;
+                };
+                !matches!(result, IRValue::Temporary(_)) || live.contains(result)
             });
         }
     }
 
+    /// The value an instruction defines, if it's a pure store that's safe to
+    /// drop when unused. Calls, memory writes, and control flow are excluded
+    /// even though some also carry a "result" value, since dropping them
+    /// would remove their side effects.
+    fn dead_store_target(instr: &IRInstruction) -> Option<&IRValue> {
+        match instr {
+            IRInstruction::BinOp { result, .. }
+            | IRInstruction::UnaryOp { result, .. }
+            | IRInstruction::Fma { result, .. }
+            | IRInstruction::Load { result, .. }
+            | IRInstruction::Index { result, .. } => Some(result),
+            IRInstruction::Assign { target, .. } => Some(target),
+            _ => None,
+        }
+    }
+
+    /// The set of values read as an operand anywhere in the function -
+    /// the "live" side of a whole-function liveness computation.
+    fn compute_live_values(&self, function: &IRFunction) -> std::collections::HashSet<IRValue> {
+        let mut live = std::collections::HashSet::new();
+        for block in &function.blocks {
+            for instr in &block.instructions {
+                self.visit_operands(instr, &mut |value| {
+                    live.insert(value.clone());
+                });
+            }
+        }
+        live
+    }
+
+    fn visit_operands(&self, instr: &IRInstruction, visit: &mut impl FnMut(&IRValue)) {
+        match instr {
+            IRInstruction::BinOp { left, right, .. } => {
+                visit(left);
+                visit(right);
+            }
+            IRInstruction::UnaryOp { operand, .. } => visit(operand),
+            IRInstruction::Fma { a, b, c, .. } => {
+                visit(a);
+                visit(b);
+                visit(c);
+            }
+            IRInstruction::Assign { value, .. } => visit(value),
+            IRInstruction::Store { value, .. } => visit(value),
+            IRInstruction::Index { array, index, .. } => {
+                visit(array);
+                visit(index);
+            }
+            IRInstruction::IndexStore { array, index, value } => {
+                visit(array);
+                visit(index);
+                visit(value);
+            }
+            IRInstruction::CacheLookup { keys, .. } => {
+                for key in keys {
+                    visit(key);
+                }
+            }
+            IRInstruction::CacheStore { keys, value, .. } => {
+                for key in keys {
+                    visit(key);
+                }
+                visit(value);
+            }
+            IRInstruction::Branch { condition, .. } => visit(condition),
+            IRInstruction::Return { value: Some(v) } => visit(v),
+            IRInstruction::Call { args, .. } => {
+                for arg in args {
+                    visit(arg);
+                }
+            }
+            IRInstruction::MethodCall { receiver, args, .. } => {
+                visit(receiver);
+                for arg in args {
+                    visit(arg);
+                }
+            }
+            IRInstruction::NewList { capacity: Some(c), .. } => visit(c),
+            IRInstruction::FormatString { parts, .. } => {
+                for part in parts {
+                    if let FormatPart::Value(v, _) = part {
+                        visit(v);
+                    }
+                }
+            }
+            IRInstruction::LoopStart { iterator, .. } => visit(iterator),
+            IRInstruction::NewStruct { fields, .. } => {
+                for (_, value) in fields {
+                    visit(value);
+                }
+            }
+            IRInstruction::Print { args, .. } => {
+                for (value, _) in args {
+                    visit(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
     fn constant_folding(&self, function: &mut IRFunction) {
         for block in &mut function.blocks {
             for instr in &mut block.instructions {
@@ -85,6 +489,34 @@ impl IROptimizer {
         }
     }
 
+    /// Python's `//` rounds toward negative infinity, unlike `div_euclid`
+    /// (which is Euclidean and only agrees with floor division for a
+    /// positive divisor - see `IRCodegen::binop_to_rust`, which has the same
+    /// split for the values this can't fold at compile time). `div_euclid`
+    /// undershoots by exactly one whenever the divisor is negative and the
+    /// division isn't exact, so that's the only case needing a correction.
+    fn floor_div(l: i64, r: i64) -> i64 {
+        let q = l.div_euclid(r);
+        if r < 0 && l.rem_euclid(r) != 0 {
+            q - 1
+        } else {
+            q
+        }
+    }
+
+    /// Companion to `floor_div`: Python's `%` takes the sign of the divisor,
+    /// while `rem_euclid` is always non-negative. Shifting the remainder by
+    /// the divisor in the same negative-divisor case `floor_div` corrects
+    /// for keeps the two in sync (`l == floor_div(l, r) * r + floor_mod(l, r)`).
+    fn floor_mod(l: i64, r: i64) -> i64 {
+        let rem = l.rem_euclid(r);
+        if r < 0 && rem != 0 {
+            rem + r
+        } else {
+            rem
+        }
+    }
+
     fn fold_constants(
         &self,
         left: IRConstant,
@@ -97,9 +529,12 @@ impl IROptimizer {
                     BinOpIR::Add => l.checked_add(*r)?,
                     BinOpIR::Sub => l.checked_sub(*r)?,
                     BinOpIR::Mul => l.checked_mul(*r)?,
-                    BinOpIR::Div if *r != 0 => Some(l / r)?,
-                    BinOpIR::FloorDiv if *r != 0 => Some(l / r)?,
-                    BinOpIR::Mod if *r != 0 => Some(l % r)?,
+                    // `Div` now compiles to true (float) division - see
+                    // `IRCodegen::binop_to_rust` - so it has no `IRConstant`
+                    // to fold into and is left for codegen to render.
+                    BinOpIR::Div => return None,
+                    BinOpIR::FloorDiv if *r != 0 => Self::floor_div(*l, *r),
+                    BinOpIR::Mod if *r != 0 => Self::floor_mod(*l, *r),
                     BinOpIR::Pow => {
                         if *r < 0 {
                             return None;
@@ -114,28 +549,12 @@ impl IROptimizer {
         }
     }
 
-    fn bounds_check_elimination(&self, function: &mut IRFunction) {
-        // Mark safe array accesses as CanElideCheck
-        for block in &mut function.blocks {
-            let mut to_add = Vec::new();
-            for instr in block.instructions.iter() {
-                if let IRInstruction::Index {
-                    array: _, index, ..
-                } = instr
-                {
-                    // Simple heuristic: if index is a constant, it might be safe
-                    if matches!(index, IRValue::Const(IRConstant::Int(_))) {
-                        to_add.push(IRInstruction::CanElideCheck);
-                    }
-                }
-            }
-            block.instructions.extend(to_add);
-        }
-    }
-
-    fn common_subexpression_elimination(&self, function: &mut IRFunction) {
-        let mut seen: HashMap<String, IRValue> = HashMap::new();
-
+    /// Folds algebraic identities (`x + 0`, `x * 1`, `x ** 1`, `x // 1`,
+    /// `x * 0`) into a plain `Assign`, so the constant-heavy boilerplate
+    /// that generic helper code translates into doesn't survive as real
+    /// arithmetic. `x * 0 -> 0` is only applied for integer constants,
+    /// since it wouldn't hold once a float operand could be `NaN`.
+    fn algebraic_simplification(&self, function: &mut IRFunction) {
         for block in &mut function.blocks {
             for instr in &mut block.instructions {
                 if let IRInstruction::BinOp {
@@ -145,78 +564,2366 @@ impl IROptimizer {
                     right,
                 } = instr
                 {
-                    let key = format!("{:?}_{:?}_{:?}", op, left, right);
-                    if let Some(cached) = seen.get(&key) {
+                    let identity = match (*op, left.clone(), right.clone()) {
+                        (BinOpIR::Add, l, IRValue::Const(IRConstant::Int(0))) => Some(l),
+                        (BinOpIR::Add, IRValue::Const(IRConstant::Int(0)), r) => Some(r),
+                        (BinOpIR::Mul, l, IRValue::Const(IRConstant::Int(1))) => Some(l),
+                        (BinOpIR::Mul, IRValue::Const(IRConstant::Int(1)), r) => Some(r),
+                        (BinOpIR::Mul, IRValue::Const(IRConstant::Int(0)), _)
+                        | (BinOpIR::Mul, _, IRValue::Const(IRConstant::Int(0))) => {
+                            Some(IRValue::Const(IRConstant::Int(0)))
+                        }
+                        (BinOpIR::Pow, l, IRValue::Const(IRConstant::Int(1))) => Some(l),
+                        (BinOpIR::FloorDiv, l, IRValue::Const(IRConstant::Int(1))) => Some(l),
+                        _ => None,
+                    };
+
+                    if let Some(value) = identity {
                         *instr = IRInstruction::Assign {
                             target: result.clone(),
-                            value: cached.clone(),
+                            value,
                         };
-                    } else {
-                        seen.insert(key, result.clone());
                     }
                 }
             }
         }
     }
 
-    fn loop_unrolling(&self, function: &mut IRFunction) {
-        // Simple unrolling for small loops (threshold: 8 iterations)
-        for block in &mut function.blocks {
-            let mut expanded = Vec::new();
+    /// Reassociates and fuses adjacent arithmetic when fast-math is enabled
+    /// (via `--fast-math` or `#adrenaline:fast-math`), collapsing an
+    /// intermediate result into the instruction that consumes it. This
+    /// changes rounding behavior for floats, so it's opt-in only - this IR
+    /// only has integer constants so far, but the same reassociation still
+    /// applies unconditionally to float-backed locals and temporaries once
+    /// `IRConstant` grows a float variant.
+    fn fast_math_transform(&self, function: &mut IRFunction) {
+        if !self.fast_math && !function.directives.fast_math() {
+            return;
+        }
+
+        for block_idx in 0..function.blocks.len() {
+            let mut i = 0;
+            while i + 1 < function.blocks[block_idx].instructions.len() {
+                let Some((replacement, intermediate)) = Self::fast_math_fuse(
+                    &function.blocks[block_idx].instructions[i],
+                    &function.blocks[block_idx].instructions[i + 1],
+                ) else {
+                    i += 1;
+                    continue;
+                };
+
+                // Only safe to drop the fused instruction if nothing else in
+                // the function still reads its result.
+                if self.operand_use_count(function, &intermediate) > 1 {
+                    i += 1;
+                    continue;
+                }
+
+                function.blocks[block_idx]
+                    .instructions
+                    .splice(i..i + 2, [replacement]);
+            }
+        }
+    }
+
+    /// Recognizes the two patterns `fast_math_transform` is allowed to fuse:
+    /// `t = a * b; r = t + c` into a single `Fma`, and `t = x + c1; r = t + c2`
+    /// into `r = x + (c1 + c2)`. Returns the replacement instruction and the
+    /// intermediate value it consumes, so the caller can check that value
+    /// isn't live anywhere else before deleting the first instruction.
+    fn fast_math_fuse(
+        first: &IRInstruction,
+        second: &IRInstruction,
+    ) -> Option<(IRInstruction, IRValue)> {
+        match (first, second) {
+            (
+                IRInstruction::BinOp {
+                    result: t,
+                    op: BinOpIR::Mul,
+                    left: a,
+                    right: b,
+                },
+                IRInstruction::BinOp {
+                    result: r,
+                    op: BinOpIR::Add,
+                    left,
+                    right,
+                },
+            ) if left == t || right == t => {
+                let c = if left == t { right.clone() } else { left.clone() };
+                Some((
+                    IRInstruction::Fma {
+                        result: r.clone(),
+                        a: a.clone(),
+                        b: b.clone(),
+                        c,
+                    },
+                    t.clone(),
+                ))
+            }
+            (
+                IRInstruction::BinOp {
+                    result: t,
+                    op: BinOpIR::Add,
+                    left: x,
+                    right: IRValue::Const(IRConstant::Int(c1)),
+                },
+                IRInstruction::BinOp {
+                    result: r,
+                    op: BinOpIR::Add,
+                    left,
+                    right,
+                },
+            ) if (left == t && matches!(right, IRValue::Const(IRConstant::Int(_))))
+                || (right == t && matches!(left, IRValue::Const(IRConstant::Int(_)))) =>
+            {
+                let c2 = match if left == t { right } else { left } {
+                    IRValue::Const(IRConstant::Int(n)) => *n,
+                    _ => unreachable!(),
+                };
+                Some((
+                    IRInstruction::BinOp {
+                        result: r.clone(),
+                        op: BinOpIR::Add,
+                        left: x.clone(),
+                        right: IRValue::Const(IRConstant::Int(c1 + c2)),
+                    },
+                    t.clone(),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// How many times `value` is read as an operand anywhere in the function.
+    fn operand_use_count(&self, function: &IRFunction, value: &IRValue) -> usize {
+        let mut count = 0;
+        for block in &function.blocks {
             for instr in &block.instructions {
-                expanded.push(instr.clone());
-                // In a full implementation, we'd detect loops and unroll them
+                self.visit_operands(instr, &mut |operand| {
+                    if operand == value {
+                        count += 1;
+                    }
+                });
             }
-            block.instructions = expanded;
         }
+        count
     }
 
-    fn inline_small_functions(&self, _function: &mut IRFunction) {
-        // Placeholder for function inlining pass
-        // Would need access to all functions in the module
+    /// When constant propagation leaves a `Branch` with a literal `Bool`
+    /// condition, replace it with a `Jump` to the side actually taken, drop
+    /// whichever successor subgraph that made unreachable, and merge any
+    /// straight-line jump chains left behind - otherwise conditions like
+    /// `if true` would survive all the way into the generated Rust.
+    fn branch_folding(&self, function: &mut IRFunction) {
+        self.fold_constant_branches(function);
+        self.remove_unreachable_blocks(function);
+        self.merge_straight_line_blocks(function);
     }
 
-    fn detect_simd_opportunities(&self, function: &mut IRFunction) {
+    fn fold_constant_branches(&self, function: &mut IRFunction) {
         for block in &mut function.blocks {
-            let mut has_consecutive_ops = false;
-            let mut op_count = 0;
+            for instr in &mut block.instructions {
+                if let IRInstruction::Branch {
+                    condition: IRValue::Const(IRConstant::Bool(taken)),
+                    true_block,
+                    false_block,
+                } = instr
+                {
+                    let target = if *taken { *true_block } else { *false_block };
+                    *instr = IRInstruction::Jump { target };
+                }
+            }
+
+            // Keep `successors` in sync with the (possibly just-folded)
+            // terminator so later passes over the CFG see the real edges.
+            match block.instructions.last() {
+                Some(IRInstruction::Jump { target }) => block.successors = vec![*target],
+                Some(IRInstruction::Branch {
+                    true_block,
+                    false_block,
+                    ..
+                }) => block.successors = vec![*true_block, *false_block],
+                _ => {}
+            }
+        }
+    }
 
+    /// Number of times each block id is targeted by a `Jump`, `Branch`, or
+    /// `LoopStart`, used to find blocks with a single predecessor (safe to
+    /// merge into) and blocks with none (safe to drop).
+    fn predecessor_counts(&self, function: &IRFunction) -> HashMap<usize, usize> {
+        let mut counts = HashMap::new();
+        for block in &function.blocks {
             for instr in &block.instructions {
-                if matches!(
-                    instr,
-                    IRInstruction::BinOp {
-                        op: BinOpIR::Add | BinOpIR::Mul | BinOpIR::Sub | BinOpIR::Div,
+                match instr {
+                    IRInstruction::Jump { target } => *counts.entry(*target).or_insert(0) += 1,
+                    IRInstruction::Branch {
+                        true_block,
+                        false_block,
+                        ..
+                    } => {
+                        *counts.entry(*true_block).or_insert(0) += 1;
+                        *counts.entry(*false_block).or_insert(0) += 1;
+                    }
+                    IRInstruction::LoopStart {
+                        body_block,
+                        exit_block,
                         ..
+                    } => {
+                        *counts.entry(*body_block).or_insert(0) += 1;
+                        *counts.entry(*exit_block).or_insert(0) += 1;
                     }
-                ) {
-                    op_count += 1;
-                    if op_count > 3 {
-                        has_consecutive_ops = true;
-                        break;
+                    IRInstruction::TryExcept {
+                        try_block,
+                        except_block,
+                        ..
+                    } => {
+                        *counts.entry(*try_block).or_insert(0) += 1;
+                        *counts.entry(*except_block).or_insert(0) += 1;
                     }
+                    _ => {}
                 }
             }
+        }
+        counts
+    }
 
-            if has_consecutive_ops {
-                block.instructions.push(IRInstruction::Vectorizable);
+    /// Drops every block no longer reachable from the entry block, i.e. the
+    /// subgraph a folded branch just cut off.
+    fn remove_unreachable_blocks(&self, function: &mut IRFunction) {
+        let Some(entry) = function.blocks.first().map(|b| b.id) else {
+            return;
+        };
+
+        let mut reachable = std::collections::HashSet::new();
+        let mut worklist = vec![entry];
+        while let Some(id) = worklist.pop() {
+            if !reachable.insert(id) {
+                continue;
+            }
+            let Some(block) = function.blocks.iter().find(|b| b.id == id) else {
+                continue;
+            };
+            for instr in &block.instructions {
+                let targets: Vec<usize> = match instr {
+                    IRInstruction::Jump { target } => vec![*target],
+                    IRInstruction::Branch {
+                        true_block,
+                        false_block,
+                        ..
+                    } => vec![*true_block, *false_block],
+                    IRInstruction::LoopStart {
+                        body_block,
+                        exit_block,
+                        ..
+                    } => vec![*body_block, *exit_block],
+                    IRInstruction::TryExcept {
+                        try_block,
+                        except_block,
+                        ..
+                    } => vec![*try_block, *except_block],
+                    _ => vec![],
+                };
+                for target in targets {
+                    if !reachable.contains(&target) {
+                        worklist.push(target);
+                    }
+                }
             }
         }
+
+        function.blocks.retain(|b| reachable.contains(&b.id));
+    }
+
+    /// Splices a block into its sole predecessor whenever that predecessor
+    /// does nothing but jump straight into it, collapsing chains of blocks
+    /// that folding and dead-code elimination leave behind into fewer,
+    /// bigger basic blocks.
+    fn merge_straight_line_blocks(&self, function: &mut IRFunction) {
+        loop {
+            let predecessor_counts = self.predecessor_counts(function);
+
+            let merge = function.blocks.iter().find_map(|block| {
+                let Some(IRInstruction::Jump { target }) = block.instructions.last() else {
+                    return None;
+                };
+                if *target == block.id {
+                    return None; // a self-loop, not a straight-line chain
+                }
+                if predecessor_counts.get(target).copied().unwrap_or(0) != 1 {
+                    return None;
+                }
+                Some((block.id, *target))
+            });
+
+            let Some((pred_id, succ_id)) = merge else {
+                break;
+            };
+            let Some(succ_idx) = function.blocks.iter().position(|b| b.id == succ_id) else {
+                break;
+            };
+            let succ_block = function.blocks.remove(succ_idx);
+
+            let Some(pred_block) = function.blocks.iter_mut().find(|b| b.id == pred_id) else {
+                continue;
+            };
+            pred_block.instructions.pop(); // drop the Jump into `succ_block`
+            pred_block.instructions.extend(succ_block.instructions);
+            pred_block.successors = succ_block.successors;
+        }
+    }
+
+    fn bounds_check_elimination(&self, function: &mut IRFunction) {
+        // Mark safe array accesses as CanElideCheck. Recomputed from scratch
+        // each run (drop any hints from a previous run, then re-derive them)
+        // rather than appended to, so this pass is idempotent - appending
+        // unconditionally never reaches a fixed point, since `optimize_function`
+        // re-runs every pass until the instruction count stops changing.
+        for block in &mut function.blocks {
+            let elidable = block
+                .instructions
+                .iter()
+                .filter(|instr| {
+                    matches!(
+                        instr,
+                        IRInstruction::Index { index, .. }
+                            if matches!(index, IRValue::Const(IRConstant::Int(_)))
+                    )
+                })
+                .count();
+            block
+                .instructions
+                .retain(|instr| !matches!(instr, IRInstruction::CanElideCheck));
+            block
+                .instructions
+                .extend(std::iter::repeat_n(IRInstruction::CanElideCheck, elidable));
+        }
     }
 
-    fn detect_parallel_opportunities(&self, function: &mut IRFunction) {
-        // Detect data-parallel loop patterns
+    /// Replaces uses of a value that is a plain copy of another (`b = a`)
+    /// with the original, so redundant copies left behind by lowering and
+    /// inlining don't hide opportunities from dead-code elimination and CSE.
+    fn copy_propagation(&self, function: &mut IRFunction) {
         for block in &mut function.blocks {
-            let mut to_add = Vec::new();
-            for instr in block.instructions.iter() {
-                if matches!(instr, IRInstruction::LoopStart { .. }) {
-                    // Defer mutation to avoid simultaneous mutable borrow
-                    to_add.push(IRInstruction::Parallelizable);
+            let mut copies: HashMap<IRValue, IRValue> = HashMap::new();
+
+            for instr in &mut block.instructions {
+                self.visit_operands_mut(instr, &mut |value| {
+                    let mut resolved = value.clone();
+                    while let Some(original) = copies.get(&resolved) {
+                        if original == &resolved {
+                            break;
+                        }
+                        resolved = original.clone();
+                    }
+                    *value = resolved;
+                });
+
+                if let IRInstruction::Assign { target, value } = instr {
+                    if matches!(value, IRValue::Local(_) | IRValue::Temporary(_)) {
+                        copies.insert(target.clone(), value.clone());
+                    }
                 }
             }
-            block.instructions.extend(to_add);
         }
     }
 
+    /// Like `visit_values_mut`, but only touches operands, leaving the
+    /// instruction's result/target value alone.
+    fn visit_operands_mut(&self, instr: &mut IRInstruction, visit: &mut impl FnMut(&mut IRValue)) {
+        match instr {
+            IRInstruction::BinOp { left, right, .. } => {
+                visit(left);
+                visit(right);
+            }
+            IRInstruction::UnaryOp { operand, .. } => visit(operand),
+            IRInstruction::Fma { a, b, c, .. } => {
+                visit(a);
+                visit(b);
+                visit(c);
+            }
+            IRInstruction::Assign { value, .. } => visit(value),
+            IRInstruction::Store { value, .. } => visit(value),
+            IRInstruction::Index { array, index, .. } => {
+                visit(array);
+                visit(index);
+            }
+            IRInstruction::IndexStore { array, index, value } => {
+                visit(array);
+                visit(index);
+                visit(value);
+            }
+            IRInstruction::CacheLookup { keys, .. } => {
+                for key in keys {
+                    visit(key);
+                }
+            }
+            IRInstruction::CacheStore { keys, value, .. } => {
+                for key in keys {
+                    visit(key);
+                }
+                visit(value);
+            }
+            IRInstruction::Branch { condition, .. } => visit(condition),
+            IRInstruction::Return { value: Some(v) } => visit(v),
+            IRInstruction::Call { args, .. } => {
+                for arg in args {
+                    visit(arg);
+                }
+            }
+            IRInstruction::MethodCall { receiver, args, .. } => {
+                visit(receiver);
+                for arg in args {
+                    visit(arg);
+                }
+            }
+            IRInstruction::NewList { capacity: Some(c), .. } => visit(c),
+            IRInstruction::FormatString { parts, .. } => {
+                for part in parts {
+                    if let FormatPart::Value(v, _) = part {
+                        visit(v);
+                    }
+                }
+            }
+            IRInstruction::LoopStart { iterator, .. } => visit(iterator),
+            IRInstruction::NewStruct { fields, .. } => {
+                for (_, value) in fields.iter_mut() {
+                    visit(value);
+                }
+            }
+            IRInstruction::Print { args, .. } => {
+                for (value, _) in args.iter_mut() {
+                    visit(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Global value numbering, scoped by dominance: a computation is only
+    /// reused at a use site if it was computed in a block that dominates it,
+    /// so (unlike a single function-wide table) values computed down one
+    /// branch of the CFG can't be mistakenly reused down a sibling branch.
+    fn common_subexpression_elimination(&self, function: &mut IRFunction) {
+        if function.blocks.is_empty() {
+            return;
+        }
+
+        let index_by_id: HashMap<usize, usize> = function
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.id, i))
+            .collect();
+        let idom = self.compute_dominators(function, &index_by_id);
+
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (&id, &dom) in &idom {
+            if dom != id {
+                children.entry(dom).or_default().push(id);
+            }
+        }
+
+        let entry_id = function.blocks[0].id;
+        self.gvn_block(entry_id, &children, &index_by_id, function, HashMap::new(), HashMap::new());
+    }
+
+    /// The `Local` a numbering-table key becomes stale on, if any - the name
+    /// of whatever `IRValue::Local` this instruction just wrote to (`Store`'s
+    /// target is already a bare name; every other write-producing
+    /// instruction carries its destination as an `IRValue` that may or may
+    /// not be a `Local`). `None` for anything that only writes a
+    /// `Temporary`, which is single-assignment and can never go stale.
+    fn gvn_write_target(instr: &IRInstruction) -> Option<String> {
+        let written = match instr {
+            IRInstruction::BinOp { result, .. }
+            | IRInstruction::UnaryOp { result, .. }
+            | IRInstruction::Fma { result, .. }
+            | IRInstruction::Load { result, .. }
+            | IRInstruction::Index { result, .. }
+            | IRInstruction::NewStruct { result, .. }
+            | IRInstruction::Call { result, .. }
+            | IRInstruction::MethodCall { result, .. }
+            | IRInstruction::NewList { result, .. }
+            | IRInstruction::FormatString { result, .. }
+            | IRInstruction::Assign { target: result, .. } => result,
+            IRInstruction::Store { target, .. } => return Some(target.clone()),
+            _ => return None,
+        };
+        match written {
+            IRValue::Local(name) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    fn gvn_block(
+        &self,
+        block_id: usize,
+        children: &HashMap<usize, Vec<usize>>,
+        index_by_id: &HashMap<usize, usize>,
+        function: &mut IRFunction,
+        mut numbering: HashMap<String, IRValue>,
+        // Reverse index: for a `Local` name, every numbering-table key whose
+        // cached value depends on that local's current contents (as a `left`
+        // or `right` operand) - so a later write to it can purge exactly the
+        // entries it invalidates, instead of leaving them to be reused
+        // against the local's new value. See `gvn_write_target`.
+        mut deps: HashMap<String, HashSet<String>>,
+    ) {
+        let Some(&idx) = index_by_id.get(&block_id) else {
+            return;
+        };
+
+        for instr in &mut function.blocks[idx].instructions {
+            if let IRInstruction::BinOp {
+                result,
+                op,
+                left,
+                right,
+            } = instr
+            {
+                let key = format!("{:?}_{:?}_{:?}", op, left, right);
+                if let Some(cached) = numbering.get(&key) {
+                    *instr = IRInstruction::Assign {
+                        target: result.clone(),
+                        value: cached.clone(),
+                    };
+                } else {
+                    for operand in [&*left, &*right] {
+                        if let IRValue::Local(name) = operand {
+                            deps.entry(name.clone()).or_default().insert(key.clone());
+                        }
+                    }
+                    numbering.insert(key, result.clone());
+                }
+            }
+
+            if let Some(name) = Self::gvn_write_target(instr) {
+                if let Some(stale_keys) = deps.remove(&name) {
+                    for key in stale_keys {
+                        numbering.remove(&key);
+                    }
+                }
+            }
+        }
+
+        if let Some(kids) = children.get(&block_id) {
+            for &child in kids {
+                // Each dominated child gets its own copy of the table: a
+                // value stays visible to everything the defining block
+                // dominates, but siblings and their subtrees don't see it.
+                self.gvn_block(child, children, index_by_id, function, numbering.clone(), deps.clone());
+            }
+        }
+    }
+
+    /// Standard iterative dominator computation (Cooper, Harvey & Kennedy),
+    /// keyed by block id rather than array index since block ids aren't
+    /// necessarily contiguous once passes like unrolling add new blocks.
+    fn compute_dominators(
+        &self,
+        function: &IRFunction,
+        index_by_id: &HashMap<usize, usize>,
+    ) -> HashMap<usize, usize> {
+        let n = function.blocks.len();
+        let entry = 0usize; // index into function.blocks
+
+        let mut preds: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (i, block) in function.blocks.iter().enumerate() {
+            for succ_id in &block.successors {
+                if let Some(&succ_idx) = index_by_id.get(succ_id) {
+                    preds[succ_idx].push(i);
+                }
+            }
+        }
+
+        let rpo = self.reverse_postorder(function, index_by_id, entry);
+        let mut postorder_num = vec![0usize; n];
+        for (order, &idx) in rpo.iter().rev().enumerate() {
+            postorder_num[idx] = order;
+        }
+
+        let mut idom: Vec<Option<usize>> = vec![None; n];
+        idom[entry] = Some(entry);
+
+        let intersect = |mut a: usize, mut b: usize, idom: &[Option<usize>], postorder_num: &[usize]| {
+            while a != b {
+                while postorder_num[a] < postorder_num[b] {
+                    a = idom[a].unwrap();
+                }
+                while postorder_num[b] < postorder_num[a] {
+                    b = idom[b].unwrap();
+                }
+            }
+            a
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &b in &rpo {
+                if b == entry {
+                    continue;
+                }
+                let mut new_idom = None;
+                for &p in &preds[b] {
+                    if idom[p].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => p,
+                        Some(cur) => intersect(cur, p, &idom, &postorder_num),
+                    });
+                }
+                if idom[b] != new_idom {
+                    idom[b] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        idom.iter()
+            .enumerate()
+            .filter_map(|(idx, dom)| dom.map(|d| (function.blocks[idx].id, function.blocks[d].id)))
+            .collect()
+    }
+
+    fn reverse_postorder(
+        &self,
+        function: &IRFunction,
+        index_by_id: &HashMap<usize, usize>,
+        entry: usize,
+    ) -> Vec<usize> {
+        let mut visited = vec![false; function.blocks.len()];
+        let mut postorder = Vec::new();
+
+        fn visit(
+            idx: usize,
+            function: &IRFunction,
+            index_by_id: &HashMap<usize, usize>,
+            visited: &mut [bool],
+            postorder: &mut Vec<usize>,
+        ) {
+            if visited[idx] {
+                return;
+            }
+            visited[idx] = true;
+            for succ_id in &function.blocks[idx].successors {
+                if let Some(&succ_idx) = index_by_id.get(succ_id) {
+                    visit(succ_idx, function, index_by_id, visited, postorder);
+                }
+            }
+            postorder.push(idx);
+        }
+
+        visit(entry, function, index_by_id, &mut visited, &mut postorder);
+        postorder.reverse();
+        postorder
+    }
+
+    /// Walks each loop body looking for the induction variable (a value that
+    /// is reassigned to itself plus a constant stride on every iteration) and
+    /// the constant it's compared against, so a known trip count can be
+    /// derived. Fills in `LoopStart::metadata` in place.
+    fn analyze_loops(&self, function: &mut IRFunction) {
+        for block_idx in 0..function.blocks.len() {
+            let loop_starts: Vec<usize> = function.blocks[block_idx]
+                .instructions
+                .iter()
+                .enumerate()
+                .filter_map(|(i, instr)| {
+                    matches!(instr, IRInstruction::LoopStart { .. }).then_some(i)
+                })
+                .collect();
+
+            for instr_idx in loop_starts {
+                let (iterator, body_block) = match &function.blocks[block_idx].instructions[instr_idx] {
+                    IRInstruction::LoopStart {
+                        iterator,
+                        body_block,
+                        ..
+                    } => (iterator.clone(), *body_block),
+                    _ => unreachable!(),
+                };
+
+                let metadata =
+                    self.analyze_loop(function, &function.blocks[block_idx], &iterator, body_block);
+
+                if let IRInstruction::LoopStart { metadata: slot, .. } =
+                    &mut function.blocks[block_idx].instructions[instr_idx]
+                {
+                    *slot = metadata;
+                }
+            }
+        }
+    }
+
+    fn analyze_loop(
+        &self,
+        function: &IRFunction,
+        preheader: &BasicBlock,
+        iterator: &IRValue,
+        body_block: usize,
+    ) -> LoopMetadata {
+        let mut metadata = LoopMetadata::new();
+
+        let Some(body) = function.blocks.iter().find(|b| b.id == body_block) else {
+            return metadata;
+        };
+
+        // Induction variable: `iterator = iterator + <const stride>` in the body.
+        let mut stride = None;
+        for instr in &body.instructions {
+            if let IRInstruction::BinOp {
+                result,
+                op: BinOpIR::Add,
+                left,
+                right,
+            } = instr
+            {
+                if result == iterator {
+                    if left == iterator {
+                        if let IRValue::Const(IRConstant::Int(n)) = right {
+                            stride = Some(*n);
+                        }
+                    } else if right == iterator {
+                        if let IRValue::Const(IRConstant::Int(n)) = left {
+                            stride = Some(*n);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(stride) = stride {
+            // Canonicalize the starting value from wherever the iterator was
+            // initialized ahead of the loop, rather than assuming every loop
+            // - a `range()`, an `enumerate()` counter, a hand-incremented
+            // `while` variable - starts at zero.
+            let start = Self::iterator_start(preheader, iterator).unwrap_or(0);
+
+            metadata.add_induction_variable(InductionVariable {
+                value: iterator.clone(),
+                start: IRConstant::Int(start),
+                stride,
+            });
+
+            // Derived induction variables: any other value computed as a
+            // single affine step (`* c` or `+ c`) of the primary counter
+            // moves in lockstep with it, so express it the same way instead
+            // of leaving unroll/vectorize to rediscover it from scratch.
+            for instr in &body.instructions {
+                let IRInstruction::BinOp {
+                    result,
+                    op,
+                    left,
+                    right,
+                } = instr
+                else {
+                    continue;
+                };
+                if result == iterator {
+                    continue; // the primary counter's own update, not a derived IV
+                }
+
+                let (derived_start, derived_stride) = match (op, left, right) {
+                    (BinOpIR::Mul, l, IRValue::Const(IRConstant::Int(c))) if l == iterator => {
+                        (start * c, stride * c)
+                    }
+                    (BinOpIR::Mul, IRValue::Const(IRConstant::Int(c)), r) if r == iterator => {
+                        (start * c, stride * c)
+                    }
+                    (BinOpIR::Add, l, IRValue::Const(IRConstant::Int(c))) if l == iterator => {
+                        (start + c, stride)
+                    }
+                    (BinOpIR::Add, IRValue::Const(IRConstant::Int(c)), r) if r == iterator => {
+                        (start + c, stride)
+                    }
+                    _ => continue,
+                };
+
+                metadata.add_induction_variable(InductionVariable {
+                    value: result.clone(),
+                    start: IRConstant::Int(derived_start),
+                    stride: derived_stride,
+                });
+            }
+        }
+
+        // Trip count: a branch on `iterator <op> <const bound>` guarding the loop.
+        for instr in &body.instructions {
+            if let IRInstruction::Branch { condition, .. } = instr {
+                if let IRValue::Temporary(_) = condition {
+                    // Condition is itself a computed value; look for the
+                    // comparison that produced it.
+                    if let Some(bound) = body.instructions.iter().find_map(|i| match i {
+                        IRInstruction::BinOp {
+                            result,
+                            op: BinOpIR::Lt | BinOpIR::LtE,
+                            left,
+                            right,
+                        } if result == condition && left == iterator => match right {
+                            IRValue::Const(IRConstant::Int(n)) => Some(*n),
+                            _ => None,
+                        },
+                        _ => None,
+                    }) {
+                        if let (Some(stride), true) = (stride, bound > 0) {
+                            let trip_count = (bound as u64).div_ceil(stride.unsigned_abs());
+                            metadata.trip_count = Some(TripCount::Known(trip_count));
+                        } else {
+                            metadata.trip_count = Some(TripCount::Estimated(self.unroll_threshold as u64));
+                        }
+                    }
+                }
+            }
+        }
+
+        metadata
+    }
+
+    /// Finds the constant an induction variable was set to right before
+    /// entering its loop, by scanning the preheader block for the last
+    /// write to its name - whatever the source syntax was (`range()`,
+    /// `enumerate()`, or a plain `i = 0`), it lowers to the same `Store`.
+    fn iterator_start(preheader: &BasicBlock, iterator: &IRValue) -> Option<i64> {
+        let IRValue::Local(name) = iterator else {
+            return None;
+        };
+
+        preheader.instructions.iter().rev().find_map(|instr| match instr {
+            IRInstruction::Store {
+                target,
+                value: IRValue::Const(IRConstant::Int(n)),
+            } if target == name => Some(*n),
+            _ => None,
+        })
+    }
+
+    /// Factor used for partial unrolling of loops too large to unroll fully.
+    const UNROLL_FACTOR: u64 = 4;
+
+    /// Unrolls counted loops whose trip count was determined by
+    /// `analyze_loops`. Loops small enough to fit under `unroll_threshold`
+    /// are unrolled fully; larger ones are unrolled by `UNROLL_FACTOR` with a
+    /// remainder loop appended to run the leftover iterations.
+    /// Swaps the iteration variables of a perfectly-nested pair of counted
+    /// loops so the tighter-strided loop ends up innermost, which is where
+    /// it has the best chance of hitting sequential memory access.
+    fn loop_interchange(&self, function: &mut IRFunction) {
+        let positions: Vec<(usize, usize)> = function
+            .blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(b_idx, block)| {
+                block
+                    .instructions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, instr)| matches!(instr, IRInstruction::LoopStart { .. }))
+                    .map(move |(i_idx, _)| (b_idx, i_idx))
+            })
+            .collect();
+
+        for (b_idx, i_idx) in positions {
+            let IRInstruction::LoopStart {
+                iterator: outer_iter,
+                body_block: outer_body_id,
+                metadata: outer_meta,
+                ..
+            } = function.blocks[b_idx].instructions[i_idx].clone()
+            else {
+                continue;
+            };
+
+            let Some(outer_body_idx) = function.blocks.iter().position(|b| b.id == outer_body_id)
+            else {
+                continue;
+            };
+
+            // Perfectly nested: the outer body's very first instruction is
+            // another loop header with nothing preceding it.
+            let Some(IRInstruction::LoopStart {
+                iterator: inner_iter,
+                metadata: inner_meta,
+                ..
+            }) = function.blocks[outer_body_idx].instructions.first().cloned()
+            else {
+                continue;
+            };
+
+            let (Some(outer_stride), Some(inner_stride)) = (
+                outer_meta.primary_induction_variable().map(|iv| iv.stride.abs()),
+                inner_meta.primary_induction_variable().map(|iv| iv.stride.abs()),
+            ) else {
+                continue;
+            };
+
+            if outer_stride >= inner_stride {
+                continue;
+            }
+
+            if let IRInstruction::LoopStart { iterator, metadata, .. } =
+                &mut function.blocks[b_idx].instructions[i_idx]
+            {
+                *iterator = inner_iter;
+                *metadata = inner_meta;
+            }
+            if let IRInstruction::LoopStart { iterator, metadata, .. } =
+                &mut function.blocks[outer_body_idx].instructions[0]
+            {
+                *iterator = outer_iter;
+                *metadata = outer_meta;
+            }
+        }
+    }
+
+    /// Tile size used when blocking a large counted loop into an outer loop
+    /// of tiles and an inner loop that walks each tile.
+    const TILE_SIZE: u64 = 32;
+
+    /// Splits a loop whose trip count is known and large into an outer loop
+    /// over tiles and an inner loop over each tile's elements, with a
+    /// remainder loop for whatever doesn't divide evenly - the same total
+    /// number of iterations, but in a shape that keeps working sets small
+    /// enough to stay cache-resident.
+    fn loop_tiling(&self, function: &mut IRFunction) {
+        let mut next_block_id = function.blocks.iter().map(|b| b.id).max().unwrap_or(0) + 1;
+        let mut next_temp = self.max_temporary(function) + 1;
+
+        let positions: Vec<(usize, usize)> = function
+            .blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(b_idx, block)| {
+                block
+                    .instructions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, instr)| matches!(instr, IRInstruction::LoopStart { .. }))
+                    .map(move |(i_idx, _)| (b_idx, i_idx))
+            })
+            .collect();
+
+        for (b_idx, i_idx) in positions {
+            let IRInstruction::LoopStart {
+                iterator,
+                body_block: body_id,
+                exit_block: exit_id,
+                metadata,
+            } = function.blocks[b_idx].instructions[i_idx].clone()
+            else {
+                continue;
+            };
+
+            let Some(TripCount::Known(n)) = metadata.trip_count else {
+                continue;
+            };
+            if n <= Self::TILE_SIZE * 2 {
+                continue;
+            }
+            let Some(iv) = metadata.primary_induction_variable() else {
+                continue;
+            };
+            let stride = iv.stride;
+            let Some(body_idx) = function.blocks.iter().position(|b| b.id == body_id) else {
+                continue;
+            };
+
+            let tiles = n / Self::TILE_SIZE;
+            let remainder = n % Self::TILE_SIZE;
+            let payload = self.loop_payload(&function.blocks[body_idx], &iterator);
+
+            // Inner loop: walks TILE_SIZE elements of one tile, reconstructing
+            // the original induction value as `tile_base + inner * stride`.
+            let inner_iter = IRValue::Temporary(next_temp);
+            next_temp += 1;
+            let tile_base = IRValue::Temporary(next_temp);
+            next_temp += 1;
+
+            let inner_body_id = next_block_id;
+            next_block_id += 1;
+            let mut inner_instructions = vec![IRInstruction::BinOp {
+                result: iterator.clone(),
+                op: BinOpIR::Add,
+                left: tile_base.clone(),
+                right: inner_iter.clone(),
+            }];
+            inner_instructions.extend(payload.iter().cloned());
+            inner_instructions.push(IRInstruction::BinOp {
+                result: inner_iter.clone(),
+                op: BinOpIR::Add,
+                left: inner_iter.clone(),
+                right: IRValue::Const(IRConstant::Int(stride)),
+            });
+            let mut inner_metadata = LoopMetadata::new();
+            inner_metadata.trip_count = Some(TripCount::Known(Self::TILE_SIZE));
+            inner_metadata.add_induction_variable(InductionVariable {
+                value: inner_iter.clone(),
+                start: IRConstant::Int(0),
+                stride,
+            });
+            // The inner loop's body and the block that re-enters it for the
+            // next tile are one and the same: once its TILE_SIZE iterations
+            // finish, control falls through to the outer loop's self-edge.
+            let outer_body_id = inner_body_id;
+            inner_instructions.push(IRInstruction::LoopStart {
+                iterator: inner_iter,
+                body_block: inner_body_id,
+                exit_block: outer_body_id,
+                metadata: inner_metadata,
+            });
+
+            // Outer loop: advances by one tile's worth of the original stride.
+            let mut outer_metadata = LoopMetadata::new();
+            outer_metadata.trip_count = Some(TripCount::Known(tiles));
+            outer_metadata.add_induction_variable(InductionVariable {
+                value: tile_base.clone(),
+                start: iv.start.clone(),
+                stride: stride * Self::TILE_SIZE as i64,
+            });
+
+            let remainder_id = if remainder > 0 {
+                let id = next_block_id;
+                next_block_id += 1;
+                let mut remainder_instructions = vec![IRInstruction::Assign {
+                    target: iterator.clone(),
+                    value: tile_base.clone(),
+                }];
+                for i in 0..remainder {
+                    if i > 0 {
+                        remainder_instructions.push(IRInstruction::BinOp {
+                            result: iterator.clone(),
+                            op: BinOpIR::Add,
+                            left: iterator.clone(),
+                            right: IRValue::Const(IRConstant::Int(stride)),
+                        });
+                    }
+                    remainder_instructions.extend(payload.iter().cloned());
+                }
+                remainder_instructions.push(IRInstruction::Jump { target: exit_id });
+                function.blocks.push(BasicBlock {
+                    id,
+                    instructions: remainder_instructions,
+                    successors: vec![exit_id],
+                });
+                id
+            } else {
+                exit_id
+            };
+
+            function.blocks[body_idx].instructions = inner_instructions;
+            function.blocks[body_idx].id = outer_body_id;
+            function.blocks[body_idx].successors = vec![outer_body_id, remainder_id];
+
+            function.blocks[b_idx].instructions[i_idx] = IRInstruction::LoopStart {
+                iterator: tile_base,
+                body_block: outer_body_id,
+                exit_block: remainder_id,
+                metadata: outer_metadata,
+            };
+        }
+    }
+
+    /// Merges two adjacent counted loops with the same trip count and stride
+    /// into one, so the body only pays for one loop's worth of branching and
+    /// increment overhead instead of two.
+    fn loop_fusion(&self, function: &mut IRFunction) {
+        loop {
+            let loop_positions: Vec<(usize, usize)> = function
+                .blocks
+                .iter()
+                .enumerate()
+                .flat_map(|(b_idx, block)| {
+                    block
+                        .instructions
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, instr)| matches!(instr, IRInstruction::LoopStart { .. }))
+                        .map(move |(i_idx, _)| (b_idx, i_idx))
+                })
+                .collect();
+
+            let mut fused = false;
+            for (b1, i1) in loop_positions {
+                let IRInstruction::LoopStart {
+                    iterator: iter1,
+                    body_block: body1_id,
+                    exit_block: exit1_id,
+                    metadata: meta1,
+                } = function.blocks[b1].instructions[i1].clone()
+                else {
+                    continue;
+                };
+
+                let Some(exit1_idx) = function.blocks.iter().position(|b| b.id == exit1_id) else {
+                    continue;
+                };
+                // Only fuse when the first loop's exit block does nothing but
+                // start the second loop - otherwise there's work in between
+                // that the fused body would run at the wrong point.
+                if function.blocks[exit1_idx].instructions.len() != 1 {
+                    continue;
+                }
+                let IRInstruction::LoopStart {
+                    iterator: iter2,
+                    body_block: body2_id,
+                    exit_block: exit2_id,
+                    metadata: meta2,
+                } = function.blocks[exit1_idx].instructions[0].clone()
+                else {
+                    continue;
+                };
+
+                let strides_match = meta1.primary_induction_variable().map(|iv| iv.stride)
+                    == meta2.primary_induction_variable().map(|iv| iv.stride);
+                if meta1.trip_count.is_none() || meta1.trip_count != meta2.trip_count || !strides_match {
+                    continue;
+                }
+
+                let (Some(body1_idx), Some(body2_idx)) = (
+                    function.blocks.iter().position(|b| b.id == body1_id),
+                    function.blocks.iter().position(|b| b.id == body2_id),
+                ) else {
+                    continue;
+                };
+
+                let payload2 = self.loop_payload(&function.blocks[body2_idx], &iter2);
+                let mut appended: Vec<IRInstruction> = payload2
+                    .into_iter()
+                    .map(|mut instr| {
+                        self.substitute_value(&mut instr, &iter2, &iter1);
+                        instr
+                    })
+                    .collect();
+
+                let insert_at = function.blocks[body1_idx]
+                    .instructions
+                    .iter()
+                    .position(|instr| {
+                        matches!(instr, IRInstruction::BinOp { result, op: BinOpIR::Add, left, .. }
+                            if result == &iter1 && left == &iter1)
+                    })
+                    .unwrap_or(function.blocks[body1_idx].instructions.len());
+                for (offset, instr) in appended.drain(..).enumerate() {
+                    function.blocks[body1_idx]
+                        .instructions
+                        .insert(insert_at + offset, instr);
+                }
+
+                if let IRInstruction::LoopStart { exit_block, .. } =
+                    &mut function.blocks[b1].instructions[i1]
+                {
+                    *exit_block = exit2_id;
+                }
+                for succ in &mut function.blocks[body1_idx].successors {
+                    if *succ == exit1_id {
+                        *succ = exit2_id;
+                    }
+                }
+
+                function
+                    .blocks
+                    .retain(|b| b.id != exit1_id && b.id != body2_id);
+
+                fused = true;
+                break;
+            }
+
+            if !fused {
+                break;
+            }
+        }
+    }
+
+    fn loop_unrolling(&mut self, function: &mut IRFunction) {
+        let mut next_temp = self.max_temporary(function) + 1;
+        let mut next_block_id = function.blocks.iter().map(|b| b.id).max().unwrap_or(0) + 1;
+
+        for block_idx in 0..function.blocks.len() {
+            let loop_starts: Vec<usize> = function.blocks[block_idx]
+                .instructions
+                .iter()
+                .enumerate()
+                .filter_map(|(i, instr)| {
+                    matches!(instr, IRInstruction::LoopStart { .. }).then_some(i)
+                })
+                .collect();
+
+            for instr_idx in loop_starts {
+                let (iterator, body_block_id, exit_block_id, metadata) =
+                    match &function.blocks[block_idx].instructions[instr_idx] {
+                        IRInstruction::LoopStart {
+                            iterator,
+                            body_block,
+                            exit_block,
+                            metadata,
+                        } => (iterator.clone(), *body_block, *exit_block, metadata.clone()),
+                        _ => unreachable!(),
+                    };
+
+                let Some(iv) = metadata.primary_induction_variable() else {
+                    continue;
+                };
+                let Some(TripCount::Known(trip_count)) = &metadata.trip_count else {
+                    continue;
+                };
+                let trip_count = *trip_count;
+                let stride = iv.stride;
+
+                let Some(body_idx) = function.blocks.iter().position(|b| b.id == body_block_id)
+                else {
+                    continue;
+                };
+
+                let payload = self.loop_payload(&function.blocks[body_idx], &iterator);
+
+                if trip_count <= self.unroll_threshold as u64 {
+                    // Fully unroll: every use of the induction variable becomes
+                    // the literal value it holds on that iteration.
+                    let mut unrolled = Vec::new();
+                    for i in 0..trip_count {
+                        let value = IRConstant::Int(i as i64 * stride);
+                        for instr in &payload {
+                            let mut cloned = self.remap_temporaries(instr.clone(), &mut next_temp);
+                            self.substitute_value(&mut cloned, &iterator, &IRValue::Const(value.clone()));
+                            unrolled.push(cloned);
+                        }
+                    }
+                    unrolled.push(IRInstruction::Jump {
+                        target: exit_block_id,
+                    });
+                    function.blocks[body_idx].instructions = unrolled;
+                    function.blocks[body_idx].successors = vec![exit_block_id];
+                    function.blocks[block_idx].instructions[instr_idx] = IRInstruction::Jump {
+                        target: body_block_id,
+                    };
+                    self.remark(
+                        &function.name.clone(),
+                        RemarkKind::Applied,
+                        format!("loop at block {body_block_id} fully unrolled ({trip_count} iterations)"),
+                    );
+                } else {
+                    // Partial unroll: run UNROLL_FACTOR copies per outer
+                    // iteration, then a remainder loop for what's left over.
+                    let factor = Self::UNROLL_FACTOR;
+                    let main_iterations = trip_count / factor;
+                    let remainder = trip_count % factor;
+
+                    let mut unrolled_body = Vec::new();
+                    for offset in 0..factor {
+                        for instr in &payload {
+                            let mut cloned = self.remap_temporaries(instr.clone(), &mut next_temp);
+                            if offset > 0 {
+                                let offset_value = IRValue::Temporary(next_temp);
+                                next_temp += 1;
+                                unrolled_body.push(IRInstruction::BinOp {
+                                    result: offset_value.clone(),
+                                    op: BinOpIR::Add,
+                                    left: iterator.clone(),
+                                    right: IRValue::Const(IRConstant::Int(offset as i64 * stride)),
+                                });
+                                self.substitute_value(&mut cloned, &iterator, &offset_value);
+                            }
+                            unrolled_body.push(cloned);
+                        }
+                    }
+                    unrolled_body.push(IRInstruction::BinOp {
+                        result: iterator.clone(),
+                        op: BinOpIR::Add,
+                        left: iterator.clone(),
+                        right: IRValue::Const(IRConstant::Int(factor as i64 * stride)),
+                    });
+
+                    let mut main_metadata = LoopMetadata::new();
+                    main_metadata.trip_count = Some(TripCount::Known(main_iterations));
+                    main_metadata.add_induction_variable(InductionVariable {
+                        value: iterator.clone(),
+                        start: iv.start.clone(),
+                        stride: stride * factor as i64,
+                    });
+
+                    let remainder_block_id = if remainder > 0 {
+                        let id = next_block_id;
+                        next_block_id += 1;
+                        let mut remainder_instructions = Vec::new();
+                        for offset in 0..remainder {
+                            for instr in &payload {
+                                let mut cloned = self.remap_temporaries(instr.clone(), &mut next_temp);
+                                if offset > 0 {
+                                    let offset_value = IRValue::Temporary(next_temp);
+                                    next_temp += 1;
+                                    remainder_instructions.push(IRInstruction::BinOp {
+                                        result: offset_value.clone(),
+                                        op: BinOpIR::Add,
+                                        left: iterator.clone(),
+                                        right: IRValue::Const(IRConstant::Int(offset as i64 * stride)),
+                                    });
+                                    self.substitute_value(&mut cloned, &iterator, &offset_value);
+                                }
+                                remainder_instructions.push(cloned);
+                            }
+                        }
+                        remainder_instructions.push(IRInstruction::Jump {
+                            target: exit_block_id,
+                        });
+                        function.blocks.push(BasicBlock {
+                            id,
+                            instructions: remainder_instructions,
+                            successors: vec![exit_block_id],
+                        });
+                        id
+                    } else {
+                        exit_block_id
+                    };
+
+                    unrolled_body.push(IRInstruction::LoopStart {
+                        iterator: iterator.clone(),
+                        body_block: body_block_id,
+                        exit_block: remainder_block_id,
+                        metadata: main_metadata,
+                    });
+
+                    function.blocks[body_idx].instructions = unrolled_body;
+                    function.blocks[body_idx].successors = vec![body_block_id, remainder_block_id];
+                    self.remark(
+                        &function.name.clone(),
+                        RemarkKind::Applied,
+                        format!("loop at block {body_block_id} unrolled x{factor} ({main_iterations} main iterations, {remainder} remainder)"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Converts expensive operations into cheaper equivalents: `x ** 2`
+    /// becomes `x * x`, multiply/divide by a power of two becomes a shift,
+    /// and multiplying a loop's induction variable by a constant becomes an
+    /// accumulator that's only ever added to.
+    fn strength_reduction(&self, function: &mut IRFunction) {
+        for block in &mut function.blocks {
+            for instr in &mut block.instructions {
+                Self::reduce_operation_strength(instr);
+            }
+        }
+
+        self.strength_reduce_induction_multiplies(function);
+    }
+
+    fn reduce_operation_strength(instr: &mut IRInstruction) {
+        if let IRInstruction::BinOp {
+            result,
+            op,
+            left,
+            right,
+        } = instr
+        {
+            match op {
+                BinOpIR::Pow if *right == IRValue::Const(IRConstant::Int(2)) => {
+                    *instr = IRInstruction::BinOp {
+                        result: result.clone(),
+                        op: BinOpIR::Mul,
+                        left: left.clone(),
+                        right: left.clone(),
+                    };
+                }
+                BinOpIR::Mul | BinOpIR::FloorDiv => {
+                    if let IRValue::Const(IRConstant::Int(n)) = right {
+                        if *n > 0 && (*n & (*n - 1)) == 0 {
+                            let shift = n.trailing_zeros() as i64;
+                            let new_op = if *op == BinOpIR::Mul {
+                                BinOpIR::LShift
+                            } else {
+                                BinOpIR::RShift
+                            };
+                            *instr = IRInstruction::BinOp {
+                                result: result.clone(),
+                                op: new_op,
+                                left: left.clone(),
+                                right: IRValue::Const(IRConstant::Int(shift)),
+                            };
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Replaces `induction_var * const` inside a loop body with a value that
+    /// starts at zero and accumulates `stride * const` every iteration,
+    /// trading a multiply for an add.
+    /// Rewrites `return f(args...)` at the tail of a block, where `f` is the
+    /// function currently being compiled, into a reassignment of the
+    /// parameters followed by a jump back to the entry block - a loop in
+    /// disguise that runs in constant stack space.
+    fn tail_call_optimization(&self, function: &mut IRFunction) {
+        let Some(entry_id) = function.blocks.first().map(|b| b.id) else {
+            return;
+        };
+        let params: Vec<String> = function.params.iter().map(|p| p.name.clone()).collect();
+        let mut next_temp = self.max_temporary(function) + 1;
+
+        for block in &mut function.blocks {
+            let mut i = 0;
+            while i + 1 < block.instructions.len() {
+                let is_self_tail_call = matches!(
+                    (&block.instructions[i], &block.instructions[i + 1]),
+                    (
+                        IRInstruction::Call { result, function: callee, .. },
+                        IRInstruction::Return { value: Some(ret_val) },
+                    ) if callee == &function.name && ret_val == result
+                );
+
+                if !is_self_tail_call {
+                    i += 1;
+                    continue;
+                }
+
+                let IRInstruction::Call { args, .. } = block.instructions[i].clone() else {
+                    unreachable!()
+                };
+
+                // Stage the new argument values in fresh temporaries before
+                // overwriting any parameter, so `f(b, a)` doesn't clobber `a`
+                // before it's read for `b`'s new value.
+                let mut replacement = Vec::new();
+                let mut staged = Vec::new();
+                for arg in &args {
+                    let temp = IRValue::Temporary(next_temp);
+                    next_temp += 1;
+                    replacement.push(IRInstruction::Assign {
+                        target: temp.clone(),
+                        value: arg.clone(),
+                    });
+                    staged.push(temp);
+                }
+                for (param, value) in params.iter().zip(staged) {
+                    replacement.push(IRInstruction::Assign {
+                        target: IRValue::Local(param.clone()),
+                        value,
+                    });
+                }
+                replacement.push(IRInstruction::Jump { target: entry_id });
+
+                let replacement_len = replacement.len();
+                block.instructions.splice(i..=i + 1, replacement);
+                i += replacement_len;
+            }
+        }
+    }
+
+    fn strength_reduce_induction_multiplies(&self, function: &mut IRFunction) {
+        let loops: Vec<(usize, usize, IRValue, usize, i64)> = function
+            .blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(b_idx, block)| {
+                block
+                    .instructions
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(i_idx, instr)| match instr {
+                        IRInstruction::LoopStart {
+                            iterator,
+                            body_block,
+                            metadata,
+                            ..
+                        } => metadata
+                            .primary_induction_variable()
+                            .map(|iv| (b_idx, i_idx, iterator.clone(), *body_block, iv.stride)),
+                        _ => None,
+                    })
+            })
+            .collect();
+
+        let mut next_temp = self.max_temporary(function) + 1;
+
+        for (entry_idx, loopstart_idx, iterator, body_block_id, stride) in loops {
+            let Some(body_idx) = function.blocks.iter().position(|b| b.id == body_block_id)
+            else {
+                continue;
+            };
+
+            let multiply = function.blocks[body_idx]
+                .instructions
+                .iter()
+                .enumerate()
+                .find_map(|(i, instr)| match instr {
+                    IRInstruction::BinOp {
+                        result,
+                        op: BinOpIR::Mul,
+                        left,
+                        right: IRValue::Const(IRConstant::Int(c)),
+                    } if left == &iterator => Some((i, result.clone(), *c)),
+                    IRInstruction::BinOp {
+                        result,
+                        op: BinOpIR::Mul,
+                        left: IRValue::Const(IRConstant::Int(c)),
+                        right,
+                    } if right == &iterator => Some((i, result.clone(), *c)),
+                    _ => None,
+                });
+
+            let Some((instr_idx, result, multiplier)) = multiply else {
+                continue;
+            };
+
+            let accumulator = IRValue::Temporary(next_temp);
+            next_temp += 1;
+
+            function.blocks[entry_idx].instructions.insert(
+                loopstart_idx,
+                IRInstruction::Assign {
+                    target: accumulator.clone(),
+                    value: IRValue::Const(IRConstant::Int(0)),
+                },
+            );
+
+            let body = &mut function.blocks[body_idx].instructions;
+            body[instr_idx] = IRInstruction::Assign {
+                target: result,
+                value: accumulator.clone(),
+            };
+            body.insert(
+                instr_idx + 1,
+                IRInstruction::BinOp {
+                    result: accumulator.clone(),
+                    op: BinOpIR::Add,
+                    left: accumulator,
+                    right: IRValue::Const(IRConstant::Int(stride * multiplier)),
+                },
+            );
+        }
+    }
+
+    /// Body instructions with the induction-variable increment and its
+    /// controlling comparison/branch stripped out, leaving just the
+    /// per-iteration payload that gets replicated by unrolling.
+    fn loop_payload(&self, body: &BasicBlock, iterator: &IRValue) -> Vec<IRInstruction> {
+        body.instructions
+            .iter()
+            .filter(|instr| match instr {
+                IRInstruction::BinOp {
+                    result,
+                    op: BinOpIR::Add,
+                    left,
+                    ..
+                } if result == iterator && left == iterator => false,
+                IRInstruction::BinOp {
+                    op: BinOpIR::Lt | BinOpIR::LtE,
+                    left,
+                    ..
+                } if left == iterator => false,
+                IRInstruction::Branch { .. } => false,
+                _ => true,
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn max_temporary(&self, function: &IRFunction) -> usize {
+        let mut max = 0;
+        for block in &function.blocks {
+            for instr in &block.instructions {
+                self.visit_values(instr, &mut |value| {
+                    if let IRValue::Temporary(n) = value {
+                        max = max.max(*n);
+                    }
+                });
+            }
+        }
+        max
+    }
+
+    /// Renumbers every `Temporary` result produced by `instr` to a fresh id
+    /// so replicated copies of the same instruction don't clash.
+    fn remap_temporaries(&self, mut instr: IRInstruction, next_temp: &mut usize) -> IRInstruction {
+        let fresh = |next_temp: &mut usize| {
+            let id = *next_temp;
+            *next_temp += 1;
+            IRValue::Temporary(id)
+        };
+
+        match &mut instr {
+            IRInstruction::BinOp { result, .. }
+            | IRInstruction::UnaryOp { result, .. }
+            | IRInstruction::Load { result, .. }
+            | IRInstruction::Index { result, .. }
+            | IRInstruction::Call { result, .. }
+            | IRInstruction::MethodCall { result, .. }
+            | IRInstruction::NewList { result, .. }
+            | IRInstruction::FormatString { result, .. } => {
+                if matches!(result, IRValue::Temporary(_)) {
+                    *result = fresh(next_temp);
+                }
+            }
+            _ => {}
+        }
+        instr
+    }
+
+    /// Replaces every occurrence of `from` with `to` inside an instruction's
+    /// operands (not its result).
+    fn substitute_value(&self, instr: &mut IRInstruction, from: &IRValue, to: &IRValue) {
+        let replace = |v: &mut IRValue| {
+            if v == from {
+                *v = to.clone();
+            }
+        };
+        match instr {
+            IRInstruction::BinOp { left, right, .. } => {
+                replace(left);
+                replace(right);
+            }
+            IRInstruction::UnaryOp { operand, .. } => replace(operand),
+            IRInstruction::Assign { value, .. } => replace(value),
+            IRInstruction::Store { value, .. } => replace(value),
+            IRInstruction::Index { array, index, .. } => {
+                replace(array);
+                replace(index);
+            }
+            IRInstruction::IndexStore { array, index, value } => {
+                replace(array);
+                replace(index);
+                replace(value);
+            }
+            IRInstruction::CacheLookup { keys, .. } => {
+                for key in keys {
+                    replace(key);
+                }
+            }
+            IRInstruction::CacheStore { keys, value, .. } => {
+                for key in keys {
+                    replace(key);
+                }
+                replace(value);
+            }
+            IRInstruction::Branch { condition, .. } => replace(condition),
+            IRInstruction::Return { value: Some(v) } => replace(v),
+            IRInstruction::Call { args, .. } => {
+                for arg in args {
+                    replace(arg);
+                }
+            }
+            IRInstruction::MethodCall { receiver, args, .. } => {
+                replace(receiver);
+                for arg in args {
+                    replace(arg);
+                }
+            }
+            IRInstruction::NewList { capacity: Some(c), .. } => replace(c),
+            IRInstruction::FormatString { parts, .. } => {
+                for part in parts {
+                    if let FormatPart::Value(v, _) = part {
+                        replace(v);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_values(&self, instr: &IRInstruction, visit: &mut impl FnMut(&IRValue)) {
+        match instr {
+            IRInstruction::BinOp {
+                result,
+                left,
+                right,
+                ..
+            } => {
+                visit(result);
+                visit(left);
+                visit(right);
+            }
+            IRInstruction::UnaryOp { result, operand, .. } => {
+                visit(result);
+                visit(operand);
+            }
+            IRInstruction::Assign { target, value } => {
+                visit(target);
+                visit(value);
+            }
+            IRInstruction::Load { result, .. } => visit(result),
+            IRInstruction::Store { value, .. } => visit(value),
+            IRInstruction::Index { result, array, index } => {
+                visit(result);
+                visit(array);
+                visit(index);
+            }
+            IRInstruction::IndexStore { array, index, value } => {
+                visit(array);
+                visit(index);
+                visit(value);
+            }
+            IRInstruction::CacheLookup { found, value, keys, .. } => {
+                visit(found);
+                visit(value);
+                for key in keys {
+                    visit(key);
+                }
+            }
+            IRInstruction::CacheStore { keys, value, .. } => {
+                for key in keys {
+                    visit(key);
+                }
+                visit(value);
+            }
+            IRInstruction::Branch { condition, .. } => visit(condition),
+            IRInstruction::Return { value: Some(v) } => visit(v),
+            IRInstruction::Call { result, args, .. } => {
+                visit(result);
+                for arg in args {
+                    visit(arg);
+                }
+            }
+            IRInstruction::MethodCall { result, receiver, args, .. } => {
+                visit(result);
+                visit(receiver);
+                for arg in args {
+                    visit(arg);
+                }
+            }
+            IRInstruction::NewList { result, capacity } => {
+                visit(result);
+                if let Some(c) = capacity {
+                    visit(c);
+                }
+            }
+            IRInstruction::FormatString { result, parts } => {
+                visit(result);
+                for part in parts {
+                    if let FormatPart::Value(v, _) = part {
+                        visit(v);
+                    }
+                }
+            }
+            IRInstruction::LoopStart { iterator, .. } => visit(iterator),
+            _ => {}
+        }
+    }
+
+    /// Size, in instructions, above which a callee is too big to inline
+    /// unless it carries the `inline` directive.
+    const INLINE_SIZE_THRESHOLD: usize = 5;
+
+    /// Module-level inlining: substitutes call sites with the callee's body
+    /// when the callee is cheap enough (or force-inlined via the `inline`
+    /// directive). Only straight-line callees (a single basic block) are
+    /// supported, since splicing multi-block control flow into a call site
+    /// would require rewiring successors and block ids across the caller.
+    fn inline_functions(&self, module: &mut IRModule) {
+        let candidates: HashMap<String, IRFunction> = module
+            .functions
+            .iter()
+            // Class methods are keyed by their bare name here, same as free
+            // functions - excluding them avoids a same-named method/function
+            // collision splicing the wrong body into a call site.
+            .filter(|f| f.owner.is_none())
+            // A `#adrenaline:no-compile` function's blocks are the ordinary
+            // lowered body, kept around only so the rest of this pipeline
+            // (this pass included) sees a normal-looking function - but
+            // `IRCodegen` never emits them, so splicing them into a caller
+            // would silently swap out the PyO3 fallback for a (possibly
+            // wrong, possibly non-compiling) inline translation.
+            .filter(|f| f.python_source.is_none())
+            .filter(|f| f.blocks.len() == 1)
+            .filter(|f| {
+                let budget = if f.directives.should_inline() {
+                    usize::MAX
+                } else {
+                    Self::INLINE_SIZE_THRESHOLD
+                };
+                f.blocks[0].instructions.len() <= budget
+            })
+            .map(|f| (f.name.clone(), f.clone()))
+            .collect();
+
+        for function in &mut module.functions {
+            self.inline_calls_in_function(function, &candidates);
+        }
+    }
+
+    fn inline_calls_in_function(
+        &self,
+        function: &mut IRFunction,
+        candidates: &HashMap<String, IRFunction>,
+    ) {
+        let mut next_temp = self.max_temporary(function) + 1;
+        let mut inlined_global_names = Vec::new();
+
+        for block in &mut function.blocks {
+            let mut expanded = Vec::with_capacity(block.instructions.len());
+
+            for instr in block.instructions.drain(..) {
+                let call = match &instr {
+                    IRInstruction::Call {
+                        result,
+                        function: callee_name,
+                        args,
+                    } if callee_name != &function.name => {
+                        candidates.get(callee_name).map(|callee| {
+                            (result.clone(), callee.clone(), args.clone())
+                        })
+                    }
+                    _ => None,
+                };
+
+                let Some((result, callee, args)) = call else {
+                    expanded.push(instr);
+                    continue;
+                };
+
+                let mut param_subst: HashMap<String, IRValue> = HashMap::new();
+                for (param, arg) in callee.params.iter().zip(args.iter()) {
+                    param_subst.insert(param.name.clone(), arg.clone());
+                }
+
+                // The callee's `global` declarations travel with its body -
+                // a caller that inlines a global-mutating function becomes
+                // a global-mutating function itself.
+                inlined_global_names.extend(callee.global_names.iter().cloned());
+
+                let mut remap: HashMap<usize, usize> = HashMap::new();
+                for callee_instr in &callee.blocks[0].instructions {
+                    let mut cloned = callee_instr.clone();
+                    self.remap_and_substitute(&mut cloned, &mut remap, &mut next_temp, &param_subst);
+
+                    match cloned {
+                        IRInstruction::Return { value: Some(value) } => {
+                            expanded.push(IRInstruction::Assign {
+                                target: result.clone(),
+                                value,
+                            });
+                        }
+                        IRInstruction::Return { value: None } => {}
+                        other => expanded.push(other),
+                    }
+                }
+            }
+
+            block.instructions = expanded;
+        }
+
+        for name in inlined_global_names {
+            if !function.global_names.contains(&name) {
+                function.global_names.push(name);
+            }
+        }
+    }
+
+    /// Remaps a cloned callee instruction's temporaries into the caller's
+    /// numbering space and substitutes references to the callee's
+    /// parameters with the actual call arguments.
+    fn remap_and_substitute(
+        &self,
+        instr: &mut IRInstruction,
+        remap: &mut HashMap<usize, usize>,
+        next_temp: &mut usize,
+        param_subst: &HashMap<String, IRValue>,
+    ) {
+        self.visit_values_mut(instr, &mut |value| match value {
+            IRValue::Temporary(n) => {
+                let fresh = *remap.entry(*n).or_insert_with(|| {
+                    let id = *next_temp;
+                    *next_temp += 1;
+                    id
+                });
+                *value = IRValue::Temporary(fresh);
+            }
+            IRValue::Local(name) => {
+                if let Some(replacement) = param_subst.get(name) {
+                    *value = replacement.clone();
+                }
+            }
+            IRValue::Const(_) => {}
+        });
+    }
+
+    fn visit_values_mut(&self, instr: &mut IRInstruction, visit: &mut impl FnMut(&mut IRValue)) {
+        match instr {
+            IRInstruction::BinOp {
+                result,
+                left,
+                right,
+                ..
+            } => {
+                visit(result);
+                visit(left);
+                visit(right);
+            }
+            IRInstruction::UnaryOp { result, operand, .. } => {
+                visit(result);
+                visit(operand);
+            }
+            IRInstruction::Assign { target, value } => {
+                visit(target);
+                visit(value);
+            }
+            IRInstruction::Load { result, .. } => visit(result),
+            IRInstruction::Store { value, .. } => visit(value),
+            IRInstruction::Index { result, array, index } => {
+                visit(result);
+                visit(array);
+                visit(index);
+            }
+            IRInstruction::IndexStore { array, index, value } => {
+                visit(array);
+                visit(index);
+                visit(value);
+            }
+            IRInstruction::CacheLookup { found, value, keys, .. } => {
+                visit(found);
+                visit(value);
+                for key in keys {
+                    visit(key);
+                }
+            }
+            IRInstruction::CacheStore { keys, value, .. } => {
+                for key in keys {
+                    visit(key);
+                }
+                visit(value);
+            }
+            IRInstruction::Branch { condition, .. } => visit(condition),
+            IRInstruction::Return { value: Some(v) } => visit(v),
+            IRInstruction::Call { result, args, .. } => {
+                visit(result);
+                for arg in args {
+                    visit(arg);
+                }
+            }
+            IRInstruction::MethodCall { result, receiver, args, .. } => {
+                visit(result);
+                visit(receiver);
+                for arg in args {
+                    visit(arg);
+                }
+            }
+            IRInstruction::NewList { result, capacity } => {
+                visit(result);
+                if let Some(c) = capacity {
+                    visit(c);
+                }
+            }
+            IRInstruction::FormatString { result, parts } => {
+                visit(result);
+                for part in parts {
+                    if let FormatPart::Value(v, _) = part {
+                        visit(v);
+                    }
+                }
+            }
+            IRInstruction::LoopStart { iterator, .. } => visit(iterator),
+            IRInstruction::NewStruct { result, fields, .. } => {
+                visit(result);
+                for (_, value) in fields {
+                    visit(value);
+                }
+            }
+            IRInstruction::Print { args, .. } => {
+                for (value, _) in args {
+                    visit(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Minimum number of independent same-op scalar instructions worth
+    /// rewriting into one `SimdBinOp` (a stand-in for the target's vector
+    /// register width).
+    const SIMD_LANE_WIDTH: usize = 4;
+
+    /// Rewrites runs of independent scalar arithmetic into `SimdBinOp`
+    /// instructions instead of just flagging the block as `Vectorizable`,
+    /// aligned to `SIMD_LANE_WIDTH` the same way a real vectorizer chunks a
+    /// loop into full vector-register-width groups plus a scalar remainder.
+    /// A run of 9 lanes becomes two `SimdBinOp`s of 4 and a single scalar
+    /// `BinOp` left in place, rather than one oddly-sized chunk. Only runs
+    /// for functions that opted in with `#adrenaline:simd`, since
+    /// `IRCodegen` renders each chunk as adjacent scalar arithmetic that
+    /// only pays off with `-C target-cpu=native` (see
+    /// `Compiler::build_rust_project`), which is itself only turned on when
+    /// generated code actually contains a chunk.
+    ///
+    /// A `c0 = a0 + b0`-style statement lowers to a `BinOp` immediately
+    /// followed by a `Store` writing its result back to `c0` - scanning for
+    /// bare consecutive `BinOp`s would never see a run longer than one,
+    /// since every statement boundary is a `Store`. Each such `Store` is
+    /// skipped over while scanning (queued in `trailers` to be re-emitted
+    /// right after its lane), but a lane whose operands reference an
+    /// *earlier* lane's stored-to local ends the run there instead of
+    /// being folded in, since that's a real dependency between lanes, not
+    /// independent same-op arithmetic.
+    fn detect_simd_opportunities(&mut self, function: &mut IRFunction) {
+        if !function.directives.simd_eligible() {
+            return;
+        }
+        let function_name = function.name.clone();
+        for block in &mut function.blocks {
+            let block_id = block.id;
+            let mut rewritten = Vec::with_capacity(block.instructions.len());
+            let mut i = 0;
+            let mut short_run_found = false;
+
+            while i < block.instructions.len() {
+                let run_op = match &block.instructions[i] {
+                    IRInstruction::BinOp {
+                        op: op @ (BinOpIR::Add | BinOpIR::Mul | BinOpIR::Sub | BinOpIR::Div),
+                        ..
+                    } => Some(*op),
+                    _ => None,
+                };
+
+                if let Some(op) = run_op {
+                    let mut lanes: Vec<(IRValue, IRValue, IRValue)> = Vec::new();
+                    let mut trailers: Vec<Option<IRInstruction>> = Vec::new();
+                    let mut written_locals = std::collections::HashSet::new();
+                    let mut j = i;
+                    while let Some(IRInstruction::BinOp { result, op: next_op, left, right }) = block.instructions.get(j) {
+                        if *next_op != op {
+                            break;
+                        }
+                        let references_prior_lane = [left, right].into_iter().any(|operand| {
+                            matches!(operand, IRValue::Local(name) if written_locals.contains(name))
+                        });
+                        if references_prior_lane {
+                            break;
+                        }
+                        lanes.push((result.clone(), left.clone(), right.clone()));
+                        j += 1;
+                        match block.instructions.get(j) {
+                            Some(IRInstruction::Store { target, value }) if value == result => {
+                                written_locals.insert(target.clone());
+                                trailers.push(Some(block.instructions[j].clone()));
+                                j += 1;
+                            }
+                            _ => trailers.push(None),
+                        }
+                    }
+
+                    if lanes.len() >= Self::SIMD_LANE_WIDTH {
+                        let chunk_count = lanes.len() / Self::SIMD_LANE_WIDTH;
+                        let remainder = lanes.len() % Self::SIMD_LANE_WIDTH;
+                        self.remark(
+                            &function_name,
+                            RemarkKind::Applied,
+                            format!(
+                                "block {block_id} vectorized: {} independent {op:?} ops rewritten to {chunk_count} SimdBinOp chunk(s) of {} plus a {remainder}-lane scalar remainder",
+                                lanes.len(),
+                                Self::SIMD_LANE_WIDTH,
+                            ),
+                        );
+                        for (chunk_idx, chunk) in lanes.chunks(Self::SIMD_LANE_WIDTH).enumerate() {
+                            let start = chunk_idx * Self::SIMD_LANE_WIDTH;
+                            if chunk.len() == Self::SIMD_LANE_WIDTH {
+                                rewritten.push(IRInstruction::SimdBinOp { op, lanes: chunk.to_vec() });
+                                for store in trailers[start..start + chunk.len()].iter().flatten() {
+                                    rewritten.push(store.clone());
+                                }
+                            } else {
+                                for (k, (result, left, right)) in chunk.iter().enumerate() {
+                                    rewritten.push(IRInstruction::BinOp {
+                                        result: result.clone(),
+                                        op,
+                                        left: left.clone(),
+                                        right: right.clone(),
+                                    });
+                                    if let Some(store) = &trailers[start + k] {
+                                        rewritten.push(store.clone());
+                                    }
+                                }
+                            }
+                        }
+                        i = j;
+                        continue;
+                    } else if lanes.len() > 1 {
+                        short_run_found = true;
+                        self.remark(
+                            &function_name,
+                            RemarkKind::NotApplied,
+                            format!("block {block_id} NOT vectorized: only {} of {} {op:?} lanes needed to fill a vector register", lanes.len(), Self::SIMD_LANE_WIDTH),
+                        );
+                    }
+                }
+
+                rewritten.push(block.instructions[i].clone());
+                i += 1;
+            }
+
+            if short_run_found {
+                rewritten.push(IRInstruction::Vectorizable);
+            }
+            block.instructions = rewritten;
+        }
+    }
+
+    /// Marks loops `Parallelizable` only when a dependence test proves it's
+    /// safe, rather than assuming every loop is embarrassingly parallel -
+    /// and only for functions that opted in with `#adrenaline:parallel`,
+    /// since spreading a loop across threads has scheduling overhead a
+    /// short or latency-sensitive loop may not want to pay even when it's
+    /// provably safe.
+    fn detect_parallel_opportunities(&mut self, function: &mut IRFunction) {
+        if !function.directives.parallelizable() {
+            return;
+        }
+        let mut hints = Vec::new();
+        let mut pending_remarks = Vec::new();
+
+        for block in &function.blocks {
+            for instr in &block.instructions {
+                if let IRInstruction::LoopStart {
+                    body_block,
+                    metadata,
+                    ..
+                } = instr
+                {
+                    // `optimize_function` runs twice (once before inlining,
+                    // once after) - skip a loop already hinted so the
+                    // second pass doesn't append duplicate hints.
+                    let already_hinted = function
+                        .blocks
+                        .iter()
+                        .find(|b| b.id == *body_block)
+                        .is_some_and(|b| {
+                            b.instructions.iter().any(|i| matches!(i, IRInstruction::Parallelizable))
+                        });
+                    if already_hinted {
+                        continue;
+                    }
+                    match self.analyze_parallel_safety(function, *body_block, metadata) {
+                        ParallelSafety::Safe => {
+                            hints.push((*body_block, IRInstruction::Parallelizable));
+                            pending_remarks.push((
+                                RemarkKind::Applied,
+                                format!("loop at block {body_block} parallelized: no cross-iteration dependency found"),
+                            ));
+                        }
+                        ParallelSafety::Reduction { target, op } => {
+                            hints.push((*body_block, IRInstruction::Parallelizable));
+                            hints.push((*body_block, IRInstruction::Reduction { target, op }));
+                            pending_remarks.push((
+                                RemarkKind::Applied,
+                                format!("loop at block {body_block} parallelized as a {op:?} reduction"),
+                            ));
+                        }
+                        ParallelSafety::Unsafe => {
+                            pending_remarks.push((
+                                RemarkKind::NotApplied,
+                                format!("loop at block {body_block} NOT parallelized: loop-carried dependency found"),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        let function_name = function.name.clone();
+        for (kind, message) in pending_remarks {
+            self.remark(&function_name, kind, message);
+        }
+
+        for (body_block, hint) in hints {
+            if let Some(block) = function.blocks.iter_mut().find(|b| b.id == body_block) {
+                block.instructions.push(hint);
+            }
+        }
+    }
+
+    /// Dependence test for a loop body: it's safe to run iterations in any
+    /// order (and thus in parallel) if every write either touches an array
+    /// slot indexed by the loop's own induction variable - so no two
+    /// iterations can touch the same slot - or is a single associative
+    /// accumulation recognized as a reduction. Any other cross-iteration
+    /// write to a named local is treated as a loop-carried dependency.
+    fn analyze_parallel_safety(
+        &self,
+        function: &IRFunction,
+        body_block: usize,
+        metadata: &LoopMetadata,
+    ) -> ParallelSafety {
+        let Some(block) = function.blocks.iter().find(|b| b.id == body_block) else {
+            return ParallelSafety::Unsafe;
+        };
+        let induction = metadata.primary_induction_variable().map(|iv| iv.value.clone());
+
+        // BinOp results that look like a reduction step on a given local -
+        // this IR reads a local directly as a `BinOp` operand (there's no
+        // separate `Load` instruction between them), so the local's name is
+        // read straight off `left`/`right` rather than through a `Load`.
+        let mut reduction_candidates: std::collections::HashMap<IRValue, (String, BinOpIR)> =
+            std::collections::HashMap::new();
+        let mut reduction: Option<(String, BinOpIR)> = None;
+
+        for instr in &block.instructions {
+            match instr {
+                IRInstruction::BinOp {
+                    result,
+                    op,
+                    left,
+                    right,
+                } => {
+                    // Only an associative *and* commutative op is safe to
+                    // reassociate across out-of-order parallel iterations -
+                    // `Sub`/`Div`/comparisons would silently change the
+                    // result depending on execution order.
+                    if !matches!(
+                        op,
+                        BinOpIR::Add | BinOpIR::Mul | BinOpIR::BitAnd | BinOpIR::BitOr | BinOpIR::BitXor
+                    ) {
+                        continue;
+                    }
+                    let name = match (left, right) {
+                        (IRValue::Local(name), _) | (_, IRValue::Local(name)) => Some(name.clone()),
+                        _ => None,
+                    };
+                    if let Some(name) = name {
+                        reduction_candidates.insert(result.clone(), (name, *op));
+                    }
+                }
+                IRInstruction::Store { target, value } => {
+                    if induction.as_ref() == Some(&IRValue::Local(target.clone())) {
+                        continue; // induction variable update, expected every iteration
+                    }
+                    let consistent_with_prior = match &reduction {
+                        None => true,
+                        Some((prior_name, prior_op)) => {
+                            reduction_candidates
+                                .get(value)
+                                .is_some_and(|(name, op)| name == prior_name && op == prior_op)
+                        }
+                    };
+                    match reduction_candidates.get(value) {
+                        Some((name, op)) if name == target && consistent_with_prior => {
+                            reduction = Some((name.clone(), *op));
+                        }
+                        _ => return ParallelSafety::Unsafe,
+                    }
+                }
+                IRInstruction::IndexStore { index, .. } if induction.as_ref() != Some(index) => {
+                    return ParallelSafety::Unsafe; // may alias across iterations
+                }
+                _ => {}
+            }
+        }
+
+        match reduction {
+            Some((name, op)) => ParallelSafety::Reduction {
+                target: IRValue::Local(name),
+                op,
+            },
+            None => ParallelSafety::Safe,
+        }
+    }
+
+    /// A function is pure if every call it makes is to itself and it never
+    /// writes through an array/pointer - so nothing it does can be observed
+    /// except through its return value, which is what makes memoizing it
+    /// (or reordering/eliding calls to it) safe.
+    fn detect_purity(&self, function: &mut IRFunction) {
+        let is_pure = function.blocks.iter().all(|block| {
+            block.instructions.iter().all(|instr| match instr {
+                IRInstruction::Call {
+                    function: callee, ..
+                } => callee == &function.name,
+                IRInstruction::IndexStore { .. } | IRInstruction::Store { .. } => false,
+                _ => true,
+            })
+        });
+
+        if is_pure {
+            if let Some(entry) = function.blocks.first_mut() {
+                entry.instructions.insert(0, IRInstruction::Pure);
+            }
+        }
+    }
+
+    /// Implements `#adrenaline:memoize`: wraps a pure function's body with a
+    /// cache keyed by its *full* argument tuple (not just the first
+    /// parameter - two calls that only share a leading argument are
+    /// different calls), so calling it twice with the same arguments reuses
+    /// the cached result instead of recomputing it. This is what turns
+    /// naive recursive fib from exponential into linear. Backed by a real
+    /// `HashMap` (see `IRInstruction::CacheLookup`/`CacheStore`) rather than
+    /// array indexing, since the argument space isn't a dense range of
+    /// small integers the way a real array index is.
+    fn memoize_pure_functions(&self, function: &mut IRFunction) {
+        if !function.directives.should_memoize() {
+            return;
+        }
+        let is_pure = matches!(
+            function.blocks.first().and_then(|b| b.instructions.first()),
+            Some(IRInstruction::Pure)
+        );
+        if !is_pure {
+            return; // memoizing an impure function could hide or reorder a side effect
+        }
+        if function.params.is_empty() {
+            return; // no arguments to key the cache on
+        }
+        let Some(original_entry) = function.blocks.first().map(|b| b.id) else {
+            return;
+        };
+
+        let cache_name = format!("__memo_{}", function.name);
+        let keys: Vec<IRValue> = function
+            .params
+            .iter()
+            .map(|p| IRValue::Local(p.name.clone()))
+            .collect();
+        let key_types: Vec<Type> = function.params.iter().map(|p| p.typ.clone()).collect();
+        let next_temp = self.max_temporary(function) + 1;
+        let next_block = function.blocks.iter().map(|b| b.id).max().unwrap_or(0) + 1;
+
+        let found = IRValue::Temporary(next_temp);
+        let value = IRValue::Temporary(next_temp + 1);
+
+        let hit_id = next_block;
+        let lookup_id = next_block + 1;
+
+        let mut lookup_block = BasicBlock::new(lookup_id);
+        lookup_block.add_instruction(IRInstruction::CacheLookup {
+            found: found.clone(),
+            value: value.clone(),
+            cache: cache_name.clone(),
+            keys: keys.clone(),
+            key_types,
+            value_type: function.return_type.clone(),
+        });
+        lookup_block.add_instruction(IRInstruction::Branch {
+            condition: found,
+            true_block: hit_id,
+            false_block: original_entry,
+        });
+        lookup_block.successors = vec![hit_id, original_entry];
+
+        let mut hit_block = BasicBlock::new(hit_id);
+        hit_block.add_instruction(IRInstruction::Return { value: Some(value) });
+
+        // Every return in the original body now also populates the cache
+        // for `keys` before handing the value back to the caller.
+        for block in &mut function.blocks {
+            let mut rewritten = Vec::with_capacity(block.instructions.len());
+            for instr in block.instructions.drain(..) {
+                if let IRInstruction::Return { value: Some(value) } = &instr {
+                    rewritten.push(IRInstruction::CacheStore {
+                        cache: cache_name.clone(),
+                        keys: keys.clone(),
+                        value: value.clone(),
+                    });
+                }
+                rewritten.push(instr);
+            }
+            block.instructions = rewritten;
+        }
+
+        function.blocks.insert(0, hit_block);
+        function.blocks.insert(0, lookup_block);
+    }
+
     fn count_instructions(&self, function: &IRFunction) -> usize {
         function.blocks.iter().map(|b| b.instructions.len()).sum()
     }
@@ -43,7 +43,43 @@ impl PythonParser {
                 self.current += 1;
             } else if trimmed.starts_with("def ") {
                 statements.push(Statement::FunctionDef(self.parse_function()?));
+            } else if trimmed.starts_with("class ") {
+                statements.push(Statement::ClassDef(self.parse_class()?));
+            } else if trimmed.starts_with("if ") && Self::is_main_guard(&trimmed) {
+                // `if __name__ == "__main__":` - always true for a script
+                // that's actually being compiled and run, so its body is
+                // spliced straight into the top level (in place, keeping
+                // program order) rather than kept as a real conditional -
+                // there's no other value `__name__` could plausibly have
+                // here worth branching on, and this sidesteps ever having
+                // to give `__name__` itself a lowering.
+                let mut directives = Vec::new();
+                if let Statement::If(if_stmt) = self.parse_if(&trimmed, 0, &mut directives)? {
+                    statements.extend(if_stmt.then_body);
+                }
+            } else if trimmed.starts_with("if ") {
+                let mut directives = Vec::new();
+                statements.push(self.parse_if(&trimmed, 0, &mut directives)?);
+            } else if trimmed.starts_with("for ") {
+                let mut directives = Vec::new();
+                statements.push(self.parse_for(&trimmed, 0, &mut directives)?);
+            } else if trimmed.starts_with("while ") {
+                let mut directives = Vec::new();
+                statements.push(self.parse_while(&trimmed, 0, &mut directives)?);
+            } else if trimmed == "try:" {
+                let mut directives = Vec::new();
+                statements.push(self.parse_try(0, &mut directives)?);
             } else {
+                // A bare top-level statement - a call (`print(...)`), a
+                // module-level assignment (`N = 1000`, see
+                // `IRLowering::lower_globals`), or anything else
+                // `parse_statement` recognizes. These become part of the
+                // synthesized `main`'s body, in program order - see
+                // `IRLowering::lower_script_main`. A line that doesn't parse
+                // is skipped, same as it always was.
+                if let Ok(stmt) = self.parse_statement(&trimmed, self.current + 1) {
+                    statements.push(stmt);
+                }
                 self.current += 1;
             }
         }
@@ -52,6 +88,7 @@ impl PythonParser {
     }
 
     fn parse_function(&mut self) -> Result<FunctionDef> {
+        let line = self.current + 1;
         let def_line = self.lines[self.current].clone();
         self.current += 1;
 
@@ -69,94 +106,570 @@ impl PythonParser {
         let return_type = self.parse_type(return_type_str);
 
         let mut directives = Vec::new();
+        let mut body = match self.peek_indent() {
+            Some(indent) => self.parse_block(indent, &mut directives)?,
+            None => Vec::new(),
+        };
+
+        if body.is_empty() {
+            body.push(Statement::Pass(line));
+        }
+
+        Ok(FunctionDef {
+            name,
+            params,
+            return_type,
+            body,
+            directives,
+            line,
+        })
+    }
+
+    /// Parses `class Name:` / `class Name(Base1, Base2):` into a `ClassDef`
+    /// whose body is only ever method definitions - see `parse_class_body`
+    /// and `IRLowering::lower_class` for the "dataclass-like" shape this
+    /// compiles.
+    fn parse_class(&mut self) -> Result<ClassDef> {
+        let line = self.current + 1;
+        let class_line = self.lines[self.current].clone();
+        self.current += 1;
+
+        let class_regex = regex::Regex::new(r"class\s+(\w+)\s*(?:\((.*?)\))?\s*:")?;
+        let caps = class_regex
+            .captures(&class_line)
+            .ok_or_else(|| anyhow!("Invalid class definition: {}", class_line))?;
+
+        let name = caps.get(1).unwrap().as_str().to_string();
+        let bases = caps
+            .get(2)
+            .map(|m| {
+                m.as_str()
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let body = match self.peek_indent() {
+            Some(indent) => self.parse_class_body(indent)?,
+            None => Vec::new(),
+        };
+
+        Ok(ClassDef { name, bases, body, line })
+    }
+
+    /// A class body: a sequence of method definitions at `indent`. Classes
+    /// only support the simple "dataclass-like" shape this compiler lowers
+    /// (see `IRLowering::lower_class`), so `def` is the only statement
+    /// recognized here - anything else at the class's indentation (a class
+    /// variable, a docstring, `pass`) is skipped rather than parsed.
+    fn parse_class_body(&mut self, indent: usize) -> Result<Vec<Statement>> {
         let mut body = Vec::new();
-        let base_indent = self.get_indent(&self.lines[self.current]);
 
         while self.current < self.lines.len() {
             let line = self.lines[self.current].clone();
-            let indent = self.get_indent(&line);
             let trimmed = line.trim().to_string();
 
-            if !line.is_empty() && !trimmed.starts_with("#") && indent <= base_indent {
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                self.current += 1;
+                continue;
+            }
+            if self.get_indent(&line) < indent {
                 break;
             }
 
-            if trimmed.contains("#adrenaline:") {
-                if let Some(start) = trimmed.find("#adrenaline:") {
-                    let directive = trimmed[start + 12..].to_string();
-                    directives.push(directive);
-                }
-            } else if !trimmed.is_empty() && !trimmed.starts_with("#") {
-                if let Ok(stmt) = self.parse_statement(&trimmed) {
+            if trimmed.starts_with("def ") {
+                body.push(Statement::FunctionDef(self.parse_function()?));
+            } else {
+                self.current += 1;
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// The indentation of the next non-blank line, without consuming it -
+    /// used to decide whether a compound statement has a body at all, and
+    /// what indentation that body's block is written at.
+    fn peek_indent(&self) -> Option<usize> {
+        let mut i = self.current;
+        while i < self.lines.len() {
+            let line = &self.lines[i];
+            if !line.trim().is_empty() {
+                return Some(self.get_indent(line));
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// Parses statements at exactly `indent`, recursing into nested blocks
+    /// for compound statements. Stops (without consuming) at the first line
+    /// less indented than `indent`, so the caller resumes at the right level -
+    /// e.g. an `if`'s `parse_else_or_elif` needs to see the un-consumed
+    /// `elif`/`else` line at the `if`'s own indentation.
+    fn parse_block(&mut self, indent: usize, directives: &mut Vec<String>) -> Result<Vec<Statement>> {
+        let mut body = Vec::new();
+
+        while self.current < self.lines.len() {
+            let line = self.lines[self.current].clone();
+            let trimmed = line.trim().to_string();
+
+            if trimmed.is_empty() {
+                self.current += 1;
+                continue;
+            }
+
+            if self.get_indent(&line) < indent {
+                break;
+            }
+
+            if let Some(start) = trimmed.find("#adrenaline:") {
+                directives.push(trimmed[start + 12..].to_string());
+                self.current += 1;
+                continue;
+            }
+            if trimmed.starts_with('#') {
+                self.current += 1;
+                continue;
+            }
+
+            if trimmed.starts_with("elif ") || trimmed == "else:" || trimmed.starts_with("else") {
+                // Belongs to an enclosing `if` this block's caller is
+                // unwinding to, not to this block.
+                break;
+            }
+
+            if trimmed.starts_with("for ") {
+                body.push(self.parse_for(&trimmed, indent, directives)?);
+            } else if trimmed.starts_with("while ") {
+                body.push(self.parse_while(&trimmed, indent, directives)?);
+            } else if trimmed.starts_with("if ") {
+                body.push(self.parse_if(&trimmed, indent, directives)?);
+            } else if trimmed == "try:" {
+                body.push(self.parse_try(indent, directives)?);
+            } else {
+                let line = self.current + 1;
+                if let Ok(stmt) = self.parse_statement(&trimmed, line) {
                     body.push(stmt);
                 }
+                self.current += 1;
             }
+        }
+
+        Ok(body)
+    }
 
-            self.current += 1;
+    /// A compound statement's body, if it has one, at whatever indentation
+    /// the first body line actually uses (typically `outer_indent + 4`, but
+    /// not assumed to be).
+    fn parse_nested_block(
+        &mut self,
+        outer_indent: usize,
+        directives: &mut Vec<String>,
+    ) -> Result<Vec<Statement>> {
+        match self.peek_indent() {
+            Some(indent) if indent > outer_indent => self.parse_block(indent, directives),
+            _ => Ok(Vec::new()),
         }
+    }
 
-        if body.is_empty() {
-            body.push(Statement::Pass);
+    fn parse_for(
+        &mut self,
+        header: &str,
+        outer_indent: usize,
+        directives: &mut Vec<String>,
+    ) -> Result<Statement> {
+        let for_regex = regex::Regex::new(r"for\s+(\w+)\s+in\s+(.+?)\s*:")?;
+        let caps = for_regex
+            .captures(header)
+            .ok_or_else(|| anyhow!("Invalid for loop: {}", header))?;
+        let target = caps.get(1).unwrap().as_str().to_string();
+        let iter = self.parse_expression(caps.get(2).unwrap().as_str())?;
+
+        let line = self.current + 1;
+        self.current += 1;
+        let body = self.parse_nested_block(outer_indent, directives)?;
+
+        Ok(Statement::For(ForLoop { target, iter, body, line }))
+    }
+
+    fn parse_while(
+        &mut self,
+        header: &str,
+        outer_indent: usize,
+        directives: &mut Vec<String>,
+    ) -> Result<Statement> {
+        let cond_str = header[6..].trim_end_matches(':');
+        let condition = self.parse_expression(cond_str)?;
+
+        let line = self.current + 1;
+        self.current += 1;
+        let body = self.parse_nested_block(outer_indent, directives)?;
+
+        Ok(Statement::While(WhileLoop { condition, body, line }))
+    }
+
+    fn parse_if(
+        &mut self,
+        header: &str,
+        outer_indent: usize,
+        directives: &mut Vec<String>,
+    ) -> Result<Statement> {
+        let cond_str = header[3..].trim_end_matches(':');
+        let condition = self.parse_expression(cond_str)?;
+
+        let line = self.current + 1;
+        self.current += 1;
+        let then_body = self.parse_nested_block(outer_indent, directives)?;
+        let else_body = self.parse_else_or_elif(outer_indent, directives)?;
+
+        Ok(Statement::If(IfStatement {
+            condition,
+            then_body,
+            else_body,
+            line,
+        }))
+    }
+
+    /// Consumes a trailing `elif`/`else` written at `outer_indent` (the
+    /// indentation of the `if` it belongs to), if one immediately follows.
+    /// An `elif` is desugared into a nested `if` in the `else` slot, the same
+    /// way Python's own grammar treats `elif` as sugar for `else: if ...`.
+    fn parse_else_or_elif(
+        &mut self,
+        outer_indent: usize,
+        directives: &mut Vec<String>,
+    ) -> Result<Option<Vec<Statement>>> {
+        let mut i = self.current;
+        while i < self.lines.len() && self.lines[i].trim().is_empty() {
+            i += 1;
+        }
+        if i >= self.lines.len() || self.get_indent(&self.lines[i]) != outer_indent {
+            return Ok(None);
         }
 
-        Ok(FunctionDef {
-            name,
-            params,
-            return_type,
+        let trimmed = self.lines[i].trim().to_string();
+        if let Some(cond_str) = trimmed.strip_prefix("elif ") {
+            let line = i + 1;
+            self.current = i + 1;
+            let condition = self.parse_expression(cond_str.trim_end_matches(':'))?;
+            let then_body = self.parse_nested_block(outer_indent, directives)?;
+            let else_body = self.parse_else_or_elif(outer_indent, directives)?;
+            Ok(Some(vec![Statement::If(IfStatement {
+                condition,
+                then_body,
+                else_body,
+                line,
+            })]))
+        } else if trimmed == "else:" {
+            self.current = i + 1;
+            Ok(Some(self.parse_nested_block(outer_indent, directives)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_try(&mut self, outer_indent: usize, directives: &mut Vec<String>) -> Result<Statement> {
+        let line = self.current + 1;
+        self.current += 1;
+        let body = self.parse_nested_block(outer_indent, directives)?;
+        let handlers = self.parse_except_clauses(outer_indent, directives)?;
+        let finalbody = self.parse_finally(outer_indent, directives)?;
+
+        Ok(Statement::Try(TryStatement {
             body,
-            directives,
-        })
+            handlers,
+            finalbody,
+            line,
+        }))
+    }
+
+    /// Consumes zero or more `except ...:` clauses written at `outer_indent`
+    /// (the indentation of the `try` they belong to) - the same
+    /// peek-then-consume shape `parse_else_or_elif` uses for `elif`/`else`,
+    /// looped since a `try` can have more than one `except`.
+    fn parse_except_clauses(
+        &mut self,
+        outer_indent: usize,
+        directives: &mut Vec<String>,
+    ) -> Result<Vec<ExceptHandler>> {
+        let except_regex = regex::Regex::new(r"^except(\s+(\w+))?(\s+as\s+(\w+))?\s*:$")?;
+        let mut handlers = Vec::new();
+
+        loop {
+            let mut i = self.current;
+            while i < self.lines.len() && self.lines[i].trim().is_empty() {
+                i += 1;
+            }
+            if i >= self.lines.len() || self.get_indent(&self.lines[i]) != outer_indent {
+                break;
+            }
+            let trimmed = self.lines[i].trim().to_string();
+            let Some(caps) = except_regex.captures(&trimmed) else {
+                break;
+            };
+
+            self.current = i + 1;
+            let exception_type = caps.get(2).map(|m| m.as_str().to_string());
+            let name = caps.get(4).map(|m| m.as_str().to_string());
+            let body = self.parse_nested_block(outer_indent, directives)?;
+            handlers.push(ExceptHandler {
+                exception_type,
+                name,
+                body,
+            });
+        }
+
+        Ok(handlers)
+    }
+
+    /// Consumes a trailing `finally:` clause written at `outer_indent`, if
+    /// one immediately follows the `try`'s `except` clauses.
+    fn parse_finally(
+        &mut self,
+        outer_indent: usize,
+        directives: &mut Vec<String>,
+    ) -> Result<Vec<Statement>> {
+        let mut i = self.current;
+        while i < self.lines.len() && self.lines[i].trim().is_empty() {
+            i += 1;
+        }
+        if i >= self.lines.len()
+            || self.get_indent(&self.lines[i]) != outer_indent
+            || self.lines[i].trim() != "finally:"
+        {
+            return Ok(Vec::new());
+        }
+
+        self.current = i + 1;
+        self.parse_nested_block(outer_indent, directives)
     }
 
-    fn parse_statement(&self, line: &str) -> Result<Statement> {
+    fn parse_statement(&self, line: &str, line_no: usize) -> Result<Statement> {
         let trimmed = line.trim();
 
-        // Assignment
-        if trimmed.contains('=') && !trimmed.contains("==") {
-            let parts: Vec<&str> = trimmed.splitn(2, '=').collect();
-            let targets = vec![parts[0].trim().to_string()];
-            let value = self.parse_expression(parts[1].trim())?;
-            return Ok(Statement::Assign(Assignment { targets, value }));
-        }
-
-        // For loop
-        if trimmed.starts_with("for ") {
-            let for_regex = regex::Regex::new(r"for\s+(\w+)\s+in\s+(.+?)\s*:")?;
-            if let Some(caps) = for_regex.captures(trimmed) {
-                let target = caps.get(1).unwrap().as_str().to_string();
-                let iter = self.parse_expression(caps.get(2).unwrap().as_str())?;
-                return Ok(Statement::For(ForLoop {
-                    target,
-                    iter,
-                    body: vec![],
-                }));
-            }
+        // Augmented assignment (`x += 1`, ...) - checked before the plain
+        // `=` case below, since `find_top_level_eq` has no notion of a
+        // compound operator and would otherwise slice the `+`/`-`/... into
+        // the assignment target's name.
+        if let Some((target, op, expr_str)) = Self::find_aug_assign(trimmed) {
+            let value = self.parse_expression(expr_str)?;
+            return Ok(Statement::AugAssign(AugAssignment { target, op, value, line: line_no }));
         }
 
-        // If statement
-        if trimmed.starts_with("if ") {
-            let cond_str = trimmed[3..].trim_end_matches(':');
-            let condition = self.parse_expression(cond_str)?;
-            return Ok(Statement::If(IfStatement {
-                condition,
-                then_body: vec![],
-                else_body: None,
-            }));
+        // Assignment - the `=` must be at paren/bracket depth 0, so a kwarg
+        // inside a bare call statement (e.g. `print(x, sep=", ")`) isn't
+        // mistaken for an assignment to `print(x, sep`.
+        if let Some(eq_pos) = Self::find_top_level_eq(trimmed) {
+            let targets = vec![trimmed[..eq_pos].trim().to_string()];
+            let value = self.parse_expression(trimmed[eq_pos + 1..].trim())?;
+            return Ok(Statement::Assign(Assignment { targets, value, line: line_no }));
         }
 
         // Return
-        if trimmed.starts_with("return ") {
-            let expr_str = &trimmed[7..];
+        if let Some(expr_str) = trimmed.strip_prefix("return ") {
             let expr = if expr_str.trim().is_empty() {
                 None
             } else {
                 Some(self.parse_expression(expr_str)?)
             };
-            return Ok(Statement::Return(expr));
+            return Ok(Statement::Return(expr, line_no));
+        }
+        if trimmed == "return" {
+            return Ok(Statement::Return(None, line_no));
+        }
+
+        // Yield - see `Statement::Yield` for how narrowly this is compiled.
+        if let Some(expr_str) = trimmed.strip_prefix("yield ") {
+            let expr = self.parse_expression(expr_str)?;
+            return Ok(Statement::Yield(expr, line_no));
+        }
+
+        // `global x, y` - see `Statement::Global`.
+        if let Some(rest) = trimmed.strip_prefix("global ") {
+            let names = rest.split(',').map(|s| s.trim().to_string()).collect();
+            return Ok(Statement::Global(names, line_no));
+        }
+
+        if trimmed == "pass" {
+            return Ok(Statement::Pass(line_no));
+        }
+        if trimmed == "break" {
+            return Ok(Statement::Break(line_no));
+        }
+        if trimmed == "continue" {
+            return Ok(Statement::Continue(line_no));
         }
 
         let expr = self.parse_expression(trimmed)?;
-        Ok(Statement::ExprStatement(expr))
+        Ok(Statement::ExprStatement(expr, line_no))
+    }
+
+    /// Splits a call's argument list on top-level commas, skipping ones
+    /// nested inside `(...)`/`[...]` or inside a quoted string literal - so
+    /// `print("a, b", sep=", ")` doesn't get split apart at the comma
+    /// inside either string, the way a plain `.split(',')` would.
+    fn split_call_args(args_str: &str) -> Vec<String> {
+        let chars: Vec<char> = args_str.chars().collect();
+        let mut parts = Vec::new();
+        let mut depth = 0i32;
+        let mut in_string: Option<char> = None;
+        let mut start = 0;
+
+        for (i, &c) in chars.iter().enumerate() {
+            match in_string {
+                Some(quote) if c == quote => in_string = None,
+                Some(_) => {}
+                None => match c {
+                    '"' | '\'' => in_string = Some(c),
+                    '(' | '[' => depth += 1,
+                    ')' | ']' => depth -= 1,
+                    ',' if depth == 0 => {
+                        parts.push(chars[start..i].iter().collect());
+                        start = i + 1;
+                    }
+                    _ => {}
+                },
+            }
+        }
+        parts.push(chars[start..].iter().collect());
+        parts
+    }
+
+    /// Rightmost index of `op_str` in `s` that sits at paren/bracket depth 0
+    /// and outside any string literal, or `None` if there's no such
+    /// occurrence. Used to split binary operators without tearing apart a
+    /// nested call's or index's arguments, e.g. `foo(a + b) * c` must split
+    /// on the `*`, not the `+` inside `foo(...)`.
+    fn rfind_top_level(s: &str, op_str: &str) -> Option<usize> {
+        let bytes = s.as_bytes();
+        let op_bytes = op_str.as_bytes();
+        let mut depth = 0i32;
+        let mut in_string: Option<u8> = None;
+        let mut best = None;
+
+        let mut i = 0;
+        while i < bytes.len() {
+            let c = bytes[i];
+            match in_string {
+                Some(quote) if c == quote => in_string = None,
+                Some(_) => {}
+                None => match c {
+                    b'"' | b'\'' => in_string = Some(c),
+                    b'(' | b'[' => depth += 1,
+                    b')' | b']' => depth -= 1,
+                    _ if depth == 0 && bytes[i..].starts_with(op_bytes) => {
+                        best = Some(i);
+                    }
+                    _ => {}
+                },
+            }
+            i += 1;
+        }
+        best
+    }
+
+    /// Whether a top-level `if ...:` header is the `__name__ == "__main__"`
+    /// entry-point idiom - checked loosely (both quote styles, either
+    /// operand order, incidental whitespace) rather than with a single
+    /// exact string match, since real scripts spell it a few different ways.
+    fn is_main_guard(header: &str) -> bool {
+        let cond = header
+            .trim_start_matches("if ")
+            .trim_end()
+            .trim_end_matches(':')
+            .replace(' ', "");
+        cond == "__name__==\"__main__\""
+            || cond == "__name__=='__main__'"
+            || cond == "\"__main__\"==__name__"
+            || cond == "'__main__'==__name__"
+    }
+
+    /// Finds a statement-level assignment `=`, ignoring one nested inside
+    /// `(...)`/`[...]` (a call's kwarg, or a list/index expression) and
+    /// `==`/`<=`/`>=`/`!=`.
+    fn find_top_level_eq(line: &str) -> Option<usize> {
+        let bytes = line.as_bytes();
+        let mut depth = 0i32;
+        for (i, &b) in bytes.iter().enumerate() {
+            match b {
+                b'(' | b'[' => depth += 1,
+                b')' | b']' => depth -= 1,
+                b'=' if depth == 0 => {
+                    let prev_is_comparison = i > 0 && matches!(bytes[i - 1], b'=' | b'<' | b'>' | b'!');
+                    let next_is_eq = bytes.get(i + 1) == Some(&b'=');
+                    if !prev_is_comparison && !next_is_eq {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Recognizes `name OP= expr` (`+=`, `-=`, `*=`, `/=`, `%=`) at the
+    /// start of a statement, splitting it into the plain target name, the
+    /// underlying `BinOp`, and the unparsed RHS text - `find_top_level_eq`
+    /// has no notion of the compound operator, so this has to run first.
+    /// Only a bare name target is recognized (no `self.x +=`/`a[i] +=`),
+    /// matching the narrower shape `Statement::AugAssign` itself supports.
+    fn find_aug_assign(line: &str) -> Option<(String, BinOp, &str)> {
+        let re = regex::Regex::new(r"^(\w+)\s*(\+|-|\*|/|%)=\s*(.+)$").ok()?;
+        let caps = re.captures(line)?;
+        let target = caps.get(1)?.as_str().to_string();
+        let op = match caps.get(2)?.as_str() {
+            "+" => BinOp::Add,
+            "-" => BinOp::Sub,
+            "*" => BinOp::Mult,
+            "/" => BinOp::Div,
+            "%" => BinOp::Mod,
+            _ => return None,
+        };
+        let expr_str = caps.get(3)?.as_str();
+        Some((target, op, expr_str))
+    }
+
+    /// Parses one call argument, recognizing `name=value` (e.g. `print`'s
+    /// `sep=`/`end=`) as `Expression::Kwarg` before falling back to a plain
+    /// positional expression.
+    fn parse_call_arg(&self, arg: &str) -> Expression {
+        if let Some(eq_pos) = Self::find_kwarg_eq(arg) {
+            let name = arg[..eq_pos].trim().to_string();
+            let value = self
+                .parse_expression(arg[eq_pos + 1..].trim())
+                .unwrap_or(Expression::IntLit(0));
+            return Expression::Kwarg(name, Box::new(value));
+        }
+        self.parse_expression(arg).unwrap_or(Expression::IntLit(0))
+    }
+
+    /// Finds the `=` in a `name=value` call argument, ignoring `==`/`<=`/
+    /// `>=`/`!=` and requiring what precedes it to look like a bare
+    /// identifier (so `x == y` and similar comparisons parse as plain
+    /// expressions, not kwargs).
+    fn find_kwarg_eq(arg: &str) -> Option<usize> {
+        let bytes = arg.as_bytes();
+        for (i, &b) in bytes.iter().enumerate() {
+            if b != b'=' {
+                continue;
+            }
+            let prev_is_comparison = i > 0 && matches!(bytes[i - 1], b'=' | b'<' | b'>' | b'!');
+            let next_is_eq = bytes.get(i + 1) == Some(&b'=');
+            if prev_is_comparison || next_is_eq {
+                continue;
+            }
+            let name = arg[..i].trim();
+            let is_identifier = !name.is_empty()
+                && name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if is_identifier {
+                return Some(i);
+            }
+        }
+        None
     }
 
     fn parse_expression(&self, expr_str: &str) -> Result<Expression> {
@@ -181,27 +694,59 @@ impl PythonParser {
             return Ok(Expression::StringLit(trimmed[1..trimmed.len() - 1].to_string()));
         }
 
-        // Function call
-        if trimmed.contains('(') && trimmed.ends_with(')') {
-            if let Some(paren_pos) = trimmed.find('(') {
-                let func_name = trimmed[..paren_pos].trim();
-                let args_str = &trimmed[paren_pos + 1..trimmed.len() - 1];
-                let args = if args_str.is_empty() {
-                    vec![]
-                } else {
-                    args_str
-                        .split(',')
-                        .map(|arg| self.parse_expression(arg.trim()).unwrap_or(Expression::IntLit(0)))
-                        .collect()
-                };
-                return Ok(Expression::Call(
-                    Box::new(Expression::Identifier(func_name.to_string())),
-                    args,
-                ));
+        // f-string: `f"...{expr}...{expr:spec}..."` - checked before the
+        // plain string literal above wouldn't ever match this (it starts
+        // with `f`, not a quote), but is kept here since it's the same kind
+        // of literal.
+        if (trimmed.starts_with("f\"") && trimmed.ends_with('"'))
+            || (trimmed.starts_with("f'") && trimmed.ends_with('\''))
+        {
+            let inner = &trimmed[2..trimmed.len() - 1];
+            return Ok(Expression::FString(self.parse_fstring_parts(inner)));
+        }
+
+        // List literal: `[1, 2, 3]` (an empty `arr_name` distinguishes this
+        // from `arr[i]` indexing below, which never starts at position 0).
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            let inner = trimmed[1..trimmed.len() - 1].trim();
+            let items = if inner.is_empty() {
+                vec![]
+            } else {
+                inner
+                    .split(',')
+                    .map(|item| self.parse_expression(item.trim()).unwrap_or(Expression::IntLit(0)))
+                    .collect()
+            };
+            return Ok(Expression::List(items));
+        }
+
+        // Membership tests (checked before the function-call/binop branches
+        // below since `in`/`not in` are keywords, not `(`/arithmetic-symbol
+        // operators).
+        if let Some(pos) = trimmed.rfind(" not in ") {
+            let left_str = trimmed[..pos].trim();
+            let right_str = trimmed[pos + " not in ".len()..].trim();
+            if !left_str.is_empty() && !right_str.is_empty() {
+                let left = self.parse_expression(left_str)?;
+                let right = self.parse_expression(right_str)?;
+                return Ok(Expression::BinOp(Box::new(left), BinOp::NotIn, Box::new(right)));
+            }
+        }
+        if let Some(pos) = trimmed.rfind(" in ") {
+            let left_str = trimmed[..pos].trim();
+            let right_str = trimmed[pos + " in ".len()..].trim();
+            if !left_str.is_empty() && !right_str.is_empty() {
+                let left = self.parse_expression(left_str)?;
+                let right = self.parse_expression(right_str)?;
+                return Ok(Expression::BinOp(Box::new(left), BinOp::In, Box::new(right)));
             }
         }
 
-        // Binary operations (check longest first)
+        // Binary operations (check longest first). Split only on operator
+        // occurrences at paren/bracket depth 0, so this runs *before* the
+        // function-call branch below - otherwise `total + square(i)` would
+        // be swallowed whole as a call to the function `"total + square"`
+        // instead of splitting into `BinOp(total, Add, Call(square, [i]))`.
         let ops = vec![
             ("**", BinOp::Pow),
             ("//", BinOp::FloorDiv),
@@ -219,19 +764,44 @@ impl PythonParser {
         ];
 
         for (op_str, op) in ops {
-            if trimmed.contains(op_str) {
-                if let Some(pos) = trimmed.rfind(op_str) {
-                    let left_str = trimmed[..pos].trim();
-                    let right_str = trimmed[pos + op_str.len()..].trim();
-                    if !left_str.is_empty() && !right_str.is_empty() {
-                        let left = self.parse_expression(left_str)?;
-                        let right = self.parse_expression(right_str)?;
-                        return Ok(Expression::BinOp(Box::new(left), op, Box::new(right)));
-                    }
+            if let Some(pos) = Self::rfind_top_level(trimmed, op_str) {
+                let left_str = trimmed[..pos].trim();
+                let right_str = trimmed[pos + op_str.len()..].trim();
+                if !left_str.is_empty() && !right_str.is_empty() {
+                    let left = self.parse_expression(left_str)?;
+                    let right = self.parse_expression(right_str)?;
+                    return Ok(Expression::BinOp(Box::new(left), op, Box::new(right)));
                 }
             }
         }
 
+        // Function call, including method calls like `lst.append(x)` -
+        // everything before the `.` immediately preceding the method name
+        // becomes the receiver of an `Attribute` callee instead of a plain
+        // `Identifier`.
+        if trimmed.contains('(') && trimmed.ends_with(')') {
+            if let Some(paren_pos) = trimmed.find('(') {
+                let func_name = trimmed[..paren_pos].trim();
+                let args_str = &trimmed[paren_pos + 1..trimmed.len() - 1];
+                let args = if args_str.is_empty() {
+                    vec![]
+                } else {
+                    Self::split_call_args(args_str)
+                        .iter()
+                        .map(|arg| self.parse_call_arg(arg.trim()))
+                        .collect()
+                };
+                let callee = match func_name.rfind('.') {
+                    Some(dot_pos) => Expression::Attribute(
+                        Box::new(Expression::Identifier(func_name[..dot_pos].to_string())),
+                        func_name[dot_pos + 1..].to_string(),
+                    ),
+                    None => Expression::Identifier(func_name.to_string()),
+                };
+                return Ok(Expression::Call(Box::new(callee), args));
+            }
+        }
+
         // Index
         if trimmed.contains('[') && trimmed.ends_with(']') {
             if let Some(bracket_pos) = trimmed.find('[') {
@@ -248,26 +818,68 @@ impl PythonParser {
         Ok(Expression::Identifier(trimmed.to_string()))
     }
 
+    /// Splits an f-string's inner text (already stripped of the leading `f`
+    /// and surrounding quotes) into literal runs and `{expr}`/`{expr:spec}`
+    /// interpolations. Braces aren't nested here (no `{{`/`}}` escaping),
+    /// matching this parser's general single-pass, no-lookahead style.
+    fn parse_fstring_parts(&self, inner: &str) -> Vec<FStringPart> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = inner.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '{' {
+                literal.push(c);
+                continue;
+            }
+            if !literal.is_empty() {
+                parts.push(FStringPart::Literal(std::mem::take(&mut literal)));
+            }
+            let field: String = chars.by_ref().take_while(|&c| c != '}').collect();
+            let (expr_str, spec) = match field.find(':') {
+                Some(pos) => (field[..pos].trim(), Some(field[pos + 1..].to_string())),
+                None => (field.trim(), None),
+            };
+            let expr = self.parse_expression(expr_str).unwrap_or(Expression::IntLit(0));
+            parts.push(FStringPart::Expr(Box::new(expr), spec));
+        }
+        if !literal.is_empty() {
+            parts.push(FStringPart::Literal(literal));
+        }
+        parts
+    }
+
+    /// Parses `name`, `name: type`, `name = default`, or `name: type =
+    /// default`, splitting the parameter list on top-level commas the same
+    /// way `split_call_args` splits a call's argument list - a default
+    /// expression can itself contain commas (`xs: list = [1, 2, 3]`), so a
+    /// plain `.split(',')` would tear it apart.
     fn parse_parameters(&self, params_str: &str) -> Result<Vec<Parameter>> {
         let mut params = Vec::new();
         if params_str.trim().is_empty() {
             return Ok(params);
         }
 
-        for param in params_str.split(',') {
+        for param in Self::split_call_args(params_str) {
             let trimmed = param.trim();
-            let parts: Vec<&str> = trimmed.split(':').collect();
+            let (decl, default) = match Self::find_top_level_eq(trimmed) {
+                Some(eq_pos) => (
+                    trimmed[..eq_pos].trim(),
+                    Some(
+                        self.parse_expression(trimmed[eq_pos + 1..].trim())
+                            .unwrap_or(Expression::IntLit(0)),
+                    ),
+                ),
+                None => (trimmed, None),
+            };
+            let parts: Vec<&str> = decl.split(':').collect();
             let name = parts[0].trim().to_string();
             let typ = if parts.len() > 1 {
                 self.parse_type(parts[1].trim())
             } else {
                 Type::Int
             };
-            params.push(Parameter {
-                name,
-                typ,
-                default: None,
-            });
+            params.push(Parameter { name, typ, default });
         }
 
         Ok(params)
@@ -1,22 +1,95 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 /// Profiler and runtime metrics
 /// Tracks function calls, execution time, and hot paths
+use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct FunctionProfile {
+    // Kept for debugging/display but no reader looks it up by field yet -
+    // callers index profiles by name in the enclosing map instead.
+    #[allow(dead_code)]
     pub name: String,
     pub call_count: Arc<AtomicUsize>,
     pub total_time_ns: Arc<AtomicUsize>,
 }
 
-#[derive(Debug, Clone)]
+/// Alternate serializations of a `Vec<ProfileData>` report, chosen via
+/// `adrenaline profile --output` - `Compiler::render_profile_report` falls
+/// back to its own table/JSON when this is `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    /// kcachegrind/speedscope's callgrind profile format - a minimal,
+    /// hand-written subset (one `fn=`/cost-line pair per function, `Calls`
+    /// and time in nanoseconds as its only two events) rather than a full
+    /// implementation of the spec, since there's no callgrind-writing crate
+    /// in this workspace.
+    Callgrind,
+}
+
+impl ProfileData {
+    /// Renders `report` per `ReportFormat::Csv` - one row per function,
+    /// unquoted since a Python function name can't contain a comma.
+    pub fn to_csv(report: &[ProfileData]) -> String {
+        let mut out = String::from(
+            "function,call_count,total_time_ms,avg_time_us,alloc_count,alloc_bytes,instructions,cache_misses,branch_misses\n",
+        );
+        for data in report {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                data.function,
+                data.call_count,
+                data.total_time_ms,
+                data.avg_time_us,
+                data.alloc_count,
+                data.alloc_bytes,
+                data.instructions,
+                data.cache_misses,
+                data.branch_misses,
+            ));
+        }
+        out
+    }
+
+    /// Renders `report` per `ReportFormat::Callgrind` - see
+    /// `ReportFormat::Callgrind`'s doc comment for what's simplified.
+    pub fn to_callgrind(report: &[ProfileData]) -> String {
+        let mut out = String::from("version: 1\ncreator: adrenaline profile\npositions: line\nevents: Calls Time_ns\n\n");
+        for data in report {
+            let total_time_ns = (data.total_time_ms * 1_000_000.0) as u64;
+            out.push_str(&format!("fn={}\n1 {} {}\n\n", data.function, data.call_count, total_time_ns));
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProfileData {
     pub function: String,
     pub call_count: usize,
     pub total_time_ms: f64,
     pub avg_time_us: f64,
+    /// Only ever non-zero for a report produced with `--profile-alloc` (see
+    /// `adrenaline_runtime::alloc_profiling`) - `#[serde(default)]` so a
+    /// profile saved before this field existed still loads.
+    #[serde(default)]
+    pub alloc_count: u64,
+    #[serde(default)]
+    pub alloc_bytes: u64,
+    /// Only ever non-zero for a report produced with `--profile-hwcounters`
+    /// (see `adrenaline_runtime::hw_counters`) - `#[serde(default)]` for the
+    /// same reason as `alloc_count`/`alloc_bytes`.
+    #[serde(default)]
+    pub instructions: u64,
+    #[serde(default)]
+    pub cache_misses: u64,
+    #[serde(default)]
+    pub branch_misses: u64,
 }
 
 pub struct Profiler {
@@ -41,6 +114,20 @@ impl Profiler {
         counter
     }
 
+    /// Records one timed call for `name`, previously seeded with
+    /// `register_function` - a no-op if it wasn't (nothing to attribute the
+    /// time to). Used by `Compiler::profile` once per iteration.
+    pub fn record_call(&self, name: &str, duration: std::time::Duration) {
+        if let Some(profile) = self.profiles.get(name) {
+            profile.call_count.fetch_add(1, Ordering::Relaxed);
+            profile.total_time_ns.fetch_add(duration.as_nanos() as usize, Ordering::Relaxed);
+        }
+    }
+
+    // No caller filters by a raw call-count threshold yet - `adrenaline
+    // profile`'s reports sort by total time instead (see
+    // `Compiler::render_profile_report`).
+    #[allow(dead_code)]
     pub fn get_hot_functions(&self, threshold: usize) -> Vec<String> {
         let mut functions: Vec<_> = self
             .profiles
@@ -55,7 +142,7 @@ impl Profiler {
             })
             .collect();
 
-        functions.sort_by(|a, b| b.1.cmp(&a.1));
+        functions.sort_by_key(|b| std::cmp::Reverse(b.1));
         functions.into_iter().map(|(name, _)| name).collect()
     }
 
@@ -78,6 +165,11 @@ impl Profiler {
                     call_count,
                     total_time_ms,
                     avg_time_us,
+                    alloc_count: 0,
+                    alloc_bytes: 0,
+                    instructions: 0,
+                    cache_misses: 0,
+                    branch_misses: 0,
                 }
             })
             .collect();
@@ -85,6 +177,23 @@ impl Profiler {
         results.sort_by(|a, b| b.total_time_ms.partial_cmp(&a.total_time_ms).unwrap());
         results
     }
+
+    /// Persists this run's report so a later build can feed it back into
+    /// the optimizer as profile-guided optimization data.
+    // No caller wires a saved report back into the optimizer yet - PGO
+    // consumption doesn't exist, only this write side.
+    #[allow(dead_code)]
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.report())?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Loads a report saved by a previous run via [`Profiler::save_to_file`].
+    pub fn load_from_file(path: &Path) -> Result<Vec<ProfileData>> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
 }
 
 impl Default for Profiler {
@@ -3,58 +3,88 @@
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 use crate::compiler::Compiler;
-use crate::ast_types::*;
+use crate::interpreter::Interpreter;
+use crate::parser::AdrenalineParser;
 use std::path::Path;
 use anyhow::Result;
 
 pub struct Repl {
+    // Held for a future "JIT hot REPL functions after N calls" path - all
+    // evaluation currently goes through `interpreter` (see
+    // `execute_expression`).
+    #[allow(dead_code)]
     compiler: Compiler,
-    variables: std::collections::HashMap<String, String>,
-    functions: std::collections::HashMap<String, FunctionDef>,
+    interpreter: Interpreter,
 }
 
 impl Repl {
     pub fn new(project_dir: &Path) -> Result<Self> {
         Ok(Self {
             compiler: Compiler::new(project_dir)?,
-            variables: std::collections::HashMap::new(),
-            functions: std::collections::HashMap::new(),
+            interpreter: Interpreter::new(),
         })
     }
 
     pub fn run(&mut self) -> Result<()> {
         let mut rl = DefaultEditor::new()?;
-        
+
         println!("⚡ Adrenaline REPL v0.1");
         println!("Type 'help' for commands, 'exit' to quit\n");
 
+        // Lines of a `def`/`if`/`for`/`while`/... block collected across
+        // several `... ` continuation prompts, joined and dispatched as one
+        // unit once a blank line closes it - like the CPython REPL, a block
+        // is only ever ended by a blank line, never by dedenting.
+        let mut block: Vec<String> = Vec::new();
+        let mut next_indent = String::new();
+
         loop {
-            let readline = rl.readline(">>> ");
+            let readline = if block.is_empty() {
+                rl.readline(">>> ")
+            } else {
+                rl.readline_with_initial("... ", (&next_indent, ""))
+            };
             match readline {
                 Ok(line) => {
                     rl.add_history_entry(&line)?;
-                    
-                    if line.trim().is_empty() {
-                        continue;
-                    }
-
-                    if line.trim() == "exit" || line.trim() == "quit" {
-                        println!("Goodbye!");
-                        break;
-                    }
-
-                    if line.trim() == "help" {
-                        self.print_help();
-                        continue;
-                    }
 
-                    if line.trim().starts_with("def ") {
-                        self.handle_function_definition(&line);
-                        continue;
-                    }
-
-                    if let Err(e) = self.execute_expression(&line) {
-                        println!("Error: {}", e);
+                    if block.is_empty() {
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+
+                        if line.trim() == "exit" || line.trim() == "quit" {
+                            println!("Goodbye!");
+                            break;
+                        }
+
+                        if line.trim() == "help" {
+                            self.print_help();
+                            continue;
+                        }
+
+                        if Self::opens_block(&line) {
+                            next_indent = " ".repeat(Self::indent_of(&line) + 4);
+                            block.push(line);
+                            continue;
+                        }
+
+                        if let Err(e) = self.execute_expression(&line) {
+                            println!("Error: {}", e);
+                        }
+                    } else if line.trim().is_empty() {
+                        let source = block.join("\n");
+                        block.clear();
+                        if let Err(e) = self.execute_expression(&source) {
+                            println!("Error: {}", e);
+                        }
+                    } else {
+                        next_indent = " ".repeat(if Self::opens_block(&line) {
+                            Self::indent_of(&line) + 4
+                        } else {
+                            Self::indent_of(&line)
+                        });
+                        block.push(line);
                     }
                 }
                 Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {
@@ -70,76 +100,34 @@ impl Repl {
         Ok(())
     }
 
-    fn execute_expression(&mut self, input: &str) -> Result<()> {
-        let trimmed = input.trim();
-
-        // Variable assignment
-        if trimmed.contains('=') && !trimmed.contains("==") {
-            let parts: Vec<&str> = trimmed.splitn(2, '=').collect();
-            let var_name = parts[0].trim().to_string();
-            let expr_str = parts[1].trim();
-
-            // Try to evaluate simple expressions
-            if let Ok(n) = expr_str.parse::<i64>() {
-                self.variables.insert(var_name.clone(), format!("{}", n));
-                println!("{} = {}", var_name, n);
-                return Ok(());
-            }
-
-            if let Ok(f) = expr_str.parse::<f64>() {
-                self.variables.insert(var_name.clone(), format!("{}", f));
-                println!("{} = {}", var_name, f);
-                return Ok(());
-            }
-        }
-
-        // Function call or expression evaluation
-        if trimmed.contains('(') {
-            println!("Function calls not yet fully implemented in REPL");
-            return Ok(());
-        }
-
-        // Simple arithmetic
-        if trimmed.contains('+') || trimmed.contains('-') || trimmed.contains('*') || trimmed.contains('/') {
-            if let Ok(result) = self.eval_simple_math(trimmed) {
-                println!("{}", result);
-                return Ok(());
-            }
-        }
-
-        // Variable lookup
-        if let Some(value) = self.variables.get(trimmed) {
-            println!("{}", value);
-            return Ok(());
-        }
-
-        println!("Error: Could not evaluate '{}'", trimmed);
-        Ok(())
+    /// Whether `line` opens an indented block that needs a `... `
+    /// continuation - a trailing `:` once any trailing whitespace is
+    /// stripped, same rule Python's own grammar uses for `def`/`if`/`for`/
+    /// `while`/`class`/`try`/`with`/`else`/`elif`/etc.
+    fn opens_block(line: &str) -> bool {
+        line.trim_end().ends_with(':')
     }
 
-    fn eval_simple_math(&self, expr: &str) -> Result<f64> {
-        let expr = expr.trim();
-        
-        // Replace variables with their values
-        let mut processed = expr.to_string();
-        for (var, value) in &self.variables {
-            processed = processed.replace(var, value);
-        }
-
-        // Try to parse and evaluate as a number
-        if let Ok(n) = processed.parse::<f64>() {
-            return Ok(n);
-        }
-
-        // Basic parsing for simple expressions
-        // This is a placeholder; real implementation would use an expression parser
-        Err(anyhow::anyhow!("Cannot evaluate: {}", expr))
+    /// Number of leading space characters on `line` - used to compute the
+    /// next continuation prompt's pre-filled indentation.
+    fn indent_of(line: &str) -> usize {
+        line.len() - line.trim_start_matches(' ').len()
     }
 
-    fn handle_function_definition(&mut self, _input: &str) {
-        // For now, just store the function definition string
-        // Real implementation would parse and store as FunctionDef
-        println!("Function definition recorded");
+    /// Parses `input` (one full statement/expression, or a joined multi-line
+    /// block from `run`, including a `def`) with the same `AdrenalineParser`
+    /// a real compile uses, then runs it through `self.interpreter` - whose
+    /// environment persists across calls, so a variable or function defined
+    /// on an earlier line is still visible here. A `def` itself just
+    /// registers the function (see `Interpreter::eval_statement`'s
+    /// `Statement::FunctionDef` arm) and echoes nothing, matching how a bare
+    /// value-producing expression is the only thing that prints a result.
+    fn execute_expression(&mut self, input: &str) -> Result<()> {
+        let program = AdrenalineParser::parse(input)?;
+        if let Some(value) = self.interpreter.eval_program(&program)? {
+            println!("{value}");
+        }
+        Ok(())
     }
 
     fn print_help(&self) {
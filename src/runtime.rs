@@ -1,9 +1,12 @@
-/// Runtime support library
-/// Embedded runtime for fallback execution and FFI
-
+// Runtime support library
+// Embedded runtime for fallback execution and FFI
+//
 // This module would contain Python FFI bindings and runtime helpers
 // For now, a placeholder that shows the architecture
 
+// Nothing in this file is wired up yet - no caller reaches the fallback
+// executor, the FFI bridge, or the arena allocator sketched below.
+#[allow(dead_code)]
 pub mod fallback {
     /// Execute Python code as fallback when compilation is skipped
     pub fn execute_fallback(_code: &str) {
@@ -12,6 +15,7 @@ pub mod fallback {
     }
 }
 
+#[allow(dead_code)]
 pub mod ffi {
     /// FFI boundary helpers for calling compiled code from Python
     pub struct FFIBridge;
@@ -23,6 +27,7 @@ pub mod ffi {
     }
 }
 
+#[allow(dead_code)]
 pub mod memory {
     /// Memory management utilities
     pub struct Arena {
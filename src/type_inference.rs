@@ -19,9 +19,20 @@ impl TypeInference {
     pub fn infer_program(&mut self, program: &mut Program) {
         // First pass: collect function signatures
         for stmt in &program.statements {
-            if let Statement::FunctionDef(func) = stmt {
-                self.function_return_types
-                    .insert(func.name.clone(), Type::Unknown);
+            match stmt {
+                Statement::FunctionDef(func) => {
+                    self.function_return_types
+                        .insert(func.name.clone(), Type::Unknown);
+                }
+                Statement::ClassDef(class_def) => {
+                    for method in &class_def.body {
+                        if let Statement::FunctionDef(func) = method {
+                            self.function_return_types
+                                .insert(func.name.clone(), Type::Unknown);
+                        }
+                    }
+                }
+                _ => {}
             }
         }
 
@@ -41,13 +52,54 @@ impl TypeInference {
                 }
             }
             Statement::FunctionDef(func) => {
-                // Infer parameter types and return type
+                // Seed each parameter's declared type before inferring the
+                // body, so a local computed from a parameter (`x = a + b`)
+                // doesn't fall back to `Unknown` for want of ever having
+                // seen `a`/`b`. This map isn't scoped per function (matching
+                // the rest of this module), so a parameter here shadows any
+                // same-named local left over from a previously inferred
+                // function for the rest of inference.
+                for param in &func.params {
+                    self.variable_types.insert(param.name.clone(), param.typ.clone());
+                }
+
                 for stmt in &mut func.body {
                     self.infer_statement(stmt);
                 }
 
-                // Infer return type from return statements
-                func.return_type = self.infer_function_return_type(&func.body);
+                // Infer return type from return statements - except for a
+                // `#adrenaline:no-compile` function, whose body may use
+                // constructs this inference can't follow at all (that's the
+                // point of the directive). Its declared annotation is kept
+                // instead, since it's the only thing `IRCodegen`'s PyO3
+                // fallback (see `Compiler::attach_python_fallbacks`) can use
+                // to marshal the return value back across the boundary.
+                //
+                // A self-recursive call inside the body still sees this
+                // function's own `function_return_types` entry as the
+                // `Unknown` placeholder from the signature-collection pass,
+                // since that's only refreshed below, after inference - so
+                // re-run inference, feeding each pass's result back in as
+                // the seed for the next, until it stops changing (bounded,
+                // since there's only ever one function to settle here).
+                if !func.directives.iter().any(|d| d.trim() == "no-compile") {
+                    for _ in 0..4 {
+                        let inferred = self.infer_function_return_type(&func.body);
+                        self.function_return_types.insert(func.name.clone(), inferred.clone());
+                        if inferred == func.return_type {
+                            break;
+                        }
+                        func.return_type = inferred;
+                    }
+                }
+
+                // Refresh the signature-collection pass's placeholder now
+                // that the body has actually been inferred, so a call to
+                // this function anywhere later in the program sees its real
+                // return type instead of the `Unknown` every function
+                // started with.
+                self.function_return_types
+                    .insert(func.name.clone(), func.return_type.clone());
             }
             Statement::For(for_loop) => {
                 let iter_type = self.infer_expression(&for_loop.iter);
@@ -78,6 +130,50 @@ impl TypeInference {
                     }
                 }
             }
+            Statement::ClassDef(class_def) => {
+                for method in &mut class_def.body {
+                    self.infer_statement(method);
+                }
+            }
+            Statement::Try(try_stmt) => {
+                for stmt in &mut try_stmt.body {
+                    self.infer_statement(stmt);
+                }
+                for handler in &mut try_stmt.handlers {
+                    for stmt in &mut handler.body {
+                        self.infer_statement(stmt);
+                    }
+                }
+                for stmt in &mut try_stmt.finalbody {
+                    self.infer_statement(stmt);
+                }
+            }
+            // `results.append(x)` on an empty-literal list (`results = []`
+            // infers `Type::List(Unknown)` - see `infer_expression`'s
+            // `Expression::List` case) is the standard way to build a list
+            // up in a loop, so backfill the element type from the first
+            // `append`'s argument instead of leaving `results` typed as a
+            // `Vec` of nothing, which `IRCodegen` would otherwise render as
+            // `Vec<()>` while the body keeps pushing real values into it.
+            Statement::ExprStatement(Expression::Call(callee, args), _) => {
+                if let Expression::Attribute(receiver, method) = &**callee {
+                    if method == "append" {
+                        if let Expression::Identifier(name) = &**receiver {
+                            if let Some(Type::List(elem)) = self.variable_types.get(name) {
+                                if **elem == Type::Unknown {
+                                    if let Some(arg) = args.first() {
+                                        let elem_type = self.infer_expression(arg);
+                                        if elem_type != Type::Unknown {
+                                            self.variable_types
+                                                .insert(name.clone(), Type::List(Box::new(elem_type)));
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
             _ => {}
         }
     }
@@ -153,15 +249,59 @@ impl TypeInference {
         }
     }
 
+    /// Scans `body` for its effective return type, recursing into
+    /// `if`/`while`/`for`/`try` so a `return` nested inside one of those
+    /// (the common case - `if n <= 1: return n`) isn't invisible to a
+    /// top-level-only scan. Stops at the first branch that resolves to a
+    /// concrete type, since once one is found there's nothing left that
+    /// would change it (matching this module's existing preference for a
+    /// single best-effort pass over real type-lattice unification).
     fn infer_function_return_type(&self, body: &[Statement]) -> Type {
         for stmt in body {
-            if let Statement::Return(Some(expr)) = stmt {
-                return self.infer_expression(expr);
+            let candidate = match stmt {
+                Statement::Return(Some(expr), _) => self.infer_expression(expr),
+                Statement::If(if_stmt) => {
+                    let then_type = self.infer_function_return_type(&if_stmt.then_body);
+                    let else_type = if_stmt
+                        .else_body
+                        .as_ref()
+                        .map(|body| self.infer_function_return_type(body))
+                        .unwrap_or(Type::Unknown);
+                    Self::unify_branch_types(then_type, else_type)
+                }
+                Statement::While(while_loop) => self.infer_function_return_type(&while_loop.body),
+                Statement::For(for_loop) => self.infer_function_return_type(&for_loop.body),
+                Statement::Try(try_stmt) => {
+                    let mut result = self.infer_function_return_type(&try_stmt.body);
+                    for handler in &try_stmt.handlers {
+                        result =
+                            Self::unify_branch_types(result, self.infer_function_return_type(&handler.body));
+                    }
+                    Self::unify_branch_types(result, self.infer_function_return_type(&try_stmt.finalbody))
+                }
+                _ => continue,
+            };
+            if candidate != Type::Unknown {
+                return candidate;
             }
         }
         Type::Unknown
     }
 
+    /// Picks the concrete side of two candidate return types found in
+    /// sibling branches, preferring `a` when both resolved (the branch
+    /// that's scanned first, e.g. `if`'s `then` over its `else`).
+    fn unify_branch_types(a: Type, b: Type) -> Type {
+        if a == Type::Unknown {
+            b
+        } else {
+            a
+        }
+    }
+
+    // No external caller looks up a single variable's inferred type yet -
+    // `IRLowering` consumes `variable_types` in bulk instead.
+    #[allow(dead_code)]
     pub fn get_variable_type(&self, name: &str) -> Type {
         self.variable_types
             .get(name)